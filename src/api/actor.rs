@@ -0,0 +1,16 @@
+use actix_web::HttpRequest;
+use mongodb::bson::oid::ObjectId;
+
+// ACTOR_HEADER is the request header naming who made a request, such as a user or a system
+const ACTOR_HEADER: &str = "X-Actor";
+
+// actor returns who made a request. Requests that do not name anyone are made by the client
+pub fn actor(req: &HttpRequest, client_id: ObjectId) -> String {
+    req.headers()
+        .get(ACTOR_HEADER)
+        .and_then(|actor| actor.to_str().ok())
+        .map(str::trim)
+        .filter(|actor| !actor.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| client_id.to_hex())
+}
@@ -0,0 +1,289 @@
+use crate::{
+    dto::alert::alert_dto::{GetLowStockResponse, GetStockAlertsRequest, GetStockAlertsResponse},
+    dto::product::product_dto::ClientId,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get,
+    web::{self, Path, Query},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// get_low_stock is the handler to list the products of a client at or below their reorder point
+#[get("/v1/{client_id}/alerts/low-stock")]
+pub async fn get_low_stock(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let products = match app_data
+        .service_manager
+        .alert_service
+        .get_low_stock(client_id)
+        .await
+    {
+        Ok(products) => products,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "low stock products retrieved successfully",
+        GetLowStockResponse::new(&products),
+    ))
+}
+
+// get_stock_alerts is the handler to list the alerts raised when the products of a client
+// became low in stock, the most recent first
+#[get("/v1/{client_id}/alerts/low-stock/events")]
+pub async fn get_stock_alerts(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+    query: Query<GetStockAlertsRequest>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    if let Err(err) = query.validate() {
+        return err.to_responder();
+    }
+
+    let alerts = match app_data
+        .service_manager
+        .alert_service
+        .get_stock_alerts(client_id, query.get_limit())
+        .await
+    {
+        Ok(alerts) => alerts,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "stock alerts retrieved successfully",
+        GetStockAlertsResponse::new(&alerts),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::api::test_helpers::{add_named_product, app, assert_errors, call};
+
+    #[test]
+    // test_low_stock_alerts tests that a product is listed as low in stock at or below its
+    // reorder point, and that an alert is raised only when a decrement crosses the reorder point
+    async fn test_low_stock_alerts() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/products", client_id);
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "name": "Mug",
+                "description": "A mug",
+                "quantity": 5,
+                "reorder_point": 3,
+                "reorder_quantity": 10,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["reorder_point"], 3);
+        let mug_id = body["data"]["id"].as_str().unwrap().to_string();
+        add_named_product(&app, &client_id, "Tea", 1).await;
+
+        // the first order keeps the mug above its reorder point, the second crosses it and the
+        // third takes it further below
+        for quantity in [1, 2, 1] {
+            let (status, _) = call(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/orders", client_id))
+                    .set_json(json!([{"product_id": mug_id, "quantity": quantity}])),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        // the tea has no reorder point, so it is never low in stock
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/alerts/low-stock", client_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"]["products"],
+            json!([{
+                "id": mug_id,
+                "name": "Mug",
+                "sku": body["data"]["products"][0]["sku"],
+                "quantity": 1,
+                "reorder_point": 3,
+                "reorder_quantity": 10,
+            }])
+        );
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/alerts/low-stock/events", client_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let alerts = body["data"]["alerts"].as_array().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0]["product_id"], mug_id);
+        assert_eq!(alerts[0]["quantity"], 2);
+        assert_eq!(alerts[0]["reason"], "order");
+
+        // raising the reorder point above the quantity lists the product without a new alert
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/{}", uri, mug_id))
+                .set_json(json!({"name": "Mug", "description": "A mug", "reorder_point": 0})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["reorder_quantity"], 10);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/alerts/low-stock", client_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["products"], json!([]));
+
+        // (request body, expected message)
+        let test_cases = vec![
+            (
+                json!({"name": "Mug", "description": "A mug", "quantity": 1, "reorder_point": -1}),
+                "reorder_point cannot be less than 0",
+            ),
+            (
+                json!({"name": "Mug", "description": "A mug", "quantity": 1, "reorder_quantity": 0}),
+                "reorder_quantity cannot be less than 1",
+            ),
+            (
+                json!({
+                    "name": "T-shirt",
+                    "description": "A t-shirt",
+                    "quantity": 1,
+                    "variant_axes": [{"name": "size", "values": ["M"]}],
+                    "reorder_point": 2,
+                }),
+                "a product with variants cannot have a reorder point, its variants can",
+            ),
+            (
+                json!({
+                    "name": "Gift box",
+                    "description": "A gift box",
+                    "quantity": 0,
+                    "components": [{"product_id": mug_id, "quantity": 1}],
+                    "reorder_point": 2,
+                }),
+                "a bundle cannot have a reorder point, its components can",
+            ),
+        ];
+        let test_cases = test_cases
+            .into_iter()
+            .map(|(body, message)| (test::TestRequest::post().uri(&uri).set_json(body), message))
+            .collect();
+        assert_errors(&app, test_cases).await;
+    }
+
+    #[test]
+    // test_low_stock_alert_raised_once tests that an order taken from two locations raises a
+    // single alert, and that a product already low in stock raises none until it is restocked
+    // above its reorder point
+    async fn test_low_stock_alert_raised_once() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mut warehouse_ids = vec![];
+        for name in ["North", "South"] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/warehouses", client_id))
+                    .set_json(json!({"name": name})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            warehouse_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+        }
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "Mug",
+                    "description": "A mug",
+                    "quantity": 5,
+                    "warehouse_id": warehouse_ids[0],
+                    "reorder_point": 3,
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let mug_id = body["data"]["id"].as_str().unwrap().to_string();
+        let set_quantity = |quantity: i32| {
+            test::TestRequest::put()
+                .uri(&format!("/v1/{}/products/{}/quantity", client_id, mug_id))
+                .set_json(json!({"quantity": quantity, "warehouse_id": warehouse_ids[1]}))
+        };
+        let (status, _) = call(&app, set_quantity(5)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // the order of 8 is taken from both locations, then the mug runs out and is restocked
+        // above its reorder point, so the last decrement crosses it again
+        for quantity in [8, 2] {
+            let (status, _) = call(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/orders", client_id))
+                    .set_json(json!([{"product_id": mug_id, "quantity": quantity}])),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let (status, _) = call(&app, set_quantity(6)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&app, set_quantity(-4)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/alerts/low-stock/events", client_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let alerts: Vec<(i64, &str)> = body["data"]["alerts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|alert| {
+                (
+                    alert["quantity"].as_i64().unwrap(),
+                    alert["reason"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(alerts, vec![(2, "set_quantity"), (2, "order")]);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    dto::attribute::attribute_dto::{
+        AttributeDefinitionRequest, AttributeDefinitionResponse, ClientIdAttributeName,
+        GetAttributeDefinitionsResponse,
+    },
+    dto::product::product_dto::ClientId,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    delete, get, put,
+    web::{self, Json, Path},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// get_attribute_definitions is the handler to list the attribute definitions of a client
+#[get("/v1/{client_id}/attributes")]
+pub async fn get_attribute_definitions(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let definitions = match app_data
+        .service_manager
+        .attribute_service
+        .get_definitions(client_id)
+        .await
+    {
+        Ok(definitions) => definitions,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "attribute definitions retrieved successfully",
+        GetAttributeDefinitionsResponse::new(&definitions),
+    ))
+}
+
+// define_attribute is the handler to set the type of the values of an attribute of the products
+// of a client
+#[put("/v1/{client_id}/attributes/{name}")]
+pub async fn define_attribute(
+    app_data: web::Data<server::AppState>,
+    request: Json<AttributeDefinitionRequest>,
+    ca_name: Path<ClientIdAttributeName>,
+) -> impl Responder {
+    let ca_name = ca_name.into_inner();
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(ca_name.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let definition = match app_data
+        .service_manager
+        .attribute_service
+        .define(client_id, &ca_name.name, request.attribute_type)
+        .await
+    {
+        Ok(definition) => definition,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "attribute defined successfully",
+        AttributeDefinitionResponse::new(&definition),
+    ))
+}
+
+// delete_attribute_definition is the handler to delete the definition of an attribute, whose
+// values can then have any type
+#[delete("/v1/{client_id}/attributes/{name}")]
+pub async fn delete_attribute_definition(
+    app_data: web::Data<server::AppState>,
+    ca_name: Path<ClientIdAttributeName>,
+) -> impl Responder {
+    let ca_name = ca_name.into_inner();
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(ca_name.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    if let Err(err) = app_data
+        .service_manager
+        .attribute_service
+        .delete_definition(client_id, &ca_name.name)
+        .await
+    {
+        return err.to_responder();
+    }
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "attribute definition deleted successfully",
+        None::<String>,
+    ))
+}
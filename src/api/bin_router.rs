@@ -0,0 +1,438 @@
+use crate::{
+    api::{idempotency, warehouse_router::parse_client_id_warehouse_id},
+    dto::bin::bin_dto::{
+        BinRequest, BinResponse, ClientIdBinId, GetBinResponse, GetBinsRequest, GetBinsResponse,
+        MoveBinStockRequest, MoveBinStockResponse,
+    },
+    dto::product::product_dto::ClientId,
+    dto::warehouse::warehouse_dto::ClientIdWarehouseId,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    delete, get, post,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// add_bin is the handler to add a bin to a warehouse
+#[post("/v1/{client_id}/warehouses/{warehouse_id}/bins")]
+pub async fn add_bin(
+    app_data: web::Data<server::AppState>,
+    request: Json<BinRequest>,
+    cw_id: Path<ClientIdWarehouseId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, warehouse_id) = match parse_client_id_warehouse_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let bin = match app_data
+        .service_manager
+        .bin_service
+        .create(client_id, warehouse_id, request.into_inner())
+        .await
+    {
+        Ok(bin) => bin,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "bin added successfully",
+        BinResponse::new(&bin),
+    ))
+}
+
+// get_bins_by_warehouse is the handler to get the bins of a warehouse
+#[get("/v1/{client_id}/warehouses/{warehouse_id}/bins")]
+pub async fn get_bins_by_warehouse(
+    app_data: web::Data<server::AppState>,
+    cw_id: Path<ClientIdWarehouseId>,
+    query: Query<GetBinsRequest>,
+) -> impl Responder {
+    let (client_id, warehouse_id) = match parse_client_id_warehouse_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let bins = match app_data
+        .service_manager
+        .bin_service
+        .get_bins_by_warehouse(client_id, warehouse_id, query.into_inner())
+        .await
+    {
+        Ok(bins) => bins.iter().map(BinResponse::new).collect(),
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "bins retrieved successfully",
+        GetBinsResponse::new(bins),
+    ))
+}
+
+// move_bin_stock is the handler to move a quantity of a product between bins
+#[post("/v1/{client_id}/bins/moves")]
+pub async fn move_bin_stock(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    request: Json<MoveBinStockRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the product id and bin ids
+    let product_id = match ObjectId::from_str(request.product_id.as_str()) {
+        Ok(product_id) => product_id,
+        Err(_) => {
+            return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+    let from_bin_id = match parse_bin_id(&request.from_bin_id) {
+        Ok(bin_id) => bin_id,
+        Err(err) => return err.to_responder(),
+    };
+    let to_bin_id = match parse_bin_id(&request.to_bin_id) {
+        Ok(bin_id) => bin_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    // run the move at most once per idempotency key
+    idempotency::idempotent(&app_data, &req, client_id, &*request, async {
+        let (from, to) = match app_data
+            .service_manager
+            .bin_service
+            .move_stock(
+                client_id,
+                product_id,
+                from_bin_id,
+                to_bin_id,
+                request.quantity,
+            )
+            .await
+        {
+            Ok(bin_stocks) => bin_stocks,
+            Err(err) => return err.to_responder(),
+        };
+
+        HttpResponse::Ok().json(APIResponse::success(
+            "stock moved successfully",
+            MoveBinStockResponse::new(product_id.to_hex(), from.as_ref(), to.as_ref()),
+        ))
+    })
+    .await
+}
+
+// get_bin is the handler to get a bin and what is stored in it
+#[get("/v1/{client_id}/bins/{bin_id}")]
+pub async fn get_bin(
+    app_data: web::Data<server::AppState>,
+    cb_id: Path<ClientIdBinId>,
+) -> impl Responder {
+    let (client_id, bin_id) = match parse_client_id_bin_id(cb_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let (bin, bin_stocks) = match app_data
+        .service_manager
+        .bin_service
+        .get_bin_contents(client_id, bin_id)
+        .await
+    {
+        Ok(contents) => contents,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "bin retrieved successfully",
+        GetBinResponse::new(&bin, &bin_stocks),
+    ))
+}
+
+// delete_bin is the handler to delete a bin that no longer holds stock
+#[delete("/v1/{client_id}/bins/{bin_id}")]
+pub async fn delete_bin(
+    app_data: web::Data<server::AppState>,
+    cb_id: Path<ClientIdBinId>,
+) -> impl Responder {
+    let (client_id, bin_id) = match parse_client_id_bin_id(cb_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    if let Err(err) = app_data
+        .service_manager
+        .bin_service
+        .delete_bin(client_id, bin_id)
+        .await
+    {
+        return err.to_responder();
+    }
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "bin deleted successfully",
+        None::<String>,
+    ))
+}
+
+// parse_client_id_bin_id converts the client id and bin id of a path to objectIds
+fn parse_client_id_bin_id(ids: ClientIdBinId) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let bin_id = ObjectId::from_str(ids.bin_id.as_str())
+        .map_err(|_| AppError::new("invalid bin id", ErrorKind::FailedAction))?;
+    Ok((client_id, bin_id))
+}
+
+// parse_bin_id converts an optional bin id from string to an objectId
+fn parse_bin_id(bin_id: &Option<String>) -> Result<Option<ObjectId>, AppError> {
+    match bin_id {
+        Some(bin_id) => ObjectId::from_str(bin_id.as_str())
+            .map(Some)
+            .map_err(|_| AppError::new("invalid bin id", ErrorKind::FailedAction)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_web::{
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test,
+    };
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+
+    use crate::api::test_helpers::{app, assert_error, assert_errors, call};
+
+    // add_warehouse adds a warehouse with a bin for every code given, and returns the id of the
+    // warehouse and the ids and codes of its bins
+    async fn add_warehouse(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        client_id: &str,
+        bins: &[[&str; 4]],
+    ) -> (String, Vec<(String, String)>) {
+        let (status, body) = call(
+            app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/warehouses", client_id))
+                .set_json(json!({"name": "Warehouse"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let warehouse_id = body["data"]["id"].as_str().unwrap().to_string();
+        let mut bin_ids = vec![];
+        for [zone, aisle, rack, bin] in bins {
+            let (status, body) = call(
+                app,
+                test::TestRequest::post()
+                    .uri(&format!(
+                        "/v1/{}/warehouses/{}/bins",
+                        client_id, warehouse_id
+                    ))
+                    .set_json(json!({"zone": zone, "aisle": aisle, "rack": rack, "bin": bin})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            bin_ids.push((
+                body["data"]["id"].as_str().unwrap().to_string(),
+                body["data"]["code"].as_str().unwrap().to_string(),
+            ));
+        }
+        (warehouse_id, bin_ids)
+    }
+
+    #[test]
+    // test_move_bin_stock tests that stock is put away into bins, moved between the bins of a
+    // warehouse and taken out of them, and that no more can be moved than there is
+    async fn test_move_bin_stock() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let (warehouse_id, bins) = add_warehouse(
+            &app,
+            &client_id,
+            &[["A", "1", "1", "1"], ["A", "1", "1", "2"]],
+        )
+        .await;
+        let (first_id, first_code) = &bins[0];
+        let (second_id, _) = &bins[1];
+        let (_, other_bins) = add_warehouse(&app, &client_id, &[["B", "1", "1", "1"]]).await;
+        let (other_id, _) = &other_bins[0];
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "Widget",
+                    "description": "A widget",
+                    "quantity": 10,
+                    "warehouse_id": warehouse_id,
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let product_id = body["data"]["id"].as_str().unwrap().to_string();
+        let uri = format!("/v1/{}/bins/moves", client_id);
+        let move_stock = |from_bin_id: Option<&String>, to_bin_id: Option<&String>, quantity| {
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "product_id": product_id,
+                "from_bin_id": from_bin_id,
+                "to_bin_id": to_bin_id,
+                "quantity": quantity,
+            }))
+        };
+
+        // (from bin, to bin, quantity, expected from, expected to)
+        let test_cases = vec![
+            // put away from the stock of the warehouse that is not in a bin
+            (
+                None,
+                Some(first_id),
+                6,
+                Value::Null,
+                json!({"bin_id": first_id, "quantity": 6}),
+            ),
+            (
+                Some(first_id),
+                Some(second_id),
+                4,
+                json!({"bin_id": first_id, "quantity": 2}),
+                json!({"bin_id": second_id, "quantity": 4}),
+            ),
+            // taken out of the bins, back to the stock of the warehouse that is not in a bin
+            (
+                Some(second_id),
+                None,
+                2,
+                json!({"bin_id": second_id, "quantity": 2}),
+                Value::Null,
+            ),
+        ];
+        for (from_bin_id, to_bin_id, quantity, from, to) in test_cases {
+            let (status, body) = call(&app, move_stock(from_bin_id, to_bin_id, quantity)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["message"], "stock moved successfully");
+            assert_eq!(body["data"]["from"], from);
+            assert_eq!(body["data"]["to"], to);
+        }
+
+        // the bins show what they hold, and the product where it can be found
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/bins/{}", client_id, second_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"]["products"],
+            json!([{"product_id": product_id, "quantity": 2}])
+        );
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quantity"], 10);
+        let mut bin_quantities: Vec<(&str, i64)> = body["data"]["bins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bin| {
+                (
+                    bin["bin_id"].as_str().unwrap(),
+                    bin["quantity"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        bin_quantities.sort();
+        let mut expected = vec![(first_id.as_str(), 2), (second_id.as_str(), 2)];
+        expected.sort();
+        assert_eq!(bin_quantities, expected);
+
+        // a bin holding stock cannot be deleted
+        let response = call(
+            &app,
+            test::TestRequest::delete().uri(&format!("/v1/{}/bins/{}", client_id, first_id)),
+        )
+        .await;
+        assert_error(response, "bin still holds stock, move it first");
+
+        // (request, expected message)
+        let put_away_too_much = format!(
+            "cannot put away 7 of product_id: {}, only 6 at the warehouse is not in a bin",
+            product_id
+        );
+        let move_too_much = format!(
+            "bin {} does not hold 3 of product_id: {}",
+            first_code, product_id
+        );
+        let missing_bin_id = ObjectId::new().to_hex();
+        let test_cases = vec![
+            (
+                move_stock(None, Some(second_id), 7),
+                put_away_too_much.as_str(),
+            ),
+            (
+                move_stock(Some(first_id), Some(second_id), 3),
+                move_too_much.as_str(),
+            ),
+            (
+                move_stock(Some(first_id), Some(other_id), 1),
+                "bins must be in the same warehouse",
+            ),
+            (
+                move_stock(Some(first_id), Some(second_id), 0),
+                "quantity cannot be less than 1",
+            ),
+            (
+                move_stock(None, None, 1),
+                "from_bin_id or to_bin_id must be set",
+            ),
+            (
+                move_stock(Some(first_id), Some(first_id), 1),
+                "from_bin_id and to_bin_id cannot be the same bin",
+            ),
+            (
+                move_stock(Some(&"x".to_string()), Some(first_id), 1),
+                "invalid bin id",
+            ),
+            (
+                move_stock(Some(&missing_bin_id), Some(first_id), 1),
+                "bin not found",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/bins/{}", client_id, first_id)),
+        )
+        .await;
+        assert_eq!(
+            body["data"]["products"],
+            json!([{"product_id": product_id, "quantity": 2}])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use actix_web::{
+    body::{self, BoxBody},
+    http::{header::ContentType, StatusCode},
+    web, HttpRequest, HttpResponse,
+};
+use log::error;
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use std::future::Future;
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    server,
+    service::idempotency_service::IdempotencyOutcome,
+};
+
+// IDEMPOTENCY_KEY_HEADER is the request header carrying the idempotency key
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+// IDEMPOTENT_REPLAYED_HEADER is set on responses replayed for a retried idempotency key
+const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+// MAX_IDEMPOTENCY_KEY_LENGTH is the longest idempotency key a client can send
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+// idempotent runs a handler at most once per idempotency key of a client. Requests without the
+// Idempotency-Key header run the handler as usual. The first response for a key is stored and
+// replayed for retries with the same payload, unless it was a server error, in which case the
+// key is given up so that a retry runs the handler again
+pub async fn idempotent<P, Fut>(
+    app_data: &web::Data<server::AppState>,
+    req: &HttpRequest,
+    client_id: ObjectId,
+    payload: &P,
+    handler: Fut,
+) -> HttpResponse
+where
+    P: Serialize,
+    Fut: Future<Output = HttpResponse>,
+{
+    // requests without a key are not idempotent
+    let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) => match key.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => key,
+            _ => {
+                return AppError::new("invalid idempotency key", ErrorKind::FailedAction)
+                    .to_responder()
+            }
+        },
+        None => return handler.await,
+    };
+
+    // the request a key is used with is identified by its method, path and payload
+    let request = match serde_json::to_string(&(req.method().as_str(), req.path(), payload)) {
+        Ok(request) => request,
+        Err(err) => {
+            error!("Error serializing idempotent request: {:?}", err);
+            return AppError::new("cannot process request", ErrorKind::InternalServerError)
+                .to_responder();
+        }
+    };
+
+    let idempotency_service = &app_data.service_manager.idempotency_service;
+    let claim_id = match idempotency_service.begin(client_id, key, &request).await {
+        Ok(IdempotencyOutcome::Proceed { claim_id }) => claim_id,
+        Ok(IdempotencyOutcome::Replay {
+            status_code,
+            response_body,
+        }) => {
+            let status_code =
+                StatusCode::from_u16(status_code as u16).unwrap_or(StatusCode::OK);
+            return HttpResponse::build(status_code)
+                .content_type(ContentType::json())
+                .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"))
+                .body(response_body);
+        }
+        Err(err) => return err.to_responder(),
+    };
+
+    // run the handler and read its response body so that it can be stored
+    let (response, response_body) = handler.await.into_parts();
+    let response_body = match body::to_bytes(response_body).await {
+        Ok(response_body) => response_body,
+        Err(err) => {
+            error!("Error reading idempotent response body: {:?}", err);
+            if let Err(err) = idempotency_service.abandon(claim_id).await {
+                error!("Error giving up idempotency key: {}", err);
+            }
+            return AppError::new("cannot process request", ErrorKind::InternalServerError)
+                .to_responder();
+        }
+    };
+
+    // server errors are not replayed, the retry should be processed again
+    let stored = if response.status().is_server_error() {
+        idempotency_service.abandon(claim_id).await
+    } else {
+        idempotency_service
+            .complete(
+                claim_id,
+                response.status().as_u16() as i32,
+                &String::from_utf8_lossy(&response_body),
+            )
+            .await
+    };
+    if let Err(err) = stored {
+        error!("Error storing idempotent response: {}", err);
+    }
+
+    response.set_body(BoxBody::new(response_body))
+}
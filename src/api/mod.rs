@@ -1,11 +1,32 @@
 use actix_web::web;
 
+pub mod actor;
+pub mod alert_router;
+pub mod attribute_router;
+pub mod bin_router;
+pub mod idempotency;
+pub mod order_router;
 pub mod product_router;
+pub mod purchase_order_router;
+pub mod reservation_router;
+pub mod return_router;
+pub mod sku_router;
+pub mod stream_router;
+pub mod supplier_router;
+#[cfg(test)]
+pub mod test_helpers;
+pub mod warehouse_router;
+pub mod webhook_router;
 
 // init configures routes for the application
 pub fn init(cfg: &mut web::ServiceConfig) {
     // product services
     cfg.service(product_router::add_product);
+    // search and lookups by sku and barcode are registered before the routes whose paths would
+    // otherwise take "search", "by-sku" and "by-barcode" as a product id
+    cfg.service(product_router::search_products);
+    cfg.service(product_router::get_product_by_sku);
+    cfg.service(product_router::get_product_by_barcode);
     cfg.service(product_router::get_product);
     cfg.service(product_router::get_products_by_client);
     cfg.service(product_router::update_product);
@@ -13,5 +34,82 @@ pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(product_router::check_availability);
     cfg.service(product_router::check_multiple_availability);
     cfg.service(product_router::delete_product);
-    cfg.service(product_router::process_orders);
+    cfg.service(product_router::get_product_movements);
+
+    // order services
+    cfg.service(order_router::process_orders);
+    cfg.service(order_router::get_orders_by_client);
+    cfg.service(order_router::get_order);
+    cfg.service(order_router::cancel_order);
+
+    // reservation services
+    cfg.service(reservation_router::reserve);
+    cfg.service(reservation_router::get_reservation);
+    cfg.service(reservation_router::commit_reservation);
+    cfg.service(reservation_router::release_reservation);
+
+    // return services
+    cfg.service(return_router::authorize_return);
+    cfg.service(return_router::get_returns_by_client);
+    cfg.service(return_router::get_return);
+    cfg.service(return_router::receive_return);
+    cfg.service(return_router::close_return);
+
+    // purchase order services
+    cfg.service(purchase_order_router::add_purchase_order);
+    cfg.service(purchase_order_router::get_purchase_orders_by_client);
+    cfg.service(purchase_order_router::get_purchase_order);
+    cfg.service(purchase_order_router::receive_purchase_order);
+    cfg.service(purchase_order_router::close_purchase_order);
+
+    // supplier services
+    cfg.service(supplier_router::add_supplier);
+    cfg.service(supplier_router::get_suppliers_by_client);
+    cfg.service(supplier_router::get_supplier);
+    cfg.service(supplier_router::update_supplier);
+    cfg.service(supplier_router::delete_supplier);
+    cfg.service(supplier_router::link_product_supplier);
+    cfg.service(supplier_router::unlink_product_supplier);
+
+    // warehouse services
+    cfg.service(warehouse_router::add_warehouse);
+    cfg.service(warehouse_router::get_warehouses_by_client);
+    cfg.service(warehouse_router::get_warehouse);
+    cfg.service(warehouse_router::update_warehouse);
+    cfg.service(warehouse_router::delete_warehouse);
+
+    // bin services
+    cfg.service(bin_router::add_bin);
+    cfg.service(bin_router::get_bins_by_warehouse);
+    cfg.service(bin_router::move_bin_stock);
+    cfg.service(bin_router::get_bin);
+    cfg.service(bin_router::delete_bin);
+
+    // sku services
+    cfg.service(sku_router::get_sku_settings);
+    cfg.service(sku_router::set_sku_settings);
+
+    // attribute services
+    cfg.service(attribute_router::get_attribute_definitions);
+    cfg.service(attribute_router::define_attribute);
+    cfg.service(attribute_router::delete_attribute_definition);
+
+    // alert services
+    cfg.service(alert_router::get_low_stock);
+    cfg.service(alert_router::get_stock_alerts);
+
+    // webhook services
+    cfg.service(webhook_router::add_webhook);
+    cfg.service(webhook_router::get_webhooks_by_client);
+    // the delivery log is registered before the routes whose paths would otherwise take
+    // "deliveries" as a webhook id
+    cfg.service(webhook_router::get_deliveries);
+    cfg.service(webhook_router::get_delivery);
+    cfg.service(webhook_router::replay_delivery);
+    cfg.service(webhook_router::get_webhook);
+    cfg.service(webhook_router::update_webhook);
+    cfg.service(webhook_router::delete_webhook);
+
+    // stream services
+    cfg.service(stream_router::stream_stock_changes);
 }
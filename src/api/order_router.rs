@@ -0,0 +1,734 @@
+use crate::{
+    api::{actor, idempotency},
+    dto::order::order_dto::{
+        CancelOrderRequest, ClientIdOrderId, GetOrdersRequest, GetOrdersResponse, OrderResponse,
+    },
+    dto::product::product_dto::{ClientId, ProductQuantityRequest},
+    dto::warehouse::warehouse_dto::{self, LocationQuery},
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get, post,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use log::error;
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// process_orders processes orders by decrementing their product quantity by the specified quantity
+#[post("/v1/{client_id}/orders")]
+pub async fn process_orders(
+    req: HttpRequest,
+    request: Json<Vec<ProductQuantityRequest>>,
+    query: Query<LocationQuery>,
+    c_id: Path<ClientId>,
+    app_data: web::Data<server::AppState>,
+) -> impl Responder {
+    // validate the client id
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the warehouse id, the order is taken across warehouses if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&query.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    // process the order at most once per idempotency key
+    let payload = (&*request, &*query);
+    idempotency::idempotent(&app_data, &req, client_id, &payload, async {
+        // process orders in the order service
+        let order = match app_data
+            .service_manager
+            .order_service
+            .place_order(
+                client_id,
+                request.to_vec(),
+                warehouse_id,
+                &actor::actor(&req, client_id),
+            )
+            .await
+        {
+            Ok(order) => order,
+            Err(err) => {
+                return match err.kind {
+                    ErrorKind::FailedAction => err.to_responder(),
+                    ErrorKind::NotFound => err.to_responder(),
+                    _ => {
+                        error!("Error processing orders");
+                        AppError::new("cannot process orders", ErrorKind::InternalServerError)
+                            .to_responder()
+                    }
+                }
+            }
+        };
+
+        HttpResponse::Ok().json(APIResponse::success(
+            "order processed successfully",
+            OrderResponse::new(&order),
+        ))
+    })
+    .await
+}
+
+// get_orders_by_client is the handler to page through the orders of a client, optionally only
+// those in one status or with a line of one product
+#[get("/v1/{client_id}/orders")]
+pub async fn get_orders_by_client(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+    query: Query<GetOrdersRequest>,
+) -> impl Responder {
+    // validate the request query
+    let order_query = match query.to_query() {
+        Ok(order_query) => order_query,
+        Err(err) => return err.to_responder(),
+    };
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // retrieve the page of orders from the service
+    let (page, limit) = (query.get_page(), query.get_limit());
+    let (orders, total) = match app_data
+        .service_manager
+        .order_service
+        .get_orders_by_client(client_id, &order_query, page, limit)
+        .await
+    {
+        Ok(orders_total) => orders_total,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "orders retrieved successfully",
+        GetOrdersResponse::new(
+            orders.iter().map(OrderResponse::new).collect(),
+            page,
+            limit,
+            total,
+        ),
+    ))
+}
+
+// get_order is the handler to get a single order
+#[get("/v1/{client_id}/orders/{order_id}")]
+pub async fn get_order(
+    app_data: web::Data<server::AppState>,
+    co_id: Path<ClientIdOrderId>,
+) -> impl Responder {
+    let client_id_order_id = co_id.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_order_id.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the order id
+    let order_id = match ObjectId::from_str(client_id_order_id.order_id.as_str()) {
+        Ok(order_id) => order_id,
+        Err(_) => return AppError::new("invalid order id", ErrorKind::FailedAction).to_responder(),
+    };
+
+    // retrieve the order from the service
+    let order = match app_data
+        .service_manager
+        .order_service
+        .get_order(client_id, order_id)
+        .await
+    {
+        Ok(order) => order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "order retrieved successfully",
+        OrderResponse::new(&order),
+    ))
+}
+
+// cancel_order is the handler to cancel some or all of an order and give its quantities back to the stock
+#[post("/v1/{client_id}/orders/{order_id}/cancel")]
+pub async fn cancel_order(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    request: Json<CancelOrderRequest>,
+    co_id: Path<ClientIdOrderId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let client_id_order_id = co_id.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_order_id.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the order id
+    let order_id = match ObjectId::from_str(client_id_order_id.order_id.as_str()) {
+        Ok(order_id) => order_id,
+        Err(_) => return AppError::new("invalid order id", ErrorKind::FailedAction).to_responder(),
+    };
+
+    // cancel the order in the order service
+    let order = match app_data
+        .service_manager
+        .order_service
+        .cancel_order(
+            client_id,
+            order_id,
+            request.into_inner(),
+            &actor::actor(&req, client_id),
+        )
+        .await
+    {
+        Ok(order) => order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "order cancelled successfully",
+        OrderResponse::new(&order),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_web::{
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test,
+    };
+    use async_trait::async_trait;
+    use mongodb::bson::oid::ObjectId;
+    use mongodb::error::Error;
+    use serde_json::{json, Value};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        api::test_helpers::{add_named_product, app, app_with, assert_error, assert_errors, call},
+        model::stock_movement::StockMovement,
+        repository::{stock_movement_repo::StockMovementStore, stores::Stores},
+        server::ServiceManager,
+    };
+
+    // FailingMovementStore keeps the stock movements of another store, but fails as many
+    // inserts as it is told to
+    struct FailingMovementStore {
+        movements: Arc<dyn StockMovementStore>,
+        failures: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl StockMovementStore for FailingMovementStore {
+        async fn insert(&self, movement: &mut StockMovement) -> Result<(), Error> {
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+            if failing {
+                return Err(Error::custom("cannot insert stock movement"));
+            }
+            self.movements.insert(movement).await
+        }
+
+        async fn get_by_product_id(
+            &self,
+            client_id: ObjectId,
+            product_id: ObjectId,
+            skip: u64,
+            limit: i64,
+        ) -> Result<Vec<StockMovement>, Error> {
+            self.movements
+                .get_by_product_id(client_id, product_id, skip, limit)
+                .await
+        }
+
+        async fn count_by_product_id(
+            &self,
+            client_id: ObjectId,
+            product_id: ObjectId,
+        ) -> Result<u64, Error> {
+            self.movements
+                .count_by_product_id(client_id, product_id)
+                .await
+        }
+
+        async fn last_sequence(&self, client_id: ObjectId) -> Result<i64, Error> {
+            self.movements.last_sequence(client_id).await
+        }
+
+        async fn get_after(
+            &self,
+            client_id: ObjectId,
+            after: i64,
+            limit: i64,
+        ) -> Result<Vec<StockMovement>, Error> {
+            self.movements.get_after(client_id, after, limit).await
+        }
+    }
+
+    // place_order processes an order of a client and returns its id
+    async fn place_order(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        client_id: &str,
+        lines: Value,
+    ) -> String {
+        let (status, body) = call(
+            app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders", client_id))
+                .set_json(lines),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        body["data"]["id"].as_str().unwrap().to_string()
+    }
+
+    // list_orders lists the orders of a client with a query, and returns their ids and the
+    // number of orders matching the query
+    async fn list_orders(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        client_id: &str,
+        query: &str,
+    ) -> (Vec<String>, u64) {
+        let (status, body) = call(
+            app,
+            test::TestRequest::get().uri(&format!("/v1/{}/orders?{}", client_id, query)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let ids = body["data"]["orders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| order["id"].as_str().unwrap().to_string())
+            .collect();
+        (ids, body["data"]["total"].as_u64().unwrap())
+    }
+
+    #[test]
+    // test_order_rollback tests that when a later line of an order cannot be taken out of stock,
+    // the lines already taken are given back and the error names the failing line
+    async fn test_order_rollback() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 1).await;
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "Tea box",
+                    "description": "A box of tea",
+                    "quantity": 0,
+                    "components": [{"product_id": tea_id, "quantity": 1}],
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let box_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        // every line is available on its own, but the tea box and the tea take two teas together,
+        // so the decrement of the second line fails after the mugs of the first were taken
+        let response = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders", client_id))
+                .set_json(json!([
+                    {"product_id": mug_id, "quantity": 2},
+                    {"product_id": box_id, "quantity": 1},
+                    {"product_id": tea_id, "quantity": 1},
+                ])),
+        )
+        .await;
+        assert_error(
+            response,
+            &format!(
+                "order line 2 with product_id: {} failed: product is low in stock. no stock was changed",
+                box_id
+            ),
+        );
+
+        for (product_id, quantity) in [(&mug_id, 5), (&tea_id, 1)] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["quantity"], quantity);
+        }
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/v1/{}/products/{}/movements", client_id, mug_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let movements: Vec<(&str, i64)> = body["data"]["movements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|movement| {
+                (
+                    movement["reason"].as_str().unwrap(),
+                    movement["delta"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            movements,
+            vec![("order_rollback", 2), ("order", -2), ("create", 5)]
+        );
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/orders", client_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["orders"], json!([]));
+    }
+
+    #[test]
+    // test_order_history tests that the orders of a client are paged through the most recent
+    // first, filtered by status and product, and looked up with their lines
+    async fn test_order_history() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 10).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 10).await;
+        let first_id = place_order(
+            &app,
+            &client_id,
+            json!([{"product_id": mug_id, "quantity": 1}]),
+        )
+        .await;
+        let second_id = place_order(
+            &app,
+            &client_id,
+            json!([{"product_id": tea_id, "quantity": 2}]),
+        )
+        .await;
+        let third_id = place_order(
+            &app,
+            &client_id,
+            json!([
+                {"product_id": mug_id, "quantity": 1},
+                {"product_id": tea_id, "quantity": 1},
+            ]),
+        )
+        .await;
+        let (status, _) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders/{}/cancel", client_id, first_id))
+                .set_json(json!({"reason": "customer changed their mind"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // (query, expected order ids, expected total)
+        let test_cases = vec![
+            ("".to_string(), vec![&third_id, &second_id, &first_id], 3),
+            ("limit=2".to_string(), vec![&third_id, &second_id], 3),
+            ("limit=2&page=2".to_string(), vec![&first_id], 3),
+            ("limit=2&page=3".to_string(), vec![], 3),
+            (
+                format!("product_id={}", mug_id),
+                vec![&third_id, &first_id],
+                2,
+            ),
+            ("status=cancelled".to_string(), vec![&first_id], 1),
+            (
+                format!("status=processed&product_id={}", tea_id),
+                vec![&third_id, &second_id],
+                2,
+            ),
+            (
+                format!("product_id={}", ObjectId::new().to_hex()),
+                vec![],
+                0,
+            ),
+        ];
+        for (query, order_ids, total) in test_cases {
+            assert_eq!(
+                list_orders(&app, &client_id, &query).await,
+                (order_ids.into_iter().cloned().collect(), total),
+                "{}",
+                query
+            );
+        }
+
+        // an order shows what it took out of stock
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/orders/{}", client_id, third_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "processed");
+        let lines: Vec<(&str, i64)> = body["data"]["lines"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| {
+                (
+                    line["product_id"].as_str().unwrap(),
+                    line["quantity"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(lines, vec![(mug_id.as_str(), 1), (tea_id.as_str(), 1)]);
+        assert!(!body["data"]["created_at"].as_str().unwrap().is_empty());
+
+        // the orders of a client are not seen by another client
+        let other_id = ObjectId::new().to_hex();
+        assert_eq!(list_orders(&app, &other_id, "").await, (vec![], 0));
+
+        let uri = format!("/v1/{}/orders", client_id);
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::get().uri(&format!("/v1/{}/orders/{}", other_id, third_id)),
+                "order not found",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/x", uri)),
+                "invalid order id",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}?page=0", uri)),
+                "page cannot be less than 1",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}?limit=101", uri)),
+                "limit must be between 1 and 100",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}?product_id=x", uri)),
+                "invalid product id",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+    }
+
+    #[test]
+    // test_cancel_order tests that cancelling part of an order and then the rest of it gives
+    // back exactly what the order took, and that an order cannot be cancelled twice
+    async fn test_cancel_order() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 10).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 10).await;
+        let coffee_id = add_named_product(&app, &client_id, "Coffee", 10).await;
+        let order_id = place_order(
+            &app,
+            &client_id,
+            json!([
+                {"product_id": mug_id, "quantity": 3},
+                {"product_id": tea_id, "quantity": 2},
+            ]),
+        )
+        .await;
+        let uri = format!("/v1/{}/orders/{}/cancel", client_id, order_id);
+        let cancel = |request: Value| test::TestRequest::post().uri(&uri).set_json(request);
+        let quantities = || async {
+            let mut quantities = vec![];
+            for product_id in [&mug_id, &tea_id] {
+                let (_, body) = call(
+                    &app,
+                    test::TestRequest::get()
+                        .uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+                )
+                .await;
+                quantities.push(body["data"]["quantity"].as_i64().unwrap());
+            }
+            quantities
+        };
+        assert_eq!(quantities().await, vec![7, 8]);
+
+        // cancelling part of a line gives back that part only
+        let (status, body) = call(
+            &app,
+            cancel(json!({
+                "reason": "damaged box",
+                "lines": [{"product_id": mug_id, "quantity": 1}],
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "order cancelled successfully");
+        assert_eq!(body["data"]["status"], "partially_cancelled");
+        assert_eq!(body["data"]["lines"][0]["cancelled_quantity"], 1);
+        assert_eq!(body["data"]["lines"][1]["cancelled_quantity"], 0);
+        assert_eq!(body["data"]["cancellations"][0]["reason"], "damaged box");
+        assert_eq!(
+            body["data"]["cancellations"][0]["lines"],
+            json!([{"product_id": mug_id, "quantity": 1}])
+        );
+        assert_eq!(quantities().await, vec![8, 8]);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/v1/{}/products/{}/movements", client_id, mug_id)),
+        )
+        .await;
+        let movement = &body["data"]["movements"][0];
+        assert_eq!(movement["reason"], "order_cancellation");
+        assert_eq!(movement["delta"], 1);
+        assert_eq!(movement["reference"], order_id);
+
+        // more than what remains of a line cannot be cancelled
+        for (request, message) in [
+            (
+                json!({"reason": "too many", "lines": [{"product_id": mug_id, "quantity": 3}]}),
+                format!(
+                    "cannot cancel 3 of product_id: {}, only 2 remain on the order",
+                    mug_id
+                ),
+            ),
+            (
+                json!({"reason": "wrong product", "lines": [{"product_id": coffee_id, "quantity": 1}]}),
+                format!("order has no line with product_id: {}", coffee_id),
+            ),
+        ] {
+            assert_error(call(&app, cancel(request)).await, &message);
+        }
+        assert_eq!(quantities().await, vec![8, 8]);
+
+        // cancelling without lines cancels everything that remains, once
+        let (status, body) = call(&app, cancel(json!({"reason": "customer left"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "cancelled");
+        assert_eq!(body["data"]["lines"][0]["cancelled_quantity"], 3);
+        assert_eq!(body["data"]["lines"][1]["cancelled_quantity"], 2);
+        assert_eq!(
+            body["data"]["cancellations"][1]["lines"],
+            json!([
+                {"product_id": mug_id, "quantity": 2},
+                {"product_id": tea_id, "quantity": 2},
+            ])
+        );
+        assert_eq!(quantities().await, vec![10, 10]);
+
+        // nothing is given back twice. (request, expected message)
+        let nothing_remains = format!(
+            "cannot cancel 1 of product_id: {}, only 0 remain on the order",
+            tea_id
+        );
+        let test_cases = vec![
+            (
+                cancel(json!({"reason": "again"})),
+                "order is already cancelled",
+            ),
+            (
+                cancel(
+                    json!({"reason": "again", "lines": [{"product_id": tea_id, "quantity": 1}]}),
+                ),
+                nothing_remains.as_str(),
+            ),
+            (cancel(json!({"reason": " "})), "reason cannot be empty"),
+            (
+                cancel(json!({"reason": "again", "lines": []})),
+                "lines cannot be empty",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/orders/x/cancel", client_id))
+                    .set_json(json!({"reason": "again"})),
+                "invalid order id",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&format!(
+                        "/v1/{}/orders/{}/cancel",
+                        client_id,
+                        ObjectId::new().to_hex()
+                    ))
+                    .set_json(json!({"reason": "again"})),
+                "order not found",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+        assert_eq!(quantities().await, vec![10, 10]);
+    }
+
+    #[test]
+    // test_order_with_unrecorded_movement tests that an order whose stock movement cannot be
+    // recorded fails and gives back what it took, leaving movements that add up to the quantity
+    async fn test_order_with_unrecorded_movement() {
+        let mut stores = Stores::memory();
+        let movements = Arc::new(FailingMovementStore {
+            movements: stores.stock_movement_store.clone(),
+            failures: AtomicUsize::new(0),
+        });
+        stores.stock_movement_store = movements.clone();
+        let app = app_with(ServiceManager::with_webhook_allowed_hosts(&stores, vec![])).await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+
+        movements.failures.store(1, Ordering::SeqCst);
+        let (status, _) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders", client_id))
+                .set_json(json!([{"product_id": mug_id, "quantity": 2}])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, mug_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["quantity"], 5);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/v1/{}/products/{}/movements", client_id, mug_id)),
+        )
+        .await;
+        let deltas: Vec<i64> = body["data"]["movements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|movement| movement["delta"].as_i64().unwrap())
+            .collect();
+        assert_eq!(deltas, vec![5]);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/orders", client_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["orders"], json!([]));
+    }
+}
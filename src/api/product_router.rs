@@ -1,25 +1,29 @@
 use crate::{
+    api::{actor, idempotency},
     dto::product::product_dto::{
-        AddProductRequest, AddProductResponse, CheckAvailabilityRequest, ClientId,
-        ClientIdProductId, GetProductResponse, ProductQuantityRequest,
+        self, AddProductRequest, AddProductResponse, CheckAvailabilityRequest, ClientId, ClientIdBarcode,
+        ClientIdProductId, ClientIdSku, GetProductResponse, GetProductsRequest, GetStockMovementsRequest,
+        ProductSearchHitResponse, SearchProductsRequest, SearchProductsResponse,
+        GetStockMovementsResponse, ProductQuantityRequest, StockMovementResponse,
         SetProductQuantityRequest, SetProductQuantityResponse, UpdateProductRequest,
-        UpdateProductResponse,
+        UpdateProductResponse, StockLocationResponse, VariantResponse,
     },
+    dto::bin::bin_dto::ProductBinResponse,
+    dto::warehouse::warehouse_dto::{self, LocationQuery},
     dto::APIResponse,
     errors::app_error::{AppError, ErrorKind},
-    model::product::Product,
+    model::{product::Product, stock::Stock, stock_movement::MovementSource},
     server,
 };
 use actix_web::{
     delete, get, post, put,
     web::{self, Json, Path, Query},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
 use log::error;
 use mongodb::bson::oid::ObjectId;
 use std::str::FromStr;
 use crate::dto::product::product_dto::GetProductsQuantityResponse;
-use crate::model::product::ProductQuantityResponse;
 
 // get_product is the handler to get a single product
 #[get("/v1/{client_id}/products/{product_id}")]
@@ -30,7 +34,7 @@ pub async fn get_product(
     let client_id_product_id = cp_id.into_inner();
 
     // validate the client id
-    let client_id = match ObjectId::from_str(&client_id_product_id.client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
@@ -38,7 +42,7 @@ pub async fn get_product(
     };
 
     // validate the product id
-    let product_id = match ObjectId::from_str(&&client_id_product_id.product_id.as_str()) {
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
         Ok(product_id) => product_id,
         Err(_) => {
             return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
@@ -57,18 +61,157 @@ pub async fn get_product(
     };
 
     // return the product and quantity
+    product_response(&app_data, client_id, product_qty).await
+}
+
+// get_product_by_sku is the handler to get a single product by sku
+#[get("/v1/{client_id}/products/by-sku/{sku}")]
+pub async fn get_product_by_sku(
+    app_data: web::Data<server::AppState>,
+    c_sku: Path<ClientIdSku>,
+) -> impl Responder {
+    let client_id_sku = c_sku.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_sku.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // retrieve the product and quantity from the service
+    let product_qty = match app_data
+        .service_manager
+        .product_service
+        .get_product_by_sku(client_id, &client_id_sku.sku)
+        .await
+    {
+        Ok(product_qty) => product_qty,
+        Err(err) => return err.to_responder(),
+    };
+
+    // return the product and quantity
+    product_response(&app_data, client_id, product_qty).await
+}
+
+// get_product_by_barcode is the handler to get a single product by one of its barcodes
+#[get("/v1/{client_id}/products/by-barcode/{code}")]
+pub async fn get_product_by_barcode(
+    app_data: web::Data<server::AppState>,
+    c_code: Path<ClientIdBarcode>,
+) -> impl Responder {
+    let client_id_barcode = c_code.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_barcode.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // retrieve the product and quantity from the service
+    let product_qty = match app_data
+        .service_manager
+        .product_service
+        .get_product_by_barcode(client_id, &client_id_barcode.code)
+        .await
+    {
+        Ok(product_qty) => product_qty,
+        Err(err) => return err.to_responder(),
+    };
+
+    // return the product and quantity
+    product_response(&app_data, client_id, product_qty).await
+}
+
+// product_response builds the response for a product with its stock at every location and the
+// bins it can be found in. The stock of a parent product is the stock of its variants, and a
+// bundle has the quantity its components can make
+async fn product_response(
+    app_data: &web::Data<server::AppState>,
+    client_id: ObjectId,
+    product_qty: (Product, Vec<Stock>),
+) -> HttpResponse {
+    let (product, stocks) = product_qty;
+
+    // retrieve the bins the product can be found in
+    let bins = match app_data
+        .service_manager
+        .bin_service
+        .get_bins_by_product(client_id, product._id)
+        .await
+    {
+        Ok(bins) => bins
+            .iter()
+            .map(|(bin, bin_stock)| ProductBinResponse::new(bin, bin_stock))
+            .collect(),
+        Err(err) => return err.to_responder(),
+    };
+
+    // retrieve the variants of a parent product, with their quantity across locations
+    let variants = match product.is_parent() {
+        true => match app_data
+            .service_manager
+            .product_service
+            .get_variants(client_id, product._id)
+            .await
+        {
+            Ok(variants) => variants
+                .iter()
+                .map(|variant| {
+                    let quantity = stocks
+                        .iter()
+                        .filter(|stock| stock.product_id == variant._id)
+                        .map(|stock| stock.get_quantity())
+                        .sum();
+                    VariantResponse::new(variant, quantity)
+                })
+                .collect(),
+            Err(err) => return err.to_responder(),
+        },
+        false => vec![],
+    };
+
+    let mut response = GetProductResponse::new(
+        &product,
+        StockLocationResponse::from_stocks(&stocks),
+        variants,
+        bins,
+    );
+    if product.is_bundle() {
+        response.quantity = match app_data
+            .service_manager
+            .product_service
+            .bundle_available(client_id, &product, None)
+            .await
+        {
+            Ok(quantity) => quantity,
+            Err(err) => return err.to_responder(),
+        };
+    }
+
     HttpResponse::Ok().json(APIResponse::success(
         "product retrieved successfully",
-        GetProductResponse::new(product_qty.0._id.to_hex(), product_qty.0.name, product_qty.0.description, product_qty.1),
+        response,
     ))
 }
 
-// get_product is the handler to get a single product
+// get_products_by_client is the handler to list a page of the products of a client
 #[get("/v1/{client_id}/products")]
 pub async fn get_products_by_client(
     app_data: web::Data<server::AppState>,
     c_id: Path<ClientId>,
+    query: Query<GetProductsRequest>,
+    parameters: Query<Vec<(String, String)>>,
 ) -> impl Responder {
+    // validate the request query
+    let list_query = match query.to_list_query() {
+        Ok(list_query) => list_query,
+        Err(err) => return err.to_responder(),
+    };
+
     // try converting the client_id from string to an objectId
     let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
         Ok(client_id) => client_id,
@@ -77,36 +220,44 @@ pub async fn get_products_by_client(
         }
     };
 
-    // retrieve the products from the service
-    let products_with_qty = match app_data
+    // retrieve the page of products from the service
+    let (products, next_cursor, total) = match app_data
         .service_manager
         .product_service
-        .get_products_by_client(client_id)
+        .get_products_by_client(
+            client_id,
+            list_query,
+            &product_dto::attribute_filters(&parameters),
+        )
         .await
     {
-        Ok(products_tup) => products_tup
-            .iter()
-            .map(|p| p.0.to_product_quantity_response(p.1))
-            .collect::<Vec<ProductQuantityResponse>>(),
+        Ok(page) => page,
         Err(err) => return err.to_responder(),
     };
 
     // return the products
     HttpResponse::Ok().json(APIResponse::success(
         "products retrieved successfully",
-        GetProductsQuantityResponse::new(products_with_qty),
+        GetProductsQuantityResponse::new(
+            products
+                .iter()
+                .map(|p| p.0.to_product_quantity_response(p.1))
+                .collect(),
+            next_cursor.as_ref(),
+            total,
+        ),
     ))
 }
 
-// add_product is the handler to add a product
-#[post("/v1/{client_id}/products")]
-pub async fn add_product(
+// search_products is the handler to search the products of a client by text
+#[get("/v1/{client_id}/products/search")]
+pub async fn search_products(
     app_data: web::Data<server::AppState>,
-    request: Json<AddProductRequest>,
     c_id: Path<ClientId>,
+    query: Query<SearchProductsRequest>,
 ) -> impl Responder {
-    // validate the request body
-    if let Err(err) = request.validate() {
+    // validate the request query
+    if let Err(err) = query.validate() {
         return err.to_responder();
     }
 
@@ -118,32 +269,110 @@ pub async fn add_product(
         }
     };
 
-    // build a new product object from the request
-    let mut product = Product::new(request.name.clone(), request.description.clone(), client_id);
-
-    // call the product service to handle creating the product
-    if let Err(err) = app_data
+    // search the products in the service
+    let hits = match app_data
         .service_manager
         .product_service
-        .create(&mut product, client_id, request.quantity)
+        .search_products(client_id, query.get_text(), query.get_limit())
         .await
     {
-        return err.to_responder();
+        Ok(hits) => hits,
+        Err(err) => return err.to_responder(),
     };
 
-    // return the product
     HttpResponse::Ok().json(APIResponse::success(
-        "product added successfully",
-        AddProductResponse::new(
-            product._id.to_hex(),
-            product.name.clone(),
-            product.description.clone(),
-            product.get_sku(),
-            request.quantity,
-        ),
+        "products retrieved successfully",
+        SearchProductsResponse::new(hits.iter().map(ProductSearchHitResponse::new).collect()),
     ))
 }
 
+// add_product is the handler to add a product
+#[post("/v1/{client_id}/products")]
+pub async fn add_product(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    request: Json<AddProductRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the warehouse id, the stock is not assigned to a warehouse if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    // run the creation at most once per idempotency key
+    idempotency::idempotent(&app_data, &req, client_id, &*request, async {
+        // the creation of the stock is made by the actor of the request
+        let source = MovementSource::new(&actor::actor(&req, client_id), None);
+
+        // build a new product object from the request
+        let mut product = Product::new(
+            request.name.clone(),
+            request.description.clone(),
+            request.category.clone(),
+            client_id,
+        );
+        product.barcodes = request.get_barcodes();
+        product.variant_axes = request.variant_axes.clone().unwrap_or_default();
+        product.attributes = request.attributes.clone().unwrap_or_default();
+        product.components = request.get_components();
+        product.reorder_point = request.reorder_point;
+        product.reorder_quantity = request.reorder_quantity;
+
+        // call the product service to handle creating the product and its variants
+        let variants = match app_data
+            .service_manager
+            .product_service
+            .create(
+                &mut product,
+                client_id,
+                request.sku.clone(),
+                request.quantity,
+                warehouse_id,
+                &source,
+            )
+            .await
+        {
+            Ok(variants) => variants,
+            Err(err) => return err.to_responder(),
+        };
+
+        // a bundle comes with how many its components can make
+        let quantity = match product.is_bundle() {
+            true => match app_data
+                .service_manager
+                .product_service
+                .bundle_available(client_id, &product, None)
+                .await
+            {
+                Ok(quantity) => quantity,
+                Err(err) => return err.to_responder(),
+            },
+            false => request.quantity,
+        };
+
+        // return the product
+        HttpResponse::Ok().json(APIResponse::success(
+            "product added successfully",
+            AddProductResponse::new(&product, &variants, quantity),
+        ))
+    })
+    .await
+}
+
 // update_product is the handler to update a product
 #[put("/v1/{client_id}/products/{product_id}")]
 pub async fn update_product(
@@ -151,10 +380,15 @@ pub async fn update_product(
     request: Json<UpdateProductRequest>,
     cp_id: Path<ClientIdProductId>,
 ) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
     let client_id_product_id = cp_id.into_inner();
 
     // validate the client id
-    let client_id = match ObjectId::from_str(&client_id_product_id.client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
@@ -162,7 +396,7 @@ pub async fn update_product(
     };
 
     // validate the product id
-    let product_id = match ObjectId::from_str(&&client_id_product_id.product_id.as_str()) {
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
         Ok(product_id) => product_id,
         Err(_) => {
             return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
@@ -173,7 +407,7 @@ pub async fn update_product(
     let product = match app_data
         .service_manager
         .product_service
-        .update_product(client_id, product_id, &*request)
+        .update_product(client_id, product_id, &request)
         .await
     {
         Ok(product) => product,
@@ -183,18 +417,14 @@ pub async fn update_product(
     // create and return the http response
     HttpResponse::Ok().json(APIResponse::success(
         "product updated successfully",
-        UpdateProductResponse::new(
-            product._id.to_hex(),
-            product.name.clone(),
-            product.description.clone(),
-            product.get_sku(),
-        ),
+        UpdateProductResponse::new(&product),
     ))
 }
 
 // set_product_quantity is the handler to set the quantity of a product
 #[put("/v1/{client_id}/products/{product_id}/quantity")]
 pub async fn set_product_quantity(
+    req: HttpRequest,
     app_data: web::Data<server::AppState>,
     request: Json<SetProductQuantityRequest>,
     cp_id: Path<ClientIdProductId>,
@@ -202,7 +432,7 @@ pub async fn set_product_quantity(
     let client_id_product_id = cp_id.into_inner();
 
     // validate the client id
-    let client_id = match ObjectId::from_str(&client_id_product_id.client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
@@ -210,28 +440,39 @@ pub async fn set_product_quantity(
     };
 
     // validate the product id
-    let product_id = match ObjectId::from_str(&&client_id_product_id.product_id.as_str()) {
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
         Ok(product_id) => product_id,
         Err(_) => {
             return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
         }
     };
 
-    // update the product using the service
-    let stock = match app_data
-        .service_manager
-        .product_service
-        .set_product_quantity(client_id, product_id, &*request)
-        .await
-    {
-        Ok(product) => product,
+    // validate the warehouse id, the stock not assigned to a warehouse is set if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
         Err(err) => return err.to_responder(),
     };
 
-    HttpResponse::Ok().json(APIResponse::success(
-        "product quantity set successfully",
-        SetProductQuantityResponse::new(stock.get_quantity()),
-    ))
+    // run the update at most once per idempotency key
+    idempotency::idempotent(&app_data, &req, client_id, &*request, async {
+        // update the product using the service
+        let source = MovementSource::new(&actor::actor(&req, client_id), None);
+        let stock = match app_data
+            .service_manager
+            .product_service
+            .set_product_quantity(client_id, product_id, &request, warehouse_id, &source)
+            .await
+        {
+            Ok(product) => product,
+            Err(err) => return err.to_responder(),
+        };
+
+        HttpResponse::Ok().json(APIResponse::success(
+            "product quantity set successfully",
+            SetProductQuantityResponse::new(stock.get_quantity()),
+        ))
+    })
+    .await
 }
 
 // check_availability checks the quantity availability of one product in the stock
@@ -244,7 +485,7 @@ pub async fn check_availability(
     let client_id_product_id = cp_id.into_inner();
 
     // validate the client id
-    let client_id = match ObjectId::from_str(&client_id_product_id.client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
@@ -252,17 +493,23 @@ pub async fn check_availability(
     };
 
     // validate the product id
-    let product_id = match ObjectId::from_str(&&client_id_product_id.product_id.as_str()) {
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
         Ok(product_id) => product_id,
         Err(_) => {
             return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
         }
     };
 
+    // validate the warehouse id, every warehouse is counted if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&query.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
     if let Err(err) = app_data
         .service_manager
         .product_service
-        .check_availability(client_id, product_id, query.number)
+        .check_availability(client_id, product_id, query.number, warehouse_id)
         .await
     {
         return match err.kind {
@@ -287,6 +534,7 @@ pub async fn check_availability(
 pub async fn check_multiple_availability(
     app_data: web::Data<server::AppState>,
     request: Json<Vec<ProductQuantityRequest>>,
+    query: Query<LocationQuery>,
     c_id: Path<ClientId>,
 ) -> impl Responder {
     // validate the client id
@@ -297,10 +545,16 @@ pub async fn check_multiple_availability(
         }
     };
 
+    // validate the warehouse id, every warehouse is counted if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&query.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
     if let Err(err) = app_data
         .service_manager
         .product_service
-        .check_multiple_availability(client_id, request.into_inner())
+        .check_multiple_availability(client_id, request.into_inner(), warehouse_id)
         .await
     {
         return match err.kind {
@@ -323,13 +577,14 @@ pub async fn check_multiple_availability(
 // delete_product deletes a product and its stock from the application
 #[delete("/v1/{client_id}/products/{product_id}")]
 pub async fn delete_product(
+    req: HttpRequest,
     app_data: web::Data<server::AppState>,
     cp_id: Path<ClientIdProductId>,
 ) -> impl Responder {
     let client_id_product_id = cp_id.into_inner();
 
     // validate the client id
-    let client_id = match ObjectId::from_str(&client_id_product_id.client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
@@ -337,7 +592,7 @@ pub async fn delete_product(
     };
 
     // validate the product id
-    let product_id = match ObjectId::from_str(&&client_id_product_id.product_id.as_str()) {
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
         Ok(product_id) => product_id,
         Err(_) => {
             return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
@@ -345,10 +600,11 @@ pub async fn delete_product(
     };
 
     // delete the product in the product service
+    let source = MovementSource::new(&actor::actor(&req, client_id), None);
     if let Err(err) = app_data
         .service_manager
         .product_service
-        .delete_product(client_id, product_id)
+        .delete_product(client_id, product_id, &source)
         .await
     {
         return err.to_responder();
@@ -360,41 +616,1329 @@ pub async fn delete_product(
     ))
 }
 
-// process_orders processes orders by decrementing their product quantity by the specified quantity
-#[post("/v1/{client_id}/orders")]
-pub async fn process_orders(
-    request: Json<Vec<ProductQuantityRequest>>,
-    c_id: Path<ClientId>,
+// get_product_movements is the handler to page through the stock movements of a product
+#[get("/v1/{client_id}/products/{product_id}/movements")]
+pub async fn get_product_movements(
     app_data: web::Data<server::AppState>,
+    cp_id: Path<ClientIdProductId>,
+    query: Query<GetStockMovementsRequest>,
 ) -> impl Responder {
+    // validate the request query
+    if let Err(err) = query.validate() {
+        return err.to_responder();
+    }
+
+    let client_id_product_id = cp_id.into_inner();
+
     // validate the client id
-    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+    let client_id = match ObjectId::from_str(client_id_product_id.client_id.as_str()) {
         Ok(client_id) => client_id,
         Err(_) => {
             return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
         }
     };
 
-    // process orders in the product service
-    if let Err(err) = app_data
+    // validate the product id
+    let product_id = match ObjectId::from_str(client_id_product_id.product_id.as_str()) {
+        Ok(product_id) => product_id,
+        Err(_) => {
+            return AppError::new("invalid product id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // retrieve the page of movements from the service
+    let (page, limit) = (query.get_page(), query.get_limit());
+    let (movements, total) = match app_data
         .service_manager
         .product_service
-        .process_orders(client_id, request.0)
+        .get_movements(client_id, product_id, page, limit)
         .await
     {
-        return match err.kind {
-            ErrorKind::FailedAction => err.to_responder(),
-            ErrorKind::NotFound => err.to_responder(),
-            _ => {
-                error!("Error processing orders");
-                AppError::new("cannot process orders", ErrorKind::InternalServerError)
-                    .to_responder()
+        Ok(movements_total) => movements_total,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "stock movements retrieved successfully",
+        GetStockMovementsResponse::new(
+            movements.iter().map(StockMovementResponse::new).collect(),
+            page,
+            limit,
+            total,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+
+    use crate::api::test_helpers::{add_named_product, add_product, app, assert_error, call};
+
+    #[test]
+    // test_add_product tests adding products, and that the envelope of the response carries the
+    // status code, message and the product
+    async fn test_add_product() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/products", client_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&uri)
+                .set_json(json!({"name": "Widget", "description": "A widget", "quantity": 5})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status_code"], 200);
+        assert_eq!(body["message"], "product added successfully");
+        assert!(ObjectId::parse_str(body["data"]["id"].as_str().unwrap()).is_ok());
+        assert_eq!(body["data"]["name"], "Widget");
+        assert_eq!(body["data"]["description"], "A widget");
+        assert_eq!(body["data"]["quantity"], 5);
+        assert!(!body["data"]["sku"].as_str().unwrap().is_empty());
+
+        // (client id, body, expected message)
+        let test_cases = vec![
+            (
+                client_id.clone(),
+                json!({"name": "Widget", "description": "A widget", "quantity": 0}),
+                "quantity cannot be less than 1",
+            ),
+            (
+                "not-an-id".to_string(),
+                json!({"name": "Widget", "description": "A widget", "quantity": 5}),
+                "invalid client id",
+            ),
+            (
+                client_id.clone(),
+                json!({"name": "Widget", "description": "A widget", "quantity": 5, "warehouse_id": "x"}),
+                "invalid warehouse id",
+            ),
+            (
+                client_id.clone(),
+                json!({"name": "Widget", "description": "A widget", "quantity": 5, "warehouse_id": ObjectId::new().to_hex()}),
+                "warehouse not found",
+            ),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/products", test_case.0))
+                    .set_json(test_case.1),
+            )
+            .await;
+            assert_error(response, test_case.2);
+        }
+    }
+
+    #[test]
+    // test_add_product_is_idempotent tests that a retried creation with the same idempotency key
+    // replays the first response instead of adding another product
+    async fn test_add_product_is_idempotent() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let request = || {
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .insert_header(("Idempotency-Key", "create-widget"))
+                .set_json(json!({"name": "Widget", "description": "A widget", "quantity": 5}))
+        };
+
+        let first = call(&app, request()).await;
+        let retry = call(&app, request()).await;
+        assert_eq!(first, retry);
+
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products", client_id)),
+        )
+        .await;
+        assert_eq!(
+            body["data"]["products_quantity"].as_array().unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    // test_get_product tests getting a product with its stock, and that the products of other
+    // clients cannot be found
+    async fn test_get_product() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 7).await;
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "product retrieved successfully");
+        assert_eq!(body["data"]["id"], product_id);
+        assert_eq!(body["data"]["quantity"], 7);
+        assert_eq!(
+            body["data"]["locations"],
+            json!([{"warehouse_id": null, "quantity": 7, "reserved": 0, "available": 7}])
+        );
+        assert_eq!(body["data"]["bins"], json!([]));
+
+        // (client id, product id, expected message)
+        let test_cases = vec![
+            (
+                client_id.clone(),
+                "not-an-id".to_string(),
+                "invalid product id",
+            ),
+            (
+                "not-an-id".to_string(),
+                product_id.clone(),
+                "invalid client id",
+            ),
+            (
+                client_id.clone(),
+                ObjectId::new().to_hex(),
+                "product not found",
+            ),
+            (
+                ObjectId::new().to_hex(),
+                product_id.clone(),
+                "product not found",
+            ),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/v1/{}/products/{}", test_case.0, test_case.1)),
+            )
+            .await;
+            assert_error(response, test_case.2);
+        }
+    }
+
+    #[test]
+    // test_get_product_by_sku tests getting a product by its sku, which only finds the products
+    // of the client
+    async fn test_get_product_by_sku() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 7).await;
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+        )
+        .await;
+        let sku = body["data"]["sku"].as_str().unwrap().to_string();
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/by-sku/{}", client_id, sku)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "product retrieved successfully");
+        assert_eq!(body["data"]["id"], product_id);
+        assert_eq!(body["data"]["sku"], sku);
+        assert_eq!(body["data"]["quantity"], 7);
+
+        // (client id, sku, expected message)
+        let test_cases = vec![
+            (
+                client_id.clone(),
+                "NONE-NONE".to_string(),
+                "product not found",
+            ),
+            (ObjectId::new().to_hex(), sku.clone(), "product not found"),
+            ("not-an-id".to_string(), sku.clone(), "invalid client id"),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::get().uri(&format!(
+                    "/v1/{}/products/by-sku/{}",
+                    test_case.0, test_case.1
+                )),
+            )
+            .await;
+            assert_error(response, test_case.2);
+        }
+    }
+
+    #[test]
+    // test_add_product_with_sku_settings tests that new products get the skus of the strategy of
+    // their client, and that a supplied sku is checked and kept
+    async fn test_add_product_with_sku_settings() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let products_uri = format!("/v1/{}/products", client_id);
+        let settings_uri = format!("/v1/{}/sku-settings", client_id);
+
+        // clients get random skus until they set a strategy
+        let (status, body) = call(&app, test::TestRequest::get().uri(&settings_uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["strategy"], json!({"type": "random"}));
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put().uri(&settings_uri).set_json(
+                json!({"strategy": {"type": "sequential", "prefix": "ACME", "width": 6}}),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "sku settings updated successfully");
+        assert_eq!(body["data"]["strategy"]["prefix"], "ACME");
+
+        let (_, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&products_uri)
+                .set_json(json!({"name": "Widget", "description": "A widget", "quantity": 1})),
+        )
+        .await;
+        assert_eq!(body["data"]["sku"], "ACME-000001");
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&products_uri)
+                .set_json(json!({
+                    "name": "Gadget",
+                    "description": "A gadget",
+                    "quantity": 1,
+                    "sku": "GADGET-1",
+                    "category": "Gadgets",
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["sku"], "GADGET-1");
+        assert_eq!(body["data"]["category"], "Gadgets");
+
+        // (body, expected message)
+        let test_cases = vec![
+            (
+                json!({"name": "Gadget", "description": "A gadget", "quantity": 1, "sku": "GADGET-1"}),
+                "sku is already used by another product",
+            ),
+            (
+                json!({"name": "Gadget", "description": "A gadget", "quantity": 1, "sku": "has space"}),
+                "invalid sku, a sku is 1 to 64 letters, digits, dashes, underscores or dots",
+            ),
+        ];
+        for (body, message) in test_cases {
+            assert_error(
+                call(
+                    &app,
+                    test::TestRequest::post().uri(&products_uri).set_json(body),
+                )
+                .await,
+                message,
+            );
+        }
+
+        assert_error(
+            call(
+                &app,
+                test::TestRequest::put().uri(&settings_uri).set_json(
+                    json!({"strategy": {"type": "sequential", "prefix": "ACME", "width": 0}}),
+                ),
+            )
+            .await,
+            "width must be between 1 and 12",
+        );
+    }
+
+    #[test]
+    // test_get_product_by_barcode tests that products are found by any form of their barcodes,
+    // and that a barcode cannot be used by two products of a client
+    async fn test_get_product_by_barcode() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let products_uri = format!("/v1/{}/products", client_id);
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&products_uri)
+                .set_json(json!({
+                    "name": "Widget",
+                    "description": "A widget",
+                    "quantity": 3,
+                    "barcodes": ["036000291452"],
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"]["barcodes"],
+            json!([{"code": "036000291452", "kind": "upc_a", "gtin": "00036000291452"}])
+        );
+        let product_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        // the UPC-A is found when written as an EAN-13
+        let uri = format!("/v1/{}/products/by-barcode/0036000291452", client_id);
+        let (status, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["id"], product_id);
+        assert_eq!(body["data"]["quantity"], 3);
+
+        let other_id = add_product(&app, &client_id, 1).await;
+        let other_uri = format!("/v1/{}/products/{}", client_id, other_id);
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put().uri(&other_uri).set_json(json!({
+                "name": "Gadget",
+                "description": "A gadget",
+                "barcodes": ["4006381333931"],
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["barcodes"][0]["kind"], "ean13");
+        let uri = format!("/v1/{}/products/by-barcode/4006381333931", client_id);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(body["data"]["id"], other_id);
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::post()
+                    .uri(&products_uri)
+                    .set_json(json!({
+                        "name": "Widget",
+                        "description": "A widget",
+                        "quantity": 1,
+                        "barcodes": ["00036000291452"],
+                    })),
+                "barcode 00036000291452 is already used by another product",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&products_uri)
+                    .set_json(json!({
+                        "name": "Widget",
+                        "description": "A widget",
+                        "quantity": 1,
+                        "barcodes": ["4006381333932"],
+                    })),
+                "invalid barcode: 4006381333932",
+            ),
+            (
+                test::TestRequest::put().uri(&other_uri).set_json(json!({
+                    "name": "Gadget",
+                    "description": "A gadget",
+                    "barcodes": ["96385074", "000096385074"],
+                })),
+                "barcode 000096385074 is given twice",
+            ),
+            (
+                test::TestRequest::put().uri(&other_uri).set_json(json!({
+                    "name": "Gadget",
+                    "description": "A gadget",
+                    "barcodes": ["036000291452"],
+                })),
+                "barcode 036000291452 is already used by another product",
+            ),
+            (
+                test::TestRequest::get()
+                    .uri(&format!("/v1/{}/products/by-barcode/96385074", client_id)),
+                "product not found",
+            ),
+            (
+                test::TestRequest::get()
+                    .uri(&format!("/v1/{}/products/by-barcode/12345", client_id)),
+                "invalid barcode",
+            ),
+        ];
+        for (req, message) in test_cases {
+            assert_error(call(&app, req).await, message);
+        }
+    }
+
+    #[test]
+    // test_add_product_with_variants tests that a product with variant axes gets a variant with
+    // its own sku and stock for every combination of values, that the parent is listed with the
+    // total quantity of its variants, and that stock is only changed through the variants
+    async fn test_add_product_with_variants() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "T-shirt",
+                    "description": "A T-shirt",
+                    "quantity": 5,
+                    "sku": "TSHIRT",
+                    "variant_axes": [
+                        {"name": "size", "values": ["S", "M"]},
+                        {"name": "colour", "values": ["Red", "Navy blue"]},
+                    ],
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quantity"], 20);
+        let variants = body["data"]["variants"].as_array().unwrap();
+        let skus: Vec<&str> = variants
+            .iter()
+            .map(|variant| variant["sku"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            skus,
+            vec![
+                "TSHIRT-S-RED",
+                "TSHIRT-S-NAVYBLUE",
+                "TSHIRT-M-RED",
+                "TSHIRT-M-NAVYBLUE"
+            ]
+        );
+        assert_eq!(variants[1]["name"], "T-shirt (S, Navy blue)");
+        assert_eq!(
+            variants[1]["options"],
+            json!({"size": "S", "colour": "Navy blue"})
+        );
+        let parent_id = body["data"]["id"].as_str().unwrap().to_string();
+        let variant_id = variants[0]["id"].as_str().unwrap().to_string();
+
+        // orders address the variants directly
+        let (status, _) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders", client_id))
+                .set_json(json!([{"product_id": variant_id, "quantity": 2}])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // only the parent is listed, with the total quantity of its variants
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products", client_id)),
+        )
+        .await;
+        let products = body["data"]["products_quantity"].as_array().unwrap();
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0]["id"], parent_id);
+        assert_eq!(products[0]["quantity"], 18);
+        assert_eq!(products[0]["has_variants"], true);
+
+        let parent_uri = format!("/v1/{}/products/{}", client_id, parent_id);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&parent_uri)).await;
+        assert_eq!(body["data"]["quantity"], 18);
+        assert_eq!(body["data"]["variants"][0]["quantity"], 3);
+        assert_eq!(body["data"]["locations"].as_array().unwrap().len(), 1);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, variant_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["parent_id"], parent_id);
+        assert_eq!(body["data"]["quantity"], 3);
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::get().uri(&format!("{}/availability?number=1", parent_uri)),
+                "product has variants, use the id of one of its variants",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&format!("{}/quantity", parent_uri))
+                    .set_json(json!({"quantity": 1})),
+                "product has variants, use the id of one of its variants",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/products", client_id))
+                    .set_json(json!({
+                        "name": "Hat",
+                        "description": "A hat",
+                        "quantity": 1,
+                        "variant_axes": [{"name": "size", "values": ["M", "m"]}],
+                    })),
+                "variant value m of axis size is given twice",
+            ),
+        ];
+        for (req, message) in test_cases {
+            assert_error(call(&app, req).await, message);
+        }
+
+        // the variants are deleted with their parent
+        let (status, _) = call(&app, test::TestRequest::delete().uri(&parent_uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_error(
+            call(
+                &app,
+                test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, variant_id)),
+            )
+            .await,
+            "product not found",
+        );
+    }
+
+    #[test]
+    // test_get_products_by_client tests that a client only gets its own products, oldest first
+    async fn test_get_products_by_client() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_ids = [
+            add_product(&app, &client_id, 1).await,
+            add_product(&app, &client_id, 2).await,
+        ];
+        add_product(&app, &ObjectId::new().to_hex(), 3).await;
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products", client_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "products retrieved successfully");
+        assert_eq!(body["data"]["total"], 2);
+        assert_eq!(body["data"]["next_cursor"], Value::Null);
+        let products: Vec<(&str, i64)> = body["data"]["products_quantity"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p["id"].as_str().unwrap(), p["quantity"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(
+            products,
+            vec![(product_ids[0].as_str(), 1), (product_ids[1].as_str(), 2)]
+        );
+
+        let response = call(&app, test::TestRequest::get().uri("/v1/not-an-id/products")).await;
+        assert_error(response, "invalid client id");
+    }
+
+    #[test]
+    // test_get_products_by_client_pages_sorts_and_filters tests following the cursors of a
+    // listing, and that the filters apply to the products and their total count
+    async fn test_get_products_by_client_pages_sorts_and_filters() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        for (name, quantity) in [("Apple", 5), ("Banana", 1), ("Cherry", 9), ("Apricot", 3)] {
+            add_named_product(&app, &client_id, name, quantity).await;
+        }
+        let banana_id = add_named_product(&app, &client_id, "Banana split", 2).await;
+        call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!(
+                    "/v1/{}/products/{}/quantity",
+                    client_id, banana_id
+                ))
+                .set_json(json!({"quantity": -2})),
+        )
+        .await;
+
+        // list returns the names of a page of products, the cursor of the next page and the total
+        let list = |query: String| {
+            let app = &app;
+            let uri = format!("/v1/{}/products?{}", client_id, query);
+            async move {
+                let (status, body) = call(app, test::TestRequest::get().uri(&uri)).await;
+                assert_eq!(status, StatusCode::OK);
+                let names: Vec<String> = body["data"]["products_quantity"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["name"].as_str().unwrap().to_string())
+                    .collect();
+                let next_cursor = body["data"]["next_cursor"].as_str().map(String::from);
+                (names, next_cursor, body["data"]["total"].as_u64().unwrap())
+            }
+        };
+
+        // follow the cursors through every page
+        let mut names = vec![];
+        let mut query = "sort=quantity&order=desc&limit=2".to_string();
+        loop {
+            let page = list(query.clone()).await;
+            assert_eq!(page.2, 5);
+            names.extend(page.0);
+            match page.1 {
+                Some(cursor) => {
+                    query = format!("sort=quantity&order=desc&limit=2&cursor={}", cursor)
+                }
+                None => break,
+            }
+        }
+        assert_eq!(
+            names,
+            vec!["Cherry", "Apple", "Apricot", "Banana", "Banana split"]
+        );
+
+        // (query, expected names, expected total)
+        let test_cases = vec![
+            ("sort=name&name_prefix=Ap", vec!["Apple", "Apricot"], 2),
+            ("min_quantity=2&max_quantity=6", vec!["Apple", "Apricot"], 2),
+            ("out_of_stock=true", vec!["Banana split"], 1),
+            (
+                "out_of_stock=false&sort=name&order=desc&limit=1",
+                vec!["Cherry"],
+                4,
+            ),
+        ];
+        for test_case in test_cases {
+            let page = list(test_case.0.to_string()).await;
+            assert_eq!(page.0, test_case.1);
+            assert_eq!(page.2, test_case.2);
+        }
+
+        // (query, expected message)
+        let test_cases = vec![
+            ("cursor=zz", "invalid cursor"),
+            ("limit=0", "limit must be between 1 and 100"),
+            (
+                "min_quantity=5&max_quantity=2",
+                "min_quantity cannot be greater than max_quantity",
+            ),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/v1/{}/products?{}", client_id, test_case.0)),
+            )
+            .await;
+            assert_error(response, test_case.1);
+        }
+    }
+
+    #[test]
+    // test_search_products tests that searched words are ranked by the field they are found in,
+    // and that products are found by the start of their name or sku
+    async fn test_search_products() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let products = [
+            ("Blue Gadget", "Blue with red accents"),
+            ("Red Widget", "A small widget"),
+            ("Widget stand", "Holds a widget"),
+        ];
+        for (name, description) in products {
+            call(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!("/v1/{}/products", client_id))
+                    .set_json(json!({"name": name, "description": description, "quantity": 4})),
+            )
+            .await;
+        }
+        add_named_product(&app, &ObjectId::new().to_hex(), "Red Widget", 1).await;
+
+        // search returns the names and quantities of the products found for a text
+        let search = |text: &str| {
+            let app = &app;
+            let uri = format!("/v1/{}/products/search?q={}", client_id, text);
+            async move {
+                let (status, body) = call(app, test::TestRequest::get().uri(&uri)).await;
+                assert_eq!(status, StatusCode::OK);
+                assert_eq!(body["message"], "products retrieved successfully");
+                body["data"]["products"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| {
+                        (
+                            p["name"].as_str().unwrap().to_string(),
+                            p["quantity"].as_i64().unwrap(),
+                            p["sku"].as_str().unwrap().to_string(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
             }
         };
+
+        // (text, expected names)
+        let test_cases = vec![
+            ("red", vec!["Red Widget", "Blue Gadget"]),
+            ("RED%20gadget", vec!["Blue Gadget", "Red Widget"]),
+            ("widg", vec!["Widget stand"]),
+            // one-letter words are not matched, though "a" is in two descriptions
+            ("a%20nothing", vec![]),
+            ("nothing", vec![]),
+        ];
+        for test_case in test_cases {
+            let hits = search(test_case.0).await;
+            let names: Vec<&str> = hits.iter().map(|hit| hit.0.as_str()).collect();
+            assert_eq!(names, test_case.1);
+            assert!(hits.iter().all(|hit| hit.1 == 4));
+        }
+
+        // a product is found by its sku, or the first group of it
+        let sku = search("stand").await[0].2.clone();
+        for text in [sku.as_str(), &sku[..4]] {
+            let names: Vec<String> = search(text).await.into_iter().map(|hit| hit.0).collect();
+            assert_eq!(names, vec!["Widget stand"]);
+        }
+
+        let response = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/search?q=%20", client_id)),
+        )
+        .await;
+        assert_error(response, "search text cannot be empty");
     }
 
-    HttpResponse::Ok().json(APIResponse::success(
-        "order processed successfully",
-        None::<String>,
-    ))
+    #[test]
+    // test_update_product tests updating the name and description of a product
+    async fn test_update_product() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 1).await;
+        let update = json!({"name": "Gadget", "description": "A gadget"});
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("/v1/{}/products/{}", client_id, product_id))
+                .set_json(&update),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "product updated successfully");
+        assert_eq!(body["data"]["id"], product_id);
+        assert_eq!(body["data"]["name"], "Gadget");
+        assert_eq!(body["data"]["description"], "A gadget");
+
+        // (client id, product id, expected message)
+        let test_cases = vec![
+            (
+                client_id.clone(),
+                "not-an-id".to_string(),
+                "invalid product id",
+            ),
+            (
+                "not-an-id".to_string(),
+                product_id.clone(),
+                "invalid client id",
+            ),
+            (
+                client_id.clone(),
+                ObjectId::new().to_hex(),
+                "product not found",
+            ),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::put()
+                    .uri(&format!("/v1/{}/products/{}", test_case.0, test_case.1))
+                    .set_json(&update),
+            )
+            .await;
+            assert_error(response, test_case.2);
+        }
+    }
+
+    #[test]
+    // test_set_product_quantity tests adding to and taking from the quantity of a product
+    async fn test_set_product_quantity() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+        let uri = format!("/v1/{}/products/{}/quantity", client_id, product_id);
+
+        // (quantity to add, expected quantity)
+        let test_cases = vec![(3, 8), (-6, 2)];
+        for test_case in test_cases {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::put()
+                    .uri(&uri)
+                    .set_json(json!({"quantity": test_case.0})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["message"], "product quantity set successfully");
+            assert_eq!(body["data"]["quantity"], test_case.1);
+        }
+
+        // more than is in stock cannot be taken out
+        let response = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&uri)
+                .set_json(json!({"quantity": -3})),
+        )
+        .await;
+        assert_error(response, "product is low in stock");
+
+        let response = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!(
+                    "/v1/{}/products/{}/quantity",
+                    client_id,
+                    ObjectId::new()
+                ))
+                .set_json(json!({"quantity": 1})),
+        )
+        .await;
+        assert_error(response, "product not found");
+
+        let response = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("/v1/{}/products/not-an-id/quantity", client_id))
+                .set_json(json!({"quantity": 1})),
+        )
+        .await;
+        assert_error(response, "invalid product id");
+    }
+
+    #[test]
+    // test_check_availability tests checking the available quantity of one product
+    async fn test_check_availability() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!(
+                "/v1/{}/products/{}/availability?number=5",
+                client_id, product_id
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "product is available in requested number");
+        assert_eq!(body["data"], Value::Null);
+
+        // (product id, expected message)
+        let test_cases = vec![
+            (
+                product_id.clone(),
+                "product quantity is less than requested number",
+            ),
+            (ObjectId::new().to_hex(), "product not found"),
+            ("not-an-id".to_string(), "invalid product id"),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::get().uri(&format!(
+                    "/v1/{}/products/{}/availability?number=6",
+                    client_id, test_case.0
+                )),
+            )
+            .await;
+            assert_error(response, test_case.1);
+        }
+    }
+
+    #[test]
+    // test_check_multiple_availability tests checking the available quantities of several
+    // products at once, which fails as a whole when any line fails
+    async fn test_check_multiple_availability() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let first_id = add_product(&app, &client_id, 5).await;
+        let second_id = add_product(&app, &client_id, 2).await;
+        let uri = format!("/v1/{}/products/availability", client_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!([
+                {"product_id": first_id, "quantity": 5},
+                {"product_id": second_id, "quantity": 2},
+            ])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["message"],
+            "all products are available in their requested number"
+        );
+
+        // (lines, expected message)
+        let missing_id = ObjectId::new();
+        let test_cases = vec![
+            (
+                json!([{"product_id": first_id, "quantity": 1}, {"product_id": first_id, "quantity": 1}]),
+                format!(
+                    "duplicate order with product_id: {:?}",
+                    ObjectId::parse_str(&first_id).unwrap()
+                ),
+            ),
+            (
+                json!([{"product_id": first_id, "quantity": 1}, {"product_id": second_id, "quantity": 3}]),
+                "product quantity is less than requested number".to_string(),
+            ),
+            (
+                json!([{"product_id": first_id, "quantity": 1}, {"product_id": missing_id.to_hex(), "quantity": 1}]),
+                "product not found".to_string(),
+            ),
+            (
+                json!([{"product_id": "not-an-id", "quantity": 1}]),
+                "invalid product id: \"not-an-id\"".to_string(),
+            ),
+            (
+                json!([{"product_id": first_id, "quantity": 0}]),
+                format!(
+                    "quantity cannot be less than 1 for product_id: {:?}",
+                    ObjectId::parse_str(&first_id).unwrap()
+                ),
+            ),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::post().uri(&uri).set_json(test_case.0),
+            )
+            .await;
+            assert_error(response, &test_case.1);
+        }
+    }
+
+    #[test]
+    // test_delete_product tests that a deleted product and its stock are gone
+    async fn test_delete_product() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+        let uri = format!("/v1/{}/products/{}", client_id, product_id);
+
+        let (status, body) = call(&app, test::TestRequest::delete().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "product deleted successfully");
+
+        let response = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_error(response, "product not found");
+
+        let response = call(
+            &app,
+            test::TestRequest::delete().uri(&format!("/v1/{}/products/not-an-id", client_id)),
+        )
+        .await;
+        assert_error(response, "invalid product id");
+    }
+
+    #[test]
+    // test_get_product_movements tests paging through the stock movements of a product, newest
+    // first
+    async fn test_get_product_movements() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+        let uri = format!("/v1/{}/products/{}", client_id, product_id);
+        call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/quantity", uri))
+                .set_json(json!({"quantity": -2})),
+        )
+        .await;
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}/movements?limit=1", uri)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "stock movements retrieved successfully");
+        assert_eq!(body["data"]["page"], 1);
+        assert_eq!(body["data"]["limit"], 1);
+        assert_eq!(body["data"]["total"], 2);
+        let movements = body["data"]["movements"].as_array().unwrap();
+        assert_eq!(movements.len(), 1);
+        assert_eq!(movements[0]["delta"], -2);
+        assert_eq!(movements[0]["before"], 5);
+        assert_eq!(movements[0]["after"], 3);
+
+        // (query, expected message)
+        let test_cases = vec![
+            ("page=0", "page cannot be less than 1"),
+            ("limit=101", "limit must be between 1 and 100"),
+        ];
+        for test_case in test_cases {
+            let response = call(
+                &app,
+                test::TestRequest::get().uri(&format!("{}/movements?{}", uri, test_case.0)),
+            )
+            .await;
+            assert_error(response, test_case.1);
+        }
+    }
+
+    #[test]
+    // test_product_attributes tests that products take custom attributes, that the values of
+    // defined attributes must have their type, and that products are listed by attribute value
+    async fn test_product_attributes() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/products", client_id);
+        for (name, attribute_type) in [("weight", "number"), ("launched", "date")] {
+            let (status, _) = call(
+                &app,
+                test::TestRequest::put()
+                    .uri(&format!("/v1/{}/attributes/{}", client_id, name))
+                    .set_json(json!({"type": attribute_type})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let mut ids = vec![];
+        for (name, weight, material) in [("Kettle", 1.5, "steel"), ("Teapot", 0.8, "clay")] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::post().uri(&uri).set_json(json!({
+                    "name": name,
+                    "description": "A product",
+                    "quantity": 1,
+                    "attributes": {
+                        "weight": weight,
+                        "launched": "2024-3-1",
+                        "material": material,
+                        "fragile": material == "clay",
+                    },
+                })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["attributes"]["launched"], "2024-03-01");
+            ids.push(body["data"]["id"].as_str().unwrap().to_string());
+        }
+
+        // (query, expected products)
+        let test_cases = vec![
+            ("attr.material=clay", vec![1]),
+            ("attr.weight=1.5", vec![0]),
+            ("attr.fragile=false", vec![0]),
+            ("attr.launched=2024-03-01&attr.material=steel", vec![0]),
+            ("attr.material=glass", vec![]),
+        ];
+        for test_case in test_cases {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::get().uri(&format!("{}?{}", uri, test_case.0)),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let listed: Vec<&str> = body["data"]["products_quantity"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|product| product["id"].as_str().unwrap())
+                .collect();
+            let expected: Vec<&str> = test_case.1.iter().map(|i| ids[*i].as_str()).collect();
+            assert_eq!(listed, expected, "{}", test_case.0);
+        }
+
+        // the attributes are replaced by an update
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/{}", uri, ids[0]))
+                .set_json(json!({
+                    "name": "Kettle",
+                    "description": "A product",
+                    "attributes": {"weight": 2},
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["attributes"], json!({"weight": 2.0}));
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::post().uri(&uri).set_json(json!({
+                    "name": "Kettle",
+                    "description": "A product",
+                    "quantity": 1,
+                    "attributes": {"weight": "heavy"},
+                })),
+                "attribute weight must be a number",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&format!("{}/{}", uri, ids[1]))
+                    .set_json(json!({
+                        "name": "Teapot",
+                        "description": "A product",
+                        "attributes": {"launched": "March 2024"},
+                    })),
+                "attribute launched must be a date written as YYYY-MM-DD",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}?attr.weight=heavy", uri)),
+                "cannot filter attribute weight by heavy",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&format!("/v1/{}/attributes/a-b", client_id))
+                    .set_json(json!({"type": "string"})),
+                "invalid attribute name \"a-b\", a name is 1 to 64 letters, digits or underscores",
+            ),
+            (
+                test::TestRequest::delete().uri(&format!("/v1/{}/attributes/material", client_id)),
+                "attribute definition not found",
+            ),
+        ];
+        for test_case in test_cases {
+            assert_error(call(&app, test_case.0).await, test_case.1);
+        }
+    }
+
+    #[test]
+    // test_bundle_availability tests that a bundle is available as many times as its components
+    // can make it, and that ordering a bundle takes its components out of stock
+    async fn test_bundle_availability() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/products", client_id);
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 7).await;
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "name": "Gift box",
+                "description": "A mug and two teas",
+                "quantity": 0,
+                "components": [
+                    {"product_id": mug_id, "quantity": 1},
+                    {"product_id": tea_id, "quantity": 2},
+                ],
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quantity"], 3);
+        assert_eq!(body["data"]["components"][1]["product_id"], tea_id);
+        let bundle_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        // (request, expected status)
+        let test_cases = vec![
+            (
+                test::TestRequest::get()
+                    .uri(&format!("{}/{}/availability?number=3", uri, bundle_id)),
+                StatusCode::OK,
+            ),
+            (
+                test::TestRequest::get()
+                    .uri(&format!("{}/{}/availability?number=4", uri, bundle_id)),
+                StatusCode::BAD_REQUEST,
+            ),
+            // the teas of the bundles and of the other line come from the same stock
+            (
+                test::TestRequest::post()
+                    .uri(&format!("{}/availability", uri))
+                    .set_json(json!([
+                        {"product_id": bundle_id, "quantity": 2},
+                        {"product_id": tea_id, "quantity": 3},
+                    ])),
+                StatusCode::OK,
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&format!("{}/availability", uri))
+                    .set_json(json!([
+                        {"product_id": bundle_id, "quantity": 2},
+                        {"product_id": tea_id, "quantity": 4},
+                    ])),
+                StatusCode::BAD_REQUEST,
+            ),
+        ];
+        for test_case in test_cases {
+            assert_eq!(call(&app, test_case.0).await.0, test_case.1);
+        }
+
+        // ordering bundles takes their components out of stock
+        let order_uri = format!("/v1/{}/orders", client_id);
+        let (status, _) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&order_uri)
+                .set_json(json!([{"product_id": bundle_id, "quantity": 2}])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // an order that needs more of a component than is left changes nothing
+        let response = call(
+            &app,
+            test::TestRequest::post().uri(&order_uri).set_json(json!([
+                {"product_id": bundle_id, "quantity": 1},
+                {"product_id": tea_id, "quantity": 2},
+            ])),
+        )
+        .await;
+        assert_error(
+            response,
+            &format!(
+                "order line 1 with product_id: {} failed: product is low in stock. no stock was changed",
+                bundle_id
+            ),
+        );
+
+        // (product id, expected quantity)
+        let test_cases = vec![(&mug_id, 3), (&tea_id, 3), (&bundle_id, 1)];
+        for test_case in test_cases {
+            let (_, body) = call(
+                &app,
+                test::TestRequest::get().uri(&format!("{}/{}", uri, test_case.0)),
+            )
+            .await;
+            assert_eq!(body["data"]["quantity"], test_case.1, "{}", test_case.0);
+        }
+        let (_, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(body["data"]["products_quantity"][2]["quantity"], 1);
+        assert_eq!(body["data"]["products_quantity"][2]["is_bundle"], true);
+
+        // (request, expected message)
+        let bundle = |quantity: i32, component_id: &str| {
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "name": "Gift box",
+                "description": "A gift box",
+                "quantity": quantity,
+                "components": [{"product_id": component_id, "quantity": 1}],
+            }))
+        };
+        let missing_id = ObjectId::new().to_hex();
+        let test_cases = vec![
+            (
+                bundle(1, &mug_id),
+                "quantity of a bundle must be 0, its stock is held by its components".to_string(),
+            ),
+            (
+                bundle(0, &bundle_id),
+                format!(
+                    "component {} is a bundle, a bundle cannot be made of bundles",
+                    bundle_id
+                ),
+            ),
+            (
+                bundle(0, &missing_id),
+                format!("component {} not found", missing_id),
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&format!("{}/{}/quantity", uri, bundle_id))
+                    .set_json(json!({"quantity": 1})),
+                "product is a bundle, change the stock of its components".to_string(),
+            ),
+            (
+                test::TestRequest::delete().uri(&format!("{}/{}", uri, mug_id)),
+                format!(
+                    "product {} is a component of bundle {}, delete the bundle first",
+                    mug_id, bundle_id
+                ),
+            ),
+        ];
+        for test_case in test_cases {
+            assert_error(call(&app, test_case.0).await, &test_case.1);
+        }
+    }
 }
@@ -0,0 +1,319 @@
+use crate::{
+    api::actor,
+    dto::product::product_dto::ClientId,
+    dto::purchase_order::purchase_order_dto::{
+        ClientIdPurchaseOrderId, CreatePurchaseOrderRequest, GetPurchaseOrdersRequest,
+        GetPurchaseOrdersResponse, PurchaseOrderResponse, ReceivePurchaseOrderRequest,
+    },
+    dto::warehouse::warehouse_dto,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get, post,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// add_purchase_order is the handler to record the goods a client expects from a supplier
+#[post("/v1/{client_id}/purchase-orders")]
+pub async fn add_purchase_order(
+    app_data: web::Data<server::AppState>,
+    request: Json<CreatePurchaseOrderRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the warehouse id, the goods are not assigned to a warehouse if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    let request = request.into_inner();
+    let purchase_order = match app_data
+        .service_manager
+        .purchase_order_service
+        .create(client_id, request.reference, warehouse_id, request.lines)
+        .await
+    {
+        Ok(purchase_order) => purchase_order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "purchase order added successfully",
+        PurchaseOrderResponse::new(&purchase_order),
+    ))
+}
+
+// get_purchase_orders_by_client is the handler to get the purchase orders of a client,
+// optionally in one status
+#[get("/v1/{client_id}/purchase-orders")]
+pub async fn get_purchase_orders_by_client(
+    app_data: web::Data<server::AppState>,
+    query: Query<GetPurchaseOrdersRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let purchase_orders = match app_data
+        .service_manager
+        .purchase_order_service
+        .get_purchase_orders_by_client(client_id, query.status)
+        .await
+    {
+        Ok(purchase_orders) => purchase_orders,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "purchase orders retrieved successfully",
+        GetPurchaseOrdersResponse::new(&purchase_orders),
+    ))
+}
+
+// get_purchase_order is the handler to get a single purchase order
+#[get("/v1/{client_id}/purchase-orders/{purchase_order_id}")]
+pub async fn get_purchase_order(
+    app_data: web::Data<server::AppState>,
+    cp_id: Path<ClientIdPurchaseOrderId>,
+) -> impl Responder {
+    let (client_id, purchase_order_id) = match parse_client_id_purchase_order_id(cp_id.into_inner())
+    {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let purchase_order = match app_data
+        .service_manager
+        .purchase_order_service
+        .get_purchase_order(client_id, purchase_order_id)
+        .await
+    {
+        Ok(purchase_order) => purchase_order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "purchase order retrieved successfully",
+        PurchaseOrderResponse::new(&purchase_order),
+    ))
+}
+
+// receive_purchase_order is the handler to record the quantities that arrived for a purchase
+// order and add them to the stock
+#[post("/v1/{client_id}/purchase-orders/{purchase_order_id}/receive")]
+pub async fn receive_purchase_order(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    request: Json<ReceivePurchaseOrderRequest>,
+    cp_id: Path<ClientIdPurchaseOrderId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, purchase_order_id) = match parse_client_id_purchase_order_id(cp_id.into_inner())
+    {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    // validate the warehouse id, the goods go to the location of the purchase order if none
+    // is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    let request = request.into_inner();
+    let purchase_order = match app_data
+        .service_manager
+        .purchase_order_service
+        .receive(
+            client_id,
+            purchase_order_id,
+            request.lines,
+            warehouse_id,
+            request.last,
+            &actor::actor(&req, client_id),
+        )
+        .await
+    {
+        Ok(purchase_order) => purchase_order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "purchase order received successfully",
+        PurchaseOrderResponse::new(&purchase_order),
+    ))
+}
+
+// close_purchase_order is the handler to close a purchase order without receiving what is still
+// expected
+#[post("/v1/{client_id}/purchase-orders/{purchase_order_id}/close")]
+pub async fn close_purchase_order(
+    app_data: web::Data<server::AppState>,
+    cp_id: Path<ClientIdPurchaseOrderId>,
+) -> impl Responder {
+    let (client_id, purchase_order_id) = match parse_client_id_purchase_order_id(cp_id.into_inner())
+    {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let purchase_order = match app_data
+        .service_manager
+        .purchase_order_service
+        .close(client_id, purchase_order_id)
+        .await
+    {
+        Ok(purchase_order) => purchase_order,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "purchase order closed successfully",
+        PurchaseOrderResponse::new(&purchase_order),
+    ))
+}
+
+// parse_client_id_purchase_order_id converts the client id and purchase order id of a path to
+// objectIds
+fn parse_client_id_purchase_order_id(
+    ids: ClientIdPurchaseOrderId,
+) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let purchase_order_id = ObjectId::from_str(ids.purchase_order_id.as_str())
+        .map_err(|_| AppError::new("invalid purchase order id", ErrorKind::FailedAction))?;
+    Ok((client_id, purchase_order_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::api::test_helpers::{add_product, app, assert_errors, call};
+
+    #[test]
+    // test_purchase_orders tests that the goods of a purchase order are received in several
+    // deliveries, that over-receipts are reported as discrepancies and that the received
+    // quantities are added to the stock
+    async fn test_purchase_orders() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+        let uri = format!("/v1/{}/purchase-orders", client_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "reference": "PO-1",
+                "lines": [{"product_id": product_id, "quantity": 10}]
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "purchase order added successfully");
+        assert_eq!(body["data"]["status"], "open");
+        let purchase_order_id = body["data"]["id"].as_str().unwrap().to_string();
+        let po_uri = format!("{}/{}", uri, purchase_order_id);
+
+        // a partial delivery leaves the purchase order open for the rest
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("{}/receive", po_uri))
+                .set_json(json!({"lines": [{"product_id": product_id, "quantity": 6}]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "purchase order received successfully");
+        assert_eq!(body["data"]["status"], "partially_received");
+        assert_eq!(body["data"]["discrepancies"][0]["discrepancy"], -4);
+
+        // receiving more than expected closes the purchase order with a discrepancy
+        let (_, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("{}/receive", po_uri))
+                .set_json(json!({"lines": [{"product_id": product_id, "quantity": 5}]})),
+        )
+        .await;
+        assert_eq!(body["data"]["status"], "closed");
+        assert_eq!(body["data"]["lines"][0]["received"], 11);
+        assert_eq!(body["data"]["discrepancies"][0]["discrepancy"], 1);
+        assert_eq!(body["data"]["receipts"].as_array().unwrap().len(), 2);
+
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["quantity"], 16);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}?status=closed", uri)),
+        )
+        .await;
+        assert_eq!(body["data"]["purchase_orders"][0]["id"], purchase_order_id);
+
+        // (uri, request body, expected message)
+        let other_id = ObjectId::new().to_hex();
+        let test_cases = vec![
+            (
+                format!("{}/receive", po_uri),
+                json!({"lines": [{"product_id": product_id, "quantity": 1}]}),
+                "purchase order is closed",
+            ),
+            (
+                uri.clone(),
+                json!({"lines": []}),
+                "purchase order must have at least one line",
+            ),
+            (
+                format!("{}/{}/receive", uri, other_id),
+                json!({"lines": [{"product_id": product_id, "quantity": 1}]}),
+                "purchase order not found",
+            ),
+            (
+                format!("{}/x/close", uri),
+                json!({}),
+                "invalid purchase order id",
+            ),
+        ];
+        let test_cases = test_cases
+            .into_iter()
+            .map(|(uri, body, message)| {
+                (test::TestRequest::post().uri(&uri).set_json(body), message)
+            })
+            .collect();
+        assert_errors(&app, test_cases).await;
+    }
+}
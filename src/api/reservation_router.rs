@@ -0,0 +1,428 @@
+use crate::{
+    api::actor,
+    dto::product::product_dto::ClientId,
+    dto::reservation::reservation_dto::{
+        ClientIdReservationId, ReservationResponse, ReserveRequest,
+    },
+    dto::warehouse::warehouse_dto,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get, post,
+    web::{self, Json, Path},
+    HttpRequest, HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// reserve is the handler to hold stock for a reservation
+#[post("/v1/{client_id}/reservations")]
+pub async fn reserve(
+    app_data: web::Data<server::AppState>,
+    request: Json<ReserveRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the warehouse id, the stock is held across warehouses if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    let request = request.into_inner();
+    let reservation = match app_data
+        .service_manager
+        .reservation_service
+        .reserve(client_id, request.lines, request.ttl_seconds, warehouse_id)
+        .await
+    {
+        Ok(reservation) => reservation,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "stock reserved successfully",
+        ReservationResponse::new(&reservation),
+    ))
+}
+
+// get_reservation is the handler to get a single reservation
+#[get("/v1/{client_id}/reservations/{reservation_id}")]
+pub async fn get_reservation(
+    app_data: web::Data<server::AppState>,
+    cr_id: Path<ClientIdReservationId>,
+) -> impl Responder {
+    let client_id_reservation_id = cr_id.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_reservation_id.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the reservation id
+    let reservation_id =
+        match ObjectId::from_str(client_id_reservation_id.reservation_id.as_str()) {
+            Ok(reservation_id) => reservation_id,
+            Err(_) => {
+                return AppError::new("invalid reservation id", ErrorKind::FailedAction)
+                    .to_responder()
+            }
+        };
+
+    let reservation = match app_data
+        .service_manager
+        .reservation_service
+        .get_reservation(client_id, reservation_id)
+        .await
+    {
+        Ok(reservation) => reservation,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "reservation retrieved successfully",
+        ReservationResponse::new(&reservation),
+    ))
+}
+
+// commit_reservation is the handler to take the stock held by a reservation out of the stock
+#[post("/v1/{client_id}/reservations/{reservation_id}/commit")]
+pub async fn commit_reservation(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    cr_id: Path<ClientIdReservationId>,
+) -> impl Responder {
+    let client_id_reservation_id = cr_id.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_reservation_id.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the reservation id
+    let reservation_id =
+        match ObjectId::from_str(client_id_reservation_id.reservation_id.as_str()) {
+            Ok(reservation_id) => reservation_id,
+            Err(_) => {
+                return AppError::new("invalid reservation id", ErrorKind::FailedAction)
+                    .to_responder()
+            }
+        };
+
+    let reservation = match app_data
+        .service_manager
+        .reservation_service
+        .commit(client_id, reservation_id, &actor::actor(&req, client_id))
+        .await
+    {
+        Ok((reservation, _)) => reservation,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "reservation committed successfully",
+        ReservationResponse::new(&reservation),
+    ))
+}
+
+// release_reservation is the handler to give the stock held by a reservation back to the stock
+#[post("/v1/{client_id}/reservations/{reservation_id}/release")]
+pub async fn release_reservation(
+    app_data: web::Data<server::AppState>,
+    cr_id: Path<ClientIdReservationId>,
+) -> impl Responder {
+    let client_id_reservation_id = cr_id.into_inner();
+
+    // validate the client id
+    let client_id = match ObjectId::from_str(client_id_reservation_id.client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the reservation id
+    let reservation_id =
+        match ObjectId::from_str(client_id_reservation_id.reservation_id.as_str()) {
+            Ok(reservation_id) => reservation_id,
+            Err(_) => {
+                return AppError::new("invalid reservation id", ErrorKind::FailedAction)
+                    .to_responder()
+            }
+        };
+
+    let reservation = match app_data
+        .service_manager
+        .reservation_service
+        .release(client_id, reservation_id)
+        .await
+    {
+        Ok(reservation) => reservation,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "reservation released successfully",
+        ReservationResponse::new(&reservation),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+    use std::time::Duration;
+
+    use crate::api::test_helpers::{
+        add_named_product, app, app_with, assert_error, assert_errors, call, services,
+    };
+
+    #[test]
+    // test_commit_failure_keeps_reservation tests that a commit failing part way gives back and
+    // holds again the quantities it already took, and leaves the reservation active
+    async fn test_commit_failure_keeps_reservation() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 3).await;
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/reservations", client_id))
+                .set_json(json!({"lines": [
+                    {"product_id": mug_id, "quantity": 2},
+                    {"product_id": tea_id, "quantity": 1},
+                ]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let uri = format!(
+            "/v1/{}/reservations/{}",
+            client_id,
+            body["data"]["id"].as_str().unwrap()
+        );
+
+        // the stock of the tea is deleted with it, so only the mugs can be committed
+        let (status, _) = call(
+            &app,
+            test::TestRequest::delete().uri(&format!("/v1/{}/products/{}", client_id, tea_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/commit", uri)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (status, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "active");
+        assert_eq!(body["data"]["order_id"], json!(null));
+        let mug_uri = format!("/v1/{}/products/{}", client_id, mug_id);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(
+            body["data"]["locations"],
+            json!([{"warehouse_id": null, "quantity": 5, "reserved": 2, "available": 3}])
+        );
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}/movements", mug_uri)),
+        )
+        .await;
+        let movements: Vec<(&str, i64)> = body["data"]["movements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|movement| {
+                (
+                    movement["reason"].as_str().unwrap(),
+                    movement["delta"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            movements,
+            vec![
+                ("reservation_commit_rollback", 2),
+                ("reservation_commit", -2),
+                ("create", 5),
+            ]
+        );
+    }
+
+    #[test]
+    // test_reservation_lifecycle tests that a reservation holds stock until it is committed
+    // into an order, released, or released by the sweep once it expired, and that it only
+    // leaves the active status once
+    async fn test_reservation_lifecycle() {
+        let services = services();
+        let reservation_service = services.reservation_service.clone();
+        let app = app_with(services).await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+        let uri = format!("/v1/{}/reservations", client_id);
+        let mug_uri = format!("/v1/{}/products/{}", client_id, mug_id);
+        let reserve = |quantity: i32, ttl_seconds: Option<i64>| {
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "lines": [{"product_id": mug_id, "quantity": quantity}],
+                "ttl_seconds": ttl_seconds,
+            }))
+        };
+        let location = |reserved: i32, available: i32| {
+            json!([{
+                "warehouse_id": null,
+                "quantity": reserved + available,
+                "reserved": reserved,
+                "available": available,
+            }])
+        };
+
+        // a reservation holds the stock without taking it out
+        let (status, body) = call(&app, reserve(2, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "active");
+        assert_eq!(body["data"]["lines"][0]["quantity"], 2);
+        let committed_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(body["data"]["locations"], location(2, 3));
+
+        // committing takes the held stock out for an order
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/{}/commit", uri, committed_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "committed");
+        let (status, order) = call(
+            &app,
+            test::TestRequest::get().uri(&format!(
+                "/v1/{}/orders/{}",
+                client_id,
+                body["data"]["order_id"].as_str().unwrap()
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(order["data"]["lines"][0]["product_id"], mug_id);
+        assert_eq!(order["data"]["lines"][0]["quantity"], 2);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(body["data"]["locations"], location(0, 3));
+
+        // releasing gives the held stock back
+        let (status, body) = call(&app, reserve(1, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let released_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/{}/release", uri, released_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "released");
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(body["data"]["locations"], location(0, 3));
+
+        // the sweep gives back the stock of the reservations that expired, and only those
+        let (status, body) = call(&app, reserve(1, Some(1))).await;
+        assert_eq!(status, StatusCode::OK);
+        let expired_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (status, body) = call(&app, reserve(1, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let active_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (status, body) = call(&app, reserve(1, Some(1))).await;
+        assert_eq!(status, StatusCode::OK);
+        let unswept_id = body["data"]["id"].as_str().unwrap().to_string();
+        actix_rt::time::sleep(Duration::from_millis(1100)).await;
+        // an expired reservation the sweep has not reached cannot be committed, and gives its
+        // stock back instead
+        let response = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/{}/commit", uri, unswept_id)),
+        )
+        .await;
+        assert_error(response, "reservation has expired");
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(body["data"]["locations"], location(2, 1));
+        reservation_service.release_expired().await.unwrap();
+        for (reservation_id, expected_status) in [
+            (&expired_id, "expired"),
+            (&unswept_id, "expired"),
+            (&active_id, "active"),
+        ] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::get().uri(&format!("{}/{}", uri, reservation_id)),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["status"], expected_status);
+        }
+        let (_, body) = call(&app, test::TestRequest::get().uri(&mug_uri)).await;
+        assert_eq!(body["data"]["locations"], location(1, 2));
+
+        // a reservation out of the active status can neither be committed nor released
+        for reservation_id in [&committed_id, &released_id, &expired_id, &unswept_id] {
+            for action in ["commit", "release"] {
+                let response = call(
+                    &app,
+                    test::TestRequest::post()
+                        .uri(&format!("{}/{}/{}", uri, reservation_id, action)),
+                )
+                .await;
+                assert_error(response, "reservation is not active");
+            }
+        }
+
+        // (request, expected message)
+        let low_in_stock = format!(
+            "reservation line 1 with product_id: {} failed: product quantity is less than requested number. no stock was reserved",
+            mug_id
+        );
+        let test_cases = vec![
+            (reserve(3, None), low_in_stock.as_str()),
+            (
+                test::TestRequest::post()
+                    .uri(&uri)
+                    .set_json(json!({"lines": []})),
+                "reservation must have at least one line",
+            ),
+            (
+                reserve(1, Some(0)),
+                "ttl_seconds must be between 1 and 86400",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/x", uri)),
+                "invalid reservation id",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/{}", uri, ObjectId::new().to_hex())),
+                "reservation not found",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    api::actor,
+    dto::product::product_dto::ClientId,
+    dto::return_authorization::return_dto::{
+        AuthorizeReturnRequest, ClientIdReturnId, GetReturnsRequest, GetReturnsResponse,
+        ReceiveReturnRequest, ReturnResponse,
+    },
+    dto::warehouse::warehouse_dto,
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get, post,
+    web::{self, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// authorize_return is the handler to authorize a customer to return goods of a past order
+#[post("/v1/{client_id}/returns")]
+pub async fn authorize_return(
+    app_data: web::Data<server::AppState>,
+    request: Json<AuthorizeReturnRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the order id
+    let request = request.into_inner();
+    let order_id = match ObjectId::from_str(request.order_id.as_str()) {
+        Ok(order_id) => order_id,
+        Err(_) => return AppError::new("invalid order id", ErrorKind::FailedAction).to_responder(),
+    };
+
+    let return_authorization = match app_data
+        .service_manager
+        .return_service
+        .authorize(client_id, order_id, request.reason, request.lines)
+        .await
+    {
+        Ok(return_authorization) => return_authorization,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "return authorized successfully",
+        ReturnResponse::new(&return_authorization),
+    ))
+}
+
+// get_returns_by_client is the handler to get the returns of a client, optionally of one order
+#[get("/v1/{client_id}/returns")]
+pub async fn get_returns_by_client(
+    app_data: web::Data<server::AppState>,
+    query: Query<GetReturnsRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // validate the order id if the returns of one order were requested
+    let order_id = match &query.order_id {
+        Some(order_id) => match ObjectId::from_str(order_id.as_str()) {
+            Ok(order_id) => Some(order_id),
+            Err(_) => {
+                return AppError::new("invalid order id", ErrorKind::FailedAction).to_responder()
+            }
+        },
+        None => None,
+    };
+
+    let returns = match app_data
+        .service_manager
+        .return_service
+        .get_returns_by_client(client_id, order_id)
+        .await
+    {
+        Ok(returns) => returns.iter().map(ReturnResponse::new).collect(),
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "returns retrieved successfully",
+        GetReturnsResponse::new(returns),
+    ))
+}
+
+// get_return is the handler to get a single return
+#[get("/v1/{client_id}/returns/{return_id}")]
+pub async fn get_return(
+    app_data: web::Data<server::AppState>,
+    cr_id: Path<ClientIdReturnId>,
+) -> impl Responder {
+    let (client_id, return_id) = match parse_client_id_return_id(cr_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let return_authorization = match app_data
+        .service_manager
+        .return_service
+        .get_return(client_id, return_id)
+        .await
+    {
+        Ok(return_authorization) => return_authorization,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "return retrieved successfully",
+        ReturnResponse::new(&return_authorization),
+    ))
+}
+
+// receive_return is the handler to record the condition of the goods that arrived for a return
+// and put the resellable units back into the stock
+#[post("/v1/{client_id}/returns/{return_id}/receive")]
+pub async fn receive_return(
+    req: HttpRequest,
+    app_data: web::Data<server::AppState>,
+    request: Json<ReceiveReturnRequest>,
+    cr_id: Path<ClientIdReturnId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, return_id) = match parse_client_id_return_id(cr_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    // validate the warehouse id, resellable units go back where they came from if none is given
+    let warehouse_id = match warehouse_dto::parse_warehouse_id(&request.warehouse_id) {
+        Ok(warehouse_id) => warehouse_id,
+        Err(err) => return err.to_responder(),
+    };
+
+    let return_authorization = match app_data
+        .service_manager
+        .return_service
+        .receive(
+            client_id,
+            return_id,
+            request.into_inner().lines,
+            warehouse_id,
+            &actor::actor(&req, client_id),
+        )
+        .await
+    {
+        Ok(return_authorization) => return_authorization,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "return received successfully",
+        ReturnResponse::new(&return_authorization),
+    ))
+}
+
+// close_return is the handler to close a received return
+#[post("/v1/{client_id}/returns/{return_id}/close")]
+pub async fn close_return(
+    app_data: web::Data<server::AppState>,
+    cr_id: Path<ClientIdReturnId>,
+) -> impl Responder {
+    let (client_id, return_id) = match parse_client_id_return_id(cr_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let return_authorization = match app_data
+        .service_manager
+        .return_service
+        .close(client_id, return_id)
+        .await
+    {
+        Ok(return_authorization) => return_authorization,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "return closed successfully",
+        ReturnResponse::new(&return_authorization),
+    ))
+}
+
+// parse_client_id_return_id converts the client id and return id of a path to objectIds
+fn parse_client_id_return_id(ids: ClientIdReturnId) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let return_id = ObjectId::from_str(ids.return_id.as_str())
+        .map_err(|_| AppError::new("invalid return id", ErrorKind::FailedAction))?;
+    Ok((client_id, return_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+
+    use crate::api::test_helpers::{add_named_product, app, assert_error, assert_errors, call};
+
+    #[test]
+    // test_return_lifecycle tests that a return is authorized against what an order took, that
+    // only its resellable units go back into the stock when it is received, and that it is
+    // received and closed once
+    async fn test_return_lifecycle() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 10).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 10).await;
+        let coffee_id = add_named_product(&app, &client_id, "Coffee", 10).await;
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/orders", client_id))
+                .set_json(json!([
+                    {"product_id": mug_id, "quantity": 3},
+                    {"product_id": tea_id, "quantity": 2},
+                ])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let order_id = body["data"]["id"].as_str().unwrap().to_string();
+        let uri = format!("/v1/{}/returns", client_id);
+        let authorize = |lines: Value| {
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "order_id": order_id,
+                "reason": "wrong size",
+                "lines": lines,
+            }))
+        };
+        let quantities = || async {
+            let mut quantities = vec![];
+            for product_id in [&mug_id, &tea_id] {
+                let (_, body) = call(
+                    &app,
+                    test::TestRequest::get()
+                        .uri(&format!("/v1/{}/products/{}", client_id, product_id)),
+                )
+                .await;
+                quantities.push(body["data"]["quantity"].as_i64().unwrap());
+            }
+            quantities
+        };
+
+        // an authorized return holds the quantities it may take back on the order
+        let (status, body) = call(
+            &app,
+            authorize(json!([
+                {"product_id": mug_id, "quantity": 2},
+                {"product_id": tea_id, "quantity": 1},
+            ])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "authorized");
+        assert_eq!(body["data"]["order_id"], order_id);
+        assert_eq!(body["data"]["reason"], "wrong size");
+        let return_id = body["data"]["id"].as_str().unwrap().to_string();
+        let return_uri = format!("{}/{}", uri, return_id);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("/v1/{}/orders/{}", client_id, order_id)),
+        )
+        .await;
+        assert_eq!(body["data"]["lines"][0]["returned_quantity"], 2);
+        assert_eq!(body["data"]["lines"][1]["returned_quantity"], 1);
+        let response = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/close", return_uri)),
+        )
+        .await;
+        assert_error(response, "return has not been received");
+
+        // receiving puts back the resellable units only
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("{}/receive", return_uri))
+                .set_json(json!({"lines": [
+                    {"product_id": mug_id, "resellable": 1, "damaged": 1},
+                    {"product_id": tea_id, "scrap": 1},
+                ]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "received");
+        assert!(body["data"]["received_at"].is_string());
+        let lines: Vec<(i64, i64, i64)> = body["data"]["lines"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| {
+                (
+                    line["resellable"].as_i64().unwrap(),
+                    line["damaged"].as_i64().unwrap(),
+                    line["scrap"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(lines, vec![(1, 1, 0), (0, 0, 1)]);
+        assert_eq!(quantities().await, vec![8, 8]);
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/v1/{}/products/{}/movements", client_id, mug_id)),
+        )
+        .await;
+        let movement = &body["data"]["movements"][0];
+        assert_eq!(movement["reason"], "return");
+        assert_eq!(movement["delta"], 1);
+        assert_eq!(movement["reference"], return_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&format!("{}/close", return_uri)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "closed");
+        assert!(body["data"]["closed_at"].is_string());
+
+        // the returns of an order are listed with it
+        for (order_id, count) in [(order_id.clone(), 1), (ObjectId::new().to_hex(), 0)] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::get().uri(&format!("{}?order_id={}", uri, order_id)),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["returns"].as_array().unwrap().len(), count);
+        }
+
+        // the last mug of the order can still be returned, but no more
+        let (status, body) = call(
+            &app,
+            authorize(json!([{"product_id": mug_id, "quantity": 1}])),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let open_uri = format!("{}/{}", uri, body["data"]["id"].as_str().unwrap());
+        let receive = |lines: Value| {
+            test::TestRequest::post()
+                .uri(&format!("{}/receive", open_uri))
+                .set_json(json!({ "lines": lines }))
+        };
+
+        // (request, expected message)
+        let too_many = format!(
+            "cannot return 1 of product_id: {}, only 0 can still be returned",
+            mug_id
+        );
+        let not_on_order = format!("order has no line with product_id: {}", coffee_id);
+        let over_received = format!(
+            "cannot receive 2 of product_id: {}, only 1 were authorized",
+            mug_id
+        );
+        let not_on_return = format!("return has no line with product_id: {}", tea_id);
+        let negative = format!(
+            "received quantities cannot be negative for product_id: {:?}",
+            mug_id
+        );
+        let test_cases = vec![
+            (
+                authorize(json!([{"product_id": mug_id, "quantity": 1}])),
+                too_many.as_str(),
+            ),
+            (
+                authorize(json!([{"product_id": coffee_id, "quantity": 1}])),
+                not_on_order.as_str(),
+            ),
+            (authorize(json!([])), "return must have at least one line"),
+            (
+                receive(json!([{"product_id": mug_id, "resellable": 1, "damaged": 1}])),
+                over_received.as_str(),
+            ),
+            (
+                receive(json!([{"product_id": tea_id, "resellable": 1}])),
+                not_on_return.as_str(),
+            ),
+            (
+                receive(json!([{"product_id": mug_id, "resellable": -1}])),
+                negative.as_str(),
+            ),
+            (receive(json!([])), "lines cannot be empty"),
+            (
+                test::TestRequest::post().uri(&format!("{}/close", open_uri)),
+                "return has not been received",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&format!("{}/receive", return_uri))
+                    .set_json(json!({"lines": [{"product_id": mug_id, "resellable": 1}]})),
+                "return is not authorized",
+            ),
+            (
+                test::TestRequest::post().uri(&format!("{}/close", return_uri)),
+                "return has not been received",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/x", uri)),
+                "invalid return id",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/{}", uri, ObjectId::new().to_hex())),
+                "return not found",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+        assert_eq!(quantities().await, vec![8, 8]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    dto::product::product_dto::ClientId,
+    dto::sku::sku_dto::{SkuSettingsRequest, SkuSettingsResponse},
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get, put,
+    web::{self, Json, Path},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// get_sku_settings is the handler to get how the skus of a client are made
+#[get("/v1/{client_id}/sku-settings")]
+pub async fn get_sku_settings(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let settings = match app_data
+        .service_manager
+        .sku_service
+        .get_settings(client_id)
+        .await
+    {
+        Ok(settings) => settings,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "sku settings retrieved successfully",
+        SkuSettingsResponse::new(&settings),
+    ))
+}
+
+// set_sku_settings is the handler to set how the skus of the new products of a client are made
+#[put("/v1/{client_id}/sku-settings")]
+pub async fn set_sku_settings(
+    app_data: web::Data<server::AppState>,
+    request: Json<SkuSettingsRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let settings = match app_data
+        .service_manager
+        .sku_service
+        .set_settings(client_id, request.into_inner().strategy)
+        .await
+    {
+        Ok(settings) => settings,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "sku settings updated successfully",
+        SkuSettingsResponse::new(&settings),
+    ))
+}
@@ -0,0 +1,179 @@
+use crate::{
+    dto::product::product_dto::ClientId,
+    dto::stream::stream_dto::{self, StockStreamRequest},
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    get,
+    http::header,
+    web::{self, Bytes, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use futures::StreamExt;
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// LAST_EVENT_ID_HEADER is the request header a reconnecting event source sends with the id of
+// the last event it got
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+// stream_stock_changes is the handler to stream the changes to the stock of a client as
+// server-sent events. The changes can be filtered by product and resumed after the last event
+// a client got
+#[get("/v1/{client_id}/stream")]
+pub async fn stream_stock_changes(
+    app_data: web::Data<server::AppState>,
+    req: HttpRequest,
+    c_id: Path<ClientId>,
+    parameters: Query<Vec<(String, String)>>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    // a header that is not text cannot be an event id
+    let last_event_id = req
+        .headers()
+        .get(LAST_EVENT_ID_HEADER)
+        .map(|last_event_id| last_event_id.to_str().unwrap_or_default());
+    let request = match StockStreamRequest::from_parameters(&parameters, last_event_id) {
+        Ok(request) => request,
+        Err(err) => return err.to_responder(),
+    };
+
+    let events = match app_data
+        .service_manager
+        .stream_service
+        .stock_changes(client_id, request.product_ids, request.last_event_id)
+        .await
+    {
+        Ok(events) => events.map(|movements| {
+            Ok::<_, actix_web::Error>(Bytes::from(stream_dto::stock_change_events(&movements)))
+        }),
+        Err(err) => return err.to_responder(),
+    };
+
+    // proxies must pass the events on as they come
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::MessageBody,
+        http::{header, StatusCode},
+        test,
+    };
+    use futures::future;
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use std::pin::Pin;
+
+    use crate::api::test_helpers::{add_named_product, app, assert_errors, call};
+
+    // next_chunk reads the next chunk of a streamed response body
+    async fn next_chunk(body: &mut (impl MessageBody + Unpin)) -> String {
+        let chunk = future::poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)).await;
+        match chunk {
+            Some(Ok(chunk)) => String::from_utf8(chunk.to_vec()).unwrap(),
+            _ => panic!("the stream ended"),
+        }
+    }
+
+    #[test]
+    // test_stream_stock_changes tests that the changes to the stock of the filtered products are
+    // streamed as server-sent events, and that a stream resumes after the last event id
+    async fn test_stream_stock_changes() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let mug_id = add_named_product(&app, &client_id, "Mug", 5).await;
+        let tea_id = add_named_product(&app, &client_id, "Tea", 5).await;
+        let uri = format!("/v1/{}/stream", client_id);
+
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("{}?product_id={}", uri, mug_id))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let mut body = resp.into_body();
+
+        // only the change to the mug is streamed
+        for (product_id, quantity) in [(&tea_id, 1), (&mug_id, -2)] {
+            let (status, _) = call(
+                &app,
+                test::TestRequest::put()
+                    .uri(&format!(
+                        "/v1/{}/products/{}/quantity",
+                        client_id, product_id
+                    ))
+                    .set_json(json!({"quantity": quantity})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let event = next_chunk(&mut body).await;
+        let lines: Vec<&str> = event.trim_end().lines().collect();
+        assert_eq!(lines.len(), 3);
+        let event_id = lines[0].strip_prefix("id: ").unwrap().to_string();
+        assert_eq!(lines[1], "event: stock_changed");
+        let data: Value = serde_json::from_str(lines[2].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(data["sequence"].to_string(), event_id);
+        assert_eq!(data["product_id"], mug_id);
+        assert_eq!(data["reason"], "set_quantity");
+        assert_eq!(data["delta"], -2);
+        assert_eq!(data["after"], 3);
+
+        // resuming after the creation of the mug streams the change that followed it
+        let (_, body) = call(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/v1/{}/products/{}/movements", client_id, mug_id)),
+        )
+        .await;
+        let created_id = body["data"]["movements"][1]["sequence"].to_string();
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("{}?product_id={}", uri, mug_id))
+                .insert_header(("Last-Event-ID", created_id.as_str()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let event = next_chunk(&mut resp.into_body()).await;
+        assert!(event.starts_with(&format!("id: {}\n", event_id)));
+
+        // (query, expected message)
+        let test_cases = vec![
+            ("product_id=x", "invalid product id"),
+            ("last_event_id=x", "invalid last event id"),
+            ("last_event_id=-1", "invalid last event id"),
+        ];
+        let test_cases = test_cases
+            .into_iter()
+            .map(|(query, message)| {
+                (
+                    test::TestRequest::get().uri(&format!("{}?{}", uri, query)),
+                    message,
+                )
+            })
+            .collect();
+        assert_errors(&app, test_cases).await;
+    }
+}
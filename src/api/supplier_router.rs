@@ -0,0 +1,359 @@
+use crate::{
+    dto::product::product_dto::ClientId,
+    dto::supplier::supplier_dto::{
+        ClientIdProductIdSupplierId, ClientIdSupplierId, GetProductSuppliersResponse,
+        GetSuppliersResponse, ProductSupplierRequest, SupplierRequest, SupplierResponse,
+    },
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json, Path},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// add_supplier is the handler to add a supplier
+#[post("/v1/{client_id}/suppliers")]
+pub async fn add_supplier(
+    app_data: web::Data<server::AppState>,
+    request: Json<SupplierRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let supplier = match app_data
+        .service_manager
+        .supplier_service
+        .create(client_id, request.into_inner())
+        .await
+    {
+        Ok(supplier) => supplier,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "supplier added successfully",
+        SupplierResponse::new(&supplier),
+    ))
+}
+
+// get_suppliers_by_client is the handler to get all suppliers of a client
+#[get("/v1/{client_id}/suppliers")]
+pub async fn get_suppliers_by_client(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let suppliers = match app_data
+        .service_manager
+        .supplier_service
+        .get_suppliers_by_client(client_id)
+        .await
+    {
+        Ok(suppliers) => suppliers.iter().map(SupplierResponse::new).collect(),
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "suppliers retrieved successfully",
+        GetSuppliersResponse::new(suppliers),
+    ))
+}
+
+// get_supplier is the handler to get a single supplier
+#[get("/v1/{client_id}/suppliers/{supplier_id}")]
+pub async fn get_supplier(
+    app_data: web::Data<server::AppState>,
+    cs_id: Path<ClientIdSupplierId>,
+) -> impl Responder {
+    let (client_id, supplier_id) = match parse_client_id_supplier_id(cs_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let supplier = match app_data
+        .service_manager
+        .supplier_service
+        .get_supplier(client_id, supplier_id)
+        .await
+    {
+        Ok(supplier) => supplier,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "supplier retrieved successfully",
+        SupplierResponse::new(&supplier),
+    ))
+}
+
+// update_supplier is the handler to update a supplier
+#[put("/v1/{client_id}/suppliers/{supplier_id}")]
+pub async fn update_supplier(
+    app_data: web::Data<server::AppState>,
+    request: Json<SupplierRequest>,
+    cs_id: Path<ClientIdSupplierId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, supplier_id) = match parse_client_id_supplier_id(cs_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let supplier = match app_data
+        .service_manager
+        .supplier_service
+        .update_supplier(client_id, supplier_id, request.into_inner())
+        .await
+    {
+        Ok(supplier) => supplier,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "supplier updated successfully",
+        SupplierResponse::new(&supplier),
+    ))
+}
+
+// delete_supplier is the handler to delete a supplier and unlink the products bought from it
+#[delete("/v1/{client_id}/suppliers/{supplier_id}")]
+pub async fn delete_supplier(
+    app_data: web::Data<server::AppState>,
+    cs_id: Path<ClientIdSupplierId>,
+) -> impl Responder {
+    let (client_id, supplier_id) = match parse_client_id_supplier_id(cs_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    if let Err(err) = app_data
+        .service_manager
+        .supplier_service
+        .delete_supplier(client_id, supplier_id)
+        .await
+    {
+        return err.to_responder();
+    }
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "supplier deleted successfully",
+        None::<String>,
+    ))
+}
+
+// link_product_supplier is the handler to link a product to a supplier it can be bought from
+#[put("/v1/{client_id}/products/{product_id}/suppliers/{supplier_id}")]
+pub async fn link_product_supplier(
+    app_data: web::Data<server::AppState>,
+    request: Json<ProductSupplierRequest>,
+    cps_id: Path<ClientIdProductIdSupplierId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, product_id, supplier_id) =
+        match parse_client_id_product_id_supplier_id(cps_id.into_inner()) {
+            Ok(ids) => ids,
+            Err(err) => return err.to_responder(),
+        };
+
+    let product = match app_data
+        .service_manager
+        .supplier_service
+        .link_product(client_id, product_id, supplier_id, request.into_inner())
+        .await
+    {
+        Ok(product) => product,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "product linked to supplier successfully",
+        GetProductSuppliersResponse::new(product._id.to_hex(), &product.suppliers),
+    ))
+}
+
+// unlink_product_supplier is the handler to unlink a product from a supplier
+#[delete("/v1/{client_id}/products/{product_id}/suppliers/{supplier_id}")]
+pub async fn unlink_product_supplier(
+    app_data: web::Data<server::AppState>,
+    cps_id: Path<ClientIdProductIdSupplierId>,
+) -> impl Responder {
+    let (client_id, product_id, supplier_id) =
+        match parse_client_id_product_id_supplier_id(cps_id.into_inner()) {
+            Ok(ids) => ids,
+            Err(err) => return err.to_responder(),
+        };
+
+    let product = match app_data
+        .service_manager
+        .supplier_service
+        .unlink_product(client_id, product_id, supplier_id)
+        .await
+    {
+        Ok(product) => product,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "product unlinked from supplier successfully",
+        GetProductSuppliersResponse::new(product._id.to_hex(), &product.suppliers),
+    ))
+}
+
+// parse_client_id_supplier_id converts the client id and supplier id of a path to objectIds
+fn parse_client_id_supplier_id(ids: ClientIdSupplierId) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let supplier_id = ObjectId::from_str(ids.supplier_id.as_str())
+        .map_err(|_| AppError::new("invalid supplier id", ErrorKind::FailedAction))?;
+    Ok((client_id, supplier_id))
+}
+
+// parse_client_id_product_id_supplier_id converts the client id, product id and supplier id of
+// a path to objectIds
+fn parse_client_id_product_id_supplier_id(
+    ids: ClientIdProductIdSupplierId,
+) -> Result<(ObjectId, ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let product_id = ObjectId::from_str(ids.product_id.as_str())
+        .map_err(|_| AppError::new("invalid product id", ErrorKind::FailedAction))?;
+    let supplier_id = ObjectId::from_str(ids.supplier_id.as_str())
+        .map_err(|_| AppError::new("invalid supplier id", ErrorKind::FailedAction))?;
+    Ok((client_id, product_id, supplier_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+
+    use crate::api::test_helpers::{add_product, app, assert_errors, call};
+
+    #[test]
+    // test_suppliers tests managing the suppliers of a client, linking products to them with the
+    // terms they are bought on, and that deleting a supplier unlinks its products
+    async fn test_suppliers() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let product_id = add_product(&app, &client_id, 5).await;
+        let uri = format!("/v1/{}/suppliers", client_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "name": "Acme",
+                "contact": "orders@acme.test",
+                "lead_time_days": 7,
+                "minimum_order_quantity": 50
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "supplier added successfully");
+        assert_eq!(body["data"]["lead_time_days"], 7);
+        let supplier_id = body["data"]["id"].as_str().unwrap().to_string();
+        let supplier_uri = format!("{}/{}", uri, supplier_id);
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&supplier_uri)
+                .set_json(json!({"name": "Acme Ltd", "lead_time_days": 10})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["name"], "Acme Ltd");
+        assert_eq!(body["data"]["minimum_order_quantity"], Value::Null);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(body["data"]["suppliers"][0]["id"], supplier_id);
+
+        // linking again replaces the terms of the link
+        let link_uri = format!(
+            "/v1/{}/products/{}/suppliers/{}",
+            client_id, product_id, supplier_id
+        );
+        for unit_cost in [2.5, 2.25] {
+            let (status, body) = call(
+                &app,
+                test::TestRequest::put()
+                    .uri(&link_uri)
+                    .set_json(json!({"supplier_sku": "AC-1", "unit_cost": unit_cost})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["message"], "product linked to supplier successfully");
+        }
+        let product_uri = format!("/v1/{}/products/{}", client_id, product_id);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&product_uri)).await;
+        let suppliers = body["data"]["suppliers"].as_array().unwrap();
+        assert_eq!(suppliers.len(), 1);
+        assert_eq!(suppliers[0]["supplier_id"], supplier_id);
+        assert_eq!(suppliers[0]["supplier_sku"], "AC-1");
+        assert_eq!(suppliers[0]["unit_cost"], 2.25);
+
+        // deleting the supplier unlinks the product
+        let (status, _) = call(&app, test::TestRequest::delete().uri(&supplier_uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, body) = call(&app, test::TestRequest::get().uri(&product_uri)).await;
+        assert_eq!(body["data"]["suppliers"], json!([]));
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::post()
+                    .uri(&uri)
+                    .set_json(json!({"name": "Acme", "minimum_order_quantity": 0})),
+                "minimum_order_quantity cannot be less than 1",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&link_uri)
+                    .set_json(json!({"unit_cost": -1.0})),
+                "unit_cost cannot be less than 0",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&link_uri)
+                    .set_json(json!({"unit_cost": 1.0})),
+                "supplier not found",
+            ),
+            (
+                test::TestRequest::delete().uri(&link_uri),
+                "product is not linked to the supplier",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+    }
+}
@@ -0,0 +1,103 @@
+// test_helpers holds what the HTTP tests of the routers share: booting the application on
+// in-memory stores, calling it, and checking the responses
+use actix_http::Request;
+use actix_web::{
+    dev::{Service, ServiceResponse},
+    http::StatusCode,
+    test, web, App,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    api,
+    repository::stores::Stores,
+    server::{AppState, ServiceManager},
+};
+
+// services starts the services the way the server does, on empty in-memory stores. The
+// webhooks can point to local listeners on 127.0.0.1
+pub fn services() -> ServiceManager {
+    ServiceManager::with_webhook_allowed_hosts(&Stores::memory(), vec!["127.0.0.1".to_string()])
+}
+
+// app boots the application the way the server does, on empty in-memory stores
+pub async fn app() -> impl Service<Request, Response = ServiceResponse, Error = actix_web::Error> {
+    app_with(services()).await
+}
+
+// app_with boots the application on the given services, so that a test can also run the
+// background work of the server on them
+pub async fn app_with(
+    service_manager: ServiceManager,
+) -> impl Service<Request, Response = ServiceResponse, Error = actix_web::Error> {
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(service_manager)))
+            .configure(api::init),
+    )
+    .await
+}
+
+// call sends a request to the application and returns the status and JSON body of the
+// response. The body is null if the response has none
+pub async fn call(
+    app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+    req: test::TestRequest,
+) -> (StatusCode, Value) {
+    let resp = test::call_service(app, req.to_request()).await;
+    let status = resp.status();
+    let body = test::read_body(resp).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// add_product adds a product with a quantity for a client and returns its id
+pub async fn add_product(
+    app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+    client_id: &str,
+    quantity: i32,
+) -> String {
+    add_named_product(app, client_id, "Widget", quantity).await
+}
+
+// add_named_product adds a product with a name and a quantity for a client and returns its id
+pub async fn add_named_product(
+    app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+    client_id: &str,
+    name: &str,
+    quantity: i32,
+) -> String {
+    let (status, body) = call(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/v1/{}/products", client_id))
+            .set_json(json!({"name": name, "description": "A product", "quantity": quantity})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body["data"]["id"].as_str().unwrap().to_string()
+}
+
+// assert_error checks the envelope of an error response. Missing resources are answered
+// with a bad request status, but carry 404 as the status code of the envelope
+pub fn assert_error(response: (StatusCode, Value), message: &str) {
+    let (status, body) = response;
+    let (status_code, kind) = match message.ends_with("not found") {
+        true => (404, "NotFound"),
+        false => (400, "FailedAction"),
+    };
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", message);
+    assert_eq!(body["status_code"], status_code);
+    assert_eq!(body["kind"], kind);
+    assert_eq!(body["message"], message);
+}
+
+// assert_errors sends every request of a table and checks that each one fails with its
+// expected message
+pub async fn assert_errors(
+    app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+    test_cases: Vec<(test::TestRequest, &str)>,
+) {
+    for test_case in test_cases {
+        assert_error(call(app, test_case.0).await, test_case.1);
+    }
+}
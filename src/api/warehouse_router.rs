@@ -0,0 +1,376 @@
+use crate::{
+    dto::product::product_dto::ClientId,
+    dto::warehouse::warehouse_dto::{
+        ClientIdWarehouseId, GetWarehousesResponse, WarehouseRequest, WarehouseResponse,
+    },
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json, Path},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// add_warehouse is the handler to add a warehouse
+#[post("/v1/{client_id}/warehouses")]
+pub async fn add_warehouse(
+    app_data: web::Data<server::AppState>,
+    request: Json<WarehouseRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let warehouse = match app_data
+        .service_manager
+        .warehouse_service
+        .create(client_id, request.into_inner())
+        .await
+    {
+        Ok(warehouse) => warehouse,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "warehouse added successfully",
+        WarehouseResponse::new(&warehouse),
+    ))
+}
+
+// get_warehouses_by_client is the handler to get all warehouses of a client
+#[get("/v1/{client_id}/warehouses")]
+pub async fn get_warehouses_by_client(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let warehouses = match app_data
+        .service_manager
+        .warehouse_service
+        .get_warehouses_by_client(client_id)
+        .await
+    {
+        Ok(warehouses) => warehouses.iter().map(WarehouseResponse::new).collect(),
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "warehouses retrieved successfully",
+        GetWarehousesResponse::new(warehouses),
+    ))
+}
+
+// get_warehouse is the handler to get a single warehouse
+#[get("/v1/{client_id}/warehouses/{warehouse_id}")]
+pub async fn get_warehouse(
+    app_data: web::Data<server::AppState>,
+    cw_id: Path<ClientIdWarehouseId>,
+) -> impl Responder {
+    let (client_id, warehouse_id) = match parse_client_id_warehouse_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let warehouse = match app_data
+        .service_manager
+        .warehouse_service
+        .get_warehouse(client_id, warehouse_id)
+        .await
+    {
+        Ok(warehouse) => warehouse,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "warehouse retrieved successfully",
+        WarehouseResponse::new(&warehouse),
+    ))
+}
+
+// update_warehouse is the handler to update a warehouse
+#[put("/v1/{client_id}/warehouses/{warehouse_id}")]
+pub async fn update_warehouse(
+    app_data: web::Data<server::AppState>,
+    request: Json<WarehouseRequest>,
+    cw_id: Path<ClientIdWarehouseId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request.validate() {
+        return err.to_responder();
+    }
+
+    let (client_id, warehouse_id) = match parse_client_id_warehouse_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let warehouse = match app_data
+        .service_manager
+        .warehouse_service
+        .update_warehouse(client_id, warehouse_id, request.into_inner())
+        .await
+    {
+        Ok(warehouse) => warehouse,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "warehouse updated successfully",
+        WarehouseResponse::new(&warehouse),
+    ))
+}
+
+// delete_warehouse is the handler to delete a warehouse that no longer holds stock
+#[delete("/v1/{client_id}/warehouses/{warehouse_id}")]
+pub async fn delete_warehouse(
+    app_data: web::Data<server::AppState>,
+    cw_id: Path<ClientIdWarehouseId>,
+) -> impl Responder {
+    let (client_id, warehouse_id) = match parse_client_id_warehouse_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    if let Err(err) = app_data
+        .service_manager
+        .warehouse_service
+        .delete_warehouse(client_id, warehouse_id)
+        .await
+    {
+        return err.to_responder();
+    }
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "warehouse deleted successfully",
+        None::<String>,
+    ))
+}
+
+// parse_client_id_warehouse_id converts the client id and warehouse id of a path to objectIds
+pub fn parse_client_id_warehouse_id(
+    ids: ClientIdWarehouseId,
+) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let warehouse_id = ObjectId::from_str(ids.warehouse_id.as_str())
+        .map_err(|_| AppError::new("invalid warehouse id", ErrorKind::FailedAction))?;
+    Ok((client_id, warehouse_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::api::test_helpers::{app, assert_error, assert_errors, call};
+
+    #[test]
+    // test_restock_after_delete_warehouse tests that a product whose only location was a deleted
+    // warehouse is still found with a quantity of 0, and can be restocked
+    async fn test_restock_after_delete_warehouse() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/warehouses", client_id))
+                .set_json(json!({"name": "North"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let warehouse_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "Widget",
+                    "description": "A widget",
+                    "quantity": 4,
+                    "warehouse_id": warehouse_id,
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let product_id = body["data"]["id"].as_str().unwrap().to_string();
+        let uri = format!("/v1/{}/products/{}", client_id, product_id);
+
+        // empty the warehouse and delete it, which deletes the only stock of the product
+        let (status, _) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/quantity", uri))
+                .set_json(json!({"quantity": -4, "warehouse_id": warehouse_id})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &app,
+            test::TestRequest::delete()
+                .uri(&format!("/v1/{}/warehouses/{}", client_id, warehouse_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quantity"], 0);
+        assert_eq!(body["data"]["locations"], json!([]));
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}/availability?number=1", uri)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "product quantity is less than requested number"
+        );
+
+        // restocking keeps the product at a location again
+        let (status, _) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/quantity", uri))
+                .set_json(json!({"quantity": 3})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quantity"], 3);
+        let (status, _) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}/availability?number=3", uri)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    // test_delete_warehouse tests that a warehouse can only be deleted once it holds no stock,
+    // and that its bins go with it
+    async fn test_delete_warehouse() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/warehouses", client_id);
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&uri)
+                .set_json(json!({"name": "North"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let warehouse_id = body["data"]["id"].as_str().unwrap().to_string();
+        let warehouse_uri = format!("{}/{}", uri, warehouse_id);
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("{}/bins", warehouse_uri))
+                .set_json(json!({"zone": "A", "aisle": "1", "rack": "2", "bin": "3"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let bin_uri = format!(
+            "/v1/{}/bins/{}",
+            client_id,
+            body["data"]["id"].as_str().unwrap()
+        );
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/v1/{}/products", client_id))
+                .set_json(json!({
+                    "name": "Widget",
+                    "description": "A widget",
+                    "quantity": 4,
+                    "warehouse_id": warehouse_id,
+                })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let product_uri = format!(
+            "/v1/{}/products/{}",
+            client_id,
+            body["data"]["id"].as_str().unwrap()
+        );
+
+        // a warehouse holding stock is kept, and so is its stock
+        let response = call(&app, test::TestRequest::delete().uri(&warehouse_uri)).await;
+        assert_error(
+            response,
+            "warehouse still holds stock, move or remove it first",
+        );
+        let (_, body) = call(&app, test::TestRequest::get().uri(&product_uri)).await;
+        assert_eq!(body["data"]["quantity"], 4);
+
+        // another client cannot delete the warehouse
+        let response = call(
+            &app,
+            test::TestRequest::delete().uri(&format!(
+                "/v1/{}/warehouses/{}",
+                ObjectId::new().to_hex(),
+                warehouse_id
+            )),
+        )
+        .await;
+        assert_error(response, "warehouse not found");
+
+        // once emptied, the warehouse is deleted with its bins
+        let (status, _) = call(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("{}/quantity", product_uri))
+                .set_json(json!({"quantity": -4, "warehouse_id": warehouse_id})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = call(&app, test::TestRequest::delete().uri(&warehouse_uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "warehouse deleted successfully");
+        let (status, body) = call(&app, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["warehouses"], json!([]));
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::get().uri(&warehouse_uri),
+                "warehouse not found",
+            ),
+            (
+                test::TestRequest::delete().uri(&warehouse_uri),
+                "warehouse not found",
+            ),
+            (test::TestRequest::get().uri(&bin_uri), "bin not found"),
+            (
+                test::TestRequest::delete().uri(&format!("{}/x", uri)),
+                "invalid warehouse id",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+    }
+}
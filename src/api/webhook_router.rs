@@ -0,0 +1,562 @@
+use crate::{
+    dto::product::product_dto::ClientId,
+    dto::webhook::webhook_dto::{
+        ClientIdDeliveryId, ClientIdWebhookId, DeliveryResponse, GetDeliveriesRequest,
+        GetDeliveriesResponse, GetWebhooksResponse, WebhookRequest, WebhookResponse,
+    },
+    dto::APIResponse,
+    errors::app_error::{AppError, ErrorKind},
+    server,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json, Path, Query},
+    HttpResponse, Responder,
+};
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+// add_webhook is the handler to register a webhook of a client. The response holds the secret
+// the deliveries are signed with, it is not given again
+#[post("/v1/{client_id}/webhooks")]
+pub async fn add_webhook(
+    app_data: web::Data<server::AppState>,
+    request: Json<WebhookRequest>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request
+        .validate(app_data.service_manager.webhook_service.allowed_hosts())
+        .await
+    {
+        return err.to_responder();
+    }
+
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let webhook = match app_data
+        .service_manager
+        .webhook_service
+        .create(client_id, &request)
+        .await
+    {
+        Ok(webhook) => webhook,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook added successfully",
+        WebhookResponse::with_secret(&webhook),
+    ))
+}
+
+// get_webhooks_by_client is the handler to get all webhooks of a client
+#[get("/v1/{client_id}/webhooks")]
+pub async fn get_webhooks_by_client(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let webhooks = match app_data
+        .service_manager
+        .webhook_service
+        .get_webhooks(client_id)
+        .await
+    {
+        Ok(webhooks) => webhooks,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhooks retrieved successfully",
+        GetWebhooksResponse::new(&webhooks),
+    ))
+}
+
+// get_deliveries is the handler to query the delivery log of the webhooks of a client, the most
+// recent first
+#[get("/v1/{client_id}/webhooks/deliveries")]
+pub async fn get_deliveries(
+    app_data: web::Data<server::AppState>,
+    c_id: Path<ClientId>,
+    query: Query<GetDeliveriesRequest>,
+) -> impl Responder {
+    // try converting the client_id from string to an objectId
+    let client_id = match ObjectId::from_str(c_id.into_inner().client_id.as_str()) {
+        Ok(client_id) => client_id,
+        Err(_) => {
+            return AppError::new("invalid client id", ErrorKind::FailedAction).to_responder()
+        }
+    };
+
+    let query = match query.to_query() {
+        Ok(query) => query,
+        Err(err) => return err.to_responder(),
+    };
+
+    let deliveries = match app_data
+        .service_manager
+        .webhook_service
+        .get_deliveries(client_id, &query)
+        .await
+    {
+        Ok(deliveries) => deliveries,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook deliveries retrieved successfully",
+        GetDeliveriesResponse::new(&deliveries),
+    ))
+}
+
+// get_delivery is the handler to get a delivery of the webhooks of a client with its attempts
+#[get("/v1/{client_id}/webhooks/deliveries/{delivery_id}")]
+pub async fn get_delivery(
+    app_data: web::Data<server::AppState>,
+    cd_id: Path<ClientIdDeliveryId>,
+) -> impl Responder {
+    let (client_id, delivery_id) = match parse_client_id_delivery_id(cd_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let delivery = match app_data
+        .service_manager
+        .webhook_service
+        .get_delivery(client_id, delivery_id)
+        .await
+    {
+        Ok(delivery) => delivery,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook delivery retrieved successfully",
+        DeliveryResponse::new(&delivery),
+    ))
+}
+
+// replay_delivery is the handler to send the payload of a delivery to its webhook again
+#[post("/v1/{client_id}/webhooks/deliveries/{delivery_id}/replay")]
+pub async fn replay_delivery(
+    app_data: web::Data<server::AppState>,
+    cd_id: Path<ClientIdDeliveryId>,
+) -> impl Responder {
+    let (client_id, delivery_id) = match parse_client_id_delivery_id(cd_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let delivery = match app_data
+        .service_manager
+        .webhook_service
+        .replay(client_id, delivery_id)
+        .await
+    {
+        Ok(delivery) => delivery,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook delivery replayed successfully",
+        DeliveryResponse::new(&delivery),
+    ))
+}
+
+// get_webhook is the handler to get a single webhook
+#[get("/v1/{client_id}/webhooks/{webhook_id}")]
+pub async fn get_webhook(
+    app_data: web::Data<server::AppState>,
+    cw_id: Path<ClientIdWebhookId>,
+) -> impl Responder {
+    let (client_id, webhook_id) = match parse_client_id_webhook_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let webhook = match app_data
+        .service_manager
+        .webhook_service
+        .get_webhook(client_id, webhook_id)
+        .await
+    {
+        Ok(webhook) => webhook,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook retrieved successfully",
+        WebhookResponse::new(&webhook),
+    ))
+}
+
+// update_webhook is the handler to change the url and events of a webhook, or disable it
+#[put("/v1/{client_id}/webhooks/{webhook_id}")]
+pub async fn update_webhook(
+    app_data: web::Data<server::AppState>,
+    request: Json<WebhookRequest>,
+    cw_id: Path<ClientIdWebhookId>,
+) -> impl Responder {
+    // validate the request body
+    if let Err(err) = request
+        .validate(app_data.service_manager.webhook_service.allowed_hosts())
+        .await
+    {
+        return err.to_responder();
+    }
+
+    let (client_id, webhook_id) = match parse_client_id_webhook_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    let webhook = match app_data
+        .service_manager
+        .webhook_service
+        .update_webhook(client_id, webhook_id, &request)
+        .await
+    {
+        Ok(webhook) => webhook,
+        Err(err) => return err.to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook updated successfully",
+        WebhookResponse::new(&webhook),
+    ))
+}
+
+// delete_webhook is the handler to delete a webhook, its deliveries stay in the log
+#[delete("/v1/{client_id}/webhooks/{webhook_id}")]
+pub async fn delete_webhook(
+    app_data: web::Data<server::AppState>,
+    cw_id: Path<ClientIdWebhookId>,
+) -> impl Responder {
+    let (client_id, webhook_id) = match parse_client_id_webhook_id(cw_id.into_inner()) {
+        Ok(ids) => ids,
+        Err(err) => return err.to_responder(),
+    };
+
+    if let Err(err) = app_data
+        .service_manager
+        .webhook_service
+        .delete_webhook(client_id, webhook_id)
+        .await
+    {
+        return err.to_responder();
+    }
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "webhook deleted successfully",
+        None::<String>,
+    ))
+}
+
+// parse_client_id_webhook_id converts the client id and webhook id of a path to objectIds
+fn parse_client_id_webhook_id(ids: ClientIdWebhookId) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let webhook_id = ObjectId::from_str(ids.webhook_id.as_str())
+        .map_err(|_| AppError::new("invalid webhook id", ErrorKind::FailedAction))?;
+    Ok((client_id, webhook_id))
+}
+
+// parse_client_id_delivery_id converts the client id and delivery id of a path to objectIds
+fn parse_client_id_delivery_id(ids: ClientIdDeliveryId) -> Result<(ObjectId, ObjectId), AppError> {
+    let client_id = ObjectId::from_str(ids.client_id.as_str())
+        .map_err(|_| AppError::new("invalid client id", ErrorKind::FailedAction))?;
+    let delivery_id = ObjectId::from_str(ids.delivery_id.as_str())
+        .map_err(|_| AppError::new("invalid delivery id", ErrorKind::FailedAction))?;
+    Ok((client_id, delivery_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_web::{
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test, web, App, HttpRequest, HttpResponse, HttpServer,
+    };
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use crate::{
+        api::test_helpers::{add_product, app, assert_errors, call},
+        service::webhook_service::{DELIVERY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+        utils::tools,
+    };
+
+    // ReceivedDelivery is a request a local webhook endpoint received
+    #[derive(Clone)]
+    struct ReceivedDelivery {
+        path: String,
+        delivery_id: String,
+        timestamp: String,
+        signature: String,
+        body: String,
+    }
+
+    // listen starts a local webhook endpoint that records the requests it receives. It accepts
+    // them, except on /failing. Returns the base url of the endpoint and the received requests
+    fn listen() -> (String, web::Data<Mutex<Vec<ReceivedDelivery>>>) {
+        let received = web::Data::new(Mutex::new(Vec::<ReceivedDelivery>::new()));
+        let data = received.clone();
+        let server = HttpServer::new(move || {
+            App::new().app_data(data.clone()).default_service(web::to(
+                |req: HttpRequest,
+                 body: String,
+                 received: web::Data<Mutex<Vec<ReceivedDelivery>>>| async move {
+                    let header = |name: &str| {
+                        req.headers()
+                            .get(name)
+                            .and_then(|value| value.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    received.lock().unwrap().push(ReceivedDelivery {
+                        path: req.path().to_string(),
+                        delivery_id: header(DELIVERY_HEADER),
+                        timestamp: header(TIMESTAMP_HEADER),
+                        signature: header(SIGNATURE_HEADER),
+                        body,
+                    });
+                    match req.path() {
+                        "/failing" => HttpResponse::InternalServerError().finish(),
+                        _ => HttpResponse::Ok().finish(),
+                    }
+                },
+            ))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_rt::spawn(server.run());
+        (url, received)
+    }
+
+    // wait_for_deliveries polls the delivery log until the query returns the given number of
+    // deliveries that were attempted, and returns them
+    async fn wait_for_deliveries(
+        app: &impl Service<Request, Response = ServiceResponse, Error = actix_web::Error>,
+        client_id: &str,
+        query: &str,
+        count: usize,
+    ) -> Vec<Value> {
+        for _ in 0..100 {
+            let (status, body) = call(
+                app,
+                test::TestRequest::get()
+                    .uri(&format!("/v1/{}/webhooks/deliveries?{}", client_id, query)),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let deliveries = body["data"]["deliveries"].as_array().unwrap().clone();
+            let attempted = deliveries
+                .iter()
+                .all(|delivery| !delivery["attempts"].as_array().unwrap().is_empty());
+            if deliveries.len() == count && attempted {
+                return deliveries;
+            }
+            actix_rt::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("deliveries matching {} were not attempted", query);
+    }
+
+    #[test]
+    // test_webhook_deliveries tests that the events a webhook subscribed to are posted to it
+    // signed with its secret, that a failed delivery is kept for a retry, and that a delivery
+    // can be replayed
+    async fn test_webhook_deliveries() {
+        let app = app().await;
+        let client_id = ObjectId::new().to_hex();
+        let uri = format!("/v1/{}/webhooks", client_id);
+        let (url, received) = listen();
+
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "url": format!("{}/hooks", url),
+                "events": ["product_created", "product_created", "quantity_changed"],
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "webhook added successfully");
+        assert_eq!(
+            body["data"]["events"],
+            json!(["product_created", "quantity_changed"])
+        );
+        assert_eq!(body["data"]["active"], true);
+        let webhook_id = body["data"]["id"].as_str().unwrap().to_string();
+        let secret = body["data"]["secret"].as_str().unwrap().to_string();
+        assert!(secret.starts_with("whsec_"));
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&uri).set_json(json!({
+                "url": format!("{}/failing", url),
+                "events": ["product_created"],
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let failing_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        // the secret is only given when the webhook is registered
+        let (status, body) = call(
+            &app,
+            test::TestRequest::get().uri(&format!("{}/{}", uri, webhook_id)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"].get("secret").is_none());
+
+        // creating a product is posted to both webhooks, and its quantity to the first one only
+        let product_id = add_product(&app, &client_id, 5).await;
+        let deliveries = wait_for_deliveries(
+            &app,
+            &client_id,
+            &format!("webhook_id={}&status=delivered", webhook_id),
+            2,
+        )
+        .await;
+        let created = deliveries
+            .iter()
+            .find(|delivery| delivery["event"] == "product_created")
+            .unwrap()
+            .clone();
+        assert_eq!(created["payload"]["data"]["id"], product_id);
+        assert_eq!(created["payload"]["client_id"], client_id);
+        assert_eq!(created["attempts"][0]["status_code"], 200);
+        assert_eq!(created["next_attempt_at"], Value::Null);
+
+        // the endpoint can check the signature of the delivery with the secret
+        let request = received
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|request| request.delivery_id == created["id"])
+            .unwrap()
+            .clone();
+        assert_eq!(request.path, "/hooks");
+        assert_eq!(
+            request.signature,
+            format!(
+                "sha256={}",
+                tools::hmac_sha256(&secret, &format!("{}.{}", request.timestamp, request.body))
+            )
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&request.body).unwrap(),
+            created["payload"]
+        );
+
+        // a delivery the endpoint refused stays pending until its retry
+        let deliveries =
+            wait_for_deliveries(&app, &client_id, &format!("webhook_id={}", failing_id), 1).await;
+        assert_eq!(deliveries[0]["status"], "pending");
+        assert_eq!(deliveries[0]["attempts"][0]["status_code"], 500);
+        assert!(deliveries[0]["next_attempt_at"].is_string());
+
+        // a replay is a new delivery of the same event
+        let (status, body) = call(
+            &app,
+            test::TestRequest::post().uri(&format!(
+                "{}/deliveries/{}/replay",
+                uri,
+                created["id"].as_str().unwrap()
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["replay_of"], created["id"]);
+        assert_eq!(body["data"]["event_id"], created["event_id"]);
+        assert_ne!(body["data"]["id"], created["id"]);
+        wait_for_deliveries(
+            &app,
+            &client_id,
+            &format!("webhook_id={}&status=delivered", webhook_id),
+            3,
+        )
+        .await;
+
+        // (request, expected message)
+        let test_cases = vec![
+            (
+                test::TestRequest::post()
+                    .uri(&uri)
+                    .set_json(json!({"url": "ftp://example.com", "events": ["product_created"]})),
+                "url must be an http or https url",
+            ),
+            (
+                test::TestRequest::post()
+                    .uri(&uri)
+                    .set_json(json!({"url": "https://example.com", "events": []})),
+                "events cannot be empty",
+            ),
+            (
+                test::TestRequest::post().uri(&uri).set_json(json!({
+                    "url": "http://169.254.169.254/latest/meta-data",
+                    "events": ["product_created"],
+                })),
+                "url must not point to a private address",
+            ),
+            (
+                test::TestRequest::post().uri(&uri).set_json(json!({
+                    "url": "http://10.0.0.1/hooks",
+                    "events": ["product_created"],
+                })),
+                "url must not point to a private address",
+            ),
+            (
+                test::TestRequest::post().uri(&uri).set_json(json!({
+                    "url": "http://[::1]:8080/hooks",
+                    "events": ["product_created"],
+                })),
+                "url must not point to a private address",
+            ),
+            (
+                test::TestRequest::put()
+                    .uri(&format!("{}/{}", uri, webhook_id))
+                    .set_json(
+                        json!({"url": "http://localhost/hooks", "events": ["product_created"]}),
+                    ),
+                "url must not point to a private address",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/{}", uri, ObjectId::new().to_hex())),
+                "webhook not found",
+            ),
+            (
+                test::TestRequest::post().uri(&format!(
+                    "{}/deliveries/{}/replay",
+                    uri,
+                    ObjectId::new().to_hex()
+                )),
+                "delivery not found",
+            ),
+            (
+                test::TestRequest::get().uri(&format!("{}/deliveries?webhook_id=x", uri)),
+                "invalid webhook id",
+            ),
+        ];
+        assert_errors(&app, test_cases).await;
+    }
+}
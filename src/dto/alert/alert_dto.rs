@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::product::product_dto::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT},
+    errors::app_error::{AppError, ErrorKind},
+    model::{product::Product, stock_alert::StockAlert, stock_movement::MovementReason},
+};
+
+// LowStockProductResponse represents a product at or below its reorder point in a response body,
+// and the product of an out of stock event
+#[derive(Serialize)]
+pub struct LowStockProductResponse {
+    pub id: String,
+    pub name: String,
+    pub sku: String,
+    // quantity is the quantity of the product across its locations
+    pub quantity: i32,
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+}
+
+impl LowStockProductResponse {
+    pub fn new(product: &Product, quantity: i32) -> Self {
+        Self {
+            id: product._id.to_hex(),
+            name: product.name.clone(),
+            sku: product.get_sku(),
+            quantity,
+            reorder_point: product.reorder_point,
+            reorder_quantity: product.reorder_quantity,
+        }
+    }
+}
+
+// GetLowStockResponse represents the response body for listing the products of a client that
+// are low in stock
+#[derive(Serialize)]
+pub struct GetLowStockResponse {
+    pub products: Vec<LowStockProductResponse>,
+}
+
+impl GetLowStockResponse {
+    pub fn new(products: &[(Product, i32)]) -> Self {
+        Self {
+            products: products
+                .iter()
+                .map(|(product, quantity)| LowStockProductResponse::new(product, *quantity))
+                .collect(),
+        }
+    }
+}
+
+// GetStockAlertsRequest represents the request query for listing the stock alerts of a client
+#[derive(Deserialize)]
+pub struct GetStockAlertsRequest {
+    pub limit: Option<i64>,
+}
+
+impl GetStockAlertsRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+                return Err(AppError::new(
+                    &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // get_limit returns the requested number of alerts, the default number if none was requested
+    pub fn get_limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+// StockAlertResponse represents a raised stock alert in a response body
+#[derive(Serialize)]
+pub struct StockAlertResponse {
+    pub id: String,
+    pub product_id: String,
+    pub quantity: i32,
+    pub reorder_point: i32,
+    pub reorder_quantity: Option<i32>,
+    pub reason: MovementReason,
+    pub reference: Option<String>,
+    pub created_at: String,
+}
+
+impl StockAlertResponse {
+    pub fn new(alert: &StockAlert) -> Self {
+        Self {
+            id: alert._id.to_hex(),
+            product_id: alert.product_id.to_hex(),
+            quantity: alert.quantity,
+            reorder_point: alert.reorder_point,
+            reorder_quantity: alert.reorder_quantity,
+            reason: alert.reason,
+            reference: alert.reference.clone(),
+            created_at: alert.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+// GetStockAlertsResponse represents the response body for listing the stock alerts of a client
+#[derive(Serialize)]
+pub struct GetStockAlertsResponse {
+    pub alerts: Vec<StockAlertResponse>,
+}
+
+impl GetStockAlertsResponse {
+    pub fn new(alerts: &[StockAlert]) -> Self {
+        Self {
+            alerts: alerts.iter().map(StockAlertResponse::new).collect(),
+        }
+    }
+}
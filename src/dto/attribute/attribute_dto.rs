@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::attribute::{AttributeDefinition, AttributeType};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and the attribute name
+pub struct ClientIdAttributeName {
+    pub client_id: String,
+    pub name: String,
+}
+
+// AttributeDefinitionRequest represents the request body for defining an attribute
+#[derive(Deserialize)]
+pub struct AttributeDefinitionRequest {
+    #[serde(rename = "type")]
+    pub attribute_type: AttributeType,
+}
+
+// AttributeDefinitionResponse represents an attribute definition in a response body
+#[derive(Serialize)]
+pub struct AttributeDefinitionResponse {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub attribute_type: AttributeType,
+    pub updated_at: String,
+}
+
+impl AttributeDefinitionResponse {
+    pub fn new(definition: &AttributeDefinition) -> Self {
+        Self {
+            name: definition.name.clone(),
+            attribute_type: definition.attribute_type,
+            updated_at: definition
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// GetAttributeDefinitionsResponse represents the response body for listing the attribute
+// definitions of a client
+#[derive(Serialize)]
+pub struct GetAttributeDefinitionsResponse {
+    pub attributes: Vec<AttributeDefinitionResponse>,
+}
+
+impl GetAttributeDefinitionsResponse {
+    pub fn new(definitions: &[AttributeDefinition]) -> Self {
+        Self {
+            attributes: definitions
+                .iter()
+                .map(AttributeDefinitionResponse::new)
+                .collect(),
+        }
+    }
+}
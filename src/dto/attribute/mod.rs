@@ -0,0 +1 @@
+pub mod attribute_dto;
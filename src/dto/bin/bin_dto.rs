@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::bin::{Bin, BinStock},
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and bin id
+pub struct ClientIdBinId {
+    pub client_id: String,
+    pub bin_id: String,
+}
+
+// BinRequest represents the request body for adding a bin to a warehouse
+#[derive(Deserialize)]
+pub struct BinRequest {
+    pub zone: String,
+    pub aisle: String,
+    pub rack: String,
+    pub bin: String,
+}
+
+impl BinRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        for (field, value) in [
+            ("zone", &self.zone),
+            ("aisle", &self.aisle),
+            ("rack", &self.rack),
+            ("bin", &self.bin),
+        ] {
+            if value.trim().is_empty() {
+                return Err(AppError::new(
+                    &format!("{} cannot be empty", field),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// GetBinsRequest represents the request query for listing the bins of a warehouse.
+// The bins can be narrowed down to a zone, an aisle of the zone and a rack of the aisle
+#[derive(Deserialize)]
+pub struct GetBinsRequest {
+    pub zone: Option<String>,
+    pub aisle: Option<String>,
+    pub rack: Option<String>,
+}
+
+// MoveBinStockRequest represents the request body for moving a quantity of a product between
+// bins of a warehouse. Without from_bin_id the quantity is put away from the stock of the
+// warehouse that is not in a bin, without to_bin_id it is taken out of the bins
+#[derive(Deserialize, Serialize)]
+pub struct MoveBinStockRequest {
+    pub product_id: String,
+    pub from_bin_id: Option<String>,
+    pub to_bin_id: Option<String>,
+    pub quantity: i32,
+}
+
+impl MoveBinStockRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.quantity < 1 {
+            return Err(AppError::new(
+                "quantity cannot be less than 1",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if self.from_bin_id.is_none() && self.to_bin_id.is_none() {
+            return Err(AppError::new(
+                "from_bin_id or to_bin_id must be set",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if self.from_bin_id == self.to_bin_id {
+            return Err(AppError::new(
+                "from_bin_id and to_bin_id cannot be the same bin",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// BinResponse represents a bin in a response body
+#[derive(Serialize)]
+pub struct BinResponse {
+    pub id: String,
+    pub warehouse_id: String,
+    pub zone: String,
+    pub aisle: String,
+    pub rack: String,
+    pub bin: String,
+    pub code: String,
+    pub created_at: String,
+}
+
+impl BinResponse {
+    pub fn new(bin: &Bin) -> Self {
+        Self {
+            id: bin._id.to_hex(),
+            warehouse_id: bin.warehouse_id.to_hex(),
+            zone: bin.zone.clone(),
+            aisle: bin.aisle.clone(),
+            rack: bin.rack.clone(),
+            bin: bin.bin.clone(),
+            code: bin.code(),
+            created_at: bin.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+// GetBinsResponse represents the response body for listing the bins of a warehouse
+#[derive(Serialize)]
+pub struct GetBinsResponse {
+    pub bins: Vec<BinResponse>,
+}
+
+impl GetBinsResponse {
+    pub fn new(bins: Vec<BinResponse>) -> Self {
+        Self { bins }
+    }
+}
+
+// BinProductResponse represents the quantity of a product stored in a bin in a response body
+#[derive(Serialize)]
+pub struct BinProductResponse {
+    pub product_id: String,
+    pub quantity: i32,
+}
+
+impl BinProductResponse {
+    pub fn new(bin_stock: &BinStock) -> Self {
+        Self {
+            product_id: bin_stock.product_id.to_hex(),
+            quantity: bin_stock.quantity,
+        }
+    }
+}
+
+// GetBinResponse represents the response body for getting a bin and what is stored in it
+#[derive(Serialize)]
+pub struct GetBinResponse {
+    pub bin: BinResponse,
+    pub products: Vec<BinProductResponse>,
+}
+
+impl GetBinResponse {
+    pub fn new(bin: &Bin, bin_stocks: &[BinStock]) -> Self {
+        Self {
+            bin: BinResponse::new(bin),
+            products: bin_stocks.iter().map(BinProductResponse::new).collect(),
+        }
+    }
+}
+
+// ProductBinResponse represents a bin a product is stored in, in a response body
+#[derive(Serialize)]
+pub struct ProductBinResponse {
+    pub bin_id: String,
+    pub warehouse_id: String,
+    pub code: String,
+    pub quantity: i32,
+}
+
+impl ProductBinResponse {
+    pub fn new(bin: &Bin, bin_stock: &BinStock) -> Self {
+        Self {
+            bin_id: bin._id.to_hex(),
+            warehouse_id: bin.warehouse_id.to_hex(),
+            code: bin.code(),
+            quantity: bin_stock.quantity,
+        }
+    }
+}
+
+// MoveBinStockResponse represents the response body for moving a quantity between bins.
+// The quantities are what the bins hold of the product after the move
+#[derive(Serialize)]
+pub struct MoveBinStockResponse {
+    pub product_id: String,
+    pub from: Option<BinProductQuantityResponse>,
+    pub to: Option<BinProductQuantityResponse>,
+}
+
+impl MoveBinStockResponse {
+    pub fn new(product_id: String, from: Option<&BinStock>, to: Option<&BinStock>) -> Self {
+        Self {
+            product_id,
+            from: from.map(BinProductQuantityResponse::new),
+            to: to.map(BinProductQuantityResponse::new),
+        }
+    }
+}
+
+// BinProductQuantityResponse represents the quantity of a product a bin holds in a response body
+#[derive(Serialize)]
+pub struct BinProductQuantityResponse {
+    pub bin_id: String,
+    pub quantity: i32,
+}
+
+impl BinProductQuantityResponse {
+    pub fn new(bin_stock: &BinStock) -> Self {
+        Self {
+            bin_id: bin_stock.bin_id.to_hex(),
+            quantity: bin_stock.quantity,
+        }
+    }
+}
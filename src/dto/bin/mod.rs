@@ -0,0 +1 @@
+pub mod bin_dto;
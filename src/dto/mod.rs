@@ -1,6 +1,18 @@
 use serde::Serialize;
 
+pub mod alert;
+pub mod attribute;
+pub mod bin;
+pub mod order;
 pub mod product;
+pub mod purchase_order;
+pub mod reservation;
+pub mod return_authorization;
+pub mod sku;
+pub mod stream;
+pub mod supplier;
+pub mod warehouse;
+pub mod webhook;
 
 #[derive(Serialize)]
 pub struct APIResponse<T> {
@@ -0,0 +1 @@
+pub mod order_dto;
@@ -0,0 +1,214 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    dto::product::product_dto::{
+        ProductQuantityRequest, StockAllocationResponse, DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT,
+    },
+    errors::app_error::{AppError, ErrorKind},
+    model::order::{
+        Order, OrderCancellation, OrderCancellationLine, OrderLine, OrderQuery, OrderStatus,
+    },
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and order id
+pub struct ClientIdOrderId {
+    pub client_id: String,
+    pub order_id: String,
+}
+
+// CancelOrderRequest represents the request body for cancelling an order
+#[derive(Deserialize)]
+pub struct CancelOrderRequest {
+    pub reason: String,
+    // lines are the products and quantities to cancel, everything that remains is cancelled if not set
+    pub lines: Option<Vec<ProductQuantityRequest>>,
+}
+
+impl CancelOrderRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.reason.trim().is_empty() {
+            return Err(AppError::new(
+                "reason cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if matches!(&self.lines, Some(lines) if lines.is_empty()) {
+            return Err(AppError::new(
+                "lines cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// GetOrdersRequest represents the request query for paging through the orders of a client,
+// optionally only those in one status or with a line of one product. Pages are numbered from 1
+#[derive(Deserialize)]
+pub struct GetOrdersRequest {
+    pub status: Option<OrderStatus>,
+    pub product_id: Option<String>,
+    pub page: Option<u64>,
+    pub limit: Option<i64>,
+}
+
+impl GetOrdersRequest {
+    // to_query validates the request and converts it to the query of the orders
+    pub fn to_query(&self) -> Result<OrderQuery, AppError> {
+        if self.page == Some(0) {
+            return Err(AppError::new(
+                "page cannot be less than 1",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+                return Err(AppError::new(
+                    &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        let product_id = match &self.product_id {
+            Some(product_id) => match ObjectId::from_str(product_id) {
+                Ok(product_id) => Some(product_id),
+                Err(_) => return Err(AppError::new("invalid product id", ErrorKind::FailedAction)),
+            },
+            None => None,
+        };
+        Ok(OrderQuery {
+            status: self.status,
+            product_id,
+        })
+    }
+
+    // get_page returns the requested page, the first page if none was requested
+    pub fn get_page(&self) -> u64 {
+        self.page.unwrap_or(1)
+    }
+
+    // get_limit returns the requested page size, the default size if none was requested
+    pub fn get_limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+// OrderLineResponse represents a line of an order in a response body
+#[derive(Serialize)]
+pub struct OrderLineResponse {
+    pub product_id: String,
+    pub quantity: i32,
+    pub cancelled_quantity: i32,
+    pub returned_quantity: i32,
+    pub allocations: Vec<StockAllocationResponse>,
+}
+
+impl OrderLineResponse {
+    pub fn new(line: &OrderLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            quantity: line.quantity,
+            cancelled_quantity: line.cancelled_quantity,
+            returned_quantity: line.returned_quantity,
+            allocations: line
+                .get_allocations()
+                .iter()
+                .map(StockAllocationResponse::new)
+                .collect(),
+        }
+    }
+}
+
+// OrderCancellationLineResponse represents a line of an order cancellation in a response body
+#[derive(Serialize)]
+pub struct OrderCancellationLineResponse {
+    pub product_id: String,
+    pub quantity: i32,
+}
+
+impl OrderCancellationLineResponse {
+    pub fn new(line: &OrderCancellationLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            quantity: line.quantity,
+        }
+    }
+}
+
+// OrderCancellationResponse represents a cancellation of an order in a response body
+#[derive(Serialize)]
+pub struct OrderCancellationResponse {
+    pub reason: String,
+    pub actor: String,
+    pub lines: Vec<OrderCancellationLineResponse>,
+    pub created_at: String,
+}
+
+impl OrderCancellationResponse {
+    pub fn new(cancellation: &OrderCancellation) -> Self {
+        Self {
+            reason: cancellation.reason.clone(),
+            actor: cancellation.actor.clone(),
+            lines: cancellation
+                .lines
+                .iter()
+                .map(OrderCancellationLineResponse::new)
+                .collect(),
+            created_at: cancellation
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// OrderResponse represents the response body for processing and getting an order
+#[derive(Serialize)]
+pub struct OrderResponse {
+    pub id: String,
+    pub status: OrderStatus,
+    pub lines: Vec<OrderLineResponse>,
+    pub cancellations: Vec<OrderCancellationResponse>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl OrderResponse {
+    pub fn new(order: &Order) -> Self {
+        Self {
+            id: order._id.to_hex(),
+            status: order.status,
+            lines: order.lines.iter().map(OrderLineResponse::new).collect(),
+            cancellations: order
+                .cancellations
+                .iter()
+                .map(OrderCancellationResponse::new)
+                .collect(),
+            created_at: order.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            updated_at: order.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+// GetOrdersResponse represents the response body for paging through the orders of a client
+#[derive(Serialize)]
+pub struct GetOrdersResponse {
+    pub orders: Vec<OrderResponse>,
+    pub page: u64,
+    pub limit: i64,
+    pub total: u64,
+}
+
+impl GetOrdersResponse {
+    pub fn new(orders: Vec<OrderResponse>, page: u64, limit: i64, total: u64) -> Self {
+        Self {
+            orders,
+            page,
+            limit,
+            total,
+        }
+    }
+}
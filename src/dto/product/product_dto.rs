@@ -1,11 +1,33 @@
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 use crate::{
+    dto::{bin::bin_dto::ProductBinResponse, supplier::supplier_dto::ProductSupplierResponse},
     errors::app_error::{AppError, ErrorKind},
-    model::product::ProductQuantityResponse,
+    model::{
+        attribute::AttributeValue,
+        barcode::Barcode,
+        product::{
+            BundleComponent, Product, ProductCursor, ProductListQuery, ProductQuantityResponse,
+            ProductSearchHit, ProductSort, SortOrder, VariantAxis, MAX_VARIANTS,
+        },
+        sku,
+        stock::{Stock, StockAllocation},
+        stock_movement::{MovementReason, StockMovement},
+    },
 };
 
+// MAX_PAGE_LIMIT is the largest number of items a page can hold
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+// DEFAULT_PAGE_LIMIT is the number of items a page holds when no limit is requested
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+
+// ATTRIBUTE_FILTER_PREFIX starts the query parameters that filter a product listing by the value
+// of an attribute, such as attr.color=red
+const ATTRIBUTE_FILTER_PREFIX: &str = "attr.";
+
 #[derive(Deserialize, Serialize)]
 // struct to aid extractor in extracting the product id
 pub struct ClientId {
@@ -13,36 +35,292 @@ pub struct ClientId {
 }
 
 #[derive(Deserialize, Serialize)]
-// struct to aid extractor in extracting the product id
-pub struct ProductId {
+// struct to aid extractor in extracting the product id and client id
+pub struct ClientIdProductId {
+    pub client_id: String,
     pub product_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-// struct to aid extractor in extracting the product id and client id
-pub struct ClientIdProductId {
+// struct to aid extractor in extracting the client id and sku
+pub struct ClientIdSku {
     pub client_id: String,
-    pub product_id: String,
+    pub sku: String,
+}
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and barcode
+pub struct ClientIdBarcode {
+    pub client_id: String,
+    pub code: String,
 }
 
 // AddProductRequest represents the request body for adding a product
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct AddProductRequest {
     pub name: String,
     pub description: String,
     pub quantity: i32,
+    // warehouse_id is where the quantity is kept, the stock is not assigned to a warehouse if not set
+    pub warehouse_id: Option<String>,
+    // sku is the sku of the product, one is generated for the client if not set
+    pub sku: Option<String>,
+    pub category: Option<String>,
+    // barcodes are EAN-8, UPC-A, EAN-13 or GTIN-14 codes
+    pub barcodes: Option<Vec<String>>,
+    // variant_axes make the product a parent with a variant for every combination of their
+    // values. The quantity is then the quantity of every variant
+    pub variant_axes: Option<Vec<VariantAxis>>,
+    // attributes are custom fields of the product, by name
+    pub attributes: Option<BTreeMap<String, AttributeValue>>,
+    // components make the product a bundle of other products, with how many of each one bundle
+    // holds. A bundle holds no stock, so its quantity must be 0
+    pub components: Option<Vec<ProductQuantityRequest>>,
+    // reorder_point is the quantity at or below which the product is low in stock, and
+    // reorder_quantity is how many to order when it is
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
 }
 
 impl AddProductRequest {
     pub fn validate(&self) -> Result<(), AppError> {
-        if self.quantity < 1 {
+        match (&self.variant_axes, &self.components) {
+            (Some(_), Some(_)) => {
+                return Err(AppError::new(
+                    "a bundle cannot have variants",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            (None, Some(components)) => {
+                parse_components(components)?;
+                if self.quantity != 0 {
+                    return Err(AppError::new(
+                        "quantity of a bundle must be 0, its stock is held by its components",
+                        ErrorKind::FailedAction,
+                    ));
+                }
+            }
+            (Some(variant_axes), None) => {
+                check_variant_axes(variant_axes)?;
+                if self.quantity < 0 {
+                    return Err(AppError::new(
+                        "quantity cannot be less than 0",
+                        ErrorKind::FailedAction,
+                    ));
+                }
+                if self
+                    .barcodes
+                    .as_ref()
+                    .is_some_and(|barcodes| !barcodes.is_empty())
+                {
+                    return Err(AppError::new(
+                        "a product with variants cannot have barcodes, its variants can",
+                        ErrorKind::FailedAction,
+                    ));
+                }
+            }
+            (None, None) if self.quantity < 1 => {
+                return Err(AppError::new(
+                    "quantity cannot be less than 1",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            (None, None) => {}
+        }
+        if let Some(sku) = &self.sku {
+            if !sku::is_valid_sku(sku) {
+                return Err(AppError::new(
+                    &format!(
+                        "invalid sku, a sku is 1 to {} letters, digits, dashes, underscores or dots",
+                        sku::MAX_SKU_LENGTH
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        if let Some(category) = &self.category {
+            if category.trim().is_empty() {
+                return Err(AppError::new(
+                    "category cannot be empty",
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        parse_barcodes(self.barcodes.as_deref().unwrap_or_default())?;
+        check_reorder_values(self.reorder_point, self.reorder_quantity)?;
+        Ok(())
+    }
+
+    // get_barcodes returns the barcodes of a validated request
+    pub fn get_barcodes(&self) -> Vec<Barcode> {
+        parse_barcodes(self.barcodes.as_deref().unwrap_or_default()).unwrap_or_default()
+    }
+
+    // get_components returns the components of a validated request, none if it is not a bundle
+    pub fn get_components(&self) -> Vec<BundleComponent> {
+        match &self.components {
+            Some(components) => parse_components(components).unwrap_or_default(),
+            None => vec![],
+        }
+    }
+}
+
+// parse_components reads the components of a bundle, failing on an invalid product id or
+// quantity, or a product given twice
+fn parse_components(
+    components: &[ProductQuantityRequest],
+) -> Result<Vec<BundleComponent>, AppError> {
+    if components.is_empty() {
+        return Err(AppError::new(
+            "components cannot be empty",
+            ErrorKind::FailedAction,
+        ));
+    }
+
+    let mut parsed: Vec<BundleComponent> = Vec::with_capacity(components.len());
+    for component in components {
+        let product_id = match ObjectId::parse_str(&component.product_id) {
+            Ok(product_id) => product_id,
+            Err(_) => {
+                return Err(AppError::new(
+                    &format!("invalid component product id: {:?}", component.product_id),
+                    ErrorKind::FailedAction,
+                ))
+            }
+        };
+        if component.quantity < 1 {
             return Err(AppError::new(
-                "quantity cannot be less than 1",
+                &format!(
+                    "quantity cannot be less than 1 for component {}",
+                    component.product_id
+                ),
                 ErrorKind::FailedAction,
             ));
         }
-        Ok(())
+        if parsed.iter().any(|other| other.product_id == product_id) {
+            return Err(AppError::new(
+                &format!("component {} is given twice", component.product_id),
+                ErrorKind::FailedAction,
+            ));
+        }
+        parsed.push(BundleComponent {
+            product_id,
+            quantity: component.quantity,
+        });
+    }
+    Ok(parsed)
+}
+
+// check_variant_axes checks that the axes of a product are named, that each has distinct values
+// that can be part of a sku, and that they do not make too many variants
+fn check_variant_axes(variant_axes: &[VariantAxis]) -> Result<(), AppError> {
+    if variant_axes.is_empty() {
+        return Err(AppError::new(
+            "variant_axes cannot be empty",
+            ErrorKind::FailedAction,
+        ));
+    }
+
+    let mut names = HashSet::new();
+    let mut variants: usize = 1;
+    for axis in variant_axes {
+        if axis.name.trim().is_empty() {
+            return Err(AppError::new(
+                "variant axis name cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if !names.insert(axis.name.as_str()) {
+            return Err(AppError::new(
+                &format!("variant axis {} is given twice", axis.name),
+                ErrorKind::FailedAction,
+            ));
+        }
+        if axis.values.is_empty() {
+            return Err(AppError::new(
+                &format!("variant axis {} has no values", axis.name),
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        // the values make the skus of the variants, so they must differ in letters or digits
+        let mut codes = HashSet::new();
+        for value in &axis.values {
+            let code = sku::sku_code(value);
+            if code.is_empty() {
+                return Err(AppError::new(
+                    &format!(
+                        "variant value {:?} of axis {} has no letters or digits",
+                        value, axis.name
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+            if !codes.insert(code) {
+                return Err(AppError::new(
+                    &format!(
+                        "variant value {} of axis {} is given twice",
+                        value, axis.name
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+
+        variants = variants.saturating_mul(axis.values.len());
+    }
+    if variants > MAX_VARIANTS {
+        return Err(AppError::new(
+            &format!("a product cannot have more than {} variants", MAX_VARIANTS),
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+// parse_barcodes reads the barcodes of a product, failing on an invalid barcode or a barcode
+// given twice
+fn parse_barcodes(codes: &[String]) -> Result<Vec<Barcode>, AppError> {
+    let mut barcodes: Vec<Barcode> = Vec::with_capacity(codes.len());
+    for code in codes {
+        let barcode = match Barcode::parse(code) {
+            Some(barcode) => barcode,
+            None => {
+                return Err(AppError::new(
+                    &format!("invalid barcode: {}", code),
+                    ErrorKind::FailedAction,
+                ))
+            }
+        };
+        if barcodes.iter().any(|other| other.gtin == barcode.gtin) {
+            return Err(AppError::new(
+                &format!("barcode {} is given twice", code),
+                ErrorKind::FailedAction,
+            ));
+        }
+        barcodes.push(barcode);
+    }
+    Ok(barcodes)
+}
+
+// check_reorder_values checks the reorder point and the reorder quantity of a product
+fn check_reorder_values(
+    reorder_point: Option<i32>,
+    reorder_quantity: Option<i32>,
+) -> Result<(), AppError> {
+    if reorder_point.is_some_and(|reorder_point| reorder_point < 0) {
+        return Err(AppError::new(
+            "reorder_point cannot be less than 0",
+            ErrorKind::FailedAction,
+        ));
     }
+    if reorder_quantity.is_some_and(|reorder_quantity| reorder_quantity < 1) {
+        return Err(AppError::new(
+            "reorder_quantity cannot be less than 1",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
 }
 
 // AddProductResponse represents the request body for adding a product
@@ -52,18 +330,39 @@ pub struct AddProductResponse {
     pub name: String,
     pub description: String,
     pub sku: String,
+    pub category: Option<String>,
+    pub barcodes: Vec<Barcode>,
+    pub attributes: BTreeMap<String, AttributeValue>,
     pub quantity: i32,
+    pub variant_axes: Vec<VariantAxis>,
+    pub variants: Vec<VariantResponse>,
+    pub components: Vec<BundleComponentResponse>,
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
 }
 
 // AddProductResponse represents the response body for adding a product
 impl AddProductResponse {
-    pub fn new(id: String, name: String, description: String, sku: String, quantity: i32) -> Self {
+    // new builds the response for a product and its variants, which were all created with the
+    // same quantity. The quantity of a bundle is how many its components can make
+    pub fn new(product: &Product, variants: &[Product], quantity: i32) -> Self {
         Self {
-            id,
-            name,
-            description,
-            sku,
-            quantity,
+            id: product._id.to_hex(),
+            name: product.name.clone(),
+            description: product.description.clone(),
+            sku: product.get_sku(),
+            category: product.category.clone(),
+            barcodes: product.barcodes.clone(),
+            attributes: product.attributes.clone(),
+            quantity: quantity * (variants.len().max(1) as i32),
+            variant_axes: product.variant_axes.clone(),
+            variants: variants
+                .iter()
+                .map(|variant| VariantResponse::new(variant, quantity))
+                .collect(),
+            components: BundleComponentResponse::from_components(&product.components),
+            reorder_point: product.reorder_point,
+            reorder_quantity: product.reorder_quantity,
         }
     }
 }
@@ -74,30 +373,322 @@ pub struct GetProductResponse {
     pub id: String,
     pub name: String,
     pub description: String,
+    pub sku: String,
+    pub category: Option<String>,
+    pub barcodes: Vec<Barcode>,
+    pub attributes: BTreeMap<String, AttributeValue>,
+    // quantity is the total quantity of the product across its locations, of its variants for a
+    // parent product, or how many its components can make for a bundle
     pub quantity: i32,
+    pub locations: Vec<StockLocationResponse>,
+    // bins are where the product can be found inside its warehouses
+    pub bins: Vec<ProductBinResponse>,
+    // parent_id and options are set for a variant
+    pub parent_id: Option<String>,
+    pub options: BTreeMap<String, String>,
+    // variant_axes and variants are set for a parent product
+    pub variant_axes: Vec<VariantAxis>,
+    pub variants: Vec<VariantResponse>,
+    // components are set for a bundle
+    pub components: Vec<BundleComponentResponse>,
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+    // suppliers are who the product can be bought from
+    pub suppliers: Vec<ProductSupplierResponse>,
 }
 
 impl GetProductResponse {
-    pub fn new(id: String, name: String, description: String, quantity: i32) -> Self {
+    pub fn new(
+        product: &Product,
+        locations: Vec<StockLocationResponse>,
+        variants: Vec<VariantResponse>,
+        bins: Vec<ProductBinResponse>,
+    ) -> Self {
+        Self {
+            id: product._id.to_hex(),
+            name: product.name.clone(),
+            description: product.description.clone(),
+            sku: product.get_sku(),
+            category: product.category.clone(),
+            barcodes: product.barcodes.clone(),
+            attributes: product.attributes.clone(),
+            quantity: locations.iter().map(|location| location.quantity).sum(),
+            locations,
+            bins,
+            parent_id: product.parent_id.map(|parent_id| parent_id.to_hex()),
+            options: product.options.clone(),
+            variant_axes: product.variant_axes.clone(),
+            variants,
+            components: BundleComponentResponse::from_components(&product.components),
+            reorder_point: product.reorder_point,
+            reorder_quantity: product.reorder_quantity,
+            suppliers: ProductSupplierResponse::from_suppliers(&product.suppliers),
+        }
+    }
+}
+
+// VariantResponse represents a variant of a product with its total quantity in a response body
+#[derive(Serialize)]
+pub struct VariantResponse {
+    pub id: String,
+    pub name: String,
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    pub quantity: i32,
+}
+
+impl VariantResponse {
+    pub fn new(variant: &Product, quantity: i32) -> Self {
         Self {
-            id,
-            name,
-            description,
+            id: variant._id.to_hex(),
+            name: variant.name.clone(),
+            sku: variant.get_sku(),
+            options: variant.options.clone(),
             quantity,
         }
     }
 }
 
-// GetProductsQuantityResponse represents the request body for getting all products
+// BundleComponentResponse represents a product a bundle is made of in a response body
+#[derive(Serialize)]
+pub struct BundleComponentResponse {
+    pub product_id: String,
+    pub quantity: i32,
+}
+
+impl BundleComponentResponse {
+    pub fn from_components(components: &[BundleComponent]) -> Vec<Self> {
+        components
+            .iter()
+            .map(|component| Self {
+                product_id: component.product_id.to_hex(),
+                quantity: component.quantity,
+            })
+            .collect()
+    }
+}
+
+// StockLocationResponse represents the stock of a product at one location in a response body.
+// The warehouse_id is null for stock that is not assigned to a warehouse
+#[derive(Serialize)]
+pub struct StockLocationResponse {
+    pub warehouse_id: Option<String>,
+    pub quantity: i32,
+    pub reserved: i32,
+    pub available: i32,
+}
+
+impl StockLocationResponse {
+    pub fn new(stock: &Stock) -> Self {
+        Self {
+            warehouse_id: stock.warehouse_id.map(|warehouse_id| warehouse_id.to_hex()),
+            quantity: stock.get_quantity(),
+            reserved: stock.get_reserved(),
+            available: stock.get_available(),
+        }
+    }
+
+    // from_stocks adds up the stocks kept at the same location, such as the stocks of the
+    // variants of a product. The locations are in the order they are first found in
+    pub fn from_stocks(stocks: &[Stock]) -> Vec<Self> {
+        let mut locations: Vec<Self> = vec![];
+        for stock in stocks {
+            let warehouse_id = stock.warehouse_id.map(|warehouse_id| warehouse_id.to_hex());
+            match locations
+                .iter_mut()
+                .find(|location| location.warehouse_id == warehouse_id)
+            {
+                Some(location) => {
+                    location.quantity += stock.get_quantity();
+                    location.reserved += stock.get_reserved();
+                    location.available += stock.get_available();
+                }
+                None => locations.push(Self::new(stock)),
+            }
+        }
+        locations
+    }
+}
+
+// StockAllocationResponse represents a quantity taken from or held at one location
+// in a response body
+#[derive(Serialize)]
+pub struct StockAllocationResponse {
+    pub warehouse_id: Option<String>,
+    pub quantity: i32,
+}
+
+impl StockAllocationResponse {
+    pub fn new(allocation: &StockAllocation) -> Self {
+        Self {
+            warehouse_id: allocation.warehouse_id.map(|warehouse_id| warehouse_id.to_hex()),
+            quantity: allocation.quantity,
+        }
+    }
+}
+
+// GetProductsRequest represents the request query for listing the products of a client.
+// The products are sorted by when they were created, oldest first, unless asked otherwise
+#[derive(Deserialize)]
+pub struct GetProductsRequest {
+    // cursor is the next_cursor of the previous page, the first page is returned if not set
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    pub sort: Option<ProductSort>,
+    pub order: Option<SortOrder>,
+    pub min_quantity: Option<i32>,
+    pub max_quantity: Option<i32>,
+    pub out_of_stock: Option<bool>,
+    pub name_prefix: Option<String>,
+}
+
+impl GetProductsRequest {
+    // to_list_query validates the request query and converts it to a product list query
+    pub fn to_list_query(&self) -> Result<ProductListQuery, AppError> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(AppError::new(
+                &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                ErrorKind::FailedAction,
+            ));
+        }
+        if let (Some(min_quantity), Some(max_quantity)) = (self.min_quantity, self.max_quantity) {
+            if min_quantity > max_quantity {
+                return Err(AppError::new(
+                    "min_quantity cannot be greater than max_quantity",
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        let after = match &self.cursor {
+            Some(cursor) => match ProductCursor::decode(cursor) {
+                Some(cursor) => Some(cursor),
+                None => return Err(AppError::new("invalid cursor", ErrorKind::FailedAction)),
+            },
+            None => None,
+        };
+
+        Ok(ProductListQuery {
+            name_prefix: self.name_prefix.clone().filter(|prefix| !prefix.is_empty()),
+            min_quantity: self.min_quantity,
+            max_quantity: self.max_quantity,
+            out_of_stock: self.out_of_stock,
+            attributes: vec![],
+            sort: self.sort.unwrap_or_default(),
+            order: self.order.unwrap_or_default(),
+            after,
+            limit,
+        })
+    }
+}
+
+// attribute_filters picks the attribute filters out of the query parameters of a product
+// listing, as pairs of attribute names and values
+pub fn attribute_filters(parameters: &[(String, String)]) -> Vec<(String, String)> {
+    parameters
+        .iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix(ATTRIBUTE_FILTER_PREFIX)
+                .map(|name| (name.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+// GetProductsQuantityResponse represents the response body for listing the products of a client
 #[derive(Serialize)]
 pub struct GetProductsQuantityResponse {
     pub products_quantity: Vec<ProductQuantityResponse>,
+    // next_cursor is passed as the cursor of the request for the next page, null on the last page
+    pub next_cursor: Option<String>,
+    // total is the number of products matching the filters, on every page
+    pub total: u64,
 }
 
-// GetProductsResponse represents the response body for retrieving a product
 impl GetProductsQuantityResponse {
-    pub fn new(pq: Vec<ProductQuantityResponse>) -> Self {
-        Self { products_quantity: pq }
+    pub fn new(
+        pq: Vec<ProductQuantityResponse>,
+        next_cursor: Option<&ProductCursor>,
+        total: u64,
+    ) -> Self {
+        Self {
+            products_quantity: pq,
+            next_cursor: next_cursor.map(ProductCursor::encode),
+            total,
+        }
+    }
+}
+
+// SearchProductsRequest represents the request query for searching the products of a client
+#[derive(Deserialize)]
+pub struct SearchProductsRequest {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+impl SearchProductsRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.q.trim().is_empty() {
+            return Err(AppError::new(
+                "search text cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+                return Err(AppError::new(
+                    &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // get_text returns the searched text without surrounding whitespace
+    pub fn get_text(&self) -> &str {
+        self.q.trim()
+    }
+
+    // get_limit returns the requested number of results, the default number if none was requested
+    pub fn get_limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+// ProductSearchHitResponse represents a product found by a search in a response body
+#[derive(Serialize)]
+pub struct ProductSearchHitResponse {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub sku: String,
+    pub quantity: i32,
+    // score is the relevance of the product to the search, higher is more relevant
+    pub score: f64,
+}
+
+impl ProductSearchHitResponse {
+    pub fn new(hit: &ProductSearchHit) -> Self {
+        Self {
+            id: hit.product._id.to_hex(),
+            name: hit.product.name.clone(),
+            description: hit.product.description.clone(),
+            sku: hit.product.get_sku(),
+            quantity: hit.quantity,
+            score: hit.score,
+        }
+    }
+}
+
+// SearchProductsResponse represents the response body for searching products
+#[derive(Serialize)]
+pub struct SearchProductsResponse {
+    pub products: Vec<ProductSearchHitResponse>,
+}
+
+impl SearchProductsResponse {
+    pub fn new(products: Vec<ProductSearchHitResponse>) -> Self {
+        Self { products }
     }
 }
 
@@ -106,6 +697,30 @@ impl GetProductsQuantityResponse {
 pub struct UpdateProductRequest {
     pub name: String,
     pub description: String,
+    // barcodes replace the barcodes of the product, which are kept if not set
+    pub barcodes: Option<Vec<String>>,
+    // attributes replace the attributes of the product, which are kept if not set
+    pub attributes: Option<BTreeMap<String, AttributeValue>>,
+    // reorder_point and reorder_quantity replace those of the product, which are kept if not set
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+}
+
+impl UpdateProductRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(barcodes) = &self.barcodes {
+            parse_barcodes(barcodes)?;
+        }
+        check_reorder_values(self.reorder_point, self.reorder_quantity)?;
+        Ok(())
+    }
+
+    // get_barcodes returns the barcodes of a validated request, None if they are not changed
+    pub fn get_barcodes(&self) -> Option<Vec<Barcode>> {
+        self.barcodes
+            .as_deref()
+            .map(|barcodes| parse_barcodes(barcodes).unwrap_or_default())
+    }
 }
 
 // UpdateProductRequest represents the response body for updating a product
@@ -115,24 +730,34 @@ pub struct UpdateProductResponse {
     pub name: String,
     pub description: String,
     pub sku: String,
+    pub barcodes: Vec<Barcode>,
+    pub attributes: BTreeMap<String, AttributeValue>,
+    pub reorder_point: Option<i32>,
+    pub reorder_quantity: Option<i32>,
 }
 
 // UpdateProductResponse represents the response body for updating a product
 impl UpdateProductResponse {
-    pub fn new(id: String, name: String, description: String, sku: String) -> Self {
+    pub fn new(product: &Product) -> Self {
         Self {
-            id,
-            name,
-            description,
-            sku,
+            id: product._id.to_hex(),
+            name: product.name.clone(),
+            description: product.description.clone(),
+            sku: product.get_sku(),
+            barcodes: product.barcodes.clone(),
+            attributes: product.attributes.clone(),
+            reorder_point: product.reorder_point,
+            reorder_quantity: product.reorder_quantity,
         }
     }
 }
 
 // SetProductQuantityRequest represents the request body for updating a product quantity
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct SetProductQuantityRequest {
     pub quantity: i32,
+    // warehouse_id is the location to change, the stock not assigned to a warehouse if not set
+    pub warehouse_id: Option<String>,
 }
 
 // SetProductQuantityResponse represents the response body for updating a product quantity
@@ -151,16 +776,112 @@ impl SetProductQuantityResponse {
 #[derive(Deserialize)]
 pub struct CheckAvailabilityRequest {
     pub number: i32,
+    // warehouse_id limits the check to one location, every location is counted if not set
+    pub warehouse_id: Option<String>,
 }
 
 // ProductQuantityRequest represents the request body for processing an order
 // and checking the availability of multiple products
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProductQuantityRequest {
     pub product_id: String,
     pub quantity: i32,
 }
 
+// GetStockMovementsRequest represents the request query for paging through stock movements.
+// Pages are numbered from 1
+#[derive(Deserialize)]
+pub struct GetStockMovementsRequest {
+    pub page: Option<u64>,
+    pub limit: Option<i64>,
+}
+
+impl GetStockMovementsRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.page == Some(0) {
+            return Err(AppError::new(
+                "page cannot be less than 1",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+                return Err(AppError::new(
+                    &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // get_page returns the requested page, the first page if none was requested
+    pub fn get_page(&self) -> u64 {
+        self.page.unwrap_or(1)
+    }
+
+    // get_limit returns the requested page size, the default size if none was requested
+    pub fn get_limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+// StockMovementResponse represents a stock movement in a response body
+#[derive(Serialize)]
+pub struct StockMovementResponse {
+    pub id: String,
+    pub product_id: String,
+    pub warehouse_id: Option<String>,
+    pub reason: MovementReason,
+    pub actor: String,
+    pub reference: Option<String>,
+    pub delta: i32,
+    pub before: i32,
+    pub after: i32,
+    pub created_at: String,
+    // sequence is the place of the movement among the movements of the client, the stream of
+    // stock changes resumes after it
+    pub sequence: i64,
+}
+
+impl StockMovementResponse {
+    pub fn new(movement: &StockMovement) -> Self {
+        Self {
+            id: movement._id.to_hex(),
+            product_id: movement.product_id.to_hex(),
+            warehouse_id: movement.warehouse_id.map(|warehouse_id| warehouse_id.to_hex()),
+            reason: movement.reason,
+            actor: movement.actor.clone(),
+            reference: movement.reference.clone(),
+            delta: movement.delta,
+            before: movement.before,
+            after: movement.after,
+            created_at: movement.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            sequence: movement.sequence,
+        }
+    }
+}
+
+// GetStockMovementsResponse represents the response body for paging through stock movements
+#[derive(Serialize)]
+pub struct GetStockMovementsResponse {
+    pub movements: Vec<StockMovementResponse>,
+    pub page: u64,
+    pub limit: i64,
+    pub total: u64,
+}
+
+impl GetStockMovementsResponse {
+    pub fn new(movements: Vec<StockMovementResponse>, page: u64, limit: i64, total: u64) -> Self {
+        Self {
+            movements,
+            page,
+            limit,
+            total,
+        }
+    }
+}
+
 /// Data Transfer Objects not involving API requests
 pub struct ProductQuantity {
     pub product_id: ObjectId,
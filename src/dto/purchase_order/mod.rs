@@ -0,0 +1 @@
+pub mod purchase_order_dto;
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::product::product_dto::ProductQuantityRequest,
+    errors::app_error::{AppError, ErrorKind},
+    model::purchase_order::{
+        PurchaseOrder, PurchaseOrderLine, PurchaseOrderReceipt, PurchaseOrderReceiptLine,
+        PurchaseOrderStatus,
+    },
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and purchase order id
+pub struct ClientIdPurchaseOrderId {
+    pub client_id: String,
+    pub purchase_order_id: String,
+}
+
+// CreatePurchaseOrderRequest represents the request body for creating a purchase order
+#[derive(Deserialize)]
+pub struct CreatePurchaseOrderRequest {
+    // reference is the number of the order at the supplier
+    pub reference: Option<String>,
+    // warehouse_id is where the goods are received, not assigned to a warehouse if not set
+    pub warehouse_id: Option<String>,
+    // lines are the products and the quantities expected from the supplier
+    pub lines: Vec<ProductQuantityRequest>,
+}
+
+impl CreatePurchaseOrderRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lines.is_empty() {
+            return Err(AppError::new(
+                "purchase order must have at least one line",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// ReceivePurchaseOrderRequest represents the request body for receiving a delivery of the goods
+// of a purchase order. Lines that are not listed are received with no units
+#[derive(Deserialize)]
+pub struct ReceivePurchaseOrderRequest {
+    // lines are the products and the quantities that actually arrived
+    pub lines: Vec<ProductQuantityRequest>,
+    // warehouse_id is where the goods are received, the location of the purchase order if not
+    // set
+    pub warehouse_id: Option<String>,
+    // last closes the purchase order after the delivery, even if less than expected arrived
+    #[serde(default)]
+    pub last: bool,
+}
+
+impl ReceivePurchaseOrderRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lines.is_empty() {
+            return Err(AppError::new(
+                "lines cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// GetPurchaseOrdersRequest represents the request query for getting the purchase orders of a
+// client
+#[derive(Deserialize)]
+pub struct GetPurchaseOrdersRequest {
+    // status only returns the purchase orders in one status if set
+    pub status: Option<PurchaseOrderStatus>,
+}
+
+// PurchaseOrderLineResponse represents a line of a purchase order in a response body
+#[derive(Serialize)]
+pub struct PurchaseOrderLineResponse {
+    pub product_id: String,
+    pub expected: i32,
+    pub received: i32,
+    // discrepancy is how many more units were received than expected, negative when fewer were
+    pub discrepancy: i32,
+}
+
+impl PurchaseOrderLineResponse {
+    pub fn new(line: &PurchaseOrderLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            expected: line.expected,
+            received: line.received,
+            discrepancy: line.discrepancy(),
+        }
+    }
+}
+
+// PurchaseOrderReceiptLineResponse represents a line of a delivery in a response body
+#[derive(Serialize)]
+pub struct PurchaseOrderReceiptLineResponse {
+    pub product_id: String,
+    pub quantity: i32,
+}
+
+impl PurchaseOrderReceiptLineResponse {
+    pub fn new(line: &PurchaseOrderReceiptLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            quantity: line.quantity,
+        }
+    }
+}
+
+// PurchaseOrderReceiptResponse represents a delivery of a purchase order in a response body
+#[derive(Serialize)]
+pub struct PurchaseOrderReceiptResponse {
+    pub actor: String,
+    pub warehouse_id: Option<String>,
+    pub lines: Vec<PurchaseOrderReceiptLineResponse>,
+    pub created_at: String,
+}
+
+impl PurchaseOrderReceiptResponse {
+    pub fn new(receipt: &PurchaseOrderReceipt) -> Self {
+        Self {
+            actor: receipt.actor.clone(),
+            warehouse_id: receipt
+                .warehouse_id
+                .map(|warehouse_id| warehouse_id.to_hex()),
+            lines: receipt
+                .lines
+                .iter()
+                .map(PurchaseOrderReceiptLineResponse::new)
+                .collect(),
+            created_at: receipt
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// PurchaseOrderResponse represents the response body for creating, getting, receiving and
+// closing a purchase order
+#[derive(Serialize)]
+pub struct PurchaseOrderResponse {
+    pub id: String,
+    pub reference: Option<String>,
+    pub warehouse_id: Option<String>,
+    pub status: PurchaseOrderStatus,
+    pub lines: Vec<PurchaseOrderLineResponse>,
+    // discrepancies are the lines where the quantity received so far differs from the quantity
+    // expected
+    pub discrepancies: Vec<PurchaseOrderLineResponse>,
+    pub receipts: Vec<PurchaseOrderReceiptResponse>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+}
+
+impl PurchaseOrderResponse {
+    pub fn new(purchase_order: &PurchaseOrder) -> Self {
+        Self {
+            id: purchase_order._id.to_hex(),
+            reference: purchase_order.reference.clone(),
+            warehouse_id: purchase_order
+                .warehouse_id
+                .map(|warehouse_id| warehouse_id.to_hex()),
+            status: purchase_order.status,
+            lines: purchase_order
+                .lines
+                .iter()
+                .map(PurchaseOrderLineResponse::new)
+                .collect(),
+            discrepancies: purchase_order
+                .lines
+                .iter()
+                .filter(|line| line.discrepancy() != 0)
+                .map(PurchaseOrderLineResponse::new)
+                .collect(),
+            receipts: purchase_order
+                .receipts
+                .iter()
+                .map(PurchaseOrderReceiptResponse::new)
+                .collect(),
+            created_at: purchase_order
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: purchase_order
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            closed_at: purchase_order
+                .closed_at
+                .map(|closed_at| closed_at.try_to_rfc3339_string().unwrap_or_default()),
+        }
+    }
+}
+
+// GetPurchaseOrdersResponse represents the response body for getting the purchase orders of a
+// client
+#[derive(Serialize)]
+pub struct GetPurchaseOrdersResponse {
+    pub purchase_orders: Vec<PurchaseOrderResponse>,
+}
+
+impl GetPurchaseOrdersResponse {
+    pub fn new(purchase_orders: &[PurchaseOrder]) -> Self {
+        Self {
+            purchase_orders: purchase_orders
+                .iter()
+                .map(PurchaseOrderResponse::new)
+                .collect(),
+        }
+    }
+}
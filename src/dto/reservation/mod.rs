@@ -0,0 +1 @@
+pub mod reservation_dto;
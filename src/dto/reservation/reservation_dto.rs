@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::product::product_dto::{ProductQuantityRequest, StockAllocationResponse},
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        reservation::{Reservation, ReservationLine, ReservationStatus},
+        stock::allocations_or_unassigned,
+    },
+};
+
+// MAX_RESERVATION_TTL_SECONDS is the longest a reservation can hold stock
+const MAX_RESERVATION_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and reservation id
+pub struct ClientIdReservationId {
+    pub client_id: String,
+    pub reservation_id: String,
+}
+
+// ReserveRequest represents the request body for reserving stock
+#[derive(Deserialize)]
+pub struct ReserveRequest {
+    pub lines: Vec<ProductQuantityRequest>,
+    // ttl_seconds is how long the stock is held for, the service default is used if not set
+    pub ttl_seconds: Option<i64>,
+    // warehouse_id holds the stock at one location, it is held across locations if not set
+    pub warehouse_id: Option<String>,
+}
+
+impl ReserveRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lines.is_empty() {
+            return Err(AppError::new(
+                "reservation must have at least one line",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if let Some(ttl_seconds) = self.ttl_seconds {
+            if !(1..=MAX_RESERVATION_TTL_SECONDS).contains(&ttl_seconds) {
+                return Err(AppError::new(
+                    &format!(
+                        "ttl_seconds must be between 1 and {}",
+                        MAX_RESERVATION_TTL_SECONDS
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// ReservationLineResponse represents a line of a reservation in a response body
+#[derive(Serialize)]
+pub struct ReservationLineResponse {
+    pub product_id: String,
+    pub quantity: i32,
+    pub allocations: Vec<StockAllocationResponse>,
+}
+
+impl ReservationLineResponse {
+    pub fn new(line: &ReservationLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            quantity: line.quantity,
+            allocations: allocations_or_unassigned(&line.allocations, line.quantity)
+                .iter()
+                .map(StockAllocationResponse::new)
+                .collect(),
+        }
+    }
+}
+
+// ReservationResponse represents the response body for reserving, getting,
+// committing and releasing a reservation
+#[derive(Serialize)]
+pub struct ReservationResponse {
+    pub id: String,
+    pub status: ReservationStatus,
+    pub lines: Vec<ReservationLineResponse>,
+    pub order_id: Option<String>,
+    pub expires_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl ReservationResponse {
+    pub fn new(reservation: &Reservation) -> Self {
+        Self {
+            id: reservation._id.to_hex(),
+            status: reservation.status,
+            lines: reservation
+                .lines
+                .iter()
+                .map(ReservationLineResponse::new)
+                .collect(),
+            order_id: reservation.order_id.map(|order_id| order_id.to_hex()),
+            expires_at: reservation
+                .expires_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            created_at: reservation
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: reservation
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
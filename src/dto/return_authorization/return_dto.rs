@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::product::product_dto::ProductQuantityRequest,
+    errors::app_error::{AppError, ErrorKind},
+    model::return_authorization::{ReturnAuthorization, ReturnLine, ReturnStatus},
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and return id
+pub struct ClientIdReturnId {
+    pub client_id: String,
+    pub return_id: String,
+}
+
+// AuthorizeReturnRequest represents the request body for authorizing a return against an order
+#[derive(Deserialize)]
+pub struct AuthorizeReturnRequest {
+    pub order_id: String,
+    pub reason: Option<String>,
+    // lines are the products of the order and the quantities the customer may send back
+    pub lines: Vec<ProductQuantityRequest>,
+}
+
+impl AuthorizeReturnRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lines.is_empty() {
+            return Err(AppError::new(
+                "return must have at least one line",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// ReceiveReturnLineRequest is the number of units of a product that arrived in each condition
+#[derive(Deserialize)]
+pub struct ReceiveReturnLineRequest {
+    pub product_id: String,
+    #[serde(default)]
+    pub resellable: i32,
+    #[serde(default)]
+    pub damaged: i32,
+    #[serde(default)]
+    pub scrap: i32,
+}
+
+// ReceiveReturnRequest represents the request body for receiving the goods of a return.
+// Authorized lines that are not listed are received with no units
+#[derive(Deserialize)]
+pub struct ReceiveReturnRequest {
+    pub lines: Vec<ReceiveReturnLineRequest>,
+    // warehouse_id is where resellable units go back to, the locations the order lines were
+    // taken from if not set
+    pub warehouse_id: Option<String>,
+}
+
+impl ReceiveReturnRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lines.is_empty() {
+            return Err(AppError::new(
+                "lines cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        for line in &self.lines {
+            if line.resellable < 0 || line.damaged < 0 || line.scrap < 0 {
+                return Err(AppError::new(
+                    &format!(
+                        "received quantities cannot be negative for product_id: {:?}",
+                        line.product_id
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// GetReturnsRequest represents the request query for getting the returns of a client
+#[derive(Deserialize)]
+pub struct GetReturnsRequest {
+    // order_id only returns the returns of one order if set
+    pub order_id: Option<String>,
+}
+
+// ReturnLineResponse represents a line of a return in a response body
+#[derive(Serialize)]
+pub struct ReturnLineResponse {
+    pub product_id: String,
+    pub quantity: i32,
+    pub warehouse_id: Option<String>,
+    pub resellable: i32,
+    pub damaged: i32,
+    pub scrap: i32,
+}
+
+impl ReturnLineResponse {
+    pub fn new(line: &ReturnLine) -> Self {
+        Self {
+            product_id: line.product_id.to_hex(),
+            quantity: line.quantity,
+            warehouse_id: line.warehouse_id.map(|warehouse_id| warehouse_id.to_hex()),
+            resellable: line.resellable,
+            damaged: line.damaged,
+            scrap: line.scrap,
+        }
+    }
+}
+
+// ReturnResponse represents the response body for authorizing, getting, receiving
+// and closing a return
+#[derive(Serialize)]
+pub struct ReturnResponse {
+    pub id: String,
+    pub order_id: String,
+    pub status: ReturnStatus,
+    pub reason: Option<String>,
+    pub lines: Vec<ReturnLineResponse>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub received_at: Option<String>,
+    pub closed_at: Option<String>,
+}
+
+impl ReturnResponse {
+    pub fn new(return_authorization: &ReturnAuthorization) -> Self {
+        Self {
+            id: return_authorization._id.to_hex(),
+            order_id: return_authorization.order_id.to_hex(),
+            status: return_authorization.status,
+            reason: return_authorization.reason.clone(),
+            lines: return_authorization
+                .lines
+                .iter()
+                .map(ReturnLineResponse::new)
+                .collect(),
+            created_at: return_authorization
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: return_authorization
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            received_at: return_authorization
+                .received_at
+                .map(|received_at| received_at.try_to_rfc3339_string().unwrap_or_default()),
+            closed_at: return_authorization
+                .closed_at
+                .map(|closed_at| closed_at.try_to_rfc3339_string().unwrap_or_default()),
+        }
+    }
+}
+
+// GetReturnsResponse represents the response body for getting the returns of a client
+#[derive(Serialize)]
+pub struct GetReturnsResponse {
+    pub returns: Vec<ReturnResponse>,
+}
+
+impl GetReturnsResponse {
+    pub fn new(returns: Vec<ReturnResponse>) -> Self {
+        Self { returns }
+    }
+}
@@ -0,0 +1 @@
+pub mod sku_dto;
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::sku::{SkuSettings, SkuStrategy};
+
+// SkuSettingsRequest represents the request body for setting how the skus of a client are made
+#[derive(Deserialize)]
+pub struct SkuSettingsRequest {
+    pub strategy: SkuStrategy,
+}
+
+// SkuSettingsResponse represents the response body for getting and setting the sku settings
+#[derive(Serialize)]
+pub struct SkuSettingsResponse {
+    pub client_id: String,
+    pub strategy: SkuStrategy,
+    pub updated_at: String,
+}
+
+impl SkuSettingsResponse {
+    pub fn new(settings: &SkuSettings) -> Self {
+        Self {
+            client_id: settings.client_id.to_hex(),
+            strategy: settings.strategy.clone(),
+            updated_at: settings
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
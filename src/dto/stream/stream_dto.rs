@@ -0,0 +1,98 @@
+use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
+
+use crate::{
+    dto::product::product_dto::StockMovementResponse,
+    errors::app_error::{AppError, ErrorKind},
+    model::stock_movement::StockMovement,
+};
+
+// PRODUCT_FILTER_PARAMETER is the query parameter naming a product to stream the changes of,
+// it can be given more than once
+const PRODUCT_FILTER_PARAMETER: &str = "product_id";
+
+// LAST_EVENT_ID_PARAMETER is the query parameter resuming a stream after an event, for clients
+// that cannot set the Last-Event-ID header
+const LAST_EVENT_ID_PARAMETER: &str = "last_event_id";
+
+// STOCK_CHANGED_EVENT is the name of the server-sent events of stock changes
+const STOCK_CHANGED_EVENT: &str = "stock_changed";
+
+// KEEP_ALIVE_COMMENT is sent when there are no changes to keep the connection open. Clients
+// ignore comments
+const KEEP_ALIVE_COMMENT: &str = ": keep-alive\n\n";
+
+// StockStreamRequest represents the query of a stream of stock changes
+pub struct StockStreamRequest {
+    // product_ids are the products to stream the changes of, every product if empty
+    pub product_ids: Vec<ObjectId>,
+    // last_event_id is the id of the last event the client got, which is the sequence number
+    // of its movement. The stream resumes after it
+    pub last_event_id: Option<i64>,
+}
+
+impl StockStreamRequest {
+    // from_parameters reads the request from the query parameters and the Last-Event-ID header
+    // of a stream. The header takes precedence over the query parameter
+    pub fn from_parameters(
+        parameters: &[(String, String)],
+        last_event_id: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let mut product_ids = vec![];
+        let mut last_event_id = last_event_id.map(String::from);
+        for (key, value) in parameters {
+            match key.as_str() {
+                PRODUCT_FILTER_PARAMETER => match ObjectId::from_str(value) {
+                    Ok(product_id) if !product_ids.contains(&product_id) => {
+                        product_ids.push(product_id)
+                    }
+                    Ok(_) => {}
+                    Err(_) => {
+                        return Err(AppError::new("invalid product id", ErrorKind::FailedAction))
+                    }
+                },
+                LAST_EVENT_ID_PARAMETER if last_event_id.is_none() => {
+                    last_event_id = Some(value.clone())
+                }
+                _ => {}
+            }
+        }
+
+        let last_event_id = match last_event_id {
+            Some(last_event_id) => match last_event_id.parse::<i64>() {
+                Ok(last_event_id) if last_event_id >= 0 => Some(last_event_id),
+                _ => {
+                    return Err(AppError::new(
+                        "invalid last event id",
+                        ErrorKind::FailedAction,
+                    ))
+                }
+            },
+            None => None,
+        };
+        Ok(Self {
+            product_ids,
+            last_event_id,
+        })
+    }
+}
+
+// stock_change_events writes a batch of stock movements as server-sent events, each with the
+// sequence number of its movement as id so that the client can resume after it. An empty batch is written as a
+// keep-alive comment
+pub fn stock_change_events(movements: &[StockMovement]) -> String {
+    if movements.is_empty() {
+        return KEEP_ALIVE_COMMENT.to_string();
+    }
+    movements
+        .iter()
+        .map(|movement| {
+            format!(
+                "id: {}\nevent: {}\ndata: {}\n\n",
+                movement.sequence,
+                STOCK_CHANGED_EVENT,
+                serde_json::to_string(&StockMovementResponse::new(movement)).unwrap_or_default()
+            )
+        })
+        .collect()
+}
@@ -0,0 +1 @@
+pub mod supplier_dto;
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::{product::ProductSupplier, supplier::Supplier},
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and supplier id
+pub struct ClientIdSupplierId {
+    pub client_id: String,
+    pub supplier_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id, product id and supplier id
+pub struct ClientIdProductIdSupplierId {
+    pub client_id: String,
+    pub product_id: String,
+    pub supplier_id: String,
+}
+
+// SupplierRequest represents the request body for creating and updating a supplier
+#[derive(Deserialize)]
+pub struct SupplierRequest {
+    pub name: String,
+    pub contact: Option<String>,
+    pub lead_time_days: Option<i32>,
+    pub minimum_order_quantity: Option<i32>,
+}
+
+impl SupplierRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.name.trim().is_empty() {
+            return Err(AppError::new(
+                "name cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if self
+            .lead_time_days
+            .is_some_and(|lead_time_days| lead_time_days < 0)
+        {
+            return Err(AppError::new(
+                "lead_time_days cannot be less than 0",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if self
+            .minimum_order_quantity
+            .is_some_and(|minimum_order_quantity| minimum_order_quantity < 1)
+        {
+            return Err(AppError::new(
+                "minimum_order_quantity cannot be less than 1",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// SupplierResponse represents the response body for creating, getting and updating a supplier
+#[derive(Serialize)]
+pub struct SupplierResponse {
+    pub id: String,
+    pub name: String,
+    pub contact: Option<String>,
+    pub lead_time_days: Option<i32>,
+    pub minimum_order_quantity: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl SupplierResponse {
+    pub fn new(supplier: &Supplier) -> Self {
+        Self {
+            id: supplier._id.to_hex(),
+            name: supplier.name.clone(),
+            contact: supplier.contact.clone(),
+            lead_time_days: supplier.lead_time_days,
+            minimum_order_quantity: supplier.minimum_order_quantity,
+            created_at: supplier
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: supplier
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// GetSuppliersResponse represents the response body for getting all suppliers of a client
+#[derive(Serialize)]
+pub struct GetSuppliersResponse {
+    pub suppliers: Vec<SupplierResponse>,
+}
+
+impl GetSuppliersResponse {
+    pub fn new(suppliers: Vec<SupplierResponse>) -> Self {
+        Self { suppliers }
+    }
+}
+
+// ProductSupplierRequest represents the request body for linking a product to a supplier
+#[derive(Deserialize)]
+pub struct ProductSupplierRequest {
+    // supplier_sku is the sku the supplier knows the product by
+    pub supplier_sku: Option<String>,
+    // unit_cost is what one unit of the product costs from the supplier
+    pub unit_cost: f64,
+}
+
+impl ProductSupplierRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self
+            .supplier_sku
+            .as_ref()
+            .is_some_and(|supplier_sku| supplier_sku.trim().is_empty())
+        {
+            return Err(AppError::new(
+                "supplier_sku cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        if !self.unit_cost.is_finite() || self.unit_cost < 0.0 {
+            return Err(AppError::new(
+                "unit_cost cannot be less than 0",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// ProductSupplierResponse represents a supplier a product can be bought from in a response body
+#[derive(Serialize)]
+pub struct ProductSupplierResponse {
+    pub supplier_id: String,
+    pub supplier_sku: Option<String>,
+    pub unit_cost: f64,
+}
+
+impl ProductSupplierResponse {
+    pub fn from_suppliers(suppliers: &[ProductSupplier]) -> Vec<Self> {
+        suppliers
+            .iter()
+            .map(|link| Self {
+                supplier_id: link.supplier_id.to_hex(),
+                supplier_sku: link.supplier_sku.clone(),
+                unit_cost: link.unit_cost,
+            })
+            .collect()
+    }
+}
+
+// GetProductSuppliersResponse represents the response body for linking a product to a supplier
+// and unlinking it, with the suppliers the product is left linked to
+#[derive(Serialize)]
+pub struct GetProductSuppliersResponse {
+    pub product_id: String,
+    pub suppliers: Vec<ProductSupplierResponse>,
+}
+
+impl GetProductSuppliersResponse {
+    pub fn new(product_id: String, suppliers: &[ProductSupplier]) -> Self {
+        Self {
+            product_id,
+            suppliers: ProductSupplierResponse::from_suppliers(suppliers),
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod warehouse_dto;
@@ -0,0 +1,95 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::warehouse::Warehouse,
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and warehouse id
+pub struct ClientIdWarehouseId {
+    pub client_id: String,
+    pub warehouse_id: String,
+}
+
+// WarehouseRequest represents the request body for creating and updating a warehouse
+#[derive(Deserialize)]
+pub struct WarehouseRequest {
+    pub name: String,
+    pub address: Option<String>,
+}
+
+impl WarehouseRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.name.trim().is_empty() {
+            return Err(AppError::new(
+                "name cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(())
+    }
+}
+
+// LocationQuery represents the request query for limiting a stock operation to one warehouse.
+// Every location is used when no warehouse is given
+#[derive(Deserialize, Serialize)]
+pub struct LocationQuery {
+    pub warehouse_id: Option<String>,
+}
+
+// parse_warehouse_id converts an optional warehouse id from string to an objectId
+pub fn parse_warehouse_id(warehouse_id: &Option<String>) -> Result<Option<ObjectId>, AppError> {
+    match warehouse_id {
+        Some(warehouse_id) => match ObjectId::from_str(warehouse_id.as_str()) {
+            Ok(warehouse_id) => Ok(Some(warehouse_id)),
+            Err(_) => Err(AppError::new(
+                "invalid warehouse id",
+                ErrorKind::FailedAction,
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+// WarehouseResponse represents the response body for creating, getting and updating a warehouse
+#[derive(Serialize)]
+pub struct WarehouseResponse {
+    pub id: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl WarehouseResponse {
+    pub fn new(warehouse: &Warehouse) -> Self {
+        Self {
+            id: warehouse._id.to_hex(),
+            name: warehouse.name.clone(),
+            address: warehouse.address.clone(),
+            created_at: warehouse
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: warehouse
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// GetWarehousesResponse represents the response body for getting all warehouses of a client
+#[derive(Serialize)]
+pub struct GetWarehousesResponse {
+    pub warehouses: Vec<WarehouseResponse>,
+}
+
+impl GetWarehousesResponse {
+    pub fn new(warehouses: Vec<WarehouseResponse>) -> Self {
+        Self { warehouses }
+    }
+}
@@ -0,0 +1,278 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::{
+    dto::product::product_dto::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT},
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        product::Product,
+        webhook::{
+            DeliveryAttempt, DeliveryQuery, DeliveryStatus, Webhook, WebhookDelivery, WebhookEvent,
+        },
+    },
+    utils::tools,
+};
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and webhook id
+pub struct ClientIdWebhookId {
+    pub client_id: String,
+    pub webhook_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+// struct to aid extractor in extracting the client id and delivery id
+pub struct ClientIdDeliveryId {
+    pub client_id: String,
+    pub delivery_id: String,
+}
+
+// WebhookRequest represents the request body for registering and updating a webhook
+#[derive(Deserialize)]
+pub struct WebhookRequest {
+    // url is the http or https endpoint the events are posted to
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    // active disables the webhook without deleting it when false, it is active if not set
+    pub active: Option<bool>,
+}
+
+impl WebhookRequest {
+    // validate checks the request, and that the url does not point to a private address unless
+    // its host is one of the allowed hosts
+    pub async fn validate(&self, allowed_hosts: &[String]) -> Result<(), AppError> {
+        let url = match reqwest::Url::parse(&self.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            _ => {
+                return Err(AppError::new(
+                    "url must be an http or https url",
+                    ErrorKind::FailedAction,
+                ))
+            }
+        };
+        if self.events.is_empty() {
+            return Err(AppError::new(
+                "events cannot be empty",
+                ErrorKind::FailedAction,
+            ));
+        }
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or_default();
+        tools::resolve_public_host(host, port, allowed_hosts).await?;
+        Ok(())
+    }
+
+    // get_events returns the events of the request, each one once
+    pub fn get_events(&self) -> Vec<WebhookEvent> {
+        let mut events: Vec<WebhookEvent> = vec![];
+        for event in &self.events {
+            if !events.contains(event) {
+                events.push(*event);
+            }
+        }
+        events
+    }
+}
+
+// WebhookResponse represents a webhook in a response body. The secret is only given when the
+// webhook is registered
+#[derive(Serialize)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl WebhookResponse {
+    pub fn new(webhook: &Webhook) -> Self {
+        Self {
+            id: webhook._id.to_hex(),
+            url: webhook.url.clone(),
+            events: webhook.events.clone(),
+            active: webhook.active,
+            secret: None,
+            created_at: webhook
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            updated_at: webhook
+                .updated_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+
+    // with_secret builds the response for a webhook that was just registered
+    pub fn with_secret(webhook: &Webhook) -> Self {
+        Self {
+            secret: Some(webhook.secret.clone()),
+            ..Self::new(webhook)
+        }
+    }
+}
+
+// GetWebhooksResponse represents the response body for listing the webhooks of a client
+#[derive(Serialize)]
+pub struct GetWebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+}
+
+impl GetWebhooksResponse {
+    pub fn new(webhooks: &[Webhook]) -> Self {
+        Self {
+            webhooks: webhooks.iter().map(WebhookResponse::new).collect(),
+        }
+    }
+}
+
+// GetDeliveriesRequest represents the request query for listing the webhook deliveries of a
+// client
+#[derive(Deserialize)]
+pub struct GetDeliveriesRequest {
+    pub webhook_id: Option<String>,
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<i64>,
+}
+
+impl GetDeliveriesRequest {
+    // to_query validates the request and converts it to the query of the deliveries
+    pub fn to_query(&self) -> Result<DeliveryQuery, AppError> {
+        let webhook_id = match &self.webhook_id {
+            Some(webhook_id) => match ObjectId::from_str(webhook_id) {
+                Ok(webhook_id) => Some(webhook_id),
+                Err(_) => return Err(AppError::new("invalid webhook id", ErrorKind::FailedAction)),
+            },
+            None => None,
+        };
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(AppError::new(
+                &format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                ErrorKind::FailedAction,
+            ));
+        }
+        Ok(DeliveryQuery {
+            webhook_id,
+            status: self.status,
+            limit,
+        })
+    }
+}
+
+// DeliveryAttemptResponse represents an attempt of a webhook delivery in a response body
+#[derive(Serialize)]
+pub struct DeliveryAttemptResponse {
+    pub attempted_at: String,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl DeliveryAttemptResponse {
+    pub fn new(attempt: &DeliveryAttempt) -> Self {
+        Self {
+            attempted_at: attempt
+                .attempted_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+            status_code: attempt.status_code,
+            error: attempt.error.clone(),
+        }
+    }
+}
+
+// DeliveryResponse represents a webhook delivery in a response body, with the payload that was
+// sent
+#[derive(Serialize)]
+pub struct DeliveryResponse {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_id: String,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub payload: Value,
+    pub attempts: Vec<DeliveryAttemptResponse>,
+    pub next_attempt_at: Option<String>,
+    pub replay_of: Option<String>,
+    pub created_at: String,
+}
+
+impl DeliveryResponse {
+    pub fn new(delivery: &WebhookDelivery) -> Self {
+        Self {
+            id: delivery._id.to_hex(),
+            webhook_id: delivery.webhook_id.to_hex(),
+            event_id: delivery.event_id.to_hex(),
+            event: delivery.event,
+            status: delivery.status,
+            payload: serde_json::from_str(&delivery.payload).unwrap_or(Value::Null),
+            attempts: delivery
+                .attempts
+                .iter()
+                .map(DeliveryAttemptResponse::new)
+                .collect(),
+            next_attempt_at: delivery
+                .next_attempt_at
+                .map(|next_attempt_at| next_attempt_at.try_to_rfc3339_string().unwrap_or_default()),
+            replay_of: delivery.replay_of.map(|replay_of| replay_of.to_hex()),
+            created_at: delivery
+                .created_at
+                .try_to_rfc3339_string()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// GetDeliveriesResponse represents the response body for listing webhook deliveries
+#[derive(Serialize)]
+pub struct GetDeliveriesResponse {
+    pub deliveries: Vec<DeliveryResponse>,
+}
+
+impl GetDeliveriesResponse {
+    pub fn new(deliveries: &[WebhookDelivery]) -> Self {
+        Self {
+            deliveries: deliveries.iter().map(DeliveryResponse::new).collect(),
+        }
+    }
+}
+
+// EventPayload represents the body posted to a webhook for an event
+#[derive(Serialize)]
+pub struct EventPayload<'a, T: Serialize> {
+    pub id: String,
+    pub event: WebhookEvent,
+    pub client_id: String,
+    pub created_at: String,
+    pub data: &'a T,
+}
+
+// ProductEventData represents a product in the payload of a product event
+#[derive(Serialize)]
+pub struct ProductEventData {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub sku: String,
+    pub category: Option<String>,
+    pub parent_id: Option<String>,
+}
+
+impl ProductEventData {
+    pub fn new(product: &Product) -> Self {
+        Self {
+            id: product._id.to_hex(),
+            name: product.name.clone(),
+            description: product.description.clone(),
+            sku: product.get_sku(),
+            category: product.category.clone(),
+            parent_id: product.parent_id.map(|parent_id| parent_id.to_hex()),
+        }
+    }
+}
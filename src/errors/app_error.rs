@@ -8,6 +8,7 @@ pub enum ErrorKind {
     InternalServerError,
     NotFound,
     FailedAction,
+    Conflict,
 }
 
 // AppError is a custom warehouse application error
@@ -23,6 +24,7 @@ impl AppError {
         let status_code = match err_kind {
             ErrorKind::FailedAction => 400,
             ErrorKind::NotFound => 404,
+            ErrorKind::Conflict => 409,
             ErrorKind::InternalServerError => 500,
         };
 
@@ -33,13 +35,14 @@ impl AppError {
         }
     }
 
-    pub fn to_responder(self) -> HttpResponse {
+    pub fn to_responder(&self) -> HttpResponse {
         match self.kind {
             ErrorKind::InternalServerError => HttpResponse::InternalServerError().finish(),
             ErrorKind::NotFound => HttpResponse::BadRequest()
                 .reason("resource not found")
                 .json(self),
             ErrorKind::FailedAction => HttpResponse::BadRequest().json(self),
+            ErrorKind::Conflict => HttpResponse::Conflict().json(self),
         }
     }
 }
@@ -9,8 +9,8 @@ mod utils;
 
 use dotenv::dotenv;
 use log::info;
-use mongodb::{options::ClientOptions, Client};
-use repository::mongo;
+use mongodb::{options::ClientOptions, Client, Database};
+use repository::{mongo, stores::Stores};
 use server::start_server;
 use std::env;
 
@@ -26,6 +26,23 @@ async fn main() -> Result<(), std::io::Error> {
     // initialize the logger
     env_logger::init();
 
+    // keep every document in memory when STORAGE_BACKEND is "memory", so that the service can
+    // run without a database, e.g. in CI or for local development
+    let stores = match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("memory") => {
+            info!("Using in-memory storage, nothing is kept when the service stops");
+            Stores::memory()
+        }
+        _ => Stores::mongo(&connect_database().await),
+    };
+
+    // start the server
+    start_server(stores).await
+}
+
+// connect_database connects to the database of the current environment and creates the
+// indexes the repositories rely on
+async fn connect_database() -> Database {
     // get the environment currently working on
     let build_env = env::var("BUILD_ENVIRONMENT").expect("BUILD_ENVIRONMENT must be set in env");
 
@@ -37,7 +54,7 @@ async fn main() -> Result<(), std::io::Error> {
     };
 
     // get the database url from the env
-    let database_url = env::var(db_url_env_name.clone()).expect(format!("{} must be set in env", db_url_env_name).as_str());
+    let database_url = env::var(db_url_env_name.clone()).unwrap_or_else(|_| panic!("{} must be set in env", db_url_env_name));
 
     // get a handle on the client connection using the client options to build it
     let client_opts = ClientOptions::parse(&database_url)
@@ -51,6 +68,10 @@ async fn main() -> Result<(), std::io::Error> {
 
     info!("Connected to database successfully!");
 
-    // start the server
-    start_server(db.clone()).await
+    // create the indexes the repositories rely on
+    mongo::ensure_indexes(&db)
+        .await
+        .expect("failed to create database indexes");
+
+    db
 }
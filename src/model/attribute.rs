@@ -0,0 +1,175 @@
+use bson::{oid::ObjectId, Bson, DateTime};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// MAX_ATTRIBUTE_NAME_LENGTH is the longest name an attribute can have
+pub const MAX_ATTRIBUTE_NAME_LENGTH: usize = 64;
+
+// DATE_FORMAT is how the values of date attributes are written
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// AttributeType is the type the values of an attribute must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeType {
+    String,
+    Number,
+    Bool,
+    // date values are calendar dates written as YYYY-MM-DD, so that they sort as strings
+    Date,
+}
+
+impl AttributeType {
+    // check returns the value if it has the type, with dates written the same way every time.
+    // Returns None if the value has another type
+    pub fn check(&self, value: &AttributeValue) -> Option<AttributeValue> {
+        match (self, value) {
+            (AttributeType::String, AttributeValue::Text(_))
+            | (AttributeType::Number, AttributeValue::Number(_))
+            | (AttributeType::Bool, AttributeValue::Bool(_)) => Some(value.clone()),
+            (AttributeType::Date, AttributeValue::Text(text)) => {
+                NaiveDate::parse_from_str(text, DATE_FORMAT)
+                    .ok()
+                    .map(|date| AttributeValue::Text(date.format(DATE_FORMAT).to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    // parse reads a value of the type from text, such as the value of a filter in a url
+    pub fn parse(&self, text: &str) -> Option<AttributeValue> {
+        match self {
+            AttributeType::String | AttributeType::Date => {
+                self.check(&AttributeValue::Text(text.to_string()))
+            }
+            AttributeType::Number => text
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(AttributeValue::Number),
+            AttributeType::Bool => text.parse::<bool>().ok().map(AttributeValue::Bool),
+        }
+    }
+}
+
+// AttributeValue is the value of an attribute of a product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl AttributeValue {
+    // candidates returns every value text can be read as when the type of an attribute is not
+    // defined, such as the string "42" and the number 42
+    pub fn candidates(text: &str) -> Vec<AttributeValue> {
+        [
+            AttributeType::String,
+            AttributeType::Number,
+            AttributeType::Bool,
+        ]
+        .iter()
+        .filter_map(|attribute_type| attribute_type.parse(text))
+        .collect()
+    }
+}
+
+impl From<&AttributeValue> for Bson {
+    fn from(value: &AttributeValue) -> Self {
+        match value {
+            AttributeValue::Bool(bool) => Bson::Boolean(*bool),
+            AttributeValue::Number(number) => Bson::Double(*number),
+            AttributeValue::Text(text) => Bson::String(text.clone()),
+        }
+    }
+}
+
+// AttributeDefinition is the model for the type a client requires the values of an attribute of
+// its products to have. Attributes without a definition take values of any type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeDefinition {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub attribute_type: AttributeType,
+    pub updated_at: DateTime,
+}
+
+impl AttributeDefinition {
+    pub fn new(client_id: ObjectId, name: String, attribute_type: AttributeType) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            name,
+            attribute_type,
+            updated_at: DateTime::now(),
+        }
+    }
+}
+
+// AttributeFilter keeps the products whose attribute has one of the values
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeFilter {
+    pub name: String,
+    pub values: Vec<AttributeValue>,
+}
+
+// is_valid_attribute_name reports whether a name is not empty, not too long, and only made of
+// letters, digits and underscores, so that it can be used as a field name and in a url
+pub fn is_valid_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_ATTRIBUTE_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+
+    use super::{AttributeType, AttributeValue};
+
+    #[test]
+    // test_check_attribute_value tests which values each attribute type takes, and that dates
+    // are written the same way every time
+    async fn test_check_attribute_value() {
+        let text = |text: &str| AttributeValue::Text(text.to_string());
+        let test_cases = vec![
+            (AttributeType::String, text("cotton"), Some(text("cotton"))),
+            (AttributeType::String, AttributeValue::Number(1.0), None),
+            (
+                AttributeType::Number,
+                AttributeValue::Number(2.5),
+                Some(AttributeValue::Number(2.5)),
+            ),
+            (AttributeType::Number, text("2.5"), None),
+            (
+                AttributeType::Bool,
+                AttributeValue::Bool(true),
+                Some(AttributeValue::Bool(true)),
+            ),
+            (
+                AttributeType::Date,
+                text("2024-2-9"),
+                Some(text("2024-02-09")),
+            ),
+            (AttributeType::Date, text("2024-02-30"), None),
+            (AttributeType::Date, AttributeValue::Bool(false), None),
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                test_case.0.check(&test_case.1),
+                test_case.2,
+                "{:?}",
+                test_case
+            );
+        }
+        assert_eq!(
+            AttributeValue::candidates("42"),
+            vec![text("42"), AttributeValue::Number(42.0)]
+        );
+    }
+}
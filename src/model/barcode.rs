@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+// GTIN_LENGTH is the number of digits of a GTIN-14, every barcode can be written as one
+const GTIN_LENGTH: usize = 14;
+
+// BarcodeKind is the symbology of a barcode, told apart by its number of digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarcodeKind {
+    Ean8,
+    UpcA,
+    Ean13,
+    Gtin14,
+}
+
+// Barcode is a GTIN printed on a product. The gtin is the code padded with zeros to 14 digits,
+// so that the same number written as a UPC-A or an EAN-13 is the same barcode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Barcode {
+    pub code: String,
+    pub kind: BarcodeKind,
+    pub gtin: String,
+}
+
+impl Barcode {
+    // parse reads a barcode, returning None if it is not 8, 12, 13 or 14 digits or its check
+    // digit is wrong
+    pub fn parse(code: &str) -> Option<Self> {
+        if !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let kind = match code.len() {
+            8 => BarcodeKind::Ean8,
+            12 => BarcodeKind::UpcA,
+            13 => BarcodeKind::Ean13,
+            14 => BarcodeKind::Gtin14,
+            _ => return None,
+        };
+        if !has_valid_check_digit(code) {
+            return None;
+        }
+
+        Some(Self {
+            code: code.to_string(),
+            kind,
+            gtin: format!("{:0>width$}", code, width = GTIN_LENGTH),
+        })
+    }
+}
+
+// has_valid_check_digit reports whether the last digit of a code is its GS1 check digit. From
+// the right, the check digit counts once and the other digits count three times and once in
+// turn, and the total must be a multiple of 10
+fn has_valid_check_digit(code: &str) -> bool {
+    let total: u32 = code
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| match i % 2 {
+            0 => digit,
+            _ => digit * 3,
+        })
+        .sum();
+    total % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+
+    use super::{Barcode, BarcodeKind};
+
+    #[test]
+    // test_parse_barcode tests which barcodes are read, and the gtin they are read as
+    async fn test_parse_barcode() {
+        let test_cases = vec![
+            ("96385074", Some((BarcodeKind::Ean8, "00000096385074"))),
+            ("036000291452", Some((BarcodeKind::UpcA, "00036000291452"))),
+            (
+                "0036000291452",
+                Some((BarcodeKind::Ean13, "00036000291452")),
+            ),
+            (
+                "4006381333931",
+                Some((BarcodeKind::Ean13, "04006381333931")),
+            ),
+            (
+                "10012345678902",
+                Some((BarcodeKind::Gtin14, "10012345678902")),
+            ),
+            ("4006381333932", None),
+            ("400638133393", None),
+            ("40063813339a1", None),
+            ("", None),
+        ];
+
+        for test_case in test_cases {
+            let parsed = Barcode::parse(test_case.0).map(|barcode| (barcode.kind, barcode.gtin));
+            let expected = test_case.1.map(|(kind, gtin)| (kind, gtin.to_string()));
+            assert_eq!(parsed, expected, "{}", test_case.0);
+        }
+    }
+}
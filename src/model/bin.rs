@@ -0,0 +1,77 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// Bin is the model for a storage location inside a warehouse. Bins are addressed by the zone,
+// aisle and rack they are in, and the name of the bin on the rack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bin {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub warehouse_id: ObjectId,
+    pub zone: String,
+    pub aisle: String,
+    pub rack: String,
+    pub bin: String,
+    pub created_at: DateTime,
+}
+
+impl Bin {
+    // new creates a new Bin in a warehouse of a client
+    pub fn new(
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        zone: String,
+        aisle: String,
+        rack: String,
+        bin: String,
+    ) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            warehouse_id,
+            zone,
+            aisle,
+            rack,
+            bin,
+            created_at: DateTime::now(),
+        }
+    }
+
+    // code is the label pickers know the bin by, made of its place in the warehouse
+    pub fn code(&self) -> String {
+        format!("{}-{}-{}-{}", self.zone, self.aisle, self.rack, self.bin)
+    }
+}
+
+// BinStock is the model for the quantity of a product stored in a bin. The quantities in the
+// bins of a warehouse are part of the stock of the product at the warehouse, never in addition to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinStock {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub warehouse_id: ObjectId,
+    pub bin_id: ObjectId,
+    pub product_id: ObjectId,
+    pub quantity: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::oid::ObjectId;
+
+    use super::Bin;
+
+    #[test]
+    // test_code tests that the code of a bin lists its place from the zone down
+    fn test_code() {
+        let bin = Bin::new(
+            ObjectId::new(),
+            ObjectId::new(),
+            "A".to_string(),
+            "03".to_string(),
+            "R2".to_string(),
+            "B14".to_string(),
+        );
+        assert_eq!(bin.code(), "A-03-R2-B14");
+    }
+}
@@ -0,0 +1,62 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// IdempotencyKey is the model for an idempotency key sent by a client with a mutation request.
+// It records the request it was first used with and, once the request completed, its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyKey {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub key: String,
+    // request is the method, path and body of the request the key was first used with
+    pub request: String,
+    // status_code and response_body are set once the request has completed
+    pub status_code: Option<i32>,
+    pub response_body: Option<String>,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    // lease_expires_at is how long the request that claimed the key holds it while it has not
+    // completed. A retry takes over a claim past its lease, as the request that made it died
+    #[serde(default)]
+    pub lease_expires_at: Option<DateTime>,
+}
+
+impl IdempotencyKey {
+    // new creates a new IdempotencyKey for a request that has not completed yet
+    pub fn new(
+        client_id: ObjectId,
+        key: String,
+        request: String,
+        ttl_seconds: i64,
+        lease_seconds: i64,
+    ) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            key,
+            request,
+            status_code: None,
+            response_body: None,
+            created_at: now,
+            expires_at: DateTime::from_millis(now.timestamp_millis() + ttl_seconds * 1000),
+            lease_expires_at: Some(DateTime::from_millis(
+                now.timestamp_millis() + lease_seconds * 1000,
+            )),
+        }
+    }
+
+    // is_expired reports whether the key is past its expiry time
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+
+    // is_abandoned reports whether the request that claimed the key has not completed within
+    // its lease. A claim made before leases were recorded has none left
+    pub fn is_abandoned(&self) -> bool {
+        self.status_code.is_none()
+            && self
+                .lease_expires_at
+                .is_none_or(|lease_expires_at| lease_expires_at <= DateTime::now())
+    }
+}
@@ -1,5 +1,36 @@
+pub mod attribute;
+pub mod barcode;
+pub mod bin;
+pub mod idempotency_key;
+pub mod order;
 pub mod product;
+pub mod purchase_order;
+pub mod reservation;
+pub mod return_authorization;
+pub mod sku;
 pub mod stock;
+pub mod stock_alert;
+pub mod stock_movement;
+pub mod supplier;
+pub mod warehouse;
+pub mod webhook;
 
 pub const PRODUCT_COLLECTION: &str = "products";
 pub const STOCK_COLLECTION: &str = "stocks";
+pub const ORDER_COLLECTION: &str = "orders";
+pub const RESERVATION_COLLECTION: &str = "reservations";
+pub const IDEMPOTENCY_KEY_COLLECTION: &str = "idempotency_keys";
+pub const STOCK_MOVEMENT_COLLECTION: &str = "stock_movements";
+pub const STOCK_MOVEMENT_SEQUENCE_COLLECTION: &str = "stock_movement_sequences";
+pub const RETURN_AUTHORIZATION_COLLECTION: &str = "return_authorizations";
+pub const WAREHOUSE_COLLECTION: &str = "warehouses";
+pub const BIN_COLLECTION: &str = "bins";
+pub const BIN_STOCK_COLLECTION: &str = "bin_stocks";
+pub const SKU_SETTINGS_COLLECTION: &str = "sku_settings";
+pub const SKU_COUNTER_COLLECTION: &str = "sku_counters";
+pub const ATTRIBUTE_DEFINITION_COLLECTION: &str = "attribute_definitions";
+pub const STOCK_ALERT_COLLECTION: &str = "stock_alerts";
+pub const WEBHOOK_COLLECTION: &str = "webhooks";
+pub const WEBHOOK_DELIVERY_COLLECTION: &str = "webhook_deliveries";
+pub const PURCHASE_ORDER_COLLECTION: &str = "purchase_orders";
+pub const SUPPLIER_COLLECTION: &str = "suppliers";
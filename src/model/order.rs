@@ -0,0 +1,355 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::product::product_dto::ProductQuantity,
+    model::stock::{allocations_or_unassigned, AllocatedQuantity, StockAllocation},
+};
+
+// OrderStatus is the state of an order in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Processed,
+    // some of the quantities of the order were given back to the stock
+    PartiallyCancelled,
+    // all of the quantities of the order were given back to the stock
+    Cancelled,
+}
+
+// OrderLine is a product and the quantity an order took out of stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+    // cancelled_quantity is the part of the quantity given back by cancellations
+    #[serde(default)]
+    pub cancelled_quantity: i32,
+    // returned_quantity is the part of the quantity authorized to be returned by the customer
+    #[serde(default)]
+    pub returned_quantity: i32,
+    // allocations are the locations the quantity was taken from
+    #[serde(default)]
+    pub allocations: Vec<StockAllocation>,
+}
+
+impl OrderLine {
+    // remaining_quantity returns the part of the quantity that has been neither cancelled
+    // nor authorized for return
+    pub fn remaining_quantity(&self) -> i32 {
+        self.quantity - self.cancelled_quantity - self.returned_quantity
+    }
+
+    // get_allocations returns the locations the quantity of the line was taken from
+    pub fn get_allocations(&self) -> Vec<StockAllocation> {
+        allocations_or_unassigned(&self.allocations, self.quantity)
+    }
+
+    // release_allocations returns the locations the given quantity of the line goes back to.
+    // Quantities go back to the locations they were taken from last first, after the quantities
+    // already cancelled or returned
+    pub fn release_allocations(&self, quantity: i32) -> Vec<StockAllocation> {
+        let mut skipped = self.cancelled_quantity + self.returned_quantity;
+        let mut remaining = quantity;
+        let mut released = vec![];
+        for allocation in self.get_allocations().into_iter().rev() {
+            let skip = skipped.min(allocation.quantity);
+            skipped -= skip;
+            let take = remaining.min(allocation.quantity - skip);
+            if take > 0 {
+                remaining -= take;
+                released.push(StockAllocation {
+                    warehouse_id: allocation.warehouse_id,
+                    quantity: take,
+                });
+            }
+        }
+        released
+    }
+}
+
+// OrderCancellationLine is a product and the quantity a cancellation gave back to the stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCancellationLine {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+}
+
+// OrderCancellation records a cancellation of some or all of the quantities of an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCancellation {
+    pub reason: String,
+    pub actor: String,
+    pub lines: Vec<OrderCancellationLine>,
+    pub created_at: DateTime,
+}
+
+// Order is the model for orders processed against the stock of a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub lines: Vec<OrderLine>,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub cancellations: Vec<OrderCancellation>,
+    // version is incremented on every change, so concurrent changes can be detected
+    #[serde(default)]
+    pub version: i32,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Order {
+    // new creates a new processed Order from the product quantities it took out of stock
+    pub fn new(order_id: ObjectId, client_id: ObjectId, lines: &[AllocatedQuantity]) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: order_id,
+            client_id,
+            lines: lines
+                .iter()
+                .map(|line| OrderLine {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                    cancelled_quantity: 0,
+                    returned_quantity: 0,
+                    allocations: line.allocations.clone(),
+                })
+                .collect(),
+            status: OrderStatus::Processed,
+            cancellations: vec![],
+            version: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    // get_line returns the line of the order for a product
+    pub fn get_line(&self, product_id: ObjectId) -> Option<&OrderLine> {
+        self.lines.iter().find(|line| line.product_id == product_id)
+    }
+
+    // cancel records a cancellation of the given quantities of the order and updates its status.
+    // The quantities must have been checked against the remaining quantities of the lines
+    pub fn cancel(&mut self, cancelled: &[ProductQuantity], reason: &str, actor: &str) {
+        for cancelled_line in cancelled {
+            if let Some(line) = self
+                .lines
+                .iter_mut()
+                .find(|line| line.product_id == cancelled_line.product_id)
+            {
+                line.cancelled_quantity += cancelled_line.quantity;
+            }
+        }
+
+        let now = DateTime::now();
+        self.cancellations.push(OrderCancellation {
+            reason: reason.to_string(),
+            actor: actor.to_string(),
+            lines: cancelled
+                .iter()
+                .map(|line| OrderCancellationLine {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                })
+                .collect(),
+            created_at: now,
+        });
+
+        self.status = if self
+            .lines
+            .iter()
+            .all(|line| line.cancelled_quantity == line.quantity)
+        {
+            OrderStatus::Cancelled
+        } else {
+            OrderStatus::PartiallyCancelled
+        };
+        self.version += 1;
+        self.updated_at = now;
+    }
+
+    // add_returned records that the given quantities of the order are authorized for return.
+    // The quantities must have been checked against the remaining quantities of the lines
+    pub fn add_returned(&mut self, returned: &[ProductQuantity]) {
+        self.adjust_returned(returned, 1);
+    }
+
+    // remove_returned undoes a return authorization recorded with add_returned
+    pub fn remove_returned(&mut self, returned: &[ProductQuantity]) {
+        self.adjust_returned(returned, -1);
+    }
+
+    // adjust_returned adds or removes returned quantities on the lines of the order
+    fn adjust_returned(&mut self, returned: &[ProductQuantity], sign: i32) {
+        for returned_line in returned {
+            if let Some(line) = self
+                .lines
+                .iter_mut()
+                .find(|line| line.product_id == returned_line.product_id)
+            {
+                line.returned_quantity += sign * returned_line.quantity;
+            }
+        }
+        self.version += 1;
+        self.updated_at = DateTime::now();
+    }
+}
+
+// OrderQuery selects the orders of a client to list, the most recent first
+#[derive(Debug, Clone, Default)]
+pub struct OrderQuery {
+    pub status: Option<OrderStatus>,
+    // product_id selects the orders with a line of the product
+    pub product_id: Option<ObjectId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+
+    use super::{Order, OrderStatus};
+    use crate::{
+        dto::product::product_dto::ProductQuantity,
+        model::stock::{AllocatedQuantity, StockAllocation},
+    };
+
+    // allocated returns a quantity of a product taken from the unassigned stock
+    fn allocated(product_id: ObjectId, quantity: i32) -> AllocatedQuantity {
+        AllocatedQuantity {
+            product_id,
+            quantity,
+            allocations: vec![],
+        }
+    }
+
+    #[test]
+    // test_cancel_updates_lines_and_status tests that cancellations add up on the lines and that
+    // the order is only cancelled once nothing remains
+    async fn test_cancel_updates_lines_and_status() {
+        let (shirt, socks) = (ObjectId::new(), ObjectId::new());
+        let mut order = Order::new(
+            ObjectId::new(),
+            ObjectId::new(),
+            &[allocated(shirt, 3), allocated(socks, 2)],
+        );
+
+        order.cancel(
+            &[ProductQuantity {
+                product_id: shirt,
+                quantity: 1,
+            }],
+            "damaged",
+            "support",
+        );
+        assert_eq!(order.status, OrderStatus::PartiallyCancelled);
+        assert_eq!(order.get_line(shirt).unwrap().remaining_quantity(), 2);
+        assert_eq!(order.get_line(socks).unwrap().remaining_quantity(), 2);
+        assert_eq!(order.version, 1);
+
+        order.cancel(
+            &[
+                ProductQuantity {
+                    product_id: shirt,
+                    quantity: 2,
+                },
+                ProductQuantity {
+                    product_id: socks,
+                    quantity: 2,
+                },
+            ],
+            "customer request",
+            "support",
+        );
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.get_line(shirt).unwrap().cancelled_quantity, 3);
+        assert_eq!(order.cancellations.len(), 2);
+        assert_eq!(order.version, 2);
+    }
+
+    #[test]
+    // test_returned_quantities_reduce_remaining tests that returned quantities cannot be cancelled
+    // and that removing them makes them available again
+    async fn test_returned_quantities_reduce_remaining() {
+        let shirt = ObjectId::new();
+        let mut order = Order::new(ObjectId::new(), ObjectId::new(), &[allocated(shirt, 3)]);
+        let returned = [ProductQuantity {
+            product_id: shirt,
+            quantity: 2,
+        }];
+
+        order.add_returned(&returned);
+        assert_eq!(order.get_line(shirt).unwrap().remaining_quantity(), 1);
+        assert_eq!(order.status, OrderStatus::Processed);
+        assert_eq!(order.version, 1);
+
+        order.remove_returned(&returned);
+        assert_eq!(order.get_line(shirt).unwrap().remaining_quantity(), 3);
+        assert_eq!(order.version, 2);
+    }
+
+    #[test]
+    // test_release_allocations_gives_back_last_taken_first tests that quantities go back to the
+    // locations they were taken from, skipping what was already given back
+    async fn test_release_allocations_gives_back_last_taken_first() {
+        let (shirt, north, south) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let mut order = Order::new(
+            ObjectId::new(),
+            ObjectId::new(),
+            &[AllocatedQuantity {
+                product_id: shirt,
+                quantity: 5,
+                allocations: vec![
+                    StockAllocation {
+                        warehouse_id: Some(north),
+                        quantity: 3,
+                    },
+                    StockAllocation {
+                        warehouse_id: Some(south),
+                        quantity: 2,
+                    },
+                ],
+            }],
+        );
+
+        let line = order.get_line(shirt).unwrap();
+        assert_eq!(
+            line.release_allocations(3),
+            vec![
+                StockAllocation {
+                    warehouse_id: Some(south),
+                    quantity: 2,
+                },
+                StockAllocation {
+                    warehouse_id: Some(north),
+                    quantity: 1,
+                },
+            ]
+        );
+
+        order.cancel(
+            &[ProductQuantity {
+                product_id: shirt,
+                quantity: 1,
+            }],
+            "damaged",
+            "support",
+        );
+        let line = order.get_line(shirt).unwrap();
+        assert_eq!(
+            line.release_allocations(2),
+            vec![
+                StockAllocation {
+                    warehouse_id: Some(south),
+                    quantity: 1,
+                },
+                StockAllocation {
+                    warehouse_id: Some(north),
+                    quantity: 1,
+                },
+            ]
+        );
+    }
+}
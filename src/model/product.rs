@@ -1,18 +1,117 @@
-use crate::utils::tools;
+use crate::{
+    model::{
+        attribute::{AttributeFilter, AttributeValue},
+        barcode::Barcode,
+        sku,
+    },
+    utils::tools,
+};
 use bson::oid::ObjectId;
-use log::error;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 // Product is the model for products
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Product {
     pub _id: ObjectId,
     pub name: String,
     pub description: String,
     sku: String,
+    // category is what kind of product it is, the skus of some clients are made from it
+    #[serde(default)]
+    pub category: Option<String>,
+    // barcodes are the GTINs printed on the product, none of them is used by another product of
+    // the client. The field is left out of the documents of products without barcodes, so that
+    // the unique barcode index skips them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub barcodes: Vec<Barcode>,
+    // variant_axes are the ways the variants of a parent product differ. A parent holds no
+    // stock, its quantity is the total quantity of its variants
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variant_axes: Vec<VariantAxis>,
+    // parent_id is the parent of a variant, and options are the value it takes on every axis
+    #[serde(default)]
+    pub parent_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+    // attributes are the custom fields the client describes the product with, by name. The
+    // values of the attributes the client has defined have the type of their definition
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, AttributeValue>,
+    // components are the products a bundle is made of. A bundle holds no stock, it is available
+    // as many times as its components can make it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<BundleComponent>,
+    // reorder_point is the quantity across locations at or below which the product is low in
+    // stock, and reorder_quantity is how many to order when it is. A product without a reorder
+    // point is never low in stock
+    #[serde(default)]
+    pub reorder_point: Option<i32>,
+    #[serde(default)]
+    pub reorder_quantity: Option<i32>,
+    // stock_level is the level of the quantity across locations as the stock checks last saw it.
+    // It is only replaced from the level a check read, so only one check raises the alerts of a
+    // change of level
+    #[serde(default)]
+    pub stock_level: StockLevel,
+    // suppliers are the suppliers the product can be bought from, with the terms of each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppliers: Vec<ProductSupplier>,
     created_by: ObjectId,
 }
 
+// StockLevel is how much of a product is left across locations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockLevel {
+    // stocked is above the reorder point, or any quantity left for a product without one
+    #[default]
+    Stocked,
+    // low is at or below the reorder point with some quantity left
+    Low,
+    Out,
+}
+
+impl StockLevel {
+    // of gives the level of a quantity of a product
+    pub fn of(product: &Product, quantity: i32) -> Self {
+        if quantity <= 0 {
+            StockLevel::Out
+        } else if product.is_low_in_stock(quantity) {
+            StockLevel::Low
+        } else {
+            StockLevel::Stocked
+        }
+    }
+}
+
+// VariantAxis is a way the variants of a product differ, such as size, with the values it takes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantAxis {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+// BundleComponent is a product a bundle is made of, with how many of it one bundle holds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleComponent {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+}
+
+// ProductSupplier links a product to a supplier it can be bought from, with the sku the supplier
+// knows the product by and what one unit costs from it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductSupplier {
+    pub supplier_id: ObjectId,
+    pub supplier_sku: Option<String>,
+    pub unit_cost: f64,
+}
+
+// MAX_VARIANTS is the most variants a product can have
+pub const MAX_VARIANTS: usize = 100;
+
 // ProductQuantityResponse is the response body for getting a product with its quantity
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProductQuantityResponse {
@@ -21,25 +120,132 @@ pub struct ProductQuantityResponse {
     pub description: String,
     sku: String,
     pub quantity: i32,
+    // has_variants is true for a parent product, whose quantity is the total of its variants
+    #[serde(default)]
+    pub has_variants: bool,
+    // is_bundle is true for a bundle, whose quantity is how many its components can make
+    #[serde(default)]
+    pub is_bundle: bool,
 }
 
 impl Product {
-    // new creates a new Product and assigns an sku and an _id value as the primary key
-    pub fn new(name: String, description: String, client_id: ObjectId) -> Self {
-        let sku = tools::generate_random_alphanum(16).unwrap_or_else(|_| {
-            error!("Failed to generate random sku"); // log the error
-            panic!("error generating random sku"); // panic because it is a critical system error
-        });
-
-        let sku = tools::split_into_parts(sku, 4);
-
-        return Self {
+    // new creates a new Product and assigns an _id value as the primary key. The product has no
+    // sku until one is set
+    pub fn new(
+        name: String,
+        description: String,
+        category: Option<String>,
+        client_id: ObjectId,
+    ) -> Self {
+        Self {
             _id: ObjectId::new(),
             name,
             description,
-            sku,
+            sku: String::new(),
+            category,
+            barcodes: vec![],
+            variant_axes: vec![],
+            parent_id: None,
+            options: BTreeMap::new(),
+            attributes: BTreeMap::new(),
+            components: vec![],
+            reorder_point: None,
+            reorder_quantity: None,
+            stock_level: StockLevel::default(),
+            suppliers: vec![],
             created_by: client_id,
-        };
+        }
+    }
+
+    // is_parent reports whether the product has variants
+    pub fn is_parent(&self) -> bool {
+        !self.variant_axes.is_empty()
+    }
+
+    // is_bundle reports whether the product is made of other products
+    pub fn is_bundle(&self) -> bool {
+        !self.components.is_empty()
+    }
+
+    // is_low_in_stock reports whether a quantity of the product is at or below its reorder point
+    pub fn is_low_in_stock(&self, quantity: i32) -> bool {
+        self.reorder_point
+            .is_some_and(|reorder_point| quantity <= reorder_point)
+    }
+
+    // set_supplier links the product to a supplier, replacing the terms of an existing link
+    pub fn set_supplier(&mut self, link: ProductSupplier) {
+        match self
+            .suppliers
+            .iter_mut()
+            .find(|existing| existing.supplier_id == link.supplier_id)
+        {
+            Some(existing) => *existing = link,
+            None => self.suppliers.push(link),
+        }
+    }
+
+    // remove_supplier unlinks the product from a supplier. Returns false if it was not linked
+    pub fn remove_supplier(&mut self, supplier_id: ObjectId) -> bool {
+        let linked = self.suppliers.len();
+        self.suppliers
+            .retain(|link| link.supplier_id != supplier_id);
+        self.suppliers.len() < linked
+    }
+
+    // make_variants makes a variant of a parent product for every combination of the values of
+    // its axes, such as "T-shirt (M, Red)" with the sku TSHIRT-M-RED for a parent with the sku
+    // TSHIRT. The variants have the attributes of their parent
+    pub fn make_variants(&self) -> Vec<Product> {
+        let mut combinations: Vec<Vec<&String>> = vec![vec![]];
+        for axis in &self.variant_axes {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    axis.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push(value);
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        combinations
+            .into_iter()
+            .map(|values| {
+                let labels: Vec<&str> = values.iter().map(|value| value.as_str()).collect();
+                let codes: Vec<String> = values.iter().map(|value| sku::sku_code(value)).collect();
+                Self {
+                    _id: ObjectId::new(),
+                    name: format!("{} ({})", self.name, labels.join(", ")),
+                    description: self.description.clone(),
+                    sku: format!("{}-{}", self.sku, codes.join("-")),
+                    category: self.category.clone(),
+                    barcodes: vec![],
+                    variant_axes: vec![],
+                    parent_id: Some(self._id),
+                    options: self
+                        .variant_axes
+                        .iter()
+                        .map(|axis| axis.name.clone())
+                        .zip(values.into_iter().cloned())
+                        .collect(),
+                    attributes: self.attributes.clone(),
+                    components: vec![],
+                    reorder_point: None,
+                    reorder_quantity: None,
+                    stock_level: self.stock_level,
+                    suppliers: vec![],
+                    created_by: self.created_by,
+                }
+            })
+            .collect()
+    }
+
+    // set_sku assigns the sku of a product before it is created
+    pub fn set_sku(&mut self, sku: String) {
+        self.sku = sku;
     }
 
     // get_sku returns the value of the sku field
@@ -47,6 +253,11 @@ impl Product {
         self.sku.clone()
     }
 
+    // get_created_by returns the id of the client the product belongs to
+    pub fn get_created_by(&self) -> ObjectId {
+        self.created_by
+    }
+
     // to_product_quantity_response converts a product to a ProductQuantityResponse
     pub fn to_product_quantity_response(&self, quantity: i32) -> ProductQuantityResponse {
         ProductQuantityResponse {
@@ -55,6 +266,145 @@ impl Product {
             description: self.description.clone(),
             sku: self.sku.clone(),
             quantity,
+            has_variants: self.is_parent(),
+            is_bundle: self.is_bundle(),
         }
     }
 }
+
+// NAME_SEARCH_WEIGHT, SKU_SEARCH_WEIGHT and DESCRIPTION_SEARCH_WEIGHT are how much a searched
+// word found in each field of a product counts towards its relevance
+pub const NAME_SEARCH_WEIGHT: i32 = 10;
+pub const SKU_SEARCH_WEIGHT: i32 = 5;
+pub const DESCRIPTION_SEARCH_WEIGHT: i32 = 1;
+
+// PREFIX_MATCH_SCORE is the relevance of a product found because its name or sku starts with
+// the searched text, when none of the searched words are found in it
+pub const PREFIX_MATCH_SCORE: f64 = 0.5;
+
+// MIN_SEARCH_WORD_LEN is the length a searched word needs to be matched against the words of a
+// product. Shorter words, such as "a", would find most products by their description
+pub const MIN_SEARCH_WORD_LEN: usize = 2;
+
+// search_words splits a searched text into the lowercase words matched against the words of the
+// products, leaving out the words that are too short to be matched
+pub fn search_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_SEARCH_WORD_LEN)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// ProductSearchHit is a product found by a search, with its total quantity across locations and
+// its relevance to the search
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductSearchHit {
+    pub product: Product,
+    pub quantity: i32,
+    pub score: f64,
+}
+
+// ProductSort is the field products are listed by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSort {
+    Name,
+    // created lists the products in the order they were created, which is the order of their ids
+    #[default]
+    Created,
+    // quantity lists the products by their total quantity across locations
+    Quantity,
+}
+
+// SortOrder is the direction products are listed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// ProductCursor marks the last product of a page, the next page starts after it. It holds every
+// field products can be listed by, so that it can be compared with any product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductCursor {
+    pub id: ObjectId,
+    pub name: String,
+    pub quantity: i32,
+}
+
+impl ProductCursor {
+    pub fn new(product: &Product, quantity: i32) -> Self {
+        Self {
+            id: product._id,
+            name: product.name.clone(),
+            quantity,
+        }
+    }
+
+    // encode turns the cursor into an opaque string that is safe to use in a url
+    pub fn encode(&self) -> String {
+        tools::encode_hex(serde_json::to_string(self).unwrap_or_default().as_bytes())
+    }
+
+    // decode reads a cursor made by encode, returning None if the string is not one
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = tools::decode_hex(cursor)?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    // cmp_product compares the position of the cursor with that of a product in a listing
+    // sorted by the given field, ties on the field are broken by the id
+    pub fn cmp_product(&self, sort: ProductSort, product: &Product, quantity: i32) -> Ordering {
+        match sort {
+            ProductSort::Name => {
+                (self.name.as_str(), self.id).cmp(&(product.name.as_str(), product._id))
+            }
+            ProductSort::Created => self.id.cmp(&product._id),
+            ProductSort::Quantity => (self.quantity, self.id).cmp(&(quantity, product._id)),
+        }
+    }
+}
+
+// ProductListQuery describes which products of a client to list and in what order
+#[derive(Debug, Clone, Default)]
+pub struct ProductListQuery {
+    // name_prefix keeps the products whose name starts with it, the match is case sensitive
+    pub name_prefix: Option<String>,
+    // min_quantity and max_quantity bound the total quantity of the products, inclusively
+    pub min_quantity: Option<i32>,
+    pub max_quantity: Option<i32>,
+    // out_of_stock keeps only the products with no quantity left if true, and only the
+    // products with some quantity left if false
+    pub out_of_stock: Option<bool>,
+    // attributes keeps the products whose attribute has one of the values of the filter, for
+    // every filter
+    pub attributes: Vec<AttributeFilter>,
+    pub sort: ProductSort,
+    pub order: SortOrder,
+    // after keeps the products that come after the cursor in the listing
+    pub after: Option<ProductCursor>,
+    pub limit: i64,
+}
+
+impl ProductListQuery {
+    // matches reports whether a product with its total quantity passes the filters of the query.
+    // The cursor is not taken into account
+    pub fn matches(&self, product: &Product, quantity: i32) -> bool {
+        self.name_prefix
+            .as_ref()
+            .is_none_or(|prefix| product.name.starts_with(prefix.as_str()))
+            && self.min_quantity.is_none_or(|min| quantity >= min)
+            && self.max_quantity.is_none_or(|max| quantity <= max)
+            && self
+                .out_of_stock
+                .is_none_or(|out_of_stock| out_of_stock == (quantity <= 0))
+            && self.attributes.iter().all(|filter| {
+                product
+                    .attributes
+                    .get(&filter.name)
+                    .is_some_and(|value| filter.values.contains(value))
+            })
+    }
+}
@@ -0,0 +1,223 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::dto::product::product_dto::ProductQuantity;
+
+// PurchaseOrderStatus is the state of a purchase order in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseOrderStatus {
+    // the goods are expected and nothing was received yet
+    Open,
+    // some of the goods were received, more are expected
+    PartiallyReceived,
+    // nothing more is expected, every line was received or the order was closed short
+    Closed,
+}
+
+// PurchaseOrderLine is a product, the quantity expected from the supplier and the quantity
+// received so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderLine {
+    pub product_id: ObjectId,
+    pub expected: i32,
+    pub received: i32,
+}
+
+impl PurchaseOrderLine {
+    // discrepancy returns how many more units were received than expected, negative when
+    // fewer were received
+    pub fn discrepancy(&self) -> i32 {
+        self.received - self.expected
+    }
+}
+
+// PurchaseOrderReceiptLine is a product and the quantity a delivery brought
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderReceiptLine {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+}
+
+// PurchaseOrderReceipt records a delivery of some of the goods of a purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderReceipt {
+    pub actor: String,
+    // warehouse_id is the location the received units were added to
+    pub warehouse_id: Option<ObjectId>,
+    pub lines: Vec<PurchaseOrderReceiptLine>,
+    pub created_at: DateTime,
+}
+
+// PurchaseOrder is the model for goods a client ordered from a supplier and receives into
+// its stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrder {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    // reference is the number of the order at the supplier
+    pub reference: Option<String>,
+    // warehouse_id is the location the goods are received at unless another is given
+    pub warehouse_id: Option<ObjectId>,
+    pub lines: Vec<PurchaseOrderLine>,
+    pub receipts: Vec<PurchaseOrderReceipt>,
+    pub status: PurchaseOrderStatus,
+    // version is incremented on every change, so concurrent changes can be detected
+    pub version: i32,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub closed_at: Option<DateTime>,
+}
+
+impl PurchaseOrder {
+    // new creates a new open purchase order expecting the given quantities
+    pub fn new(
+        client_id: ObjectId,
+        reference: Option<String>,
+        warehouse_id: Option<ObjectId>,
+        lines: &[ProductQuantity],
+    ) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            reference,
+            warehouse_id,
+            lines: lines
+                .iter()
+                .map(|line| PurchaseOrderLine {
+                    product_id: line.product_id,
+                    expected: line.quantity,
+                    received: 0,
+                })
+                .collect(),
+            receipts: vec![],
+            status: PurchaseOrderStatus::Open,
+            version: 0,
+            created_at: now,
+            updated_at: now,
+            closed_at: None,
+        }
+    }
+
+    // get_line returns the line of the purchase order for a product
+    pub fn get_line(&self, product_id: ObjectId) -> Option<&PurchaseOrderLine> {
+        self.lines.iter().find(|line| line.product_id == product_id)
+    }
+
+    // receive records a delivery of the given quantities and updates the status. The order is
+    // closed once every line has been received in full, or when the delivery is the last one.
+    // The products must have been checked to be lines of the order
+    pub fn receive(
+        &mut self,
+        received: &[ProductQuantity],
+        warehouse_id: Option<ObjectId>,
+        actor: &str,
+        last: bool,
+    ) {
+        for received_line in received {
+            if let Some(line) = self
+                .lines
+                .iter_mut()
+                .find(|line| line.product_id == received_line.product_id)
+            {
+                line.received += received_line.quantity;
+            }
+        }
+
+        self.receipts.push(PurchaseOrderReceipt {
+            actor: actor.to_string(),
+            warehouse_id,
+            lines: received
+                .iter()
+                .map(|line| PurchaseOrderReceiptLine {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                })
+                .collect(),
+            created_at: DateTime::now(),
+        });
+
+        if last || self.lines.iter().all(|line| line.received >= line.expected) {
+            self.close();
+        } else {
+            self.status = PurchaseOrderStatus::PartiallyReceived;
+            self.version += 1;
+            self.updated_at = DateTime::now();
+        }
+    }
+
+    // close marks the purchase order as done, whatever is still expected is not
+    pub fn close(&mut self) {
+        let now = DateTime::now();
+        self.status = PurchaseOrderStatus::Closed;
+        self.closed_at = Some(now);
+        self.version += 1;
+        self.updated_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+
+    use super::{PurchaseOrder, PurchaseOrderStatus};
+    use crate::dto::product::product_dto::ProductQuantity;
+
+    // quantity returns a quantity of a product
+    fn quantity(product_id: ObjectId, quantity: i32) -> ProductQuantity {
+        ProductQuantity {
+            product_id,
+            quantity,
+        }
+    }
+
+    #[test]
+    // test_receive_updates_lines_and_status tests that deliveries add up on the lines and that
+    // the purchase order is only closed once every line was received, over-receipts included
+    async fn test_receive_updates_lines_and_status() {
+        let (shirt, socks) = (ObjectId::new(), ObjectId::new());
+        let mut purchase_order = PurchaseOrder::new(
+            ObjectId::new(),
+            None,
+            None,
+            &[quantity(shirt, 10), quantity(socks, 4)],
+        );
+        assert_eq!(purchase_order.status, PurchaseOrderStatus::Open);
+
+        purchase_order.receive(&[quantity(shirt, 6)], None, "dock", false);
+        assert_eq!(
+            purchase_order.status,
+            PurchaseOrderStatus::PartiallyReceived
+        );
+        assert_eq!(purchase_order.get_line(shirt).unwrap().discrepancy(), -4);
+        assert_eq!(purchase_order.version, 1);
+
+        purchase_order.receive(
+            &[quantity(shirt, 4), quantity(socks, 5)],
+            None,
+            "dock",
+            false,
+        );
+        assert_eq!(purchase_order.status, PurchaseOrderStatus::Closed);
+        assert_eq!(purchase_order.get_line(shirt).unwrap().discrepancy(), 0);
+        assert_eq!(purchase_order.get_line(socks).unwrap().discrepancy(), 1);
+        assert_eq!(purchase_order.receipts.len(), 2);
+        assert!(purchase_order.closed_at.is_some());
+        assert_eq!(purchase_order.version, 2);
+    }
+
+    #[test]
+    // test_last_receipt_closes_short tests that the last delivery closes the purchase order even
+    // though less than expected was received
+    async fn test_last_receipt_closes_short() {
+        let shirt = ObjectId::new();
+        let mut purchase_order =
+            PurchaseOrder::new(ObjectId::new(), None, None, &[quantity(shirt, 10)]);
+
+        purchase_order.receive(&[quantity(shirt, 7)], None, "dock", true);
+        assert_eq!(purchase_order.status, PurchaseOrderStatus::Closed);
+        assert_eq!(purchase_order.get_line(shirt).unwrap().discrepancy(), -3);
+    }
+}
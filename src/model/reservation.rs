@@ -0,0 +1,84 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::model::stock::{allocations_or_unassigned, AllocatedQuantity, StockAllocation};
+
+// ReservationStatus is the state of a reservation in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationStatus {
+    // the quantities are held against the stock
+    Active,
+    // the quantities were taken out of the stock
+    Committed,
+    // the quantities were given back to the stock on request
+    Released,
+    // the quantities were given back to the stock after the reservation expired
+    Expired,
+}
+
+// ReservationLine is a product and the quantity a reservation holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReservationLine {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+    // allocations are the locations the quantity is held at
+    #[serde(default)]
+    pub allocations: Vec<StockAllocation>,
+}
+
+// Reservation is the model for quantities held against the stock of a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reservation {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub lines: Vec<ReservationLine>,
+    pub status: ReservationStatus,
+    // order_id is the order created when the reservation was committed
+    pub order_id: Option<ObjectId>,
+    pub expires_at: DateTime,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Reservation {
+    // new creates a new active Reservation holding the product quantities until it expires
+    pub fn new(client_id: ObjectId, lines: &[AllocatedQuantity], ttl_seconds: i64) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            lines: lines
+                .iter()
+                .map(|line| ReservationLine {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                    allocations: line.allocations.clone(),
+                })
+                .collect(),
+            status: ReservationStatus::Active,
+            order_id: None,
+            expires_at: DateTime::from_millis(now.timestamp_millis() + ttl_seconds * 1000),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    // is_expired reports whether the reservation is past its expiry time
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+
+    // to_allocated_quantities converts the lines of the reservation to the quantities
+    // and the locations they are held at
+    pub fn to_allocated_quantities(&self) -> Vec<AllocatedQuantity> {
+        self.lines
+            .iter()
+            .map(|line| AllocatedQuantity {
+                product_id: line.product_id,
+                quantity: line.quantity,
+                allocations: allocations_or_unassigned(&line.allocations, line.quantity),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,87 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{dto::product::product_dto::ProductQuantity, model::order::Order};
+
+// ReturnStatus is the state of a return authorization in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnStatus {
+    // the customer may send the goods back
+    Authorized,
+    // the goods arrived and their condition was recorded
+    Received,
+    // nothing more is expected for the return
+    Closed,
+}
+
+// ReturnLine is a product of the original order, the quantity authorized to be returned
+// and, once received, how many units arrived in each condition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnLine {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+    // warehouse_id is the location resellable units go back to, the location the order line
+    // was first taken from unless another is given when the return is received
+    #[serde(default)]
+    pub warehouse_id: Option<ObjectId>,
+    // resellable units go back into the stock
+    pub resellable: i32,
+    // damaged and scrap units do not go back into the stock
+    pub damaged: i32,
+    pub scrap: i32,
+}
+
+impl ReturnLine {
+    // received_quantity returns the number of units that arrived in any condition
+    pub fn received_quantity(&self) -> i32 {
+        self.resellable + self.damaged + self.scrap
+    }
+}
+
+// ReturnAuthorization is the model for goods a customer returns against a past order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnAuthorization {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub order_id: ObjectId,
+    pub reason: Option<String>,
+    pub lines: Vec<ReturnLine>,
+    pub status: ReturnStatus,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub received_at: Option<DateTime>,
+    pub closed_at: Option<DateTime>,
+}
+
+impl ReturnAuthorization {
+    // new creates a new authorized return of the given quantities of an order
+    pub fn new(order: &Order, reason: Option<String>, lines: &[ProductQuantity]) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id: order.client_id,
+            order_id: order._id,
+            reason,
+            lines: lines
+                .iter()
+                .map(|line| ReturnLine {
+                    product_id: line.product_id,
+                    quantity: line.quantity,
+                    warehouse_id: order
+                        .get_line(line.product_id)
+                        .and_then(|order_line| order_line.get_allocations().first().cloned())
+                        .and_then(|allocation| allocation.warehouse_id),
+                    resellable: 0,
+                    damaged: 0,
+                    scrap: 0,
+                })
+                .collect(),
+            status: ReturnStatus::Authorized,
+            created_at: now,
+            updated_at: now,
+            received_at: None,
+            closed_at: None,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// MAX_SKU_LENGTH is the longest sku a product can have
+pub const MAX_SKU_LENGTH: usize = 64;
+
+// SkuStrategy is how the skus of the new products of a client are made
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SkuStrategy {
+    // random skus are 16 random alphanumeric characters in groups of 4
+    #[default]
+    Random,
+    // sequential skus are a prefix and a counter of the client padded to a width,
+    // such as ACME-000123
+    Sequential {
+        prefix: String,
+        width: usize,
+    },
+    // category skus are the code of the category of a product and a counter per code, such as
+    // ELE-0042. The code of a category is looked up in codes, or made of the first three letters
+    // or digits of the category if it has none
+    Category {
+        #[serde(default)]
+        codes: BTreeMap<String, String>,
+        width: usize,
+    },
+    // client supplied skus are given in the request adding a product, which must have one
+    ClientSupplied,
+}
+
+// SkuSettings is the model for how the skus of the products of a client are made.
+// Clients without settings get random skus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkuSettings {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub strategy: SkuStrategy,
+    pub updated_at: DateTime,
+}
+
+impl SkuSettings {
+    pub fn new(client_id: ObjectId, strategy: SkuStrategy) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            strategy,
+            updated_at: DateTime::now(),
+        }
+    }
+}
+
+// SkuCounter is the model for the last number used in the skus starting with a prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkuCounter {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub prefix: String,
+    pub value: i64,
+}
+
+// is_valid_sku reports whether a sku is not empty, not too long, and only made of letters,
+// digits, dashes, underscores and dots, so that it can be used in a url as it is
+pub fn is_valid_sku(sku: &str) -> bool {
+    !sku.is_empty()
+        && sku.len() <= MAX_SKU_LENGTH
+        && sku
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+// sku_code turns a value, such as the value of a variant axis, into the part of a sku made of
+// its letters and digits in upper case
+pub fn sku_code(value: &str) -> String {
+    value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+
+    use super::is_valid_sku;
+
+    #[test]
+    // test_is_valid_sku tests which skus can be used
+    async fn test_is_valid_sku() {
+        let test_cases = vec![
+            ("ACME-000123", true),
+            ("a_b.c", true),
+            ("", false),
+            ("has space", false),
+            ("slash/sku", false),
+            ("ÉLAN-1", false),
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(is_valid_sku(test_case.0), test_case.1, "{}", test_case.0);
+        }
+        assert!(!is_valid_sku(&"A".repeat(65)));
+    }
+}
@@ -1,23 +1,41 @@
 use bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
-// Stock is the model for stocks
-#[derive(Debug, Serialize, Deserialize)]
+// Stock is the model for stocks. A product has one stock per location it is kept at
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stock {
     pub _id: ObjectId,
     pub client_id: ObjectId,
     pub product_id: ObjectId,
+    // warehouse_id is the location of the stock. Stocks that are not assigned to a warehouse,
+    // such as those created before warehouses, have none
+    #[serde(default)]
+    pub warehouse_id: Option<ObjectId>,
     quantity: i32,
+    // reserved is the part of the quantity held by active reservations
+    #[serde(default)]
+    reserved: i32,
+    // binned is the part of the quantity at a warehouse that is put away in its bins
+    #[serde(default)]
+    binned: i32,
 }
 
 impl Stock {
     // new returns a new stock object
-    pub fn new(client_id: ObjectId, product_id: ObjectId, quantity: i32) -> Self {
+    pub fn new(
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        quantity: i32,
+    ) -> Self {
         Self {
             _id: ObjectId::new(),
             client_id,
             product_id,
+            warehouse_id,
             quantity,
+            reserved: 0,
+            binned: 0,
         }
     }
 
@@ -30,4 +48,64 @@ impl Stock {
     pub fn get_quantity(&self) -> i32 {
         self.quantity
     }
+
+    // get_reserved returns the quantity in a stock held by reservations
+    pub fn get_reserved(&self) -> i32 {
+        self.reserved
+    }
+
+    // set_reserved sets the quantity in a stock held by reservations
+    pub fn set_reserved(&mut self, reserved: i32) {
+        self.reserved = reserved;
+    }
+
+    // get_available returns the quantity in a stock that is not held by reservations
+    pub fn get_available(&self) -> i32 {
+        self.quantity - self.reserved
+    }
+
+    // get_binned returns the quantity in a stock that is put away in bins
+    pub fn get_binned(&self) -> i32 {
+        self.binned
+    }
+
+    // set_binned sets the quantity in a stock that is put away in bins
+    pub fn set_binned(&mut self, binned: i32) {
+        self.binned = binned;
+    }
+
+    // get_unbinned returns the quantity in a stock that is not put away in a bin yet
+    pub fn get_unbinned(&self) -> i32 {
+        self.quantity - self.binned
+    }
+}
+
+// StockAllocation is a quantity of a product taken from or held at one location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StockAllocation {
+    pub warehouse_id: Option<ObjectId>,
+    pub quantity: i32,
+}
+
+// AllocatedQuantity is a quantity of a product and the locations it was taken from or held at
+#[derive(Debug, Clone)]
+pub struct AllocatedQuantity {
+    pub product_id: ObjectId,
+    pub quantity: i32,
+    pub allocations: Vec<StockAllocation>,
+}
+
+// allocations_or_unassigned returns the recorded allocations of a quantity. Quantities recorded
+// before warehouses have no allocations and were taken from the unassigned stock
+pub fn allocations_or_unassigned(
+    allocations: &[StockAllocation],
+    quantity: i32,
+) -> Vec<StockAllocation> {
+    if allocations.is_empty() {
+        return vec![StockAllocation {
+            warehouse_id: None,
+            quantity,
+        }];
+    }
+    allocations.to_vec()
 }
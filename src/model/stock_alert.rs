@@ -0,0 +1,45 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::model::stock_movement::MovementReason;
+
+// StockAlert is the model for the alert raised when a decrement takes the quantity of a product
+// across locations from above its reorder point to the reorder point or below
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockAlert {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub product_id: ObjectId,
+    // quantity is the quantity of the product across locations right after the decrement
+    pub quantity: i32,
+    pub reorder_point: i32,
+    pub reorder_quantity: Option<i32>,
+    // reason and reference are those of the stock movement of the decrement
+    pub reason: MovementReason,
+    pub reference: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl StockAlert {
+    pub fn new(
+        client_id: ObjectId,
+        product_id: ObjectId,
+        quantity: i32,
+        reorder_point: i32,
+        reorder_quantity: Option<i32>,
+        reason: MovementReason,
+        reference: Option<String>,
+    ) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            product_id,
+            quantity,
+            reorder_point,
+            reorder_quantity,
+            reason,
+            reference,
+            created_at: DateTime::now(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// MovementReason is why the quantity of a stock changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementReason {
+    // the stock was created with the product
+    Create,
+    // the quantity was set through the product quantity endpoint
+    SetQuantity,
+    // the quantity was taken out by an order
+    Order,
+    // the quantity taken out by an order was given back because the order failed
+    OrderRollback,
+    // the quantity taken out by an order was given back because the order was cancelled
+    OrderCancellation,
+    // a quantity that was given back was taken out again because the change it was given back
+    // for could not be recorded, such as a cancellation another request recorded first
+    RestockRollback,
+    // the quantity held by a reservation was taken out when it was committed
+    ReservationCommit,
+    // the quantity taken out by a reservation commit was given back and held for the
+    // reservation again because the commit failed
+    ReservationCommitRollback,
+    // resellable units returned by a customer were put back into the stock
+    Return,
+    // the stock was deleted with the product
+    Delete,
+    // units delivered against a purchase order were received into the stock
+    PurchaseReceipt,
+}
+
+// MovementSource describes who made a stock movement and what it belongs to
+#[derive(Debug, Clone)]
+pub struct MovementSource {
+    // actor is who made the movement
+    pub actor: String,
+    // reference is what the movement belongs to, such as an order id
+    pub reference: Option<String>,
+}
+
+impl MovementSource {
+    pub fn new(actor: &str, reference: Option<String>) -> Self {
+        Self {
+            actor: actor.to_string(),
+            reference,
+        }
+    }
+}
+
+// StockMovement is the model for a change to the quantity of a stock. Movements are only
+// ever appended, so they record the full history of a stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockMovement {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub product_id: ObjectId,
+    // warehouse_id is the location of the stock that changed
+    #[serde(default)]
+    pub warehouse_id: Option<ObjectId>,
+    pub reason: MovementReason,
+    pub actor: String,
+    pub reference: Option<String>,
+    pub delta: i32,
+    pub before: i32,
+    pub after: i32,
+    pub created_at: DateTime,
+    // sequence numbers the movements of a client from 1 in the order they were inserted. It is
+    // assigned by the storage, so the movements are streamed in an order every instance agrees on
+    #[serde(default)]
+    pub sequence: i64,
+}
+
+impl StockMovement {
+    // new creates a new StockMovement of a product from its quantity after the change
+    pub fn new(
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        reason: MovementReason,
+        source: &MovementSource,
+        delta: i32,
+        after: i32,
+    ) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            product_id,
+            warehouse_id,
+            reason,
+            actor: source.actor.clone(),
+            reference: source.reference.clone(),
+            delta,
+            before: after - delta,
+            after,
+            created_at: DateTime::now(),
+            sequence: 0,
+        }
+    }
+}
+
+// MovementSequence is the model for the last sequence number given to a movement of a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovementSequence {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub value: i64,
+}
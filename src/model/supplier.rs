@@ -0,0 +1,41 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// Supplier is the model for a business a client buys its products from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Supplier {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub name: String,
+    // contact is who or how to reach at the supplier, such as an email address
+    pub contact: Option<String>,
+    // lead_time_days is how many days the goods take to arrive once ordered
+    pub lead_time_days: Option<i32>,
+    // minimum_order_quantity is the fewest units of a product the supplier takes an order for
+    pub minimum_order_quantity: Option<i32>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Supplier {
+    // new creates a new Supplier of a client
+    pub fn new(
+        client_id: ObjectId,
+        name: String,
+        contact: Option<String>,
+        lead_time_days: Option<i32>,
+        minimum_order_quantity: Option<i32>,
+    ) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            name,
+            contact,
+            lead_time_days,
+            minimum_order_quantity,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// Warehouse is the model for a location a client keeps stock at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warehouse {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub name: String,
+    pub address: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Warehouse {
+    // new creates a new Warehouse of a client
+    pub fn new(client_id: ObjectId, name: String, address: Option<String>) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            name,
+            address,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// WebhookEvent is a kind of inventory event a webhook can be notified of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    ProductCreated,
+    ProductUpdated,
+    ProductDeleted,
+    // the quantity of a stock changed, for any reason
+    QuantityChanged,
+    OrderProcessed,
+    // the quantity of a product across locations went down to zero
+    OutOfStock,
+}
+
+// Webhook is the model for an endpoint of a client notified of the events it subscribed to.
+// The secret signs every delivery, so that the endpoint can check that it was sent by us
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub secret: String,
+    // active is false for a webhook that is not notified of any event until it is enabled again
+    pub active: bool,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Webhook {
+    pub fn new(
+        client_id: ObjectId,
+        url: String,
+        events: Vec<WebhookEvent>,
+        secret: String,
+    ) -> Self {
+        let now = DateTime::now();
+        Self {
+            _id: ObjectId::new(),
+            client_id,
+            url,
+            events,
+            secret,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+// DeliveryStatus is the state of a webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    // the payload has not been accepted by the endpoint yet and will be sent again
+    Pending,
+    Delivered,
+    // every attempt failed, the delivery is only sent again if it is replayed
+    Failed,
+}
+
+// DeliveryAttempt is one time a payload was sent to an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub attempted_at: DateTime,
+    // status_code is the status of the response of the endpoint, None if it did not respond
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+// WebhookDelivery is the model for the notification of an event to a webhook, with every
+// attempt to send it. The payload is kept as it is sent, so that a replay is signed over the
+// same body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub _id: ObjectId,
+    pub client_id: ObjectId,
+    pub webhook_id: ObjectId,
+    // event_id identifies the event in the payload, it is the same for every delivery of the
+    // event so that endpoints can tell replays apart from new events
+    pub event_id: ObjectId,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<DeliveryAttempt>,
+    // next_attempt_at is when a pending delivery is sent next
+    pub next_attempt_at: Option<DateTime>,
+    // replay_of is the delivery this one replays
+    pub replay_of: Option<ObjectId>,
+    pub created_at: DateTime,
+}
+
+impl WebhookDelivery {
+    pub fn new(
+        webhook: &Webhook,
+        event_id: ObjectId,
+        event: WebhookEvent,
+        payload: String,
+        next_attempt_at: DateTime,
+    ) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            client_id: webhook.client_id,
+            webhook_id: webhook._id,
+            event_id,
+            event,
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: vec![],
+            next_attempt_at: Some(next_attempt_at),
+            replay_of: None,
+            created_at: DateTime::now(),
+        }
+    }
+}
+
+// DeliveryQuery selects the deliveries of a client to list, the most recent first
+#[derive(Debug, Clone)]
+pub struct DeliveryQuery {
+    pub webhook_id: Option<ObjectId>,
+    pub status: Option<DeliveryStatus>,
+    pub limit: i64,
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOptions, IndexOptions, UpdateOptions};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::attribute::AttributeDefinition;
+
+// AttributeDefinitionStore is the storage of the attribute definitions of every client. There
+// is at most one definition per client and attribute name
+#[async_trait]
+pub trait AttributeDefinitionStore: Send + Sync {
+    // get_by_client_id gets the attribute definitions of a client, by name
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<AttributeDefinition>, Error>;
+
+    // upsert replaces the definition of an attribute of a client, creating it if the attribute
+    // has none
+    async fn upsert(&self, definition: &AttributeDefinition) -> Result<(), Error>;
+
+    // delete deletes the definition of an attribute of a client. Returns false if the attribute
+    // has none
+    async fn delete(&self, client_id: ObjectId, name: &str) -> Result<bool, Error>;
+}
+
+#[derive(Clone)]
+pub struct AttributeDefinitionRepo {
+    collection: Collection<AttributeDefinition>,
+}
+
+impl AttributeDefinitionRepo {
+    // new creates an attribute definition repository instance
+    pub fn new(collection: Collection<AttributeDefinition>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the unique index that keeps one definition per client and name
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let name_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "name": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.collection.create_index(name_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttributeDefinitionStore for AttributeDefinitionRepo {
+    // get_by_client_id gets the attribute definitions of a client from the database
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<AttributeDefinition>, Error> {
+        let options = FindOptions::builder().sort(doc! {"name": 1}).build();
+        let cursor = self
+            .collection
+            .find(doc! {"client_id": client_id}, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    // upsert sets the type of an attribute of a client, the id of an existing definition is kept
+    async fn upsert(&self, definition: &AttributeDefinition) -> Result<(), Error> {
+        let filter = doc! {"client_id": definition.client_id, "name": &definition.name};
+        let update_doc = doc! {
+            "$set": {
+                "type": bson::to_bson(&definition.attribute_type)?,
+                "updated_at": definition.updated_at,
+            },
+            "$setOnInsert": {"_id": definition._id},
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.collection
+            .update_one(filter, update_doc, options)
+            .await?;
+        Ok(())
+    }
+
+    // delete deletes the definition of an attribute of a client from the database
+    async fn delete(&self, client_id: ObjectId, name: &str) -> Result<bool, Error> {
+        let result = self
+            .collection
+            .delete_one(doc! {"client_id": client_id, "name": name}, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOptions, IndexOptions};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::{model::bin::Bin, repository::mongo::is_duplicate_key_error};
+
+// BinStore is the storage of the bins in the warehouses of every client. There is at most
+// one bin per place in a warehouse
+#[async_trait]
+pub trait BinStore: Send + Sync {
+    // insert stores a new bin. Returns false without storing it if the warehouse already
+    // has a bin at the same place
+    async fn insert(&self, bin: &Bin) -> Result<bool, Error>;
+
+    // get_by_id retrieves a bin of a client by id
+    async fn get_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<Option<Bin>, Error>;
+
+    // get_by_ids retrieves the bins of a client with the given ids
+    async fn get_by_ids(
+        &self,
+        client_id: ObjectId,
+        bin_ids: &[ObjectId],
+    ) -> Result<Vec<Bin>, Error>;
+
+    // get_by_warehouse_id retrieves the bins of a warehouse in the order of their places.
+    // The bins can be narrowed down to a zone, an aisle of the zone and a rack of the aisle
+    async fn get_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        zone: Option<String>,
+        aisle: Option<String>,
+        rack: Option<String>,
+    ) -> Result<Vec<Bin>, Error>;
+
+    // delete_by_id deletes a bin of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error>;
+
+    // delete_by_warehouse_id deletes all bins of a warehouse
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct BinRepo {
+    collection: Collection<Bin>,
+}
+
+impl BinRepo {
+    // new creates a bin repository instance
+    pub fn new(collection: Collection<Bin>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the unique index that keeps one bin per place in a warehouse
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let place_index = IndexModel::builder()
+            .keys(doc! {
+                "client_id": 1,
+                "warehouse_id": 1,
+                "zone": 1,
+                "aisle": 1,
+                "rack": 1,
+                "bin": 1,
+            })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.collection.create_index(place_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BinStore for BinRepo {
+    // insert inserts a bin in the database. Returns false without inserting if the warehouse
+    // already has a bin at the same place
+    async fn insert(&self, bin: &Bin) -> Result<bool, Error> {
+        match self.collection.insert_one(bin, None).await {
+            Ok(_) => Ok(true),
+            Err(err) if is_duplicate_key_error(&err) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // get_by_id retrieves a bin of a client from the database by id
+    async fn get_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<Option<Bin>, Error> {
+        self.collection
+            .find_one(Some(doc! {"_id": bin_id, "client_id": client_id}), None)
+            .await
+    }
+
+    // get_by_ids retrieves the bins of a client with the given ids
+    async fn get_by_ids(
+        &self,
+        client_id: ObjectId,
+        bin_ids: &[ObjectId],
+    ) -> Result<Vec<Bin>, Error> {
+        let filter = doc! {"client_id": client_id, "_id": {"$in": bin_ids}};
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    // get_by_warehouse_id retrieves the bins of a warehouse in the order of their places.
+    // The bins can be narrowed down to a zone, an aisle of the zone and a rack of the aisle
+    async fn get_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        zone: Option<String>,
+        aisle: Option<String>,
+        rack: Option<String>,
+    ) -> Result<Vec<Bin>, Error> {
+        let mut filter = doc! {"client_id": client_id, "warehouse_id": warehouse_id};
+        if let Some(zone) = zone {
+            filter.insert("zone", zone);
+        }
+        if let Some(aisle) = aisle {
+            filter.insert("aisle", aisle);
+        }
+        if let Some(rack) = rack {
+            filter.insert("rack", rack);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! {"zone": 1, "aisle": 1, "rack": 1, "bin": 1})
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    // delete_by_id deletes a bin of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error> {
+        self.collection
+            .delete_one(doc! {"_id": bin_id, "client_id": client_id}, None)
+            .await?;
+        Ok(())
+    }
+
+    // delete_by_warehouse_id deletes all bins of a warehouse
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.collection
+            .delete_many(
+                doc! {"client_id": client_id, "warehouse_id": warehouse_id},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::bin::BinStock;
+
+// BinStockStore is the storage of the quantities of products stored in bins. There is at most
+// one bin stock per product and bin
+#[async_trait]
+pub trait BinStockStore: Send + Sync {
+    // get_by_bin_id gets the stocks of every product in a bin
+    async fn get_by_bin_id(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+    ) -> Result<Vec<BinStock>, Error>;
+
+    // get_by_product_id gets the stocks of a product in every bin, or only in the bins
+    // of a warehouse if one is given
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<Vec<BinStock>, Error>;
+
+    // decrement_if_enough atomically decrements the quantity of a product in a bin by the given
+    // number. Returns the updated bin stock, or None if the bin does not hold that number
+    async fn decrement_if_enough(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error>;
+
+    // increment_or_insert atomically increments the quantity of a product in a bin by the given
+    // number, creating the bin stock if the product is not stored in the bin yet
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error>;
+
+    // delete_empty_by_product_id deletes the bin stocks of a product that hold no quantity
+    async fn delete_empty_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<(), Error>;
+
+    // delete_by_bin_id deletes the bin stocks of a bin
+    async fn delete_by_bin_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error>;
+
+    // delete_by_warehouse_id deletes the bin stocks of every bin of a warehouse
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct BinStockRepo {
+    collection: Collection<BinStock>,
+}
+
+impl BinStockRepo {
+    // new creates a bin stock repository instance
+    pub fn new(collection: Collection<BinStock>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the unique index that keeps one bin stock per product and bin
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let bin_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "bin_id": 1, "product_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.collection.create_index(bin_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BinStockStore for BinStockRepo {
+    // get_by_bin_id gets the stocks of every product in a bin
+    async fn get_by_bin_id(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+    ) -> Result<Vec<BinStock>, Error> {
+        let filter = doc! {"client_id": client_id, "bin_id": bin_id};
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    // get_by_product_id gets the stocks of a product in every bin, or only in the bins
+    // of a warehouse if one is given
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<Vec<BinStock>, Error> {
+        let mut filter = doc! {"client_id": client_id, "product_id": product_id};
+        if let Some(warehouse_id) = warehouse_id {
+            filter.insert("warehouse_id", warehouse_id);
+        }
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    // decrement_if_enough atomically decrements the quantity of a product in a bin by the given
+    // number. Returns the updated bin stock, or None if the bin does not hold that number
+    async fn decrement_if_enough(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error> {
+        let filter = doc! {
+            "client_id": client_id,
+            "bin_id": bin_id,
+            "product_id": product_id,
+            "quantity": {"$gte": number},
+        };
+        let update_doc = doc! {"$inc": {"quantity": -number}};
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+
+    // increment_or_insert atomically increments the quantity of a product in a bin by the given
+    // number, creating the bin stock if the product is not stored in the bin yet
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error> {
+        let filter = doc! {"client_id": client_id, "bin_id": bin_id, "product_id": product_id};
+        let update_doc = doc! {
+            "$inc": {"quantity": number},
+            "$setOnInsert": {"warehouse_id": warehouse_id},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+
+    // delete_empty_by_product_id deletes the bin stocks of a product that hold no quantity
+    async fn delete_empty_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<(), Error> {
+        let filter = doc! {
+            "client_id": client_id,
+            "product_id": product_id,
+            "quantity": {"$lte": 0},
+        };
+        self.collection.delete_many(filter, None).await?;
+        Ok(())
+    }
+
+    // delete_by_bin_id deletes the bin stocks of a bin
+    async fn delete_by_bin_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error> {
+        self.collection
+            .delete_many(doc! {"client_id": client_id, "bin_id": bin_id}, None)
+            .await?;
+        Ok(())
+    }
+
+    // delete_by_warehouse_id deletes the bin stocks of every bin of a warehouse
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.collection
+            .delete_many(
+                doc! {"client_id": client_id, "warehouse_id": warehouse_id},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use mongodb::options::IndexOptions;
+use mongodb::{error::Error, Collection, IndexModel};
+use std::time::Duration;
+
+use crate::{model::idempotency_key::IdempotencyKey, repository::mongo::is_duplicate_key_error};
+
+// IdempotencyStore is the storage of the idempotency keys of every client. Keys are unique
+// per client
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    // insert stores an idempotency key. Returns false without storing it if the client
+    // already has the key
+    async fn insert(&self, idempotency_key: &IdempotencyKey) -> Result<bool, Error>;
+
+    // get_by_key retrieves an idempotency key of a client
+    async fn get_by_key(
+        &self,
+        client_id: ObjectId,
+        key: &str,
+    ) -> Result<Option<IdempotencyKey>, Error>;
+
+    // set_response records the response of the request an idempotency key was claimed for by
+    // id, unless the claim was completed or given up in the meantime
+    async fn set_response(
+        &self,
+        id: ObjectId,
+        status_code: i32,
+        response_body: &str,
+    ) -> Result<(), Error>;
+
+    // delete_by_id deletes an idempotency key by id
+    async fn delete_by_id(&self, id: ObjectId) -> Result<(), Error>;
+
+    // delete_pending deletes an idempotency key by id if its request has not completed
+    async fn delete_pending(&self, id: ObjectId) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct IdempotencyRepo {
+    collection: Collection<IdempotencyKey>,
+}
+
+impl IdempotencyRepo {
+    // new creates an idempotency key repository instance
+    pub fn new(collection: Collection<IdempotencyKey>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the indexes the repository relies on. Keys are unique per client,
+    // and MongoDB removes them once they are past their expiry time
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let unique_key = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "key": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let expiry = IndexModel::builder()
+            .keys(doc! {"expires_at": 1})
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+        self.collection
+            .create_indexes(vec![unique_key, expiry], None)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for IdempotencyRepo {
+    // insert inserts an idempotency key in the database. Returns false without inserting
+    // if the client already has the key
+    async fn insert(&self, idempotency_key: &IdempotencyKey) -> Result<bool, Error> {
+        match self.collection.insert_one(idempotency_key, None).await {
+            Ok(_) => Ok(true),
+            Err(err) if is_duplicate_key_error(&err) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // get_by_key retrieves an idempotency key of a client from the database
+    async fn get_by_key(
+        &self,
+        client_id: ObjectId,
+        key: &str,
+    ) -> Result<Option<IdempotencyKey>, Error> {
+        self.collection
+            .find_one(Some(doc! {"client_id": client_id, "key": key}), None)
+            .await
+    }
+
+    // set_response records the response of the request an idempotency key was claimed for in
+    // the database
+    async fn set_response(
+        &self,
+        id: ObjectId,
+        status_code: i32,
+        response_body: &str,
+    ) -> Result<(), Error> {
+        let filter = doc! {"_id": id, "status_code": null};
+        let update_doc = doc! {
+            "$set": {"status_code": status_code, "response_body": response_body},
+        };
+        self.collection.update_one(filter, update_doc, None).await?;
+        Ok(())
+    }
+
+    // delete_by_id deletes an idempotency key by id
+    async fn delete_by_id(&self, id: ObjectId) -> Result<(), Error> {
+        self.collection.delete_one(doc! {"_id": id}, None).await?;
+        Ok(())
+    }
+
+    // delete_pending deletes an idempotency key by id if its request has not completed. A
+    // missing status code matches null
+    async fn delete_pending(&self, id: ObjectId) -> Result<(), Error> {
+        self.collection
+            .delete_one(doc! {"_id": id, "status_code": null}, None)
+            .await?;
+        Ok(())
+    }
+}
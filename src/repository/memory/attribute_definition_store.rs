@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::attribute::AttributeDefinition,
+    repository::{attribute_definition_repo::AttributeDefinitionStore, memory::MemoryCollection},
+};
+
+// MemoryAttributeDefinitionStore keeps the attribute definitions of the clients in memory
+#[derive(Clone, Default)]
+pub struct MemoryAttributeDefinitionStore {
+    definitions: MemoryCollection<AttributeDefinition>,
+}
+
+#[async_trait]
+impl AttributeDefinitionStore for MemoryAttributeDefinitionStore {
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<AttributeDefinition>, Error> {
+        let mut definitions = self
+            .definitions
+            .find(|definition| definition.client_id == client_id);
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(definitions)
+    }
+
+    async fn upsert(&self, definition: &AttributeDefinition) -> Result<(), Error> {
+        self.definitions.update_or_insert(
+            |existing| {
+                existing.client_id == definition.client_id && existing.name == definition.name
+            },
+            || definition.clone(),
+            |existing| {
+                existing.attribute_type = definition.attribute_type;
+                existing.updated_at = definition.updated_at;
+            },
+        );
+        Ok(())
+    }
+
+    async fn delete(&self, client_id: ObjectId, name: &str) -> Result<bool, Error> {
+        let deleted = self
+            .definitions
+            .delete(|definition| definition.client_id == client_id && definition.name == name);
+        Ok(!deleted.is_empty())
+    }
+}
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::bin::BinStock,
+    repository::{bin_stock_repo::BinStockStore, memory::MemoryCollection},
+};
+
+// MemoryBinStockStore keeps the quantities of products stored in bins in memory
+#[derive(Clone, Default)]
+pub struct MemoryBinStockStore {
+    bin_stocks: MemoryCollection<BinStock>,
+}
+
+#[async_trait]
+impl BinStockStore for MemoryBinStockStore {
+    async fn get_by_bin_id(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+    ) -> Result<Vec<BinStock>, Error> {
+        Ok(self
+            .bin_stocks
+            .find(|bin_stock| bin_stock.client_id == client_id && bin_stock.bin_id == bin_id))
+    }
+
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<Vec<BinStock>, Error> {
+        Ok(self.bin_stocks.find(|bin_stock| {
+            bin_stock.client_id == client_id
+                && bin_stock.product_id == product_id
+                && warehouse_id.is_none_or(|warehouse_id| bin_stock.warehouse_id == warehouse_id)
+        }))
+    }
+
+    async fn decrement_if_enough(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error> {
+        Ok(self.bin_stocks.update_one(
+            |bin_stock| {
+                in_bin(bin_stock, client_id, bin_id, product_id) && bin_stock.quantity >= number
+            },
+            |bin_stock| bin_stock.quantity -= number,
+        ))
+    }
+
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        bin_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+    ) -> Result<Option<BinStock>, Error> {
+        Ok(Some(self.bin_stocks.update_or_insert(
+            |bin_stock| in_bin(bin_stock, client_id, bin_id, product_id),
+            || BinStock {
+                _id: ObjectId::new(),
+                client_id,
+                warehouse_id,
+                bin_id,
+                product_id,
+                quantity: 0,
+            },
+            |bin_stock| bin_stock.quantity += number,
+        )))
+    }
+
+    async fn delete_empty_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.bin_stocks.delete(|bin_stock| {
+            bin_stock.client_id == client_id
+                && bin_stock.product_id == product_id
+                && bin_stock.quantity <= 0
+        });
+        Ok(())
+    }
+
+    async fn delete_by_bin_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error> {
+        self.bin_stocks
+            .delete(|bin_stock| bin_stock.client_id == client_id && bin_stock.bin_id == bin_id);
+        Ok(())
+    }
+
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.bin_stocks.delete(|bin_stock| {
+            bin_stock.client_id == client_id && bin_stock.warehouse_id == warehouse_id
+        });
+        Ok(())
+    }
+}
+
+// in_bin reports whether a bin stock is the stock of a product in a bin
+fn in_bin(
+    bin_stock: &BinStock,
+    client_id: ObjectId,
+    bin_id: ObjectId,
+    product_id: ObjectId,
+) -> bool {
+    bin_stock.client_id == client_id
+        && bin_stock.bin_id == bin_id
+        && bin_stock.product_id == product_id
+}
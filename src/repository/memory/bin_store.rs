@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::bin::Bin,
+    repository::{bin_repo::BinStore, memory::MemoryCollection},
+};
+
+// MemoryBinStore keeps the bins in memory
+#[derive(Clone, Default)]
+pub struct MemoryBinStore {
+    bins: MemoryCollection<Bin>,
+}
+
+#[async_trait]
+impl BinStore for MemoryBinStore {
+    async fn insert(&self, bin: &Bin) -> Result<bool, Error> {
+        Ok(self.bins.insert_unless(bin.clone(), |existing| {
+            existing.client_id == bin.client_id
+                && existing.warehouse_id == bin.warehouse_id
+                && existing.zone == bin.zone
+                && existing.aisle == bin.aisle
+                && existing.rack == bin.rack
+                && existing.bin == bin.bin
+        }))
+    }
+
+    async fn get_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<Option<Bin>, Error> {
+        Ok(self
+            .bins
+            .find_one(|bin| bin._id == bin_id && bin.client_id == client_id))
+    }
+
+    async fn get_by_ids(
+        &self,
+        client_id: ObjectId,
+        bin_ids: &[ObjectId],
+    ) -> Result<Vec<Bin>, Error> {
+        Ok(self
+            .bins
+            .find(|bin| bin.client_id == client_id && bin_ids.contains(&bin._id)))
+    }
+
+    async fn get_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        zone: Option<String>,
+        aisle: Option<String>,
+        rack: Option<String>,
+    ) -> Result<Vec<Bin>, Error> {
+        let mut bins = self.bins.find(|bin| {
+            bin.client_id == client_id
+                && bin.warehouse_id == warehouse_id
+                && zone.as_ref().is_none_or(|zone| &bin.zone == zone)
+                && aisle.as_ref().is_none_or(|aisle| &bin.aisle == aisle)
+                && rack.as_ref().is_none_or(|rack| &bin.rack == rack)
+        });
+        bins.sort_by(|a, b| {
+            (&a.zone, &a.aisle, &a.rack, &a.bin).cmp(&(&b.zone, &b.aisle, &b.rack, &b.bin))
+        });
+        Ok(bins)
+    }
+
+    async fn delete_by_id(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), Error> {
+        self.bins
+            .delete(|bin| bin._id == bin_id && bin.client_id == client_id);
+        Ok(())
+    }
+
+    async fn delete_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.bins
+            .delete(|bin| bin.client_id == client_id && bin.warehouse_id == warehouse_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::idempotency_key::IdempotencyKey,
+    repository::{idempotency_repo::IdempotencyStore, memory::MemoryCollection},
+};
+
+// MemoryIdempotencyStore keeps the idempotency keys in memory. Expired keys are not removed
+// in the background, the idempotency service forgets them when they are used again
+#[derive(Clone, Default)]
+pub struct MemoryIdempotencyStore {
+    keys: MemoryCollection<IdempotencyKey>,
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyStore {
+    async fn insert(&self, idempotency_key: &IdempotencyKey) -> Result<bool, Error> {
+        Ok(self
+            .keys
+            .insert_unless(idempotency_key.clone(), |existing| {
+                existing.client_id == idempotency_key.client_id
+                    && existing.key == idempotency_key.key
+            }))
+    }
+
+    async fn get_by_key(
+        &self,
+        client_id: ObjectId,
+        key: &str,
+    ) -> Result<Option<IdempotencyKey>, Error> {
+        Ok(self
+            .keys
+            .find_one(|existing| existing.client_id == client_id && existing.key == key))
+    }
+
+    async fn set_response(
+        &self,
+        id: ObjectId,
+        status_code: i32,
+        response_body: &str,
+    ) -> Result<(), Error> {
+        self.keys.update_one(
+            |existing| existing._id == id && existing.status_code.is_none(),
+            |existing| {
+                existing.status_code = Some(status_code);
+                existing.response_body = Some(response_body.to_string());
+            },
+        );
+        Ok(())
+    }
+
+    async fn delete_by_id(&self, id: ObjectId) -> Result<(), Error> {
+        self.keys.delete(|existing| existing._id == id);
+        Ok(())
+    }
+
+    async fn delete_pending(&self, id: ObjectId) -> Result<(), Error> {
+        self.keys
+            .delete(|existing| existing._id == id && existing.status_code.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+pub mod attribute_definition_store;
+pub mod bin_stock_store;
+pub mod bin_store;
+pub mod idempotency_store;
+pub mod order_store;
+pub mod product_store;
+pub mod purchase_order_store;
+pub mod reservation_store;
+pub mod return_store;
+pub mod sku_counter_store;
+pub mod sku_settings_store;
+pub mod stock_alert_store;
+pub mod stock_movement_store;
+pub mod stock_store;
+pub mod supplier_store;
+pub mod warehouse_store;
+pub mod webhook_delivery_store;
+pub mod webhook_store;
+
+// MemoryCollection is a list of documents kept in memory and shared by the clones of a store.
+// Every operation holds the lock for its whole duration, so each one is atomic like a single
+// document operation in MongoDB
+pub struct MemoryCollection<T> {
+    documents: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> Clone for MemoryCollection<T> {
+    fn clone(&self) -> Self {
+        Self {
+            documents: self.documents.clone(),
+        }
+    }
+}
+
+impl<T> Default for MemoryCollection<T> {
+    fn default() -> Self {
+        Self {
+            documents: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl<T: Clone> MemoryCollection<T> {
+    // insert adds a document to the collection
+    pub fn insert(&self, document: T) {
+        self.lock().push(document);
+    }
+
+    // insert_with adds a document made from the documents already in the collection, such as
+    // one numbered after them, and returns it
+    pub fn insert_with(&self, make: impl FnOnce(&[T]) -> T) -> T {
+        let mut documents = self.lock();
+        let document = make(&documents);
+        documents.push(document.clone());
+        document
+    }
+
+    // insert_unless adds a document unless one matching the filter already exists.
+    // Returns false if the document was not added
+    pub fn insert_unless(&self, document: T, exists: impl Fn(&T) -> bool) -> bool {
+        let mut documents = self.lock();
+        if documents.iter().any(exists) {
+            return false;
+        }
+        documents.push(document);
+        true
+    }
+
+    // find_one returns the first document matching the filter
+    pub fn find_one(&self, filter: impl Fn(&T) -> bool) -> Option<T> {
+        self.lock()
+            .iter()
+            .find(|document| filter(document))
+            .cloned()
+    }
+
+    // find returns every document matching the filter, in the order they were added
+    pub fn find(&self, filter: impl Fn(&T) -> bool) -> Vec<T> {
+        self.lock()
+            .iter()
+            .filter(|document| filter(document))
+            .cloned()
+            .collect()
+    }
+
+    // count counts the documents matching the filter
+    pub fn count(&self, filter: impl Fn(&T) -> bool) -> u64 {
+        self.lock()
+            .iter()
+            .filter(|document| filter(document))
+            .count() as u64
+    }
+
+    // update_one applies an update to the first document matching the filter and returns the
+    // document as it is after the update, or None if no document matches
+    pub fn update_one(
+        &self,
+        filter: impl Fn(&T) -> bool,
+        update: impl FnOnce(&mut T),
+    ) -> Option<T> {
+        let mut documents = self.lock();
+        let document = documents.iter_mut().find(|document| filter(document))?;
+        update(document);
+        Some(document.clone())
+    }
+
+    // update_many applies an update to every document matching the filter and returns how many
+    // documents matched
+    pub fn update_many(&self, filter: impl Fn(&T) -> bool, update: impl Fn(&mut T)) -> u64 {
+        let mut documents = self.lock();
+        let mut matched = 0;
+        for document in documents.iter_mut().filter(|document| filter(document)) {
+            update(document);
+            matched += 1;
+        }
+        matched
+    }
+
+    // update_or_insert applies an update to the first document matching the filter, adding the
+    // inserted document first if none matches. Returns the document as it is after the update
+    pub fn update_or_insert(
+        &self,
+        filter: impl Fn(&T) -> bool,
+        insert: impl FnOnce() -> T,
+        update: impl FnOnce(&mut T),
+    ) -> T {
+        let mut documents = self.lock();
+        let index = match documents.iter().position(filter) {
+            Some(index) => index,
+            None => {
+                documents.push(insert());
+                documents.len() - 1
+            }
+        };
+        update(&mut documents[index]);
+        documents[index].clone()
+    }
+
+    // delete removes every document matching the filter and returns them
+    pub fn delete(&self, filter: impl Fn(&T) -> bool) -> Vec<T> {
+        let mut documents = self.lock();
+        let (deleted, kept) = documents.drain(..).partition(|document| filter(document));
+        *documents = kept;
+        deleted
+    }
+
+    // lock gives access to the documents. A panic while the lock was held leaves every
+    // document whole, so the documents are still used after one
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        self.documents
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::MemoryCollection;
+
+    #[test]
+    // test_update_one_is_atomic tests that concurrent conditional updates never take a value
+    // below zero, as every check and update happens under the same lock
+    fn test_update_one_is_atomic() {
+        let collection: MemoryCollection<i32> = MemoryCollection::default();
+        collection.insert(50);
+
+        let handles: Vec<_> = (0..100)
+            .map(|_| {
+                let collection = collection.clone();
+                thread::spawn(move || {
+                    collection.update_one(|value| *value >= 1, |value| *value -= 1)
+                })
+            })
+            .collect();
+        let taken = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .count();
+
+        assert_eq!(taken, 50);
+        assert_eq!(collection.find_one(|_| true), Some(0));
+    }
+}
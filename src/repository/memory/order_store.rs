@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::order::{Order, OrderQuery},
+    repository::{memory::MemoryCollection, order_repo::OrderStore},
+};
+
+// MemoryOrderStore keeps the orders in memory
+#[derive(Clone, Default)]
+pub struct MemoryOrderStore {
+    orders: MemoryCollection<Order>,
+}
+
+#[async_trait]
+impl OrderStore for MemoryOrderStore {
+    async fn insert(&self, order: &Order) -> Result<(), Error> {
+        self.orders.insert(order.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Option<Order>, Error> {
+        Ok(self
+            .orders
+            .find_one(|order| order._id == order_id && order.client_id == client_id))
+    }
+
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Order>, Error> {
+        let mut orders = self
+            .orders
+            .find(|order| order.client_id == client_id && matches_query(order, query));
+        orders.sort_by_key(|order| Reverse((order.created_at, order._id)));
+        // like MongoDB, a limit of zero means no limit
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        Ok(orders.into_iter().skip(skip as usize).take(limit).collect())
+    }
+
+    async fn count_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+    ) -> Result<u64, Error> {
+        Ok(self
+            .orders
+            .count(|order| order.client_id == client_id && matches_query(order, query)))
+    }
+
+    async fn replace_if_unchanged(&self, order: &Order, read_version: i32) -> Result<bool, Error> {
+        let replaced = self.orders.update_one(
+            |existing| {
+                existing._id == order._id
+                    && existing.client_id == order.client_id
+                    && existing.version == read_version
+            },
+            |existing| *existing = order.clone(),
+        );
+        Ok(replaced.is_some())
+    }
+}
+
+// matches_query tells if an order matches a query
+fn matches_query(order: &Order, query: &OrderQuery) -> bool {
+    query.status.is_none_or(|status| order.status == status)
+        && query
+            .product_id
+            .is_none_or(|product_id| order.lines.iter().any(|line| line.product_id == product_id))
+}
@@ -0,0 +1,307 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+    model::{
+        product::{
+            search_words, Product, ProductCursor, ProductListQuery, ProductSearchHit,
+            ProductSupplier, SortOrder, StockLevel, DESCRIPTION_SEARCH_WEIGHT, NAME_SEARCH_WEIGHT,
+            PREFIX_MATCH_SCORE, SKU_SEARCH_WEIGHT,
+        },
+        stock::Stock,
+    },
+    repository::{memory::MemoryCollection, product_repo::ProductStore},
+};
+
+// MemoryProductStore keeps the products in memory. It reads the stocks kept by the stock store
+// to list the products with their quantities
+#[derive(Clone)]
+pub struct MemoryProductStore {
+    products: MemoryCollection<Product>,
+    stocks: MemoryCollection<Stock>,
+}
+
+impl MemoryProductStore {
+    // new creates a product store that lists quantities from the given stocks
+    pub fn new(stocks: MemoryCollection<Stock>) -> Self {
+        Self {
+            products: MemoryCollection::default(),
+            stocks,
+        }
+    }
+
+    // quantities returns the total quantity across locations of every product of a client. The
+    // quantity of a parent product is the total of its variants
+    fn quantities(&self, client_id: ObjectId) -> HashMap<ObjectId, i32> {
+        let mut quantities: HashMap<ObjectId, i32> = HashMap::new();
+        for stock in self.stocks.find(|stock| stock.client_id == client_id) {
+            *quantities.entry(stock.product_id).or_default() += stock.get_quantity();
+        }
+        for variant in self
+            .products
+            .find(|product| product.get_created_by() == client_id && product.parent_id.is_some())
+        {
+            let quantity = quantities.get(&variant._id).copied().unwrap_or_default();
+            if let Some(parent_id) = variant.parent_id {
+                *quantities.entry(parent_id).or_default() += quantity;
+            }
+        }
+        quantities
+    }
+}
+
+#[async_trait]
+impl ProductStore for MemoryProductStore {
+    async fn insert(&self, product: &Product) -> Result<bool, Error> {
+        Ok(self.products.insert_unless(product.clone(), |existing| {
+            existing.get_created_by() == product.get_created_by()
+                && (existing.get_sku() == product.get_sku()
+                    || existing.barcodes.iter().any(|barcode| {
+                        product
+                            .barcodes
+                            .iter()
+                            .any(|new_barcode| new_barcode.gtin == barcode.gtin)
+                    }))
+        }))
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Option<Product>, Error> {
+        Ok(self
+            .products
+            .find_one(|product| product._id == product_id && product.get_created_by() == client_id))
+    }
+
+    async fn get_by_sku(&self, client_id: ObjectId, sku: &str) -> Result<Option<Product>, Error> {
+        Ok(self
+            .products
+            .find_one(|product| product.get_sku() == sku && product.get_created_by() == client_id))
+    }
+
+    async fn get_by_barcode(
+        &self,
+        client_id: ObjectId,
+        gtin: &str,
+    ) -> Result<Option<Product>, Error> {
+        Ok(self.products.find_one(|product| {
+            product.get_created_by() == client_id
+                && product.barcodes.iter().any(|barcode| barcode.gtin == gtin)
+        }))
+    }
+
+    async fn get_variants(
+        &self,
+        client_id: ObjectId,
+        parent_id: ObjectId,
+    ) -> Result<Vec<Product>, Error> {
+        Ok(self.products.find(|product| {
+            product.get_created_by() == client_id && product.parent_id == Some(parent_id)
+        }))
+    }
+
+    async fn get_bundles(
+        &self,
+        client_id: ObjectId,
+        component_id: ObjectId,
+    ) -> Result<Vec<Product>, Error> {
+        Ok(self.products.find(|product| {
+            product.get_created_by() == client_id
+                && product
+                    .components
+                    .iter()
+                    .any(|component| component.product_id == component_id)
+        }))
+    }
+
+    async fn list(
+        &self,
+        client_id: ObjectId,
+        query: &ProductListQuery,
+    ) -> Result<(Vec<(Product, i32)>, u64), Error> {
+        let quantities = self.quantities(client_id);
+        let mut products: Vec<(Product, i32)> = self
+            .products
+            .find(|product| product.get_created_by() == client_id && product.parent_id.is_none())
+            .into_iter()
+            .map(|product| {
+                let quantity = quantities.get(&product._id).copied().unwrap_or_default();
+                (product, quantity)
+            })
+            .filter(|(product, quantity)| query.matches(product, *quantity))
+            .collect();
+        let total = products.len() as u64;
+
+        // ties on the sort field are broken by the id, and the page starts after the cursor
+        let ordered = |ordering: Ordering| match query.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        if let Some(cursor) = &query.after {
+            products.retain(|(product, quantity)| {
+                ordered(cursor.cmp_product(query.sort, product, *quantity)) == Ordering::Less
+            });
+        }
+        products.sort_by(|a, b| {
+            ordered(ProductCursor::new(&a.0, a.1).cmp_product(query.sort, &b.0, b.1))
+        });
+        products.truncate(query.limit.max(0) as usize);
+        Ok((products, total))
+    }
+
+    async fn search(
+        &self,
+        client_id: ObjectId,
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<ProductSearchHit>, Error> {
+        let quantities = self.quantities(client_id);
+        let searched = search_words(text);
+        let prefix = text.to_lowercase();
+
+        let mut hits: Vec<ProductSearchHit> = self
+            .products
+            .find(|product| product.get_created_by() == client_id)
+            .into_iter()
+            .filter_map(|product| {
+                // every searched word found in a field counts the weight of the field
+                let fields = [
+                    (&product.name, NAME_SEARCH_WEIGHT),
+                    (&product.get_sku(), SKU_SEARCH_WEIGHT),
+                    (&product.description, DESCRIPTION_SEARCH_WEIGHT),
+                ];
+                let mut score: f64 = 0.0;
+                for (field, weight) in fields {
+                    let found = words(field)
+                        .iter()
+                        .filter(|word| searched.contains(word))
+                        .count();
+                    score += (found as i32 * weight) as f64;
+                }
+                if score == 0.0
+                    && (product.name.to_lowercase().starts_with(&prefix)
+                        || product.get_sku().to_lowercase().starts_with(&prefix))
+                {
+                    score = PREFIX_MATCH_SCORE;
+                }
+
+                (score > 0.0).then(|| ProductSearchHit {
+                    quantity: quantities.get(&product._id).copied().unwrap_or_default(),
+                    product,
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.product._id.cmp(&b.product._id))
+        });
+        hits.truncate(limit.max(0) as usize);
+        Ok(hits)
+    }
+
+    async fn list_low_stock(&self, client_id: ObjectId) -> Result<Vec<(Product, i32)>, Error> {
+        let quantities = self.quantities(client_id);
+        let mut low_stock: Vec<(Product, i32)> = self
+            .products
+            .find(|product| product.get_created_by() == client_id)
+            .into_iter()
+            .map(|product| {
+                let quantity = quantities.get(&product._id).copied().unwrap_or_default();
+                (product, quantity)
+            })
+            .filter(|(product, quantity)| product.is_low_in_stock(*quantity))
+            .collect();
+        low_stock.sort_by(|a, b| (a.0.name.as_str(), a.0._id).cmp(&(b.0.name.as_str(), b.0._id)));
+        Ok(low_stock)
+    }
+
+    async fn update(&self, client_id: ObjectId, update: &Product) -> Result<bool, Error> {
+        let mut modified = false;
+        self.products.update_one(
+            |product| product._id == update._id && product.get_created_by() == client_id,
+            |product| {
+                modified = product.name != update.name
+                    || product.description != update.description
+                    || product.barcodes != update.barcodes
+                    || product.attributes != update.attributes
+                    || product.reorder_point != update.reorder_point
+                    || product.reorder_quantity != update.reorder_quantity
+                    || product.stock_level != update.stock_level;
+                product.name = update.name.clone();
+                product.description = update.description.clone();
+                product.barcodes = update.barcodes.clone();
+                product.attributes = update.attributes.clone();
+                product.reorder_point = update.reorder_point;
+                product.reorder_quantity = update.reorder_quantity;
+                product.stock_level = update.stock_level;
+            },
+        );
+        Ok(modified)
+    }
+
+    async fn set_suppliers(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        suppliers: &[ProductSupplier],
+    ) -> Result<bool, Error> {
+        let updated = self.products.update_one(
+            |product| product._id == product_id && product.get_created_by() == client_id,
+            |product| product.suppliers = suppliers.to_vec(),
+        );
+        Ok(updated.is_some())
+    }
+
+    async fn replace_stock_level(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        current: StockLevel,
+        level: StockLevel,
+    ) -> Result<bool, Error> {
+        let replaced = self.products.update_one(
+            |product| {
+                product._id == product_id
+                    && product.get_created_by() == client_id
+                    && product.stock_level == current
+            },
+            |product| product.stock_level = level,
+        );
+        Ok(replaced.is_some())
+    }
+
+    async fn remove_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.products.update_many(
+            |product| product.get_created_by() == client_id,
+            |product| {
+                product.remove_supplier(supplier_id);
+            },
+        );
+        Ok(())
+    }
+
+    async fn delete_by_id(&self, client_id: ObjectId, product_id: ObjectId) -> Result<(), Error> {
+        self.products
+            .delete(|product| product._id == product_id && product.get_created_by() == client_id);
+        Ok(())
+    }
+}
+
+// words splits a text into its lowercase words, like the text index of MongoDB does
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::purchase_order::{PurchaseOrder, PurchaseOrderStatus},
+    repository::{memory::MemoryCollection, purchase_order_repo::PurchaseOrderStore},
+};
+
+// MemoryPurchaseOrderStore keeps the purchase orders in memory
+#[derive(Clone, Default)]
+pub struct MemoryPurchaseOrderStore {
+    purchase_orders: MemoryCollection<PurchaseOrder>,
+}
+
+#[async_trait]
+impl PurchaseOrderStore for MemoryPurchaseOrderStore {
+    async fn insert(&self, purchase_order: &PurchaseOrder) -> Result<(), Error> {
+        self.purchase_orders.insert(purchase_order.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+    ) -> Result<Option<PurchaseOrder>, Error> {
+        Ok(self.purchase_orders.find_one(|purchase_order| {
+            purchase_order._id == purchase_order_id && purchase_order.client_id == client_id
+        }))
+    }
+
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        status: Option<PurchaseOrderStatus>,
+    ) -> Result<Vec<PurchaseOrder>, Error> {
+        let mut purchase_orders = self.purchase_orders.find(|purchase_order| {
+            purchase_order.client_id == client_id
+                && status.is_none_or(|status| purchase_order.status == status)
+        });
+        purchase_orders
+            .sort_by_key(|purchase_order| Reverse((purchase_order.created_at, purchase_order._id)));
+        Ok(purchase_orders)
+    }
+
+    async fn replace_if_unchanged(
+        &self,
+        purchase_order: &PurchaseOrder,
+        read_version: i32,
+    ) -> Result<bool, Error> {
+        let replaced = self.purchase_orders.update_one(
+            |existing| {
+                existing._id == purchase_order._id
+                    && existing.client_id == purchase_order.client_id
+                    && existing.version == read_version
+            },
+            |existing| *existing = purchase_order.clone(),
+        );
+        Ok(replaced.is_some())
+    }
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+
+use crate::{
+    model::reservation::{Reservation, ReservationStatus},
+    repository::{memory::MemoryCollection, reservation_repo::ReservationStore},
+};
+
+// MemoryReservationStore keeps the reservations in memory
+#[derive(Clone, Default)]
+pub struct MemoryReservationStore {
+    reservations: MemoryCollection<Reservation>,
+}
+
+#[async_trait]
+impl ReservationStore for MemoryReservationStore {
+    async fn insert(&self, reservation: &Reservation) -> Result<(), Error> {
+        self.reservations.insert(reservation.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error> {
+        Ok(self.reservations.find_one(|reservation| {
+            reservation._id == reservation_id && reservation.client_id == client_id
+        }))
+    }
+
+    async fn get_expired(&self, now: DateTime) -> Result<Vec<Reservation>, Error> {
+        Ok(self.reservations.find(|reservation| {
+            reservation.status == ReservationStatus::Active && reservation.expires_at <= now
+        }))
+    }
+
+    async fn transition(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        from: ReservationStatus,
+        to: ReservationStatus,
+    ) -> Result<Option<Reservation>, Error> {
+        Ok(self.reservations.update_one(
+            |reservation| {
+                reservation._id == reservation_id
+                    && reservation.client_id == client_id
+                    && reservation.status == from
+            },
+            |reservation| {
+                reservation.status = to;
+                reservation.updated_at = DateTime::now();
+            },
+        ))
+    }
+
+    async fn commit_if_unexpired(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        now: DateTime,
+    ) -> Result<Option<Reservation>, Error> {
+        Ok(self.reservations.update_one(
+            |reservation| {
+                reservation._id == reservation_id
+                    && reservation.client_id == client_id
+                    && reservation.status == ReservationStatus::Active
+                    && reservation.expires_at > now
+            },
+            |reservation| {
+                reservation.status = ReservationStatus::Committed;
+                reservation.updated_at = DateTime::now();
+            },
+        ))
+    }
+
+    async fn set_order_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error> {
+        Ok(self.reservations.update_one(
+            |reservation| reservation._id == reservation_id && reservation.client_id == client_id,
+            |reservation| {
+                reservation.order_id = Some(order_id);
+                reservation.updated_at = DateTime::now();
+            },
+        ))
+    }
+}
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::return_authorization::{ReturnAuthorization, ReturnLine, ReturnStatus},
+    repository::{memory::MemoryCollection, return_repo::ReturnStore},
+};
+
+// MemoryReturnStore keeps the return authorizations in memory
+#[derive(Clone, Default)]
+pub struct MemoryReturnStore {
+    returns: MemoryCollection<ReturnAuthorization>,
+}
+
+impl MemoryReturnStore {
+    // transition applies an update to a return authorization only if it is in the given status
+    fn transition(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        from: ReturnStatus,
+        update: impl FnOnce(&mut ReturnAuthorization),
+    ) -> Option<ReturnAuthorization> {
+        self.returns.update_one(
+            |return_authorization| {
+                return_authorization._id == return_id
+                    && return_authorization.client_id == client_id
+                    && return_authorization.status == from
+            },
+            update,
+        )
+    }
+}
+
+#[async_trait]
+impl ReturnStore for MemoryReturnStore {
+    async fn insert(&self, return_authorization: &ReturnAuthorization) -> Result<(), Error> {
+        self.returns.insert(return_authorization.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        Ok(self.returns.find_one(|return_authorization| {
+            return_authorization._id == return_id && return_authorization.client_id == client_id
+        }))
+    }
+
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        order_id: Option<ObjectId>,
+    ) -> Result<Vec<ReturnAuthorization>, Error> {
+        let mut returns = self.returns.find(|return_authorization| {
+            return_authorization.client_id == client_id
+                && order_id.is_none_or(|order_id| return_authorization.order_id == order_id)
+        });
+        returns.sort_by_key(|return_authorization| {
+            Reverse((return_authorization.created_at, return_authorization._id))
+        });
+        Ok(returns)
+    }
+
+    async fn mark_received(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        lines: &[ReturnLine],
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        let now = DateTime::now();
+        Ok(self.transition(
+            client_id,
+            return_id,
+            ReturnStatus::Authorized,
+            |return_authorization| {
+                return_authorization.status = ReturnStatus::Received;
+                return_authorization.lines = lines.to_vec();
+                return_authorization.received_at = Some(now);
+                return_authorization.updated_at = now;
+            },
+        ))
+    }
+
+    async fn mark_closed(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        let now = DateTime::now();
+        Ok(self.transition(
+            client_id,
+            return_id,
+            ReturnStatus::Received,
+            |return_authorization| {
+                return_authorization.status = ReturnStatus::Closed;
+                return_authorization.closed_at = Some(now);
+                return_authorization.updated_at = now;
+            },
+        ))
+    }
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::sku::SkuCounter,
+    repository::{memory::MemoryCollection, sku_counter_repo::SkuCounterStore},
+};
+
+// MemorySkuCounterStore keeps the counters of the sequential skus in memory
+#[derive(Clone, Default)]
+pub struct MemorySkuCounterStore {
+    counters: MemoryCollection<SkuCounter>,
+}
+
+#[async_trait]
+impl SkuCounterStore for MemorySkuCounterStore {
+    async fn next(&self, client_id: ObjectId, prefix: &str) -> Result<i64, Error> {
+        let counter = self.counters.update_or_insert(
+            |counter| counter.client_id == client_id && counter.prefix == prefix,
+            || SkuCounter {
+                _id: ObjectId::new(),
+                client_id,
+                prefix: prefix.to_string(),
+                value: 0,
+            },
+            |counter| counter.value += 1,
+        );
+        Ok(counter.value)
+    }
+}
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::{
+    model::sku::SkuSettings,
+    repository::{memory::MemoryCollection, sku_settings_repo::SkuSettingsStore},
+};
+
+// MemorySkuSettingsStore keeps the sku settings of the clients in memory
+#[derive(Clone, Default)]
+pub struct MemorySkuSettingsStore {
+    settings: MemoryCollection<SkuSettings>,
+}
+
+#[async_trait]
+impl SkuSettingsStore for MemorySkuSettingsStore {
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Option<SkuSettings>, Error> {
+        Ok(self
+            .settings
+            .find_one(|settings| settings.client_id == client_id))
+    }
+
+    async fn upsert(&self, settings: &SkuSettings) -> Result<(), Error> {
+        self.settings.update_or_insert(
+            |existing| existing.client_id == settings.client_id,
+            || settings.clone(),
+            |existing| {
+                existing.strategy = settings.strategy.clone();
+                existing.updated_at = settings.updated_at;
+            },
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::stock_alert::StockAlert,
+    repository::{memory::MemoryCollection, stock_alert_repo::StockAlertStore},
+};
+
+// MemoryStockAlertStore keeps the stock alerts in memory
+#[derive(Clone, Default)]
+pub struct MemoryStockAlertStore {
+    alerts: MemoryCollection<StockAlert>,
+}
+
+#[async_trait]
+impl StockAlertStore for MemoryStockAlertStore {
+    async fn insert(&self, alert: &StockAlert) -> Result<(), Error> {
+        self.alerts.insert(alert.clone());
+        Ok(())
+    }
+
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<StockAlert>, Error> {
+        let mut alerts = self.alerts.find(|alert| alert.client_id == client_id);
+        alerts.sort_by_key(|alert| Reverse((alert.created_at, alert._id)));
+        // like MongoDB, a limit of zero means no limit
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        Ok(alerts.into_iter().take(limit).collect())
+    }
+}
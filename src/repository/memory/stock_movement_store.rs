@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::stock_movement::StockMovement,
+    repository::{memory::MemoryCollection, stock_movement_repo::StockMovementStore},
+};
+
+// MemoryStockMovementStore keeps the stock movements in memory
+#[derive(Clone, Default)]
+pub struct MemoryStockMovementStore {
+    movements: MemoryCollection<StockMovement>,
+}
+
+#[async_trait]
+impl StockMovementStore for MemoryStockMovementStore {
+    // insert numbers the movement under the same lock it is added with, so the movements of a
+    // client are always inserted in the order of their sequence numbers
+    async fn insert(&self, movement: &mut StockMovement) -> Result<(), Error> {
+        let inserted = self.movements.insert_with(|movements| {
+            let mut inserted = movement.clone();
+            inserted.sequence = last_sequence(movements, movement.client_id) + 1;
+            inserted
+        });
+        movement.sequence = inserted.sequence;
+        Ok(())
+    }
+
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error> {
+        let mut movements = self
+            .movements
+            .find(|movement| movement.client_id == client_id && movement.product_id == product_id);
+        movements.sort_by_key(|movement| Reverse((movement.created_at, movement._id)));
+        // like MongoDB, a limit of zero means no limit
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        Ok(movements
+            .into_iter()
+            .skip(skip as usize)
+            .take(limit)
+            .collect())
+    }
+
+    async fn count_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<u64, Error> {
+        Ok(self
+            .movements
+            .count(|movement| movement.client_id == client_id && movement.product_id == product_id))
+    }
+
+    async fn last_sequence(&self, client_id: ObjectId) -> Result<i64, Error> {
+        Ok(last_sequence(
+            &self
+                .movements
+                .find(|movement| movement.client_id == client_id),
+            client_id,
+        ))
+    }
+
+    async fn get_after(
+        &self,
+        client_id: ObjectId,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error> {
+        let mut movements = self
+            .movements
+            .find(|movement| movement.client_id == client_id && movement.sequence > after);
+        movements.sort_by_key(|movement| movement.sequence);
+        // like MongoDB, a limit of zero means no limit
+        let limit = if limit > 0 {
+            limit as usize
+        } else {
+            usize::MAX
+        };
+        Ok(movements.into_iter().take(limit).collect())
+    }
+}
+
+// last_sequence finds the last sequence number given to a movement of a client
+fn last_sequence(movements: &[StockMovement], client_id: ObjectId) -> i64 {
+    movements
+        .iter()
+        .filter(|movement| movement.client_id == client_id)
+        .map(|movement| movement.sequence)
+        .max()
+        .unwrap_or(0)
+}
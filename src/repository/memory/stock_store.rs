@@ -0,0 +1,284 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::error::{Error, Result as MongoResult};
+use std::cmp;
+
+use crate::{
+    model::stock::Stock,
+    repository::{memory::MemoryCollection, stock_repo::StockStore},
+};
+
+// MemoryStockStore keeps the stocks in memory
+#[derive(Clone, Default)]
+pub struct MemoryStockStore {
+    stocks: MemoryCollection<Stock>,
+}
+
+impl MemoryStockStore {
+    // new creates a stock store that keeps its stocks in the given collection
+    pub fn new(stocks: MemoryCollection<Stock>) -> Self {
+        Self { stocks }
+    }
+}
+
+impl MemoryStockStore {
+    // update_at_location applies an update to the stock of a product at a location if the
+    // stock passes the check, and returns the stock as it is after the update
+    fn update_at_location(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        check: impl Fn(&Stock) -> bool,
+        update: impl FnOnce(&mut Stock),
+    ) -> Option<Stock> {
+        self.stocks.update_one(
+            |stock| at_location(stock, client_id, product_id, warehouse_id) && check(stock),
+            update,
+        )
+    }
+}
+
+#[async_trait]
+impl StockStore for MemoryStockStore {
+    async fn insert(&self, stock: &Stock) -> Result<(), Error> {
+        let inserted = self.stocks.insert_unless(stock.clone(), |existing| {
+            at_location(
+                existing,
+                stock.client_id,
+                stock.product_id,
+                stock.warehouse_id,
+            )
+        });
+        if !inserted {
+            return Err(Error::custom("product already has a stock at the location"));
+        }
+        Ok(())
+    }
+
+    async fn get_by_client_id_and_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Vec<Stock>, Error> {
+        Ok(self
+            .stocks
+            .find(|stock| stock.client_id == client_id && stock.product_id == product_id))
+    }
+
+    async fn get_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Vec<Stock>, Error> {
+        Ok(self
+            .stocks
+            .find(|stock| stock.client_id == client_id && stock.warehouse_id == Some(warehouse_id)))
+    }
+
+    async fn decrement_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |stock| stock.get_available() >= number,
+            |stock| stock.set_quantity(stock.get_quantity() - number),
+        ))
+    }
+
+    async fn reserve_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |stock| stock.get_available() >= number,
+            |stock| stock.set_reserved(stock.get_reserved() + number),
+        ))
+    }
+
+    async fn release_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |stock| stock.get_reserved() >= number,
+            |stock| stock.set_reserved(stock.get_reserved() - number),
+        ))
+    }
+
+    async fn commit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |stock| stock.get_reserved() >= number && stock.get_quantity() >= number,
+            |stock| {
+                stock.set_quantity(stock.get_quantity() - number);
+                stock.set_reserved(stock.get_reserved() - number);
+            },
+        ))
+    }
+
+    async fn uncommit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |_| true,
+            |stock| {
+                stock.set_quantity(stock.get_quantity() + number);
+                stock.set_reserved(stock.get_reserved() + number);
+            },
+        ))
+    }
+
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(Some(self.stocks.update_or_insert(
+            |stock| at_location(stock, client_id, product_id, warehouse_id),
+            || Stock::new(client_id, product_id, warehouse_id, 0),
+            |stock| stock.set_quantity(stock.get_quantity() + number),
+        )))
+    }
+
+    async fn bin_if_unbinned(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |stock| stock.get_unbinned() >= number,
+            |stock| stock.set_binned(stock.get_binned() + number),
+        ))
+    }
+
+    async fn unbin(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        Ok(self.update_at_location(
+            client_id,
+            product_id,
+            warehouse_id,
+            |_| true,
+            |stock| stock.set_binned(cmp::max(stock.get_binned() - number, 0)),
+        ))
+    }
+
+    async fn delete_by_client_id_and_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> MongoResult<Vec<Stock>> {
+        Ok(self
+            .stocks
+            .delete(|stock| stock.client_id == client_id && stock.product_id == product_id))
+    }
+
+    async fn delete_empty_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> MongoResult<()> {
+        self.stocks.delete(|stock| {
+            stock.client_id == client_id
+                && stock.warehouse_id == Some(warehouse_id)
+                && stock.get_quantity() == 0
+                && stock.get_reserved() == 0
+        });
+        Ok(())
+    }
+}
+
+// at_location reports whether a stock is the stock of a product at a location
+fn at_location(
+    stock: &Stock,
+    client_id: ObjectId,
+    product_id: ObjectId,
+    warehouse_id: Option<ObjectId>,
+) -> bool {
+    stock.client_id == client_id
+        && stock.product_id == product_id
+        && stock.warehouse_id == warehouse_id
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+
+    use super::MemoryStockStore;
+    use crate::{model::stock::Stock, repository::stock_repo::StockStore};
+
+    #[test]
+    // test_reserved_stock_is_not_available tests that reserved quantities can be neither
+    // reserved again nor taken out of stock until they are released
+    async fn test_reserved_stock_is_not_available() {
+        let stock_store = MemoryStockStore::default();
+        let (client_id, product_id) = (ObjectId::new(), ObjectId::new());
+        stock_store
+            .insert(&Stock::new(client_id, product_id, None, 10))
+            .await
+            .unwrap();
+
+        let reserve =
+            |number| stock_store.reserve_if_available(client_id, product_id, None, number);
+        let decrement =
+            |number| stock_store.decrement_if_available(client_id, product_id, None, number);
+
+        assert!(reserve(8).await.unwrap().is_some());
+        assert!(reserve(3).await.unwrap().is_none());
+        assert!(decrement(3).await.unwrap().is_none());
+        assert!(decrement(2).await.unwrap().is_some());
+
+        let stock = stock_store
+            .release_reserved(client_id, product_id, None, 8)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((stock.get_quantity(), stock.get_reserved()), (8, 0));
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+
+use crate::{
+    model::supplier::Supplier,
+    repository::{memory::MemoryCollection, supplier_repo::SupplierStore},
+};
+
+// MemorySupplierStore keeps the suppliers in memory
+#[derive(Clone, Default)]
+pub struct MemorySupplierStore {
+    suppliers: MemoryCollection<Supplier>,
+}
+
+#[async_trait]
+impl SupplierStore for MemorySupplierStore {
+    async fn insert(&self, supplier: &Supplier) -> Result<(), Error> {
+        self.suppliers.insert(supplier.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Option<Supplier>, Error> {
+        Ok(self
+            .suppliers
+            .find_one(|supplier| supplier._id == supplier_id && supplier.client_id == client_id))
+    }
+
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Supplier>, Error> {
+        let mut suppliers = self
+            .suppliers
+            .find(|supplier| supplier.client_id == client_id);
+        suppliers.sort_by_key(|supplier| (supplier.created_at, supplier._id));
+        Ok(suppliers)
+    }
+
+    async fn update(&self, supplier: &Supplier) -> Result<bool, Error> {
+        let updated = self.suppliers.update_one(
+            |existing| existing._id == supplier._id && existing.client_id == supplier.client_id,
+            |existing| {
+                existing.name = supplier.name.clone();
+                existing.contact = supplier.contact.clone();
+                existing.lead_time_days = supplier.lead_time_days;
+                existing.minimum_order_quantity = supplier.minimum_order_quantity;
+                existing.updated_at = DateTime::now();
+            },
+        );
+        Ok(updated.is_some())
+    }
+
+    async fn delete_by_id(&self, client_id: ObjectId, supplier_id: ObjectId) -> Result<(), Error> {
+        self.suppliers
+            .delete(|supplier| supplier._id == supplier_id && supplier.client_id == client_id);
+        Ok(())
+    }
+}
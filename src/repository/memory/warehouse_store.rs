@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+
+use crate::{
+    model::warehouse::Warehouse,
+    repository::{memory::MemoryCollection, warehouse_repo::WarehouseStore},
+};
+
+// MemoryWarehouseStore keeps the warehouses in memory
+#[derive(Clone, Default)]
+pub struct MemoryWarehouseStore {
+    warehouses: MemoryCollection<Warehouse>,
+}
+
+#[async_trait]
+impl WarehouseStore for MemoryWarehouseStore {
+    async fn insert(&self, warehouse: &Warehouse) -> Result<(), Error> {
+        self.warehouses.insert(warehouse.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Option<Warehouse>, Error> {
+        Ok(self.warehouses.find_one(|warehouse| {
+            warehouse._id == warehouse_id && warehouse.client_id == client_id
+        }))
+    }
+
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Warehouse>, Error> {
+        let mut warehouses = self
+            .warehouses
+            .find(|warehouse| warehouse.client_id == client_id);
+        warehouses.sort_by_key(|warehouse| (warehouse.created_at, warehouse._id));
+        Ok(warehouses)
+    }
+
+    async fn update(&self, warehouse: &Warehouse) -> Result<bool, Error> {
+        let updated = self.warehouses.update_one(
+            |existing| existing._id == warehouse._id && existing.client_id == warehouse.client_id,
+            |existing| {
+                existing.name = warehouse.name.clone();
+                existing.address = warehouse.address.clone();
+                existing.updated_at = DateTime::now();
+            },
+        );
+        Ok(updated.is_some())
+    }
+
+    async fn delete_by_id(&self, client_id: ObjectId, warehouse_id: ObjectId) -> Result<(), Error> {
+        self.warehouses
+            .delete(|warehouse| warehouse._id == warehouse_id && warehouse.client_id == client_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+use std::cmp::Reverse;
+
+use crate::{
+    model::webhook::{DeliveryQuery, DeliveryStatus, WebhookDelivery},
+    repository::{memory::MemoryCollection, webhook_delivery_repo::WebhookDeliveryStore},
+};
+
+// MemoryWebhookDeliveryStore keeps the webhook deliveries in memory
+#[derive(Clone, Default)]
+pub struct MemoryWebhookDeliveryStore {
+    deliveries: MemoryCollection<WebhookDelivery>,
+}
+
+#[async_trait]
+impl WebhookDeliveryStore for MemoryWebhookDeliveryStore {
+    async fn insert(&self, delivery: &WebhookDelivery) -> Result<(), Error> {
+        self.deliveries.insert(delivery.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        delivery_id: ObjectId,
+    ) -> Result<Option<WebhookDelivery>, Error> {
+        Ok(self
+            .deliveries
+            .find_one(|delivery| delivery._id == delivery_id && delivery.client_id == client_id))
+    }
+
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &DeliveryQuery,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        let mut deliveries = self.deliveries.find(|delivery| {
+            delivery.client_id == client_id
+                && query
+                    .webhook_id
+                    .is_none_or(|webhook_id| delivery.webhook_id == webhook_id)
+                && query.status.is_none_or(|status| delivery.status == status)
+        });
+        deliveries.sort_by_key(|delivery| Reverse((delivery.created_at, delivery._id)));
+        // like MongoDB, a limit of zero means no limit
+        let limit = if query.limit > 0 {
+            query.limit as usize
+        } else {
+            usize::MAX
+        };
+        Ok(deliveries.into_iter().take(limit).collect())
+    }
+
+    async fn claim_due(
+        &self,
+        now: DateTime,
+        lease_until: DateTime,
+    ) -> Result<Option<WebhookDelivery>, Error> {
+        Ok(self.deliveries.update_one(
+            |delivery| {
+                delivery.status == DeliveryStatus::Pending
+                    && delivery
+                        .next_attempt_at
+                        .is_some_and(|next_attempt_at| next_attempt_at <= now)
+            },
+            |delivery| delivery.next_attempt_at = Some(lease_until),
+        ))
+    }
+
+    async fn update(&self, delivery: &WebhookDelivery) -> Result<(), Error> {
+        self.deliveries.update_one(
+            |existing| existing._id == delivery._id,
+            |existing| {
+                existing.status = delivery.status;
+                existing.attempts = delivery.attempts.clone();
+                existing.next_attempt_at = delivery.next_attempt_at;
+            },
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use mongodb::error::Error;
+
+use crate::{
+    model::webhook::{Webhook, WebhookEvent},
+    repository::{memory::MemoryCollection, webhook_repo::WebhookStore},
+};
+
+// MemoryWebhookStore keeps the webhooks in memory
+#[derive(Clone, Default)]
+pub struct MemoryWebhookStore {
+    webhooks: MemoryCollection<Webhook>,
+}
+
+#[async_trait]
+impl WebhookStore for MemoryWebhookStore {
+    async fn insert(&self, webhook: &Webhook) -> Result<(), Error> {
+        self.webhooks.insert(webhook.clone());
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+    ) -> Result<Option<Webhook>, Error> {
+        Ok(self
+            .webhooks
+            .find_one(|webhook| webhook._id == webhook_id && webhook.client_id == client_id))
+    }
+
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Webhook>, Error> {
+        let mut webhooks = self.webhooks.find(|webhook| webhook.client_id == client_id);
+        webhooks.sort_by_key(|webhook| (webhook.created_at, webhook._id));
+        Ok(webhooks)
+    }
+
+    async fn get_subscribed(
+        &self,
+        client_id: ObjectId,
+        event: WebhookEvent,
+    ) -> Result<Vec<Webhook>, Error> {
+        Ok(self.webhooks.find(|webhook| {
+            webhook.client_id == client_id && webhook.active && webhook.events.contains(&event)
+        }))
+    }
+
+    async fn update(&self, webhook: &Webhook) -> Result<bool, Error> {
+        let updated = self.webhooks.update_one(
+            |existing| existing._id == webhook._id && existing.client_id == webhook.client_id,
+            |existing| {
+                existing.url = webhook.url.clone();
+                existing.events = webhook.events.clone();
+                existing.active = webhook.active;
+                existing.updated_at = DateTime::now();
+            },
+        );
+        Ok(updated.is_some())
+    }
+
+    async fn delete_by_id(&self, client_id: ObjectId, webhook_id: ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .webhooks
+            .delete(|webhook| webhook._id == webhook_id && webhook.client_id == client_id);
+        Ok(!deleted.is_empty())
+    }
+}
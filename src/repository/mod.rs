@@ -1,3 +1,21 @@
+pub mod attribute_definition_repo;
+pub mod bin_repo;
+pub mod bin_stock_repo;
+pub mod idempotency_repo;
+pub mod memory;
 pub mod mongo;
+pub mod order_repo;
 pub mod product_repo;
+pub mod purchase_order_repo;
+pub mod reservation_repo;
+pub mod return_repo;
+pub mod sku_counter_repo;
+pub mod sku_settings_repo;
+pub mod stock_alert_repo;
+pub mod stock_movement_repo;
 pub mod stock_repo;
+pub mod stores;
+pub mod supplier_repo;
+pub mod warehouse_repo;
+pub mod webhook_delivery_repo;
+pub mod webhook_repo;
@@ -1,9 +1,110 @@
-use mongodb::{Client, Database};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::{error::Error, Client, Database};
 use std::env;
 
+use crate::{
+    model::{
+        ATTRIBUTE_DEFINITION_COLLECTION, BIN_COLLECTION, BIN_STOCK_COLLECTION,
+        IDEMPOTENCY_KEY_COLLECTION, PRODUCT_COLLECTION, PURCHASE_ORDER_COLLECTION,
+        SKU_COUNTER_COLLECTION, SKU_SETTINGS_COLLECTION, STOCK_ALERT_COLLECTION, STOCK_COLLECTION,
+        STOCK_MOVEMENT_COLLECTION, STOCK_MOVEMENT_SEQUENCE_COLLECTION, WEBHOOK_COLLECTION,
+        WEBHOOK_DELIVERY_COLLECTION,
+    },
+    repository::{
+        attribute_definition_repo::AttributeDefinitionRepo, bin_repo::BinRepo,
+        bin_stock_repo::BinStockRepo, idempotency_repo::IdempotencyRepo, product_repo::ProductRepo,
+        purchase_order_repo::PurchaseOrderRepo, sku_counter_repo::SkuCounterRepo,
+        sku_settings_repo::SkuSettingsRepo, stock_alert_repo::StockAlertRepo,
+        stock_movement_repo::StockMovementRepo, stock_repo::StockRepo,
+        webhook_delivery_repo::WebhookDeliveryRepo, webhook_repo::WebhookRepo,
+    },
+};
+
+// DUPLICATE_KEY_ERROR_CODE is the error code MongoDB returns when a unique index is violated
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+
 pub fn establish_connection(client: &Client) -> Database {
     let database_name = env::var("DATABASE_NAME").expect("DATABASE_NAME is not set in env");
 
     // return a handle to the database
     client.database(&database_name)
 }
+
+// ensure_indexes creates the indexes the repositories rely on
+pub async fn ensure_indexes(database: &Database) -> Result<(), Error> {
+    IdempotencyRepo::new(database.collection(IDEMPOTENCY_KEY_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    StockMovementRepo::new(
+        database.collection(STOCK_MOVEMENT_COLLECTION),
+        database.collection(STOCK_MOVEMENT_SEQUENCE_COLLECTION),
+    )
+    .ensure_indexes()
+    .await?;
+    ProductRepo::new(database.collection(PRODUCT_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    StockRepo::new(database.collection(STOCK_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    BinRepo::new(database.collection(BIN_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    BinStockRepo::new(database.collection(BIN_STOCK_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    SkuSettingsRepo::new(database.collection(SKU_SETTINGS_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    SkuCounterRepo::new(database.collection(SKU_COUNTER_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    AttributeDefinitionRepo::new(database.collection(ATTRIBUTE_DEFINITION_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    StockAlertRepo::new(database.collection(STOCK_ALERT_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    WebhookRepo::new(database.collection(WEBHOOK_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    WebhookDeliveryRepo::new(database.collection(WEBHOOK_DELIVERY_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    PurchaseOrderRepo::new(database.collection(PURCHASE_ORDER_COLLECTION))
+        .ensure_indexes()
+        .await?;
+    Ok(())
+}
+
+// is_duplicate_key_error reports whether an error is caused by a unique index violation
+pub fn is_duplicate_key_error(err: &Error) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY_ERROR_CODE
+    )
+}
+
+// test_database returns a handle to the database used by tests that need a running MongoDB.
+// The tests are skipped when TEST_DATABASE_URL is not set in env
+#[cfg(test)]
+pub async fn test_database() -> Option<Database> {
+    use mongodb::options::ClientOptions;
+
+    let database_url = match env::var("TEST_DATABASE_URL") {
+        Ok(database_url) => database_url,
+        Err(_) => {
+            log::warn!("TEST_DATABASE_URL is not set in env, skipping database test");
+            return None;
+        }
+    };
+
+    let client_opts = ClientOptions::parse(&database_url)
+        .await
+        .expect("TEST_DATABASE_URL is incorrect");
+    let client =
+        Client::with_options(client_opts).expect("failed to start client with client_options");
+
+    Some(client.database("warehouse_service_test"))
+}
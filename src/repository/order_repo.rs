@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, Document};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection};
+
+use crate::model::order::{Order, OrderQuery};
+
+// OrderStore is the storage of the orders of every client
+#[async_trait]
+pub trait OrderStore: Send + Sync {
+    // insert stores a new order
+    async fn insert(&self, order: &Order) -> Result<(), Error>;
+
+    // get_by_id retrieves an order of a client by id
+    async fn get_by_id(&self, client_id: ObjectId, order_id: ObjectId)
+        -> Result<Option<Order>, Error>;
+
+    // get_by_client_id retrieves a page of the orders of a client matching a query, the most
+    // recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Order>, Error>;
+
+    // count_by_client_id counts the orders of a client matching a query
+    async fn count_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+    ) -> Result<u64, Error>;
+
+    // replace_if_unchanged replaces an order, but only if it is still at the version it was
+    // read at. Returns false if the order was changed in the meantime
+    async fn replace_if_unchanged(&self, order: &Order, read_version: i32) -> Result<bool, Error>;
+}
+
+#[derive(Clone)]
+pub struct OrderRepo {
+    collection: Collection<Order>,
+}
+
+impl OrderRepo {
+    // new creates an order repository instance
+    pub fn new(collection: Collection<Order>) -> Self {
+        Self { collection }
+    }
+}
+
+#[async_trait]
+impl OrderStore for OrderRepo {
+    // insert inserts an order in the database
+    async fn insert(&self, order: &Order) -> Result<(), Error> {
+        self.collection.insert_one(order, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves an order of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Option<Order>, Error> {
+        let order = self
+            .collection
+            .find_one(Some(doc! {"_id": order_id, "client_id": client_id}), None)
+            .await?;
+        Ok(order)
+    }
+
+    // get_by_client_id retrieves a page of the orders of a client matching a query from the
+    // database, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Order>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let cursor = self
+            .collection
+            .find(order_filter(client_id, query)?, options)
+            .await?;
+        let orders: Vec<Order> = cursor.try_collect().await?;
+        Ok(orders)
+    }
+
+    // count_by_client_id counts the orders of a client matching a query in the database
+    async fn count_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+    ) -> Result<u64, Error> {
+        self.collection
+            .count_documents(order_filter(client_id, query)?, None)
+            .await
+    }
+
+    // replace_if_unchanged replaces an order in the database, but only if it is still at the
+    // version it was read at. Returns false if the order was changed in the meantime
+    async fn replace_if_unchanged(
+        &self,
+        order: &Order,
+        read_version: i32,
+    ) -> Result<bool, Error> {
+        // orders created before versioning have no version field
+        let version_filter = if read_version == 0 {
+            doc! {"$in": [0, null]}
+        } else {
+            doc! {"$eq": read_version}
+        };
+        let filter = doc! {
+            "_id": order._id,
+            "client_id": order.client_id,
+            "version": version_filter,
+        };
+        let result = self.collection.replace_one(filter, order, None).await?;
+        Ok(result.matched_count == 1)
+    }
+}
+
+// order_filter is the filter of the orders of a client matching a query
+fn order_filter(client_id: ObjectId, query: &OrderQuery) -> Result<Document, Error> {
+    let mut filter = doc! {"client_id": client_id};
+    if let Some(status) = query.status {
+        filter.insert("status", bson::to_bson(&status)?);
+    }
+    if let Some(product_id) = query.product_id {
+        filter.insert("lines.product_id", product_id);
+    }
+    Ok(filter)
+}
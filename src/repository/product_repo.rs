@@ -1,9 +1,115 @@
-use bson::{doc, oid::ObjectId};
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, Bson, Document, Regex};
 use futures::stream::TryStreamExt;
-use mongodb::results::{DeleteResult, UpdateResult};
-use mongodb::{error::Error, results::InsertOneResult, Collection};
+use mongodb::{error::Error, options::IndexOptions, Collection, IndexModel};
+use serde::Deserialize;
 
-use crate::model::product::Product;
+use crate::{
+    model::{
+        product::{
+            search_words, Product, ProductListQuery, ProductSearchHit, ProductSort,
+            ProductSupplier, SortOrder, StockLevel, DESCRIPTION_SEARCH_WEIGHT, NAME_SEARCH_WEIGHT,
+            PREFIX_MATCH_SCORE, SKU_SEARCH_WEIGHT,
+        },
+        STOCK_COLLECTION,
+    },
+    repository::mongo,
+};
+
+// ProductStore is the storage of the products of every client
+#[async_trait]
+pub trait ProductStore: Send + Sync {
+    // insert stores a new product. Returns false if the client already has a product with the
+    // same sku or one of its barcodes
+    async fn insert(&self, product: &Product) -> Result<bool, Error>;
+
+    // get_by_id retrieves a product of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Option<Product>, Error>;
+
+    // get_by_sku retrieves a product of a client by sku
+    async fn get_by_sku(&self, client_id: ObjectId, sku: &str) -> Result<Option<Product>, Error>;
+
+    // get_by_barcode retrieves a product of a client by the gtin of one of its barcodes
+    async fn get_by_barcode(
+        &self,
+        client_id: ObjectId,
+        gtin: &str,
+    ) -> Result<Option<Product>, Error>;
+
+    // get_variants retrieves the variants of a parent product of a client
+    async fn get_variants(
+        &self,
+        client_id: ObjectId,
+        parent_id: ObjectId,
+    ) -> Result<Vec<Product>, Error>;
+
+    // get_bundles retrieves the bundles of a client that a product is a component of
+    async fn get_bundles(
+        &self,
+        client_id: ObjectId,
+        component_id: ObjectId,
+    ) -> Result<Vec<Product>, Error>;
+
+    // list retrieves a page of the products of a client with their total quantity across
+    // locations, and the number of products matching the filters of the query on every page.
+    // Variants are not listed, the quantity of a parent product is the total of its variants
+    async fn list(
+        &self,
+        client_id: ObjectId,
+        query: &ProductListQuery,
+    ) -> Result<(Vec<(Product, i32)>, u64), Error>;
+
+    // search retrieves the products of a client matching a text, most relevant first. Products
+    // match if one of the words of the text is in their name, description or sku, or if their
+    // name or sku starts with the text. The quantity of a parent product is the total of its
+    // variants
+    async fn search(
+        &self,
+        client_id: ObjectId,
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<ProductSearchHit>, Error>;
+
+    // list_low_stock retrieves the products of a client whose quantity across locations is at or
+    // below their reorder point, with that quantity
+    async fn list_low_stock(&self, client_id: ObjectId) -> Result<Vec<(Product, i32)>, Error>;
+
+    // update updates the name, description, barcodes, attributes, reorder settings and stock
+    // level of a product. Returns false if nothing changed
+    async fn update(&self, client_id: ObjectId, update: &Product) -> Result<bool, Error>;
+
+    // set_suppliers replaces the supplier links of a product. Returns false if it does not exist
+    async fn set_suppliers(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        suppliers: &[ProductSupplier],
+    ) -> Result<bool, Error>;
+
+    // replace_stock_level atomically sets the stock level of a product, only if it is still the
+    // given current level. Returns false if the product does not exist or its level has changed
+    async fn replace_stock_level(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        current: StockLevel,
+        level: StockLevel,
+    ) -> Result<bool, Error>;
+
+    // remove_supplier unlinks every product of a client from a supplier
+    async fn remove_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<(), Error>;
+
+    // delete_by_id deletes a product of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, product_id: ObjectId) -> Result<(), Error>;
+}
 
 #[derive(Clone)]
 pub struct ProductRepo {
@@ -16,13 +122,100 @@ impl ProductRepo {
         Self { collection }
     }
 
-    // insert inserts a product in the database
-    pub async fn insert(&self, product: &Product) -> Result<InsertOneResult, Error> {
-        self.collection.insert_one(product, None).await
+    // ensure_indexes creates the unique indexes that keep the skus and barcodes of a client
+    // unique, the indexes the variants of a product, the bundles a product is a component of and
+    // the products linked to a supplier are found with, the index the products of a client are
+    // listed by name with, and the text index the products of a client are searched with
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let sku_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "sku": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        // products without barcodes have no barcodes field, and are left out of the index
+        let barcode_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "barcodes.gtin": 1})
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .partial_filter_expression(doc! {"barcodes": {"$exists": true}})
+                    .build(),
+            )
+            .build();
+        let variant_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "parent_id": 1})
+            .build();
+        let component_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "components.product_id": 1})
+            .build();
+        let supplier_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "suppliers.supplier_id": 1})
+            .build();
+        let name_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "name": 1, "_id": 1})
+            .build();
+        let text_index = IndexModel::builder()
+            .keys(doc! {"created_by": 1, "name": "text", "sku": "text", "description": "text"})
+            .options(
+                IndexOptions::builder()
+                    .name("product_text".to_string())
+                    .weights(doc! {
+                        "name": NAME_SEARCH_WEIGHT,
+                        "sku": SKU_SEARCH_WEIGHT,
+                        "description": DESCRIPTION_SEARCH_WEIGHT,
+                    })
+                    .build(),
+            )
+            .build();
+        self.collection
+            .create_indexes(
+                [
+                    sku_index,
+                    barcode_index,
+                    variant_index,
+                    component_index,
+                    supplier_index,
+                    name_index,
+                    text_index,
+                ],
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+// ProductPage is the result of the listing pipeline
+#[derive(Deserialize)]
+struct ProductPage {
+    total: Vec<ProductCount>,
+    products: Vec<ListedProduct>,
+}
+
+#[derive(Deserialize)]
+struct ProductCount {
+    count: i64,
+}
+
+#[derive(Deserialize)]
+struct ListedProduct {
+    product: Product,
+    quantity: i32,
+}
+
+#[async_trait]
+impl ProductStore for ProductRepo {
+    // insert inserts a product in the database. The unique sku and barcode indexes reject a
+    // product whose sku or barcodes the client already uses
+    async fn insert(&self, product: &Product) -> Result<bool, Error> {
+        match self.collection.insert_one(product, None).await {
+            Ok(_) => Ok(true),
+            Err(err) if mongo::is_duplicate_key_error(&err) => Ok(false),
+            Err(err) => Err(err),
+        }
     }
 
     // get_by_id retrieves a product from the database by id
-    pub async fn get_by_id(
+    async fn get_by_id(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
@@ -37,33 +230,699 @@ impl ProductRepo {
         Ok(product)
     }
 
-    // get_by_client_id retrieves all products created by the client
-    pub async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Product>, Error> {
-        let filter = doc! {"created_by": client_id};
+    // get_by_sku retrieves a product from the database by sku
+    async fn get_by_sku(&self, client_id: ObjectId, sku: &str) -> Result<Option<Product>, Error> {
+        self.collection
+            .find_one(doc! {"sku": sku, "created_by": client_id}, None)
+            .await
+    }
+
+    // get_by_barcode retrieves a product from the database by the gtin of one of its barcodes
+    async fn get_by_barcode(
+        &self,
+        client_id: ObjectId,
+        gtin: &str,
+    ) -> Result<Option<Product>, Error> {
+        self.collection
+            .find_one(doc! {"barcodes.gtin": gtin, "created_by": client_id}, None)
+            .await
+    }
+
+    // get_variants retrieves the variants of a parent product from the database
+    async fn get_variants(
+        &self,
+        client_id: ObjectId,
+        parent_id: ObjectId,
+    ) -> Result<Vec<Product>, Error> {
+        let filter = doc! {"created_by": client_id, "parent_id": parent_id};
         let cursor = self.collection.find(filter, None).await?;
-        let products: Vec<Product> = cursor.try_collect().await?;
-        Ok(products)
+        cursor.try_collect().await
     }
 
-    // update updates a product in the database
-    pub async fn update(
+    // get_bundles retrieves the bundles a product is a component of from the database
+    async fn get_bundles(
         &self,
         client_id: ObjectId,
-        update: &Product,
-    ) -> mongodb::error::Result<UpdateResult> {
+        component_id: ObjectId,
+    ) -> Result<Vec<Product>, Error> {
+        let filter = doc! {"created_by": client_id, "components.product_id": component_id};
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    // list retrieves a page of the products of a client. The total quantity of every product is
+    // looked up in the stocks, so that the filters and the sort can use it
+    async fn list(
+        &self,
+        client_id: ObjectId,
+        query: &ProductListQuery,
+    ) -> Result<(Vec<(Product, i32)>, u64), Error> {
+        // variants are counted in the quantity of their parent rather than listed
+        let mut filter = doc! {"created_by": client_id, "parent_id": null};
+        if let Some(name_prefix) = &query.name_prefix {
+            filter.insert(
+                "name",
+                Regex {
+                    pattern: format!("^{}", escape_regex(name_prefix)),
+                    options: String::new(),
+                },
+            );
+        }
+
+        for attribute in &query.attributes {
+            let values: Vec<Bson> = attribute.values.iter().map(Bson::from).collect();
+            filter.insert(
+                format!("attributes.{}", attribute.name),
+                doc! {"$in": values},
+            );
+        }
+
+        let mut pipeline = vec![doc! {"$match": filter}];
+        pipeline.extend(quantity_stages(self.collection.name()));
+        pipeline.extend([
+            doc! {"$match": quantity_filter(query)},
+            // count every matching product, and take the page after the cursor
+            doc! {"$facet": {
+                "total": [{"$count": "count"}],
+                "products": [
+                    {"$match": after_filter(query)},
+                    {"$sort": sort_doc(query)},
+                    {"$limit": query.limit},
+                    {"$project": {"_id": 0, "product": "$$ROOT", "quantity": 1}},
+                ],
+            }},
+        ]);
+
+        let mut cursor = self.collection.aggregate(pipeline, None).await?;
+        let page = match cursor.try_next().await? {
+            Some(page) => bson::from_document::<ProductPage>(page)?,
+            None => return Ok((vec![], 0)),
+        };
+        let total = page.total.first().map_or(0, |total| total.count as u64);
+        let products = page
+            .products
+            .into_iter()
+            .map(|listed| (listed.product, listed.quantity))
+            .collect();
+        Ok((products, total))
+    }
+
+    // search retrieves the products of a client matching a text. The words of the text are
+    // looked up in the text index, and the products whose name or sku starts with the text are
+    // added with a lower relevance than any word match. $unionWith needs MongoDB 4.4 or later
+    async fn search(
+        &self,
+        client_id: ObjectId,
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<ProductSearchHit>, Error> {
+        let prefix = Regex {
+            pattern: format!("^{}", escape_regex(text)),
+            options: "i".to_string(),
+        };
+
+        // the words too short to be matched are left out of the text search, a text without
+        // other words finds the products by prefix only
+        let searched = search_words(text).join(" ");
+
+        let mut pipeline = vec![
+            doc! {"$match": {"created_by": client_id, "$text": {"$search": searched}}},
+            doc! {"$project": {"score": {"$meta": "textScore"}}},
+            doc! {"$sort": {"score": -1}},
+            doc! {"$limit": limit},
+            doc! {"$unionWith": {
+                "coll": self.collection.name(),
+                "pipeline": [
+                    {"$match": {
+                        "created_by": client_id,
+                        "$or": [{"name": prefix.clone()}, {"sku": prefix}],
+                    }},
+                    {"$limit": limit},
+                    {"$project": {"score": {"$literal": PREFIX_MATCH_SCORE}}},
+                ],
+            }},
+            // a product found both ways keeps its best relevance
+            doc! {"$group": {"_id": "$_id", "score": {"$max": "$score"}}},
+            doc! {"$sort": {"score": -1, "_id": 1}},
+            doc! {"$limit": limit},
+            doc! {"$lookup": {
+                "from": self.collection.name(),
+                "localField": "_id",
+                "foreignField": "_id",
+                "as": "product",
+            }},
+            doc! {"$unwind": "$product"},
+        ];
+        pipeline.extend(quantity_stages(self.collection.name()));
+        pipeline.push(doc! {"$project": {"_id": 0, "product": 1, "score": 1, "quantity": 1}});
+
+        let cursor = self.collection.aggregate(pipeline, None).await?;
+        let hits: Vec<Document> = cursor.try_collect().await?;
+        hits.into_iter()
+            .map(|hit| bson::from_document(hit).map_err(Error::from))
+            .collect()
+    }
+
+    // list_low_stock retrieves the products of a client at or below their reorder point. The
+    // total quantity of every product with a reorder point is looked up in the stocks
+    async fn list_low_stock(&self, client_id: ObjectId) -> Result<Vec<(Product, i32)>, Error> {
+        let mut pipeline = vec![doc! {"$match": {
+            "created_by": client_id,
+            "reorder_point": {"$ne": null},
+        }}];
+        pipeline.extend(quantity_stages(self.collection.name()));
+        pipeline.extend([
+            doc! {"$match": {"$expr": {"$lte": ["$quantity", "$reorder_point"]}}},
+            doc! {"$sort": {"name": 1, "_id": 1}},
+            doc! {"$project": {"_id": 0, "product": "$$ROOT", "quantity": 1}},
+        ]);
+
+        let cursor = self.collection.aggregate(pipeline, None).await?;
+        let listed: Vec<Document> = cursor.try_collect().await?;
+        listed
+            .into_iter()
+            .map(|listed| {
+                let listed = bson::from_document::<ListedProduct>(listed)?;
+                Ok((listed.product, listed.quantity))
+            })
+            .collect()
+    }
+
+    // update updates a product in the database
+    async fn update(&self, client_id: ObjectId, update: &Product) -> Result<bool, Error> {
         let filter = doc! {"_id": update._id, "created_by": client_id};
-        let update_doc =
-            doc! {"$set": {"name": update.name.clone(), "description": update.description.clone()}};
-        self.collection.update_one(filter, update_doc, None).await
+        let mut set = doc! {
+            "name": update.name.clone(),
+            "description": update.description.clone(),
+            "attributes": bson::to_bson(&update.attributes)?,
+            "reorder_point": update.reorder_point,
+            "reorder_quantity": update.reorder_quantity,
+            "stock_level": bson::to_bson(&update.stock_level)?,
+        };
+        // the barcodes field is removed rather than emptied, as products without barcodes are
+        // left out of the unique barcode index
+        let update_doc = match update.barcodes.is_empty() {
+            true => doc! {"$set": set, "$unset": {"barcodes": ""}},
+            false => {
+                set.insert("barcodes", bson::to_bson(&update.barcodes)?);
+                doc! {"$set": set}
+            }
+        };
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.modified_count > 0)
     }
 
-    // delete_by_id deletes a product by id
-    pub async fn delete_by_id(
+    // set_suppliers replaces the supplier links of a product in the database. The field is
+    // removed rather than emptied, like it is left out of the documents of new products
+    async fn set_suppliers(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        suppliers: &[ProductSupplier],
+    ) -> Result<bool, Error> {
+        let filter = doc! {"_id": product_id, "created_by": client_id};
+        let update_doc = match suppliers.is_empty() {
+            true => doc! {"$unset": {"suppliers": ""}},
+            false => doc! {"$set": {"suppliers": bson::to_bson(suppliers)?}},
+        };
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // replace_stock_level sets the stock level of a product in the database if it is still the
+    // given current level. Products created before stock levels have none, and are stocked
+    async fn replace_stock_level(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
-    ) -> mongodb::error::Result<DeleteResult> {
+        current: StockLevel,
+        level: StockLevel,
+    ) -> Result<bool, Error> {
+        let mut current_levels = vec![bson::to_bson(&current)?];
+        if current == StockLevel::Stocked {
+            current_levels.push(Bson::Null);
+        }
+        let filter = doc! {
+            "_id": product_id,
+            "created_by": client_id,
+            "stock_level": {"$in": current_levels},
+        };
+        let update_doc = doc! {"$set": {"stock_level": bson::to_bson(&level)?}};
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // remove_supplier pulls the links to a supplier from the products of a client in the
+    // database
+    async fn remove_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<(), Error> {
+        let filter = doc! {"created_by": client_id, "suppliers.supplier_id": supplier_id};
+        let update_doc = doc! {"$pull": {"suppliers": {"supplier_id": supplier_id}}};
+        self.collection
+            .update_many(filter, update_doc, None)
+            .await?;
+        Ok(())
+    }
+
+    // delete_by_id deletes a product by id
+    async fn delete_by_id(&self, client_id: ObjectId, product_id: ObjectId) -> Result<(), Error> {
         let filter = doc! {"_id": product_id, "created_by": client_id};
-        self.collection.delete_one(filter, None).await
+        self.collection.delete_one(filter, None).await?;
+        Ok(())
+    }
+}
+
+// quantity_stages builds the stages that set the quantity of the products of a pipeline to their
+// total quantity across locations. The quantity of a parent product is the total of its variants,
+// which are looked up in the products collection
+fn quantity_stages(products_collection: &str) -> Vec<Document> {
+    vec![
+        doc! {"$lookup": {
+            "from": products_collection,
+            "localField": "_id",
+            "foreignField": "parent_id",
+            "as": "variants",
+        }},
+        doc! {"$set": {"stock_product_ids": {"$concatArrays": [["$_id"], "$variants._id"]}}},
+        doc! {"$lookup": {
+            "from": STOCK_COLLECTION,
+            "localField": "stock_product_ids",
+            "foreignField": "product_id",
+            "as": "stocks",
+        }},
+        doc! {"$set": {"quantity": {"$sum": "$stocks.quantity"}}},
+        doc! {"$unset": ["variants", "stock_product_ids", "stocks"]},
+    ]
+}
+
+// quantity_filter builds the filter on the total quantity of the products of a listing
+fn quantity_filter(query: &ProductListQuery) -> Document {
+    let mut bounds = Document::new();
+    if let Some(min_quantity) = query.min_quantity {
+        bounds.insert("$gte", min_quantity);
+    }
+    if let Some(max_quantity) = query.max_quantity {
+        bounds.insert("$lte", max_quantity);
+    }
+
+    let mut filters = vec![];
+    if !bounds.is_empty() {
+        filters.push(doc! {"quantity": bounds});
+    }
+    match query.out_of_stock {
+        Some(true) => filters.push(doc! {"quantity": {"$lte": 0}}),
+        Some(false) => filters.push(doc! {"quantity": {"$gt": 0}}),
+        None => {}
+    }
+
+    match filters.is_empty() {
+        true => Document::new(),
+        false => doc! {"$and": filters},
+    }
+}
+
+// after_filter builds the filter that keeps the products after the cursor of a listing
+fn after_filter(query: &ProductListQuery) -> Document {
+    let cursor = match &query.after {
+        Some(cursor) => cursor,
+        None => return Document::new(),
+    };
+    let op = match query.order {
+        SortOrder::Asc => "$gt",
+        SortOrder::Desc => "$lt",
+    };
+
+    // ties on the sort field are broken by the id
+    let (field, value): (&str, Bson) = match query.sort {
+        ProductSort::Created => return doc! {"_id": {op: cursor.id}},
+        ProductSort::Name => ("name", cursor.name.clone().into()),
+        ProductSort::Quantity => ("quantity", cursor.quantity.into()),
+    };
+    doc! {"$or": [
+        {field: {op: value.clone()}},
+        {field: value, "_id": {op: cursor.id}},
+    ]}
+}
+
+// sort_doc builds the sort of a listing, ties on the sort field are broken by the id
+fn sort_doc(query: &ProductListQuery) -> Document {
+    let direction = match query.order {
+        SortOrder::Asc => 1,
+        SortOrder::Desc => -1,
+    };
+    match query.sort {
+        ProductSort::Created => doc! {"_id": direction},
+        ProductSort::Name => doc! {"name": direction, "_id": direction},
+        ProductSort::Quantity => doc! {"quantity": direction, "_id": direction},
+    }
+}
+
+// escape_regex escapes the characters of a string that have a meaning in a regular expression
+fn escape_regex(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for c in str.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::{doc, oid::ObjectId};
+    use mongodb::Database;
+
+    use super::{ProductRepo, ProductStore};
+    use crate::{
+        model::{
+            product::{
+                Product, ProductCursor, ProductListQuery, ProductSort, SortOrder, StockLevel,
+                PREFIX_MATCH_SCORE,
+            },
+            stock::Stock,
+            STOCK_COLLECTION,
+        },
+        repository::{
+            memory::{
+                product_store::MemoryProductStore, stock_store::MemoryStockStore, MemoryCollection,
+            },
+            mongo,
+            stock_repo::{StockRepo, StockStore},
+        },
+    };
+
+    // new_product makes a product of a client with a name, a sku and a description
+    fn new_product(client_id: ObjectId, name: &str, sku: &str, description: &str) -> Product {
+        let mut product = Product::new(name.to_string(), description.to_string(), None, client_id);
+        product.set_sku(sku.to_string());
+        product
+    }
+
+    // add_product stores a product with a stock of the given quantity
+    async fn add_product(
+        product_store: &dyn ProductStore,
+        stock_store: &dyn StockStore,
+        product: Product,
+        quantity: i32,
+    ) -> Product {
+        assert!(product_store.insert(&product).await.unwrap());
+        stock_store
+            .insert(&Stock::new(
+                product.get_created_by(),
+                product._id,
+                None,
+                quantity,
+            ))
+            .await
+            .unwrap();
+        product
+    }
+
+    // names returns the names of listed products with their quantities
+    fn names(products: &[(Product, i32)]) -> Vec<(&str, i32)> {
+        products
+            .iter()
+            .map(|(product, quantity)| (product.name.as_str(), *quantity))
+            .collect()
+    }
+
+    // mongo_stores creates a product repository on a collection of its own, and a stock repository
+    // on the stocks collection its pipelines look the quantities up in
+    async fn mongo_stores(database: &Database) -> (ProductRepo, StockRepo) {
+        let product_repo =
+            ProductRepo::new(database.collection(&format!("products_{}", ObjectId::new())));
+        product_repo.ensure_indexes().await.unwrap();
+        (
+            product_repo,
+            StockRepo::new(database.collection(STOCK_COLLECTION)),
+        )
+    }
+
+    // drop_products drops the collection of a product repository and the stocks of its products
+    async fn drop_products(database: &Database, product_repo: &ProductRepo) {
+        let product_ids = product_repo
+            .collection
+            .distinct("_id", None, None)
+            .await
+            .unwrap();
+        database
+            .collection::<Stock>(STOCK_COLLECTION)
+            .delete_many(doc! {"product_id": {"$in": product_ids}}, None)
+            .await
+            .unwrap();
+        product_repo.collection.drop(None).await.unwrap();
+    }
+
+    // assert_list_pages checks that the products of a client are paged through with cursors, with
+    // the number of products matching the filters on every page
+    async fn assert_list_pages(product_store: &dyn ProductStore, stock_store: &dyn StockStore) {
+        let client_id = ObjectId::new();
+        for (name, quantity) in [("Apple", 3), ("Banana", 0), ("Cherry", 7)] {
+            let product = new_product(client_id, name, name, "A fruit");
+            add_product(product_store, stock_store, product, quantity).await;
+        }
+        let date = new_product(client_id, "Date", "DATE", "A fruit");
+        let date = add_product(product_store, stock_store, date, 2).await;
+        // a variant is counted in the quantity of its parent rather than listed
+        let mut variant = new_product(client_id, "Date box", "DATE-BOX", "A box of dates");
+        variant.parent_id = Some(date._id);
+        add_product(product_store, stock_store, variant, 4).await;
+        let other_product = new_product(ObjectId::new(), "Apricot", "APRICOT", "A fruit");
+        add_product(product_store, stock_store, other_product, 1).await;
+
+        // (query, pages of names and quantities, total)
+        let test_cases = vec![
+            (
+                ProductListQuery {
+                    limit: 2,
+                    ..Default::default()
+                },
+                vec![
+                    vec![("Apple", 3), ("Banana", 0)],
+                    vec![("Cherry", 7), ("Date", 6)],
+                    vec![],
+                ],
+                4,
+            ),
+            (
+                ProductListQuery {
+                    out_of_stock: Some(false),
+                    sort: ProductSort::Quantity,
+                    order: SortOrder::Desc,
+                    limit: 2,
+                    ..Default::default()
+                },
+                vec![vec![("Cherry", 7), ("Date", 6)], vec![("Apple", 3)]],
+                3,
+            ),
+            (
+                ProductListQuery {
+                    name_prefix: Some("B".to_string()),
+                    limit: 2,
+                    ..Default::default()
+                },
+                vec![vec![("Banana", 0)]],
+                1,
+            ),
+        ];
+        for (mut query, pages, total) in test_cases {
+            for page in pages {
+                let (products, listed) = product_store.list(client_id, &query).await.unwrap();
+                assert_eq!(names(&products), page, "{:?}", query);
+                assert_eq!(listed, total, "{:?}", query);
+                query.after = products
+                    .last()
+                    .map(|(product, quantity)| ProductCursor::new(product, *quantity));
+            }
+        }
+    }
+
+    // assert_search_ranks checks that the products matching a searched word are found the most
+    // relevant first, followed by the products whose name only starts with the text
+    async fn assert_search_ranks(product_store: &dyn ProductStore, stock_store: &dyn StockStore) {
+        let client_id = ObjectId::new();
+        let products = [
+            ("Coffee mug", "CM-1", "Holds coffee", 3),
+            ("Tea cup", "MUG-TC", "Holds tea", 2),
+            ("Mugwort tea", "MW-1", "Herbal", 0),
+            ("Teapot", "TP-1", "Holds tea", 1),
+        ];
+        for (name, sku, description, quantity) in products {
+            let product = new_product(client_id, name, sku, description);
+            add_product(product_store, stock_store, product, quantity).await;
+        }
+        let other_product = new_product(ObjectId::new(), "Mug", "MUG", "A mug");
+        add_product(product_store, stock_store, other_product, 1).await;
+
+        // (text, limit, names and quantities of the hits, the most relevant first)
+        let test_cases = vec![
+            (
+                "mug",
+                10,
+                vec![("Coffee mug", 3), ("Tea cup", 2), ("Mugwort tea", 0)],
+            ),
+            ("mug", 2, vec![("Coffee mug", 3), ("Tea cup", 2)]),
+            ("MUGW", 10, vec![("Mugwort tea", 0)]),
+            ("kettle", 10, vec![]),
+        ];
+        for (text, limit, expected) in test_cases {
+            let hits = product_store.search(client_id, text, limit).await.unwrap();
+            let found: Vec<(&str, i32)> = hits
+                .iter()
+                .map(|hit| (hit.product.name.as_str(), hit.quantity))
+                .collect();
+            assert_eq!(found, expected, "{}", text);
+            // a product found by prefix only ranks below every word match
+            for hit in &hits {
+                let prefix_only = hit.product.name == "Mugwort tea";
+                assert_eq!(hit.score == PREFIX_MATCH_SCORE, prefix_only, "{}", text);
+            }
+        }
+    }
+
+    // assert_low_stock checks that the products at or below their reorder point are listed by
+    // name with their quantity, the quantity of a parent product being the total of its variants
+    async fn assert_low_stock(product_store: &dyn ProductStore, stock_store: &dyn StockStore) {
+        let client_id = ObjectId::new();
+        // (name, reorder point, quantity)
+        let products = [
+            ("Bolt", Some(5), 5),
+            ("Nut", Some(5), 6),
+            ("Screw", None, 0),
+            ("Anchor", Some(10), 0),
+        ];
+        let mut anchor_id = None;
+        for (name, reorder_point, quantity) in products {
+            let mut product = new_product(client_id, name, name, "Hardware");
+            product.reorder_point = reorder_point;
+            let product = add_product(product_store, stock_store, product, quantity).await;
+            if name == "Anchor" {
+                anchor_id = Some(product._id);
+            }
+        }
+        let mut variant = new_product(client_id, "Anchor small", "ANCHOR-S", "Hardware");
+        variant.parent_id = anchor_id;
+        add_product(product_store, stock_store, variant, 4).await;
+        let mut other_product = new_product(ObjectId::new(), "Bolt", "BOLT", "Hardware");
+        other_product.reorder_point = Some(5);
+        add_product(product_store, stock_store, other_product, 0).await;
+
+        let low_stock = product_store.list_low_stock(client_id).await.unwrap();
+        assert_eq!(names(&low_stock), vec![("Anchor", 4), ("Bolt", 5)]);
+    }
+
+    // assert_stock_level_replaced checks that the stock level of a product is only replaced
+    // from the level it currently has
+    async fn assert_stock_level_replaced(
+        product_store: &dyn ProductStore,
+        stock_store: &dyn StockStore,
+    ) {
+        let client_id = ObjectId::new();
+        let product = new_product(client_id, "Bolt", "BOLT", "Hardware");
+        let product = add_product(product_store, stock_store, product, 1).await;
+
+        // (client, current level, new level, replaced)
+        let test_cases = [
+            (client_id, StockLevel::Stocked, StockLevel::Low, true),
+            (client_id, StockLevel::Stocked, StockLevel::Out, false),
+            (ObjectId::new(), StockLevel::Low, StockLevel::Out, false),
+            (client_id, StockLevel::Low, StockLevel::Out, true),
+        ];
+        for (client, current, level, replaced) in test_cases {
+            assert_eq!(
+                product_store
+                    .replace_stock_level(client, product._id, current, level)
+                    .await
+                    .unwrap(),
+                replaced,
+                "{:?} to {:?}",
+                current,
+                level
+            );
+        }
+        let product = product_store
+            .get_by_id(client_id, product._id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(product.stock_level, StockLevel::Out);
+    }
+
+    #[test]
+    // test_list_pages tests that the listing pipeline counts the products matching the filters
+    // and pages through them after the cursor
+    async fn test_list_pages() {
+        let database = match mongo::test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+
+        let (product_repo, stock_repo) = mongo_stores(&database).await;
+        assert_list_pages(&product_repo, &stock_repo).await;
+        drop_products(&database, &product_repo).await;
+    }
+
+    #[test]
+    // test_memory_list_pages runs the same check against the in-memory stores, so it runs
+    // without a database
+    async fn test_memory_list_pages() {
+        let stocks = MemoryCollection::default();
+        let product_store = MemoryProductStore::new(stocks.clone());
+        assert_list_pages(&product_store, &MemoryStockStore::new(stocks)).await;
+    }
+
+    #[test]
+    // test_search_ranks tests that the search pipeline ranks the products matching the searched
+    // words by relevance, and adds the products whose name starts with the text after them
+    async fn test_search_ranks() {
+        let database = match mongo::test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+
+        let (product_repo, stock_repo) = mongo_stores(&database).await;
+        assert_search_ranks(&product_repo, &stock_repo).await;
+        drop_products(&database, &product_repo).await;
+    }
+
+    #[test]
+    // test_memory_search_ranks runs the same check against the in-memory stores, so it runs
+    // without a database
+    async fn test_memory_search_ranks() {
+        let stocks = MemoryCollection::default();
+        let product_store = MemoryProductStore::new(stocks.clone());
+        assert_search_ranks(&product_store, &MemoryStockStore::new(stocks)).await;
+    }
+
+    #[test]
+    // test_low_stock tests that the low stock pipeline lists the products at or below their
+    // reorder point, and that stock levels are only replaced from their current level
+    async fn test_low_stock() {
+        let database = match mongo::test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+
+        let (product_repo, stock_repo) = mongo_stores(&database).await;
+        assert_low_stock(&product_repo, &stock_repo).await;
+        assert_stock_level_replaced(&product_repo, &stock_repo).await;
+        drop_products(&database, &product_repo).await;
+    }
+
+    #[test]
+    // test_memory_low_stock runs the same checks against the in-memory stores, so it runs
+    // without a database
+    async fn test_memory_low_stock() {
+        let stocks = MemoryCollection::default();
+        let product_store = MemoryProductStore::new(stocks.clone());
+        let stock_store = MemoryStockStore::new(stocks);
+        assert_low_stock(&product_store, &stock_store).await;
+        assert_stock_level_replaced(&product_store, &stock_store).await;
     }
 }
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::purchase_order::{PurchaseOrder, PurchaseOrderStatus};
+
+// PurchaseOrderStore is the storage of the purchase orders of every client
+#[async_trait]
+pub trait PurchaseOrderStore: Send + Sync {
+    // insert stores a new purchase order
+    async fn insert(&self, purchase_order: &PurchaseOrder) -> Result<(), Error>;
+
+    // get_by_id retrieves a purchase order of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+    ) -> Result<Option<PurchaseOrder>, Error>;
+
+    // get_by_client_id retrieves the purchase orders of a client, optionally only those in one
+    // status, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        status: Option<PurchaseOrderStatus>,
+    ) -> Result<Vec<PurchaseOrder>, Error>;
+
+    // replace_if_unchanged replaces a purchase order, but only if it is still at the version it
+    // was read at. Returns false if the purchase order was changed in the meantime
+    async fn replace_if_unchanged(
+        &self,
+        purchase_order: &PurchaseOrder,
+        read_version: i32,
+    ) -> Result<bool, Error>;
+}
+
+#[derive(Clone)]
+pub struct PurchaseOrderRepo {
+    collection: Collection<PurchaseOrder>,
+}
+
+impl PurchaseOrderRepo {
+    // new creates a purchase order repository instance
+    pub fn new(collection: Collection<PurchaseOrder>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the indexes the repository relies on. Purchase orders are listed
+    // per client, the most recent first
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let by_client = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "created_at": -1, "_id": -1})
+            .build();
+        self.collection.create_index(by_client, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PurchaseOrderStore for PurchaseOrderRepo {
+    // insert inserts a purchase order in the database
+    async fn insert(&self, purchase_order: &PurchaseOrder) -> Result<(), Error> {
+        self.collection.insert_one(purchase_order, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a purchase order of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+    ) -> Result<Option<PurchaseOrder>, Error> {
+        self.collection
+            .find_one(
+                Some(doc! {"_id": purchase_order_id, "client_id": client_id}),
+                None,
+            )
+            .await
+    }
+
+    // get_by_client_id retrieves the purchase orders of a client, optionally only those in one
+    // status, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        status: Option<PurchaseOrderStatus>,
+    ) -> Result<Vec<PurchaseOrder>, Error> {
+        let mut filter = doc! {"client_id": client_id};
+        if let Some(status) = status {
+            filter.insert("status", bson::to_bson(&status)?);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    // replace_if_unchanged replaces a purchase order in the database, but only if it is still at
+    // the version it was read at. Returns false if the purchase order was changed in the meantime
+    async fn replace_if_unchanged(
+        &self,
+        purchase_order: &PurchaseOrder,
+        read_version: i32,
+    ) -> Result<bool, Error> {
+        let filter = doc! {
+            "_id": purchase_order._id,
+            "client_id": purchase_order.client_id,
+            "version": read_version,
+        };
+        let result = self
+            .collection
+            .replace_one(filter, purchase_order, None)
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+}
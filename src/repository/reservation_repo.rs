@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::{error::Error, Collection};
+
+use crate::model::reservation::{Reservation, ReservationStatus};
+
+// ReservationStore is the storage of the reservations of every client
+#[async_trait]
+pub trait ReservationStore: Send + Sync {
+    // insert stores a new reservation
+    async fn insert(&self, reservation: &Reservation) -> Result<(), Error>;
+
+    // get_by_id retrieves a reservation of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error>;
+
+    // get_expired retrieves the active reservations of every client that are past their expiry
+    async fn get_expired(&self, now: DateTime) -> Result<Vec<Reservation>, Error>;
+
+    // transition atomically moves a reservation from one status to another. Only one caller
+    // can move a reservation out of a status. Returns the updated reservation, or None if the
+    // reservation does not exist or is not in the expected status
+    async fn transition(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        from: ReservationStatus,
+        to: ReservationStatus,
+    ) -> Result<Option<Reservation>, Error>;
+
+    // commit_if_unexpired atomically moves an active reservation that has not expired by the given
+    // time to committed. Returns the updated reservation, or None if the reservation does not
+    // exist, is not active or has expired
+    async fn commit_if_unexpired(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        now: DateTime,
+    ) -> Result<Option<Reservation>, Error>;
+
+    // set_order_id records the order created when a reservation was committed
+    async fn set_order_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error>;
+}
+
+#[derive(Clone)]
+pub struct ReservationRepo {
+    collection: Collection<Reservation>,
+}
+
+impl ReservationRepo {
+    // new creates a reservation repository instance
+    pub fn new(collection: Collection<Reservation>) -> Self {
+        Self { collection }
+    }
+}
+
+#[async_trait]
+impl ReservationStore for ReservationRepo {
+    // insert inserts a reservation in the database
+    async fn insert(&self, reservation: &Reservation) -> Result<(), Error> {
+        self.collection.insert_one(reservation, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a reservation of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error> {
+        let reservation = self
+            .collection
+            .find_one(
+                Some(doc! {"_id": reservation_id, "client_id": client_id}),
+                None,
+            )
+            .await?;
+        Ok(reservation)
+    }
+
+    // get_expired retrieves the active reservations of every client that are past their expiry
+    async fn get_expired(&self, now: DateTime) -> Result<Vec<Reservation>, Error> {
+        let filter = doc! {
+            "status": bson::to_bson(&ReservationStatus::Active)?,
+            "expires_at": {"$lte": now},
+        };
+        let cursor = self.collection.find(filter, None).await?;
+        let reservations: Vec<Reservation> = cursor.try_collect().await?;
+        Ok(reservations)
+    }
+
+    // transition atomically moves a reservation from one status to another. Only one caller
+    // can move a reservation out of a status. Returns the updated reservation, or None if the
+    // reservation does not exist or is not in the expected status
+    async fn transition(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        from: ReservationStatus,
+        to: ReservationStatus,
+    ) -> Result<Option<Reservation>, Error> {
+        let filter = doc! {
+            "_id": reservation_id,
+            "client_id": client_id,
+            "status": bson::to_bson(&from)?,
+        };
+        let update_doc = doc! {
+            "$set": {"status": bson::to_bson(&to)?, "updated_at": DateTime::now()},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+
+    // commit_if_unexpired atomically moves an active reservation that has not expired to
+    // committed, so a commit and the expiry sweep cannot both take a reservation out of active
+    async fn commit_if_unexpired(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        now: DateTime,
+    ) -> Result<Option<Reservation>, Error> {
+        let filter = doc! {
+            "_id": reservation_id,
+            "client_id": client_id,
+            "status": bson::to_bson(&ReservationStatus::Active)?,
+            "expires_at": {"$gt": now},
+        };
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&ReservationStatus::Committed)?,
+                "updated_at": DateTime::now(),
+            },
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+
+    // set_order_id records the order created when a reservation was committed
+    async fn set_order_id(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Option<Reservation>, Error> {
+        let filter = doc! {"_id": reservation_id, "client_id": client_id};
+        let update_doc = doc! {"$set": {"order_id": order_id, "updated_at": DateTime::now()}};
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+}
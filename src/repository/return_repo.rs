@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime, Document};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument};
+use mongodb::{error::Error, Collection};
+
+use crate::model::return_authorization::{ReturnAuthorization, ReturnLine, ReturnStatus};
+
+// ReturnStore is the storage of the return authorizations of every client
+#[async_trait]
+pub trait ReturnStore: Send + Sync {
+    // insert stores a new return authorization
+    async fn insert(&self, return_authorization: &ReturnAuthorization) -> Result<(), Error>;
+
+    // get_by_id retrieves a return authorization of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error>;
+
+    // get_by_client_id retrieves the return authorizations of a client, optionally only those
+    // of one order, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        order_id: Option<ObjectId>,
+    ) -> Result<Vec<ReturnAuthorization>, Error>;
+
+    // mark_received atomically moves an authorized return to received and records the condition
+    // of its lines. Returns None if the return does not exist or is not authorized
+    async fn mark_received(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        lines: &[ReturnLine],
+    ) -> Result<Option<ReturnAuthorization>, Error>;
+
+    // mark_closed atomically moves a received return to closed.
+    // Returns None if the return does not exist or has not been received
+    async fn mark_closed(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error>;
+}
+
+#[derive(Clone)]
+pub struct ReturnRepo {
+    collection: Collection<ReturnAuthorization>,
+}
+
+impl ReturnRepo {
+    // new creates a return authorization repository instance
+    pub fn new(collection: Collection<ReturnAuthorization>) -> Self {
+        Self { collection }
+    }
+
+    // transition applies an update to a return authorization only if it is in the given status
+    async fn transition(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        from: ReturnStatus,
+        update_doc: Document,
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        let filter = doc! {
+            "_id": return_id,
+            "client_id": client_id,
+            "status": bson::to_bson(&from)?,
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+}
+
+#[async_trait]
+impl ReturnStore for ReturnRepo {
+    // insert inserts a return authorization in the database
+    async fn insert(&self, return_authorization: &ReturnAuthorization) -> Result<(), Error> {
+        self.collection.insert_one(return_authorization, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a return authorization of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        self.collection
+            .find_one(Some(doc! {"_id": return_id, "client_id": client_id}), None)
+            .await
+    }
+
+    // get_by_client_id retrieves the return authorizations of a client, optionally only those
+    // of one order, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        order_id: Option<ObjectId>,
+    ) -> Result<Vec<ReturnAuthorization>, Error> {
+        let mut filter = doc! {"client_id": client_id};
+        if let Some(order_id) = order_id {
+            filter.insert("order_id", order_id);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    // mark_received atomically moves an authorized return to received and records the condition
+    // of its lines. Returns None if the return does not exist or is not authorized
+    async fn mark_received(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        lines: &[ReturnLine],
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        let now = DateTime::now();
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&ReturnStatus::Received)?,
+                "lines": bson::to_bson(lines)?,
+                "received_at": now,
+                "updated_at": now,
+            },
+        };
+        self.transition(client_id, return_id, ReturnStatus::Authorized, update_doc)
+            .await
+    }
+
+    // mark_closed atomically moves a received return to closed.
+    // Returns None if the return does not exist or has not been received
+    async fn mark_closed(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<Option<ReturnAuthorization>, Error> {
+        let now = DateTime::now();
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&ReturnStatus::Closed)?,
+                "closed_at": now,
+                "updated_at": now,
+            },
+        };
+        self.transition(client_id, return_id, ReturnStatus::Received, update_doc)
+            .await
+    }
+}
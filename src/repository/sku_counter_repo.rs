@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use mongodb::options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::sku::SkuCounter;
+
+// SkuCounterStore is the storage of the counters of the sequential skus of every client. There
+// is one counter per client and prefix
+#[async_trait]
+pub trait SkuCounterStore: Send + Sync {
+    // next atomically increments the counter of a prefix and returns its new value, starting
+    // from 1 for a prefix that was never used
+    async fn next(&self, client_id: ObjectId, prefix: &str) -> Result<i64, Error>;
+}
+
+#[derive(Clone)]
+pub struct SkuCounterRepo {
+    collection: Collection<SkuCounter>,
+}
+
+impl SkuCounterRepo {
+    // new creates a sku counter repository instance
+    pub fn new(collection: Collection<SkuCounter>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the unique index that keeps one counter per client and prefix
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let prefix_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "prefix": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.collection.create_index(prefix_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SkuCounterStore for SkuCounterRepo {
+    // next increments the counter of a prefix in the database, creating it if needed
+    async fn next(&self, client_id: ObjectId, prefix: &str) -> Result<i64, Error> {
+        let filter = doc! {"client_id": client_id, "prefix": prefix};
+        let update_doc = doc! {
+            "$inc": {"value": 1_i64},
+            "$setOnInsert": {"_id": ObjectId::new()},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let counter = self
+            .collection
+            .find_one_and_update(filter, update_doc, options)
+            .await?;
+        Ok(counter.map_or(1, |counter| counter.value))
+    }
+}
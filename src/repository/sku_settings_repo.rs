@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::sku::SkuSettings;
+
+// SkuSettingsStore is the storage of how the skus of every client are made. There are at most
+// one settings per client
+#[async_trait]
+pub trait SkuSettingsStore: Send + Sync {
+    // get_by_client_id gets the sku settings of a client
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Option<SkuSettings>, Error>;
+
+    // upsert replaces the sku settings of a client, creating them if the client has none
+    async fn upsert(&self, settings: &SkuSettings) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct SkuSettingsRepo {
+    collection: Collection<SkuSettings>,
+}
+
+impl SkuSettingsRepo {
+    // new creates a sku settings repository instance
+    pub fn new(collection: Collection<SkuSettings>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the unique index that keeps one settings per client
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let client_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.collection.create_index(client_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SkuSettingsStore for SkuSettingsRepo {
+    // get_by_client_id gets the sku settings of a client from the database
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Option<SkuSettings>, Error> {
+        self.collection
+            .find_one(doc! {"client_id": client_id}, None)
+            .await
+    }
+
+    // upsert sets the strategy of the sku settings of a client, the id of existing settings
+    // is kept
+    async fn upsert(&self, settings: &SkuSettings) -> Result<(), Error> {
+        let filter = doc! {"client_id": settings.client_id};
+        let update_doc = doc! {
+            "$set": {
+                "strategy": bson::to_bson(&settings.strategy)?,
+                "updated_at": settings.updated_at,
+            },
+            "$setOnInsert": {"_id": settings._id},
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.collection
+            .update_one(filter, update_doc, options)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::stock_alert::StockAlert;
+
+// StockAlertStore is the append-only storage of the low stock alerts raised for every client
+#[async_trait]
+pub trait StockAlertStore: Send + Sync {
+    // insert appends a stock alert
+    async fn insert(&self, alert: &StockAlert) -> Result<(), Error>;
+
+    // get_by_client_id retrieves the most recent alerts of a client, the most recent first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<StockAlert>, Error>;
+}
+
+#[derive(Clone)]
+pub struct StockAlertRepo {
+    collection: Collection<StockAlert>,
+}
+
+impl StockAlertRepo {
+    // new creates a stock alert repository instance
+    pub fn new(collection: Collection<StockAlert>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the index the alerts of a client are listed with, the most recent
+    // first
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let by_client = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "created_at": -1, "_id": -1})
+            .build();
+        self.collection.create_index(by_client, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StockAlertStore for StockAlertRepo {
+    // insert appends a stock alert in the database
+    async fn insert(&self, alert: &StockAlert) -> Result<(), Error> {
+        self.collection.insert_one(alert, None).await?;
+        Ok(())
+    }
+
+    // get_by_client_id retrieves the most recent alerts of a client from the database
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<StockAlert>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .limit(limit)
+            .build();
+        let cursor = self
+            .collection
+            .find(doc! {"client_id": client_id}, options)
+            .await?;
+        cursor.try_collect().await
+    }
+}
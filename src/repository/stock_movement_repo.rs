@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::stock_movement::{MovementSequence, StockMovement};
+
+// StockMovementStore is the append-only storage of the changes to the stock of every product
+#[async_trait]
+pub trait StockMovementStore: Send + Sync {
+    // insert appends a stock movement, giving it the next sequence number of its client
+    async fn insert(&self, movement: &mut StockMovement) -> Result<(), Error>;
+
+    // get_by_product_id retrieves a page of the movements of a product, the most recent first
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error>;
+
+    // count_by_product_id counts the movements of a product
+    async fn count_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<u64, Error>;
+
+    // last_sequence retrieves the sequence number of the last movement of a client, 0 if the
+    // client has none
+    async fn last_sequence(&self, client_id: ObjectId) -> Result<i64, Error>;
+
+    // get_after retrieves the movements of a client with a sequence number greater than the
+    // given one, in the order of their sequence numbers
+    async fn get_after(
+        &self,
+        client_id: ObjectId,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error>;
+}
+
+#[derive(Clone)]
+pub struct StockMovementRepo {
+    collection: Collection<StockMovement>,
+    sequences: Collection<MovementSequence>,
+}
+
+impl StockMovementRepo {
+    // new creates a stock movement repository instance
+    pub fn new(
+        collection: Collection<StockMovement>,
+        sequences: Collection<MovementSequence>,
+    ) -> Self {
+        Self {
+            collection,
+            sequences,
+        }
+    }
+
+    // ensure_indexes creates the indexes the repository relies on. Movements are
+    // paged through per product, the most recent first, and streamed per client in the order
+    // of their sequence numbers, which come from one counter per client
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let by_product = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "product_id": 1, "created_at": -1, "_id": -1})
+            .build();
+        self.collection.create_index(by_product, None).await?;
+        let by_client = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "sequence": 1})
+            .build();
+        self.collection.create_index(by_client, None).await?;
+        let sequence_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.sequences.create_index(sequence_index, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StockMovementStore for StockMovementRepo {
+    // insert appends a stock movement in the database. The sequence number is taken from the
+    // counter of the client just before, so a movement can be inserted shortly after one with
+    // a greater number
+    async fn insert(&self, movement: &mut StockMovement) -> Result<(), Error> {
+        let filter = doc! {"client_id": movement.client_id};
+        let update_doc = doc! {
+            "$inc": {"value": 1_i64},
+            "$setOnInsert": {"_id": ObjectId::new()},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let sequence = self
+            .sequences
+            .find_one_and_update(filter, update_doc, options)
+            .await?;
+        movement.sequence = sequence.map_or(1, |sequence| sequence.value);
+        self.collection.insert_one(&*movement, None).await?;
+        Ok(())
+    }
+
+    // get_by_product_id retrieves a page of the movements of a product, the most recent first
+    async fn get_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error> {
+        let filter = doc! {"client_id": client_id, "product_id": product_id};
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        let movements: Vec<StockMovement> = cursor.try_collect().await?;
+        Ok(movements)
+    }
+
+    // count_by_product_id counts the movements of a product
+    async fn count_by_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<u64, Error> {
+        let filter = doc! {"client_id": client_id, "product_id": product_id};
+        self.collection.count_documents(filter, None).await
+    }
+
+    // last_sequence retrieves the last sequence number given to a movement of a client from the
+    // database
+    async fn last_sequence(&self, client_id: ObjectId) -> Result<i64, Error> {
+        let sequence = self
+            .sequences
+            .find_one(doc! {"client_id": client_id}, None)
+            .await?;
+        Ok(sequence.map_or(0, |sequence| sequence.value))
+    }
+
+    // get_after retrieves the movements of a client after a sequence number, in the order of
+    // their sequence numbers
+    async fn get_after(
+        &self,
+        client_id: ObjectId,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<StockMovement>, Error> {
+        let filter = doc! {"client_id": client_id, "sequence": {"$gt": after}};
+        let options = FindOptions::builder()
+            .sort(doc! {"sequence": 1})
+            .limit(limit)
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        let movements: Vec<StockMovement> = cursor.try_collect().await?;
+        Ok(movements)
+    }
+}
@@ -1,11 +1,136 @@
-use bson::doc;
+use async_trait::async_trait;
 use bson::oid::ObjectId;
+use bson::{doc, Bson, Document};
 use futures::stream::TryStreamExt;
-use mongodb::results::{DeleteResult, UpdateResult};
-use mongodb::{error::Error, error::Result as MongoResult, results::InsertOneResult, Collection};
+use mongodb::options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument};
+use mongodb::IndexModel;
+use mongodb::{error::Error, error::Result as MongoResult, Collection};
 
 use crate::model::stock::Stock;
 
+// StockStore is the storage of the stock of every product. Methods that take a warehouse_id
+// address the stock of a product at that location. A warehouse_id of None addresses the stock
+// that is not assigned to a warehouse. There is at most one stock per product and location
+#[async_trait]
+pub trait StockStore: Send + Sync {
+    // insert stores a new stock
+    async fn insert(&self, stock: &Stock) -> Result<(), Error>;
+
+    // get_by_client_id_and_product_id gets the stocks of a product at every location
+    async fn get_by_client_id_and_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Vec<Stock>, Error>;
+
+    // get_by_warehouse_id gets the stocks of every product at a warehouse
+    async fn get_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Vec<Stock>, Error>;
+
+    // decrement_if_available atomically decrements the quantity of a stock by the given number,
+    // only if the stock holds at least that number outside of reservations. Returns the updated
+    // stock, or None if the stock does not exist or does not hold enough quantity
+    async fn decrement_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // reserve_if_available atomically holds the given number of a stock for a reservation, only
+    // if the stock holds at least that number outside of reservations. Returns the updated stock,
+    // or None if the stock does not exist or does not hold enough quantity
+    async fn reserve_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // release_reserved atomically gives back the given number of a stock held for a reservation.
+    // Returns the updated stock, or None if the stock does not exist or holds less than that number
+    async fn release_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // commit_reserved atomically takes the given number of a stock held for a reservation out of
+    // the stock. Returns the updated stock, or None if the stock does not exist or holds less
+    // than that number for reservations
+    async fn commit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // uncommit_reserved atomically undoes commit_reserved, giving the given number back to a
+    // stock and holding it for the reservation again. Returns the updated stock, or None if the
+    // stock does not exist
+    async fn uncommit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // increment_or_insert atomically increments the quantity of a stock by the given number,
+    // creating the stock at the location if the product is not kept there yet
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // bin_if_unbinned atomically counts the given number of a stock as put away in bins, only if
+    // at least that number of the stock is not in a bin yet. Returns the updated stock, or None if
+    // the stock does not exist or does not hold that number outside of bins
+    async fn bin_if_unbinned(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // unbin atomically counts the given number of a stock as no longer in bins, never taking the
+    // count below zero. Returns the updated stock, or None if the stock does not exist
+    async fn unbin(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>>;
+
+    // delete_by_client_id_and_product_id deletes the stocks of a product at every location
+    // and returns them
+    async fn delete_by_client_id_and_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> MongoResult<Vec<Stock>>;
+
+    // delete_empty_by_warehouse_id deletes the stocks at a warehouse that hold no quantity
+    async fn delete_empty_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> MongoResult<()>;
+}
+
 #[derive(Clone)]
 pub struct StockRepo {
     collection: Collection<Stock>,
@@ -16,53 +141,508 @@ impl StockRepo {
         Self { collection }
     }
 
+    // ensure_indexes creates the unique index that keeps one stock per product and location,
+    // and the index the stocks of a product are looked up by when products are listed
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let location_index = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "product_id": 1, "warehouse_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        let product_index = IndexModel::builder().keys(doc! {"product_id": 1}).build();
+        self.collection
+            .create_indexes([location_index, product_index], None)
+            .await?;
+        Ok(())
+    }
+
+    // find_one_and_update applies an update to the stock matching the filter and returns
+    // the stock as it is after the update
+    async fn find_one_and_update(
+        &self,
+        filter: Document,
+        update_doc: Document,
+    ) -> MongoResult<Option<Stock>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+}
+
+#[async_trait]
+impl StockStore for StockRepo {
     // insert inserts a stock document in the database
-    pub async fn insert(&self, stock: &Stock) -> Result<InsertOneResult, Error> {
-        self.collection.insert_one(stock, None).await
+    async fn insert(&self, stock: &Stock) -> Result<(), Error> {
+        self.collection.insert_one(stock, None).await?;
+        Ok(())
     }
 
-    // get_by_client_id_and_product_id gets a stock from the database by
-    // the client id and product id
-    pub async fn get_by_client_id_and_product_id(
+    // get_by_client_id_and_product_id gets the stocks of a product at every location
+    async fn get_by_client_id_and_product_id(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
-    ) -> Result<Option<Stock>, Error> {
-        let stock_result = self
-            .collection
-            .find_one(
-                Some(doc! {"client_id": client_id, "product_id": product_id}),
-                None,
-            )
-            .await?;
-        Ok(stock_result)
+    ) -> Result<Vec<Stock>, Error> {
+        let filter = doc! {"client_id": client_id, "product_id": product_id};
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
     }
 
-    // get_by_client_id_and_product_ids gets stocks from the database by
-    // the client id and a list of product ids
-    pub async fn get_by_client_id(
+    // get_by_warehouse_id gets the stocks of every product at a warehouse
+    async fn get_by_warehouse_id(
         &self,
         client_id: ObjectId,
+        warehouse_id: ObjectId,
     ) -> Result<Vec<Stock>, Error> {
-        let filter = doc! {"client_id": client_id};
+        let filter = doc! {"client_id": client_id, "warehouse_id": warehouse_id};
         let cursor = self.collection.find(filter, None).await?;
-        let stocks: Vec<Stock> = cursor.try_collect().await?;
-        Ok(stocks)
+        cursor.try_collect().await
+    }
+
+    // decrement_if_available atomically decrements the quantity of a stock by the given number.
+    // The decrement only happens if the stock holds at least that number outside of reservations,
+    // so concurrent callers can never take the quantity below what is reserved. Returns the
+    // updated stock, or None if the stock does not exist or does not hold enough quantity
+    async fn decrement_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let filter = available_filter(client_id, product_id, warehouse_id, number);
+        let update_doc = doc! {"$inc": {"quantity": -number}};
+        self.find_one_and_update(filter, update_doc).await
     }
 
-    // update updates the stock in the database
-    pub async fn update(&self, update: &Stock) -> MongoResult<UpdateResult> {
-        let filter = doc! {"_id": update._id, "client_id": update.client_id, "product_id": update.product_id};
-        let update_doc = doc! {"$set": {"quantity": update.get_quantity()}};
-        self.collection.update_one(filter, update_doc, None).await
+    // reserve_if_available atomically holds the given number of a stock for a reservation.
+    // The hold only happens if the stock holds at least that number outside of reservations.
+    // Returns the updated stock, or None if the stock does not exist or does not hold enough quantity
+    async fn reserve_if_available(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let filter = available_filter(client_id, product_id, warehouse_id, number);
+        let update_doc = doc! {"$inc": {"reserved": number}};
+        self.find_one_and_update(filter, update_doc).await
     }
 
-    pub async fn delete_by_client_id_and_product_id(
+    // release_reserved atomically gives back the given number of a stock held for a reservation.
+    // Returns the updated stock, or None if the stock does not exist or holds less than that number
+    async fn release_reserved(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
-    ) -> MongoResult<DeleteResult> {
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let mut filter = location_filter(client_id, product_id, warehouse_id);
+        filter.insert("reserved", doc! {"$gte": number});
+        let update_doc = doc! {"$inc": {"reserved": -number}};
+        self.find_one_and_update(filter, update_doc).await
+    }
+
+    // commit_reserved atomically takes the given number of a stock held for a reservation out of
+    // the stock. Returns the updated stock, or None if the stock does not exist or holds less
+    // than that number for reservations
+    async fn commit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let mut filter = location_filter(client_id, product_id, warehouse_id);
+        filter.insert("reserved", doc! {"$gte": number});
+        filter.insert("quantity", doc! {"$gte": number});
+        let update_doc = doc! {"$inc": {"quantity": -number, "reserved": -number}};
+        self.find_one_and_update(filter, update_doc).await
+    }
+
+    // uncommit_reserved atomically gives back the given number taken out of a stock by
+    // commit_reserved and holds it for the reservation again
+    async fn uncommit_reserved(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let filter = location_filter(client_id, product_id, warehouse_id);
+        let update_doc = doc! {"$inc": {"quantity": number, "reserved": number}};
+        self.find_one_and_update(filter, update_doc).await
+    }
+
+    // increment_or_insert atomically increments the quantity of a stock by the given number,
+    // creating the stock at the location if the product is not kept there yet
+    async fn increment_or_insert(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let filter = location_filter(client_id, product_id, warehouse_id);
+        let update_doc = doc! {
+            "$inc": {"quantity": number},
+            "$setOnInsert": {"reserved": 0, "binned": 0},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_doc, options)
+            .await
+    }
+
+    // bin_if_unbinned atomically counts the given number of a stock as put away in bins. The
+    // count only changes if at least that number of the stock is not in a bin yet, so concurrent
+    // put-aways can never bin more than the stock holds. Stocks created before bins have no
+    // binned field
+    async fn bin_if_unbinned(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let mut filter = location_filter(client_id, product_id, warehouse_id);
+        filter.insert(
+            "$expr",
+            doc! {
+                "$gte": [
+                    {"$subtract": ["$quantity", {"$ifNull": ["$binned", 0]}]},
+                    number,
+                ],
+            },
+        );
+        let update_doc = doc! {"$inc": {"binned": number}};
+        self.find_one_and_update(filter, update_doc).await
+    }
+
+    // unbin atomically counts the given number of a stock as no longer in bins, never taking the
+    // count below zero
+    async fn unbin(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        number: i32,
+    ) -> MongoResult<Option<Stock>> {
+        let filter = location_filter(client_id, product_id, warehouse_id);
+        let update_pipeline = vec![doc! {"$set": {"binned": {"$max": [
+            0,
+            {"$subtract": [{"$ifNull": ["$binned", 0]}, number]},
+        ]}}}];
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(filter, update_pipeline, options)
+            .await
+    }
+
+    // delete_by_client_id_and_product_id deletes the stocks of a product at every location
+    // and returns them
+    async fn delete_by_client_id_and_product_id(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> MongoResult<Vec<Stock>> {
         let filter = doc! {"client_id": client_id, "product_id": product_id};
-        self.collection.delete_one(filter, None).await
+        let mut deleted = vec![];
+        while let Some(stock) = self
+            .collection
+            .find_one_and_delete(filter.clone(), None)
+            .await?
+        {
+            deleted.push(stock);
+        }
+        Ok(deleted)
+    }
+
+    // delete_empty_by_warehouse_id deletes the stocks at a warehouse that hold no quantity
+    async fn delete_empty_by_warehouse_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> MongoResult<()> {
+        let filter = doc! {
+            "client_id": client_id,
+            "warehouse_id": warehouse_id,
+            "quantity": 0,
+            "reserved": {"$in": [0, Bson::Null]},
+        };
+        self.collection.delete_many(filter, None).await?;
+        Ok(())
+    }
+}
+
+// location_filter matches the stock of a product at one location. Stocks created before
+// warehouses have no warehouse_id field, which null also matches
+fn location_filter(
+    client_id: ObjectId,
+    product_id: ObjectId,
+    warehouse_id: Option<ObjectId>,
+) -> Document {
+    doc! {
+        "client_id": client_id,
+        "product_id": product_id,
+        "warehouse_id": warehouse_id,
+    }
+}
+
+// available_filter matches the stock of a product at one location that holds at least the
+// given number outside of reservations. Stocks created before reservations have no reserved field
+fn available_filter(
+    client_id: ObjectId,
+    product_id: ObjectId,
+    warehouse_id: Option<ObjectId>,
+    number: i32,
+) -> Document {
+    let mut filter = location_filter(client_id, product_id, warehouse_id);
+    filter.insert(
+        "$expr",
+        doc! {
+            "$gte": [
+                {"$subtract": ["$quantity", {"$ifNull": ["$reserved", 0]}]},
+                number,
+            ],
+        },
+    );
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+    use futures::{executor, future};
+    use std::{sync::Arc, thread};
+
+    use super::{StockRepo, StockStore};
+    use crate::{
+        model::stock::Stock,
+        repository::{
+            memory::{stock_store::MemoryStockStore, MemoryCollection},
+            mongo,
+        },
+    };
+
+    // DECREMENT_TEST_CASES are the (initial quantity, number of concurrent decrements, decrement
+    // size) the decrements are checked with
+    const DECREMENT_TEST_CASES: [(i32, i32, i32); 3] = [(50, 80, 1), (50, 40, 3), (10, 10, 10)];
+
+    // assert_decrements_applied checks that the decrements that succeeded are all reflected in
+    // the stock, that as many succeeded as the stock allowed and that it never went below zero
+    async fn assert_decrements_applied(
+        stock_repo: &dyn StockStore,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        test_case: (i32, i32, i32),
+        successes: i32,
+    ) {
+        let remaining = stock_repo
+            .get_by_client_id_and_product_id(client_id, product_id)
+            .await
+            .unwrap()[0]
+            .get_quantity();
+
+        assert!(remaining >= 0);
+        assert_eq!(successes, test_case.0 / test_case.2);
+        assert_eq!(remaining, test_case.0 - successes * test_case.2);
+    }
+
+    // assert_bins_applied checks that the put-aways that succeeded are all counted as binned, that
+    // as many succeeded as the stock allowed and that no more than the stock was binned
+    async fn assert_bins_applied(
+        stock_repo: &dyn StockStore,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        test_case: (i32, i32, i32),
+        successes: i32,
+    ) {
+        let stock = stock_repo
+            .get_by_client_id_and_product_id(client_id, product_id)
+            .await
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(stock.get_quantity(), test_case.0);
+        assert_eq!(successes, test_case.0 / test_case.2);
+        assert_eq!(stock.get_binned(), successes * test_case.2);
+        assert!(stock.get_unbinned() >= 0);
+    }
+
+    #[test]
+    // test_decrement_if_available_is_race_free tests that concurrent decrements never take the
+    // quantity below zero and that every successful decrement is reflected in the stock
+    async fn test_decrement_if_available_is_race_free() {
+        let database = match mongo::test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+
+        let collection = database.collection::<Stock>(&format!("stocks_{}", ObjectId::new()));
+        let stock_repo = StockRepo::new(collection.clone());
+
+        for test_case in DECREMENT_TEST_CASES {
+            let (client_id, product_id) = (ObjectId::new(), ObjectId::new());
+            stock_repo
+                .insert(&Stock::new(client_id, product_id, None, test_case.0))
+                .await
+                .unwrap();
+
+            let decrements = (0..test_case.1)
+                .map(|_| stock_repo.decrement_if_available(client_id, product_id, None, test_case.2));
+            let results = future::join_all(decrements).await;
+
+            let successes = results
+                .into_iter()
+                .filter(|result| result.as_ref().unwrap().is_some())
+                .count() as i32;
+            assert_decrements_applied(&stock_repo, client_id, product_id, test_case, successes)
+                .await;
+        }
+
+        collection.drop(None).await.unwrap();
+    }
+
+    #[test]
+    // test_memory_decrement_if_available_is_race_free runs the same check against the in-memory
+    // store, so it runs without a database. Every decrement runs on its own thread
+    async fn test_memory_decrement_if_available_is_race_free() {
+        let stock_repo = Arc::new(MemoryStockStore::new(MemoryCollection::default()));
+
+        for test_case in DECREMENT_TEST_CASES {
+            let (client_id, product_id) = (ObjectId::new(), ObjectId::new());
+            stock_repo
+                .insert(&Stock::new(client_id, product_id, None, test_case.0))
+                .await
+                .unwrap();
+
+            let decrements: Vec<_> = (0..test_case.1)
+                .map(|_| {
+                    let stock_repo = stock_repo.clone();
+                    thread::spawn(move || {
+                        executor::block_on(stock_repo.decrement_if_available(
+                            client_id,
+                            product_id,
+                            None,
+                            test_case.2,
+                        ))
+                    })
+                })
+                .collect();
+
+            let successes = decrements
+                .into_iter()
+                .map(|decrement| decrement.join().unwrap().unwrap())
+                .filter(Option::is_some)
+                .count() as i32;
+            assert_decrements_applied(
+                stock_repo.as_ref(),
+                client_id,
+                product_id,
+                test_case,
+                successes,
+            )
+            .await;
+        }
+    }
+
+    #[test]
+    // test_bin_if_unbinned_is_race_free tests that concurrent put-aways never bin more than the
+    // quantity of the stock and that every successful put-away is counted
+    async fn test_bin_if_unbinned_is_race_free() {
+        let database = match mongo::test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+
+        let collection = database.collection::<Stock>(&format!("stocks_{}", ObjectId::new()));
+        let stock_repo = StockRepo::new(collection.clone());
+
+        for test_case in DECREMENT_TEST_CASES {
+            let (client_id, product_id, warehouse_id) =
+                (ObjectId::new(), ObjectId::new(), Some(ObjectId::new()));
+            stock_repo
+                .insert(&Stock::new(
+                    client_id,
+                    product_id,
+                    warehouse_id,
+                    test_case.0,
+                ))
+                .await
+                .unwrap();
+
+            let put_aways = (0..test_case.1).map(|_| {
+                stock_repo.bin_if_unbinned(client_id, product_id, warehouse_id, test_case.2)
+            });
+            let results = future::join_all(put_aways).await;
+
+            let successes = results
+                .into_iter()
+                .filter(|result| result.as_ref().unwrap().is_some())
+                .count() as i32;
+            assert_bins_applied(&stock_repo, client_id, product_id, test_case, successes).await;
+        }
+
+        collection.drop(None).await.unwrap();
+    }
+
+    #[test]
+    // test_memory_bin_if_unbinned_is_race_free runs the same check against the in-memory store,
+    // so it runs without a database. Every put-away runs on its own thread
+    async fn test_memory_bin_if_unbinned_is_race_free() {
+        let stock_repo = Arc::new(MemoryStockStore::new(MemoryCollection::default()));
+
+        for test_case in DECREMENT_TEST_CASES {
+            let (client_id, product_id, warehouse_id) =
+                (ObjectId::new(), ObjectId::new(), Some(ObjectId::new()));
+            stock_repo
+                .insert(&Stock::new(
+                    client_id,
+                    product_id,
+                    warehouse_id,
+                    test_case.0,
+                ))
+                .await
+                .unwrap();
+
+            let put_aways: Vec<_> = (0..test_case.1)
+                .map(|_| {
+                    let stock_repo = stock_repo.clone();
+                    thread::spawn(move || {
+                        executor::block_on(stock_repo.bin_if_unbinned(
+                            client_id,
+                            product_id,
+                            warehouse_id,
+                            test_case.2,
+                        ))
+                    })
+                })
+                .collect();
+
+            let successes = put_aways
+                .into_iter()
+                .map(|put_away| put_away.join().unwrap().unwrap())
+                .filter(Option::is_some)
+                .count() as i32;
+            assert_bins_applied(
+                stock_repo.as_ref(),
+                client_id,
+                product_id,
+                test_case,
+                successes,
+            )
+            .await;
+        }
     }
 }
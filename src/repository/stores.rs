@@ -0,0 +1,173 @@
+use mongodb::Database;
+use std::sync::Arc;
+
+use crate::{
+    model::{
+        attribute::AttributeDefinition,
+        bin::{Bin, BinStock},
+        idempotency_key::IdempotencyKey,
+        order::Order,
+        product::Product,
+        purchase_order::PurchaseOrder,
+        reservation::Reservation,
+        return_authorization::ReturnAuthorization,
+        sku::{SkuCounter, SkuSettings},
+        stock::Stock,
+        stock_alert::StockAlert,
+        stock_movement::{MovementSequence, StockMovement},
+        supplier::Supplier,
+        warehouse::Warehouse,
+        webhook::{Webhook, WebhookDelivery},
+        ATTRIBUTE_DEFINITION_COLLECTION, BIN_COLLECTION, BIN_STOCK_COLLECTION,
+        IDEMPOTENCY_KEY_COLLECTION, ORDER_COLLECTION, PRODUCT_COLLECTION,
+        PURCHASE_ORDER_COLLECTION, RESERVATION_COLLECTION, RETURN_AUTHORIZATION_COLLECTION,
+        SKU_COUNTER_COLLECTION, SKU_SETTINGS_COLLECTION, STOCK_ALERT_COLLECTION, STOCK_COLLECTION,
+        STOCK_MOVEMENT_COLLECTION, STOCK_MOVEMENT_SEQUENCE_COLLECTION, SUPPLIER_COLLECTION,
+        WAREHOUSE_COLLECTION, WEBHOOK_COLLECTION, WEBHOOK_DELIVERY_COLLECTION,
+    },
+    repository::{
+        attribute_definition_repo::{AttributeDefinitionRepo, AttributeDefinitionStore},
+        bin_repo::{BinRepo, BinStore},
+        bin_stock_repo::{BinStockRepo, BinStockStore},
+        idempotency_repo::{IdempotencyRepo, IdempotencyStore},
+        memory::{
+            attribute_definition_store::MemoryAttributeDefinitionStore,
+            bin_stock_store::MemoryBinStockStore, bin_store::MemoryBinStore,
+            idempotency_store::MemoryIdempotencyStore, order_store::MemoryOrderStore,
+            product_store::MemoryProductStore, purchase_order_store::MemoryPurchaseOrderStore,
+            reservation_store::MemoryReservationStore, return_store::MemoryReturnStore,
+            sku_counter_store::MemorySkuCounterStore, sku_settings_store::MemorySkuSettingsStore,
+            stock_alert_store::MemoryStockAlertStore,
+            stock_movement_store::MemoryStockMovementStore, stock_store::MemoryStockStore,
+            supplier_store::MemorySupplierStore, warehouse_store::MemoryWarehouseStore,
+            webhook_delivery_store::MemoryWebhookDeliveryStore, webhook_store::MemoryWebhookStore,
+            MemoryCollection,
+        },
+        order_repo::{OrderRepo, OrderStore},
+        product_repo::{ProductRepo, ProductStore},
+        purchase_order_repo::{PurchaseOrderRepo, PurchaseOrderStore},
+        reservation_repo::{ReservationRepo, ReservationStore},
+        return_repo::{ReturnRepo, ReturnStore},
+        sku_counter_repo::{SkuCounterRepo, SkuCounterStore},
+        sku_settings_repo::{SkuSettingsRepo, SkuSettingsStore},
+        stock_alert_repo::{StockAlertRepo, StockAlertStore},
+        stock_movement_repo::{StockMovementRepo, StockMovementStore},
+        stock_repo::{StockRepo, StockStore},
+        supplier_repo::{SupplierRepo, SupplierStore},
+        warehouse_repo::{WarehouseRepo, WarehouseStore},
+        webhook_delivery_repo::{WebhookDeliveryRepo, WebhookDeliveryStore},
+        webhook_repo::{WebhookRepo, WebhookStore},
+    },
+};
+
+// Stores holds the storage of every kind of document the services use. Cloning it shares the
+// same storage, so the services of every server worker see the same documents
+#[derive(Clone)]
+pub struct Stores {
+    pub product_store: Arc<dyn ProductStore>,
+    pub stock_store: Arc<dyn StockStore>,
+    pub stock_movement_store: Arc<dyn StockMovementStore>,
+    pub order_store: Arc<dyn OrderStore>,
+    pub reservation_store: Arc<dyn ReservationStore>,
+    pub return_store: Arc<dyn ReturnStore>,
+    pub warehouse_store: Arc<dyn WarehouseStore>,
+    pub bin_store: Arc<dyn BinStore>,
+    pub bin_stock_store: Arc<dyn BinStockStore>,
+    pub idempotency_store: Arc<dyn IdempotencyStore>,
+    pub sku_settings_store: Arc<dyn SkuSettingsStore>,
+    pub sku_counter_store: Arc<dyn SkuCounterStore>,
+    pub attribute_definition_store: Arc<dyn AttributeDefinitionStore>,
+    pub stock_alert_store: Arc<dyn StockAlertStore>,
+    pub webhook_store: Arc<dyn WebhookStore>,
+    pub webhook_delivery_store: Arc<dyn WebhookDeliveryStore>,
+    pub purchase_order_store: Arc<dyn PurchaseOrderStore>,
+    pub supplier_store: Arc<dyn SupplierStore>,
+}
+
+impl Stores {
+    // mongo creates the stores backed by the collections of a MongoDB database
+    pub fn mongo(database: &Database) -> Self {
+        Self {
+            product_store: Arc::new(ProductRepo::new(
+                database.collection::<Product>(PRODUCT_COLLECTION),
+            )),
+            stock_store: Arc::new(StockRepo::new(
+                database.collection::<Stock>(STOCK_COLLECTION),
+            )),
+            stock_movement_store: Arc::new(StockMovementRepo::new(
+                database.collection::<StockMovement>(STOCK_MOVEMENT_COLLECTION),
+                database.collection::<MovementSequence>(STOCK_MOVEMENT_SEQUENCE_COLLECTION),
+            )),
+            order_store: Arc::new(OrderRepo::new(
+                database.collection::<Order>(ORDER_COLLECTION),
+            )),
+            reservation_store: Arc::new(ReservationRepo::new(
+                database.collection::<Reservation>(RESERVATION_COLLECTION),
+            )),
+            return_store: Arc::new(ReturnRepo::new(
+                database.collection::<ReturnAuthorization>(RETURN_AUTHORIZATION_COLLECTION),
+            )),
+            warehouse_store: Arc::new(WarehouseRepo::new(
+                database.collection::<Warehouse>(WAREHOUSE_COLLECTION),
+            )),
+            bin_store: Arc::new(BinRepo::new(database.collection::<Bin>(BIN_COLLECTION))),
+            bin_stock_store: Arc::new(BinStockRepo::new(
+                database.collection::<BinStock>(BIN_STOCK_COLLECTION),
+            )),
+            idempotency_store: Arc::new(IdempotencyRepo::new(
+                database.collection::<IdempotencyKey>(IDEMPOTENCY_KEY_COLLECTION),
+            )),
+            sku_settings_store: Arc::new(SkuSettingsRepo::new(
+                database.collection::<SkuSettings>(SKU_SETTINGS_COLLECTION),
+            )),
+            sku_counter_store: Arc::new(SkuCounterRepo::new(
+                database.collection::<SkuCounter>(SKU_COUNTER_COLLECTION),
+            )),
+            attribute_definition_store: Arc::new(AttributeDefinitionRepo::new(
+                database.collection::<AttributeDefinition>(ATTRIBUTE_DEFINITION_COLLECTION),
+            )),
+            stock_alert_store: Arc::new(StockAlertRepo::new(
+                database.collection::<StockAlert>(STOCK_ALERT_COLLECTION),
+            )),
+            webhook_store: Arc::new(WebhookRepo::new(
+                database.collection::<Webhook>(WEBHOOK_COLLECTION),
+            )),
+            webhook_delivery_store: Arc::new(WebhookDeliveryRepo::new(
+                database.collection::<WebhookDelivery>(WEBHOOK_DELIVERY_COLLECTION),
+            )),
+            purchase_order_store: Arc::new(PurchaseOrderRepo::new(
+                database.collection::<PurchaseOrder>(PURCHASE_ORDER_COLLECTION),
+            )),
+            supplier_store: Arc::new(SupplierRepo::new(
+                database.collection::<Supplier>(SUPPLIER_COLLECTION),
+            )),
+        }
+    }
+
+    // memory creates empty stores that keep the documents in memory, for running the service
+    // without a database. Nothing is kept when the process stops
+    pub fn memory() -> Self {
+        // products are listed with the quantities of their stocks
+        let stocks = MemoryCollection::default();
+        Self {
+            product_store: Arc::new(MemoryProductStore::new(stocks.clone())),
+            stock_store: Arc::new(MemoryStockStore::new(stocks)),
+            stock_movement_store: Arc::new(MemoryStockMovementStore::default()),
+            order_store: Arc::new(MemoryOrderStore::default()),
+            reservation_store: Arc::new(MemoryReservationStore::default()),
+            return_store: Arc::new(MemoryReturnStore::default()),
+            warehouse_store: Arc::new(MemoryWarehouseStore::default()),
+            bin_store: Arc::new(MemoryBinStore::default()),
+            bin_stock_store: Arc::new(MemoryBinStockStore::default()),
+            idempotency_store: Arc::new(MemoryIdempotencyStore::default()),
+            sku_settings_store: Arc::new(MemorySkuSettingsStore::default()),
+            sku_counter_store: Arc::new(MemorySkuCounterStore::default()),
+            attribute_definition_store: Arc::new(MemoryAttributeDefinitionStore::default()),
+            stock_alert_store: Arc::new(MemoryStockAlertStore::default()),
+            webhook_store: Arc::new(MemoryWebhookStore::default()),
+            webhook_delivery_store: Arc::new(MemoryWebhookDeliveryStore::default()),
+            purchase_order_store: Arc::new(MemoryPurchaseOrderStore::default()),
+            supplier_store: Arc::new(MemorySupplierStore::default()),
+        }
+    }
+}
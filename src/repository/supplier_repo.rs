@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection};
+
+use crate::model::supplier::Supplier;
+
+// SupplierStore is the storage of the suppliers of every client
+#[async_trait]
+pub trait SupplierStore: Send + Sync {
+    // insert stores a new supplier
+    async fn insert(&self, supplier: &Supplier) -> Result<(), Error>;
+
+    // get_by_id retrieves a supplier of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Option<Supplier>, Error>;
+
+    // get_by_client_id retrieves all suppliers of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Supplier>, Error>;
+
+    // update updates the name, contact, lead time and minimum order quantity of a supplier.
+    // Returns false if it does not exist
+    async fn update(&self, supplier: &Supplier) -> Result<bool, Error>;
+
+    // delete_by_id deletes a supplier of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, supplier_id: ObjectId) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct SupplierRepo {
+    collection: Collection<Supplier>,
+}
+
+impl SupplierRepo {
+    // new creates a supplier repository instance
+    pub fn new(collection: Collection<Supplier>) -> Self {
+        Self { collection }
+    }
+}
+
+#[async_trait]
+impl SupplierStore for SupplierRepo {
+    // insert inserts a supplier in the database
+    async fn insert(&self, supplier: &Supplier) -> Result<(), Error> {
+        self.collection.insert_one(supplier, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a supplier of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Option<Supplier>, Error> {
+        self.collection
+            .find_one(
+                Some(doc! {"_id": supplier_id, "client_id": client_id}),
+                None,
+            )
+            .await
+    }
+
+    // get_by_client_id retrieves all suppliers of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Supplier>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": 1, "_id": 1})
+            .build();
+        let cursor = self
+            .collection
+            .find(doc! {"client_id": client_id}, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    // update updates the name, contact, lead time and minimum order quantity of a supplier in
+    // the database
+    async fn update(&self, supplier: &Supplier) -> Result<bool, Error> {
+        let filter = doc! {"_id": supplier._id, "client_id": supplier.client_id};
+        let update_doc = doc! {
+            "$set": {
+                "name": supplier.name.clone(),
+                "contact": supplier.contact.clone(),
+                "lead_time_days": supplier.lead_time_days,
+                "minimum_order_quantity": supplier.minimum_order_quantity,
+                "updated_at": DateTime::now(),
+            },
+        };
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // delete_by_id deletes a supplier of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, supplier_id: ObjectId) -> Result<(), Error> {
+        self.collection
+            .delete_one(doc! {"_id": supplier_id, "client_id": client_id}, None)
+            .await?;
+        Ok(())
+    }
+}
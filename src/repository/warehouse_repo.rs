@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection};
+
+use crate::model::warehouse::Warehouse;
+
+// WarehouseStore is the storage of the warehouses of every client
+#[async_trait]
+pub trait WarehouseStore: Send + Sync {
+    // insert stores a new warehouse
+    async fn insert(&self, warehouse: &Warehouse) -> Result<(), Error>;
+
+    // get_by_id retrieves a warehouse of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Option<Warehouse>, Error>;
+
+    // get_by_client_id retrieves all warehouses of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Warehouse>, Error>;
+
+    // update updates the name and address of a warehouse. Returns false if it does not exist
+    async fn update(&self, warehouse: &Warehouse) -> Result<bool, Error>;
+
+    // delete_by_id deletes a warehouse of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, warehouse_id: ObjectId) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct WarehouseRepo {
+    collection: Collection<Warehouse>,
+}
+
+impl WarehouseRepo {
+    // new creates a warehouse repository instance
+    pub fn new(collection: Collection<Warehouse>) -> Self {
+        Self { collection }
+    }
+}
+
+#[async_trait]
+impl WarehouseStore for WarehouseRepo {
+    // insert inserts a warehouse in the database
+    async fn insert(&self, warehouse: &Warehouse) -> Result<(), Error> {
+        self.collection.insert_one(warehouse, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a warehouse of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Option<Warehouse>, Error> {
+        self.collection
+            .find_one(
+                Some(doc! {"_id": warehouse_id, "client_id": client_id}),
+                None,
+            )
+            .await
+    }
+
+    // get_by_client_id retrieves all warehouses of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Warehouse>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": 1, "_id": 1})
+            .build();
+        let cursor = self
+            .collection
+            .find(doc! {"client_id": client_id}, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    // update updates the name and address of a warehouse in the database
+    async fn update(&self, warehouse: &Warehouse) -> Result<bool, Error> {
+        let filter = doc! {"_id": warehouse._id, "client_id": warehouse.client_id};
+        let update_doc = doc! {
+            "$set": {
+                "name": warehouse.name.clone(),
+                "address": warehouse.address.clone(),
+                "updated_at": DateTime::now(),
+            },
+        };
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // delete_by_id deletes a warehouse of a client by id
+    async fn delete_by_id(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), Error> {
+        self.collection
+            .delete_one(doc! {"_id": warehouse_id, "client_id": client_id}, None)
+            .await?;
+        Ok(())
+    }
+}
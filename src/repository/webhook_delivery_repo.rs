@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::stream::TryStreamExt;
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument};
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::webhook::{DeliveryQuery, DeliveryStatus, WebhookDelivery};
+
+// WebhookDeliveryStore is the log of the deliveries of the events of every client to their
+// webhooks
+#[async_trait]
+pub trait WebhookDeliveryStore: Send + Sync {
+    // insert adds a delivery to the log
+    async fn insert(&self, delivery: &WebhookDelivery) -> Result<(), Error>;
+
+    // get_by_id retrieves a delivery of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        delivery_id: ObjectId,
+    ) -> Result<Option<WebhookDelivery>, Error>;
+
+    // get_by_client_id retrieves the deliveries of a client matching a query, the most recent
+    // first
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &DeliveryQuery,
+    ) -> Result<Vec<WebhookDelivery>, Error>;
+
+    // claim_due takes a pending delivery whose next attempt is due, and moves its next attempt
+    // to lease_until so that no other caller takes it while it is sent. Returns None if no
+    // delivery is due
+    async fn claim_due(
+        &self,
+        now: DateTime,
+        lease_until: DateTime,
+    ) -> Result<Option<WebhookDelivery>, Error>;
+
+    // update records the status, attempts and next attempt of a delivery
+    async fn update(&self, delivery: &WebhookDelivery) -> Result<(), Error>;
+}
+
+#[derive(Clone)]
+pub struct WebhookDeliveryRepo {
+    collection: Collection<WebhookDelivery>,
+}
+
+impl WebhookDeliveryRepo {
+    // new creates a webhook delivery repository instance
+    pub fn new(collection: Collection<WebhookDelivery>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the index the deliveries of a client are listed with, the most
+    // recent first, and the index the due deliveries are found with
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let by_client = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "created_at": -1, "_id": -1})
+            .build();
+        let due = IndexModel::builder()
+            .keys(doc! {"status": 1, "next_attempt_at": 1})
+            .build();
+        self.collection
+            .create_indexes([by_client, due], None)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveryStore for WebhookDeliveryRepo {
+    // insert adds a delivery to the log in the database
+    async fn insert(&self, delivery: &WebhookDelivery) -> Result<(), Error> {
+        self.collection.insert_one(delivery, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a delivery of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        delivery_id: ObjectId,
+    ) -> Result<Option<WebhookDelivery>, Error> {
+        self.collection
+            .find_one(doc! {"_id": delivery_id, "client_id": client_id}, None)
+            .await
+    }
+
+    // get_by_client_id retrieves the deliveries of a client matching a query from the database
+    async fn get_by_client_id(
+        &self,
+        client_id: ObjectId,
+        query: &DeliveryQuery,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        let mut filter = doc! {"client_id": client_id};
+        if let Some(webhook_id) = query.webhook_id {
+            filter.insert("webhook_id", webhook_id);
+        }
+        if let Some(status) = query.status {
+            filter.insert("status", bson::to_bson(&status)?);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": -1, "_id": -1})
+            .limit(query.limit)
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    // claim_due takes a due pending delivery from the database
+    async fn claim_due(
+        &self,
+        now: DateTime,
+        lease_until: DateTime,
+    ) -> Result<Option<WebhookDelivery>, Error> {
+        let filter = doc! {
+            "status": bson::to_bson(&DeliveryStatus::Pending)?,
+            "next_attempt_at": {"$lte": now},
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! {"next_attempt_at": 1})
+            .return_document(ReturnDocument::After)
+            .build();
+        self.collection
+            .find_one_and_update(
+                filter,
+                doc! {"$set": {"next_attempt_at": lease_until}},
+                options,
+            )
+            .await
+    }
+
+    // update records the status, attempts and next attempt of a delivery in the database
+    async fn update(&self, delivery: &WebhookDelivery) -> Result<(), Error> {
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&delivery.status)?,
+                "attempts": bson::to_bson(&delivery.attempts)?,
+                "next_attempt_at": delivery.next_attempt_at,
+            },
+        };
+        self.collection
+            .update_one(doc! {"_id": delivery._id}, update_doc, None)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::stream::TryStreamExt;
+use mongodb::options::FindOptions;
+use mongodb::{error::Error, Collection, IndexModel};
+
+use crate::model::webhook::{Webhook, WebhookEvent};
+
+// WebhookStore is the storage of the webhooks of every client
+#[async_trait]
+pub trait WebhookStore: Send + Sync {
+    // insert stores a new webhook
+    async fn insert(&self, webhook: &Webhook) -> Result<(), Error>;
+
+    // get_by_id retrieves a webhook of a client by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+    ) -> Result<Option<Webhook>, Error>;
+
+    // get_by_client_id retrieves all webhooks of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Webhook>, Error>;
+
+    // get_subscribed retrieves the active webhooks of a client subscribed to an event
+    async fn get_subscribed(
+        &self,
+        client_id: ObjectId,
+        event: WebhookEvent,
+    ) -> Result<Vec<Webhook>, Error>;
+
+    // update updates the url, events and active flag of a webhook. Returns false if it does not
+    // exist
+    async fn update(&self, webhook: &Webhook) -> Result<bool, Error>;
+
+    // delete_by_id deletes a webhook of a client by id. Returns false if it does not exist
+    async fn delete_by_id(&self, client_id: ObjectId, webhook_id: ObjectId) -> Result<bool, Error>;
+}
+
+#[derive(Clone)]
+pub struct WebhookRepo {
+    collection: Collection<Webhook>,
+}
+
+impl WebhookRepo {
+    // new creates a webhook repository instance
+    pub fn new(collection: Collection<Webhook>) -> Self {
+        Self { collection }
+    }
+
+    // ensure_indexes creates the index the webhooks subscribed to an event are found with
+    pub async fn ensure_indexes(&self) -> Result<(), Error> {
+        let by_event = IndexModel::builder()
+            .keys(doc! {"client_id": 1, "events": 1})
+            .build();
+        self.collection.create_index(by_event, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WebhookStore for WebhookRepo {
+    // insert inserts a webhook in the database
+    async fn insert(&self, webhook: &Webhook) -> Result<(), Error> {
+        self.collection.insert_one(webhook, None).await?;
+        Ok(())
+    }
+
+    // get_by_id retrieves a webhook of a client from the database by id
+    async fn get_by_id(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+    ) -> Result<Option<Webhook>, Error> {
+        self.collection
+            .find_one(doc! {"_id": webhook_id, "client_id": client_id}, None)
+            .await
+    }
+
+    // get_by_client_id retrieves all webhooks of a client, the oldest first
+    async fn get_by_client_id(&self, client_id: ObjectId) -> Result<Vec<Webhook>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"created_at": 1, "_id": 1})
+            .build();
+        let cursor = self
+            .collection
+            .find(doc! {"client_id": client_id}, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    // get_subscribed retrieves the active webhooks of a client subscribed to an event
+    async fn get_subscribed(
+        &self,
+        client_id: ObjectId,
+        event: WebhookEvent,
+    ) -> Result<Vec<Webhook>, Error> {
+        let filter = doc! {
+            "client_id": client_id,
+            "events": bson::to_bson(&event)?,
+            "active": true,
+        };
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    // update updates the url, events and active flag of a webhook in the database
+    async fn update(&self, webhook: &Webhook) -> Result<bool, Error> {
+        let filter = doc! {"_id": webhook._id, "client_id": webhook.client_id};
+        let update_doc = doc! {
+            "$set": {
+                "url": webhook.url.clone(),
+                "events": bson::to_bson(&webhook.events)?,
+                "active": webhook.active,
+                "updated_at": DateTime::now(),
+            },
+        };
+        let result = self.collection.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // delete_by_id deletes a webhook of a client by id
+    async fn delete_by_id(&self, client_id: ObjectId, webhook_id: ObjectId) -> Result<bool, Error> {
+        let result = self
+            .collection
+            .delete_one(doc! {"_id": webhook_id, "client_id": client_id}, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
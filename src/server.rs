@@ -1,17 +1,60 @@
 use actix_cors::Cors;
 use actix_web::{http, middleware, web, App, HttpServer};
-use mongodb::Database;
+use log::error;
+use std::time::Duration;
 
 use crate::{
     api,
-    model::{product::Product, stock::Stock, PRODUCT_COLLECTION, STOCK_COLLECTION},
-    repository::{product_repo::ProductRepo, stock_repo::StockRepo},
-    service::product_service::ProductService,
+    repository::stores::Stores,
+    service::{
+        alert_service::AlertService, attribute_service::AttributeService, bin_service::BinService,
+        idempotency_service::IdempotencyService, order_service::OrderService,
+        product_service::ProductService, purchase_order_service::PurchaseOrderService,
+        reservation_service::ReservationService, return_service::ReturnService,
+        sku_service::SkuService, stream_service::StreamService, supplier_service::SupplierService,
+        warehouse_service::WarehouseService, webhook_service::WebhookService,
+    },
+    utils::tools,
 };
 
+// DEFAULT_RESERVATION_TTL_SECONDS is how long a reservation holds stock unless
+// RESERVATION_TTL_SECONDS is set in env
+const DEFAULT_RESERVATION_TTL_SECONDS: i64 = 15 * 60;
+
+// DEFAULT_IDEMPOTENCY_KEY_TTL_SECONDS is how long idempotency keys are remembered unless
+// IDEMPOTENCY_KEY_TTL_SECONDS is set in env
+const DEFAULT_IDEMPOTENCY_KEY_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+// DEFAULT_IDEMPOTENCY_KEY_LEASE_SECONDS is how long a request holds the idempotency key it
+// claimed before a retry can take it over, unless IDEMPOTENCY_KEY_LEASE_SECONDS is set in env
+const DEFAULT_IDEMPOTENCY_KEY_LEASE_SECONDS: i64 = 60;
+
+// WEBHOOK_ALLOWED_HOSTS_ENV lists the hosts, separated by commas, that webhooks can point to
+// even if they resolve to a private address, such as local listeners in development
+const WEBHOOK_ALLOWED_HOSTS_ENV: &str = "WEBHOOK_ALLOWED_HOSTS";
+
+// RESERVATION_SWEEP_INTERVAL is how often expired reservations are released
+const RESERVATION_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+// WEBHOOK_RETRY_INTERVAL is how often the webhook deliveries due for a retry are sent again
+const WEBHOOK_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
 // ServiceManager is the struct for managing services
 pub struct ServiceManager {
     pub product_service: ProductService,
+    pub order_service: OrderService,
+    pub reservation_service: ReservationService,
+    pub return_service: ReturnService,
+    pub warehouse_service: WarehouseService,
+    pub bin_service: BinService,
+    pub idempotency_service: IdempotencyService,
+    pub sku_service: SkuService,
+    pub attribute_service: AttributeService,
+    pub alert_service: AlertService,
+    pub webhook_service: WebhookService,
+    pub stream_service: StreamService,
+    pub purchase_order_service: PurchaseOrderService,
+    pub supplier_service: SupplierService,
 }
 
 // AppState holds the state of the application
@@ -28,27 +71,170 @@ impl AppState {
 
 // implement service manager methods
 impl ServiceManager {
-    // start_services starts all the services and returns the manager for the services
-    pub fn new(database: &Database) -> Self {
+    // new starts all the services on the given stores and returns the manager for the services
+    pub fn new(stores: &Stores) -> Self {
+        Self::with_webhook_allowed_hosts(stores, tools::env_list(WEBHOOK_ALLOWED_HOSTS_ENV))
+    }
+
+    // with_webhook_allowed_hosts starts all the services on the given stores, letting the
+    // webhooks point to the given hosts even if they are private
+    pub fn with_webhook_allowed_hosts(stores: &Stores, webhook_allowed_hosts: Vec<String>) -> Self {
+        // create the injections for the warehouse service worker
+        let warehouse_service_worker = WarehouseService::new(
+            stores.warehouse_store.clone(),
+            stores.stock_store.clone(),
+            stores.bin_store.clone(),
+            stores.bin_stock_store.clone(),
+        );
+
+        // create the injections for the bin service worker
+        let bin_service_worker = BinService::new(
+            stores.bin_store.clone(),
+            stores.bin_stock_store.clone(),
+            stores.stock_store.clone(),
+            warehouse_service_worker.clone(),
+        );
+
+        // create the injections for the sku service worker
+        let sku_service_worker = SkuService::new(
+            stores.sku_settings_store.clone(),
+            stores.sku_counter_store.clone(),
+        );
+
+        // create the injections for the attribute service worker
+        let attribute_service_worker =
+            AttributeService::new(stores.attribute_definition_store.clone());
+
+        // create the injections for the webhook service worker
+        let webhook_service_worker = WebhookService::new(
+            stores.webhook_store.clone(),
+            stores.webhook_delivery_store.clone(),
+            webhook_allowed_hosts,
+        );
+
+        // create the injections for the alert service worker
+        let alert_service_worker = AlertService::new(
+            stores.product_store.clone(),
+            stores.stock_store.clone(),
+            stores.stock_alert_store.clone(),
+            webhook_service_worker.clone(),
+        );
+
         // create the injections for the product service worker
-        let product_collection = database.collection::<Product>(PRODUCT_COLLECTION);
-        let stock_collection = database.collection::<Stock>(STOCK_COLLECTION);
-        let product_repo_worker = ProductRepo::new(product_collection);
-        let stock_repo_worker = StockRepo::new(stock_collection);
-        let product_service_worker = ProductService::new(product_repo_worker, stock_repo_worker);
+        let product_service_worker = ProductService::new(
+            stores.product_store.clone(),
+            stores.stock_store.clone(),
+            stores.stock_movement_store.clone(),
+            warehouse_service_worker.clone(),
+            bin_service_worker.clone(),
+            sku_service_worker.clone(),
+            attribute_service_worker.clone(),
+            alert_service_worker.clone(),
+            webhook_service_worker.clone(),
+        );
+
+        // create the injections for the order service worker
+        let order_service_worker = OrderService::new(
+            stores.order_store.clone(),
+            product_service_worker.clone(),
+            webhook_service_worker.clone(),
+        );
+
+        // create the injections for the reservation service worker
+        let reservation_service_worker = ReservationService::new(
+            stores.reservation_store.clone(),
+            stores.stock_store.clone(),
+            product_service_worker.clone(),
+            order_service_worker.clone(),
+            tools::env_or_default("RESERVATION_TTL_SECONDS", DEFAULT_RESERVATION_TTL_SECONDS),
+        );
+
+        // create the injections for the return service worker
+        let return_service_worker = ReturnService::new(
+            stores.return_store.clone(),
+            product_service_worker.clone(),
+            order_service_worker.clone(),
+            warehouse_service_worker.clone(),
+        );
+
+        // create the injections for the purchase order service worker
+        let purchase_order_service_worker = PurchaseOrderService::new(
+            stores.purchase_order_store.clone(),
+            product_service_worker.clone(),
+            warehouse_service_worker.clone(),
+        );
+
+        // create the injections for the supplier service worker
+        let supplier_service_worker = SupplierService::new(
+            stores.supplier_store.clone(),
+            product_service_worker.clone(),
+        );
+
+        // create the injections for the idempotency service worker
+        let idempotency_service_worker = IdempotencyService::new(
+            stores.idempotency_store.clone(),
+            tools::env_or_default(
+                "IDEMPOTENCY_KEY_TTL_SECONDS",
+                DEFAULT_IDEMPOTENCY_KEY_TTL_SECONDS,
+            ),
+            tools::env_or_default(
+                "IDEMPOTENCY_KEY_LEASE_SECONDS",
+                DEFAULT_IDEMPOTENCY_KEY_LEASE_SECONDS,
+            ),
+        );
+
+        // create the injections for the stream service worker
+        let stream_service_worker = StreamService::new(stores.stock_movement_store.clone());
 
         // build and return the service manager
         ServiceManager {
             product_service: product_service_worker,
+            order_service: order_service_worker,
+            reservation_service: reservation_service_worker,
+            return_service: return_service_worker,
+            warehouse_service: warehouse_service_worker,
+            bin_service: bin_service_worker,
+            idempotency_service: idempotency_service_worker,
+            sku_service: sku_service_worker,
+            attribute_service: attribute_service_worker,
+            alert_service: alert_service_worker,
+            webhook_service: webhook_service_worker,
+            stream_service: stream_service_worker,
+            purchase_order_service: purchase_order_service_worker,
+            supplier_service: supplier_service_worker,
         }
     }
 }
 
 // start_server starts and launches the http server
-pub async fn start_server(database: Database) -> Result<(), std::io::Error> {
+pub async fn start_server(stores: Stores) -> Result<(), std::io::Error> {
+    // release the stock held by expired reservations in the background
+    let reservation_service = ServiceManager::new(&stores).reservation_service;
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(RESERVATION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = reservation_service.release_expired().await {
+                error!("Error releasing expired reservations: {}", err);
+            }
+        }
+    });
+
+    // send the webhook deliveries that failed again once their backoff is over
+    let webhook_service = ServiceManager::new(&stores).webhook_service;
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(WEBHOOK_RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = webhook_service.retry_due().await {
+                error!("Error retrying webhook deliveries: {}", err);
+            }
+        }
+    });
+
     HttpServer::new(move || {
         // get the handle for the service manager
-        let service_manager = ServiceManager::new(&database);
+        let service_manager = ServiceManager::new(&stores);
 
         // initialize cors for the resource gate keeping
         let _cors_middleware = Cors::default()
@@ -0,0 +1,214 @@
+use bson::oid::ObjectId;
+use log::{error, warn};
+use std::sync::Arc;
+
+use crate::{
+    dto::alert::alert_dto::LowStockProductResponse,
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        product::{Product, StockLevel},
+        stock::Stock,
+        stock_alert::StockAlert,
+        stock_movement::StockMovement,
+        webhook::WebhookEvent,
+    },
+    repository::{
+        product_repo::ProductStore, stock_alert_repo::StockAlertStore, stock_repo::StockStore,
+    },
+    service::webhook_service::WebhookService,
+};
+
+// STOCK_LEVEL_CHECKS is how many times a stock check reads the quantity of a product before it
+// leaves the level to the checks of the changes that keep moving it
+const STOCK_LEVEL_CHECKS: usize = 5;
+
+// AlertService watches the quantities of the products of a client against their reorder points
+// and tells the webhooks of the client when a product runs out
+#[derive(Clone)]
+pub struct AlertService {
+    product_repo: Arc<dyn ProductStore>,
+    stock_repo: Arc<dyn StockStore>,
+    stock_alert_repo: Arc<dyn StockAlertStore>,
+    webhook_service: WebhookService,
+}
+
+impl AlertService {
+    // new creates a new alert service instance
+    pub fn new(
+        product_repo: Arc<dyn ProductStore>,
+        stock_repo: Arc<dyn StockStore>,
+        stock_alert_repo: Arc<dyn StockAlertStore>,
+        webhook_service: WebhookService,
+    ) -> AlertService {
+        AlertService {
+            product_repo,
+            stock_repo,
+            stock_alert_repo,
+            webhook_service,
+        }
+    }
+
+    // check_stock looks at the quantity of a product across locations after a change to it, and
+    // replaces the stock level of the product with the level of that quantity. When the level
+    // becomes out of stock the out of stock event is published, and when it falls from stocked to
+    // low or out of stock an alert is recorded. The level is only replaced from the level the
+    // check read, so the checks of the locations of one order, or of concurrent orders, raise a
+    // single alert. The quantity is read again after every replacement, so a level worked out
+    // from a quantity another change has since moved on from does not stay. The change has
+    // already been applied, so a failure is logged rather than returned
+    pub async fn check_stock(&self, stock: &Stock, movement: &StockMovement) {
+        let mut product = match self.get_product(stock).await {
+            Some(product) => product,
+            None => return,
+        };
+
+        for _ in 0..STOCK_LEVEL_CHECKS {
+            let stocks = match self
+                .stock_repo
+                .get_by_client_id_and_product_id(stock.client_id, stock.product_id)
+                .await
+            {
+                Ok(stocks) => stocks,
+                Err(err) => {
+                    error!("Error fetching stocks for stock alert: {:?}", err);
+                    return;
+                }
+            };
+
+            let quantity: i32 = stocks.iter().map(Stock::get_quantity).sum();
+            let before = product.stock_level;
+            let level = StockLevel::of(&product, quantity);
+            if before == level {
+                return;
+            }
+            match self
+                .product_repo
+                .replace_stock_level(stock.client_id, stock.product_id, before, level)
+                .await
+            {
+                Ok(true) => {
+                    product.stock_level = level;
+                    self.level_changed(&product, before, quantity, movement)
+                        .await;
+                }
+                // another check replaced the level first, start over from the level it left
+                Ok(false) => {
+                    product = match self.get_product(stock).await {
+                        Some(product) => product,
+                        None => return,
+                    };
+                }
+                Err(err) => {
+                    error!("Error updating stock level for stock alert: {:?}", err);
+                    return;
+                }
+            }
+        }
+        warn!(
+            "Stock level of product {} kept changing, left as {:?}",
+            stock.product_id, product.stock_level
+        );
+    }
+
+    // get_product gets the product of a stock for a stock check, None if it is gone or
+    // cannot be fetched
+    async fn get_product(&self, stock: &Stock) -> Option<Product> {
+        match self
+            .product_repo
+            .get_by_id(stock.client_id, stock.product_id)
+            .await
+        {
+            Ok(product) => product,
+            Err(err) => {
+                error!("Error fetching product for stock alert: {:?}", err);
+                None
+            }
+        }
+    }
+
+    // level_changed publishes the out of stock event and records the alert of a change of the
+    // stock level of a product from the given level to its current one
+    async fn level_changed(
+        &self,
+        product: &Product,
+        before: StockLevel,
+        quantity: i32,
+        movement: &StockMovement,
+    ) {
+        let client_id = product.get_created_by();
+        let level = product.stock_level;
+        if level == StockLevel::Out {
+            self.webhook_service
+                .publish(
+                    client_id,
+                    WebhookEvent::OutOfStock,
+                    &LowStockProductResponse::new(product, quantity),
+                )
+                .await;
+        }
+
+        let reorder_point = match product.reorder_point {
+            Some(reorder_point) => reorder_point,
+            None => return,
+        };
+        if before != StockLevel::Stocked {
+            return;
+        }
+        let alert = StockAlert::new(
+            client_id,
+            product._id,
+            quantity,
+            reorder_point,
+            product.reorder_quantity,
+            movement.reason,
+            movement.reference.clone(),
+        );
+        warn!(
+            "Product {} is low in stock: {} left, reorder point {}",
+            product._id, quantity, reorder_point
+        );
+        if let Err(err) = self.stock_alert_repo.insert(&alert).await {
+            error!("Error recording stock alert: {:?}. Error: {:?}", alert, err);
+        }
+    }
+
+    // get_low_stock gets the products of a client at or below their reorder point, with their
+    // quantity across locations
+    pub async fn get_low_stock(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<(Product, i32)>, AppError> {
+        match self.product_repo.list_low_stock(client_id).await {
+            Ok(products) => Ok(products),
+            Err(err) => {
+                error!("Error fetching low stock products: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch low stock products",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_stock_alerts gets the most recent stock alerts of a client
+    pub async fn get_stock_alerts(
+        &self,
+        client_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<StockAlert>, AppError> {
+        match self
+            .stock_alert_repo
+            .get_by_client_id(client_id, limit)
+            .await
+        {
+            Ok(alerts) => Ok(alerts),
+            Err(err) => {
+                error!("Error fetching stock alerts: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch stock alerts",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use bson::oid::ObjectId;
+use log::error;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::attribute::{self, AttributeDefinition, AttributeFilter, AttributeType, AttributeValue},
+    repository::attribute_definition_repo::AttributeDefinitionStore,
+};
+
+#[derive(Clone)]
+pub struct AttributeService {
+    attribute_definition_repo: Arc<dyn AttributeDefinitionStore>,
+}
+
+impl AttributeService {
+    // new creates a new attribute service instance
+    pub fn new(attribute_definition_repo: Arc<dyn AttributeDefinitionStore>) -> AttributeService {
+        AttributeService {
+            attribute_definition_repo,
+        }
+    }
+
+    // get_definitions gets the attribute definitions of a client, by name
+    pub async fn get_definitions(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<AttributeDefinition>, AppError> {
+        match self
+            .attribute_definition_repo
+            .get_by_client_id(client_id)
+            .await
+        {
+            Ok(definitions) => Ok(definitions),
+            Err(err) => {
+                error!("Error fetching attribute definitions: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch attribute definitions",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // define sets the type the values of an attribute of the products of a client must have.
+    // The values products already have are left as they are
+    pub async fn define(
+        &self,
+        client_id: ObjectId,
+        name: &str,
+        attribute_type: AttributeType,
+    ) -> Result<AttributeDefinition, AppError> {
+        check_attribute_name(name)?;
+
+        let definition = AttributeDefinition::new(client_id, name.to_string(), attribute_type);
+        if let Err(err) = self.attribute_definition_repo.upsert(&definition).await {
+            error!("Error updating attribute definition: {:?}", err);
+            return Err(AppError::new(
+                "cannot update attribute definition",
+                ErrorKind::InternalServerError,
+            ));
+        }
+        Ok(definition)
+    }
+
+    // delete_definition deletes the definition of an attribute of a client, the attribute then
+    // takes values of any type
+    pub async fn delete_definition(&self, client_id: ObjectId, name: &str) -> Result<(), AppError> {
+        match self.attribute_definition_repo.delete(client_id, name).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::new(
+                "attribute definition not found",
+                ErrorKind::NotFound,
+            )),
+            Err(err) => {
+                error!("Error deleting attribute definition: {:?}", err);
+                Err(AppError::new(
+                    "cannot delete attribute definition",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // check_attributes checks the attributes of a product of a client against the definitions
+    // of the client, and returns them with their dates written the same way every time
+    pub async fn check_attributes(
+        &self,
+        client_id: ObjectId,
+        attributes: &BTreeMap<String, AttributeValue>,
+    ) -> Result<BTreeMap<String, AttributeValue>, AppError> {
+        if attributes.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let types = self.types(client_id).await?;
+
+        let mut checked = BTreeMap::new();
+        for (name, value) in attributes {
+            check_attribute_name(name)?;
+            let value = match types.get(name) {
+                Some(attribute_type) => match attribute_type.check(value) {
+                    Some(value) => value,
+                    None => {
+                        return Err(AppError::new(
+                            &format!("attribute {} must be a {}", name, type_name(attribute_type)),
+                            ErrorKind::FailedAction,
+                        ))
+                    }
+                },
+                None => value.clone(),
+            };
+            checked.insert(name.clone(), value);
+        }
+        Ok(checked)
+    }
+
+    // filters reads the attribute filters of a product listing of a client from pairs of names
+    // and values as they are written in a url. The value of a defined attribute is read as its
+    // type, that of an attribute without a definition as any type it can be read as
+    pub async fn filters(
+        &self,
+        client_id: ObjectId,
+        pairs: &[(String, String)],
+    ) -> Result<Vec<AttributeFilter>, AppError> {
+        if pairs.is_empty() {
+            return Ok(vec![]);
+        }
+        let types = self.types(client_id).await?;
+
+        let mut filters = vec![];
+        for (name, text) in pairs {
+            check_attribute_name(name)?;
+            let values = match types.get(name) {
+                Some(attribute_type) => attribute_type.parse(text).into_iter().collect(),
+                None => AttributeValue::candidates(text),
+            };
+            if values.is_empty() {
+                return Err(AppError::new(
+                    &format!("cannot filter attribute {} by {}", name, text),
+                    ErrorKind::FailedAction,
+                ));
+            }
+            filters.push(AttributeFilter {
+                name: name.clone(),
+                values,
+            });
+        }
+        Ok(filters)
+    }
+
+    // types gets the type of every defined attribute of a client, by name
+    async fn types(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<BTreeMap<String, AttributeType>, AppError> {
+        Ok(self
+            .get_definitions(client_id)
+            .await?
+            .into_iter()
+            .map(|definition| (definition.name, definition.attribute_type))
+            .collect())
+    }
+}
+
+// check_attribute_name checks that an attribute name can be used as a field name and in a url
+fn check_attribute_name(name: &str) -> Result<(), AppError> {
+    if !attribute::is_valid_attribute_name(name) {
+        return Err(AppError::new(
+            &format!(
+                "invalid attribute name {:?}, a name is 1 to {} letters, digits or underscores",
+                name,
+                attribute::MAX_ATTRIBUTE_NAME_LENGTH
+            ),
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+// type_name is how an attribute type is named in error messages
+fn type_name(attribute_type: &AttributeType) -> &'static str {
+    match attribute_type {
+        AttributeType::String => "string",
+        AttributeType::Number => "number",
+        AttributeType::Bool => "bool",
+        AttributeType::Date => "date written as YYYY-MM-DD",
+    }
+}
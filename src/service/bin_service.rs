@@ -0,0 +1,544 @@
+use bson::oid::ObjectId;
+use log::error;
+use std::cmp;
+use std::sync::Arc;
+
+use crate::{
+    dto::bin::bin_dto::{BinRequest, GetBinsRequest},
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        bin::{Bin, BinStock},
+        stock::Stock,
+    },
+    repository::{bin_repo::BinStore, bin_stock_repo::BinStockStore, stock_repo::StockStore},
+    service::warehouse_service::WarehouseService,
+};
+
+// BinService keeps track of where the stock of a warehouse is stored. The quantities in the bins
+// of a warehouse are part of the stock of the product there, the rest of it is not in a bin yet
+#[derive(Clone)]
+pub struct BinService {
+    bin_repo: Arc<dyn BinStore>,
+    bin_stock_repo: Arc<dyn BinStockStore>,
+    stock_repo: Arc<dyn StockStore>,
+    warehouse_service: WarehouseService,
+}
+
+impl BinService {
+    // new creates a new bin service instance
+    pub fn new(
+        bin_repo: Arc<dyn BinStore>,
+        bin_stock_repo: Arc<dyn BinStockStore>,
+        stock_repo: Arc<dyn StockStore>,
+        warehouse_service: WarehouseService,
+    ) -> BinService {
+        BinService {
+            bin_repo,
+            bin_stock_repo,
+            stock_repo,
+            warehouse_service,
+        }
+    }
+
+    // create adds a bin to a warehouse of a client
+    pub async fn create(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        request: BinRequest,
+    ) -> Result<Bin, AppError> {
+        self.warehouse_service
+            .get_warehouse(client_id, warehouse_id)
+            .await?;
+
+        let bin = Bin::new(
+            client_id,
+            warehouse_id,
+            request.zone,
+            request.aisle,
+            request.rack,
+            request.bin,
+        );
+        match self.bin_repo.insert(&bin).await {
+            Ok(true) => Ok(bin),
+            Ok(false) => Err(AppError::new(
+                &format!("warehouse already has bin {}", bin.code()),
+                ErrorKind::FailedAction,
+            )),
+            Err(err) => {
+                error!("Error inserting bin: {:?}", err);
+                Err(AppError::new(
+                    "cannot create bin",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_bin gets a bin of a client from the application storage
+    pub async fn get_bin(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<Bin, AppError> {
+        match self.bin_repo.get_by_id(client_id, bin_id).await {
+            Ok(Some(bin)) => Ok(bin),
+            Ok(None) => Err(AppError::new("bin not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a bin: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch bin",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_bin_contents gets a bin of a client and the products stored in it
+    pub async fn get_bin_contents(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+    ) -> Result<(Bin, Vec<BinStock>), AppError> {
+        let bin = self.get_bin(client_id, bin_id).await?;
+        let bin_stocks = self.get_bin_stocks(client_id, bin_id).await?;
+        Ok((bin, bin_stocks))
+    }
+
+    // get_bins_by_warehouse gets the bins of a warehouse, narrowed down to a zone, aisle or
+    // rack if requested
+    pub async fn get_bins_by_warehouse(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        request: GetBinsRequest,
+    ) -> Result<Vec<Bin>, AppError> {
+        self.warehouse_service
+            .get_warehouse(client_id, warehouse_id)
+            .await?;
+
+        match self
+            .bin_repo
+            .get_by_warehouse_id(
+                client_id,
+                warehouse_id,
+                request.zone,
+                request.aisle,
+                request.rack,
+            )
+            .await
+        {
+            Ok(bins) => Ok(bins),
+            Err(err) => {
+                error!("Error fetching bins of warehouse: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch bins",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_bins_by_product gets the bins a product is stored in and the quantity in each
+    pub async fn get_bins_by_product(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Vec<(Bin, BinStock)>, AppError> {
+        let bin_stocks = match self
+            .bin_stock_repo
+            .get_by_product_id(client_id, product_id, None)
+            .await
+        {
+            Ok(bin_stocks) => bin_stocks,
+            Err(err) => {
+                error!("Error fetching bin stocks of product: {:?}", err);
+                return Err(AppError::new(
+                    "cannot fetch bins",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+        let bin_stocks: Vec<BinStock> = bin_stocks
+            .into_iter()
+            .filter(|bin_stock| bin_stock.quantity > 0)
+            .collect();
+
+        let bin_ids: Vec<ObjectId> = bin_stocks
+            .iter()
+            .map(|bin_stock| bin_stock.bin_id)
+            .collect();
+        let mut bins = match self.bin_repo.get_by_ids(client_id, &bin_ids).await {
+            Ok(bins) => bins,
+            Err(err) => {
+                error!("Error fetching bins of product: {:?}", err);
+                return Err(AppError::new(
+                    "cannot fetch bins",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+        bins.sort_by_key(|bin| (bin.warehouse_id, bin.code()));
+
+        Ok(bins
+            .into_iter()
+            .filter_map(|bin| {
+                bin_stocks
+                    .iter()
+                    .find(|bin_stock| bin_stock.bin_id == bin._id)
+                    .cloned()
+                    .map(|bin_stock| (bin, bin_stock))
+            })
+            .collect())
+    }
+
+    // delete_bin deletes a bin that no longer holds any stock
+    pub async fn delete_bin(&self, client_id: ObjectId, bin_id: ObjectId) -> Result<(), AppError> {
+        self.get_bin(client_id, bin_id).await?;
+
+        // a bin holding stock cannot be deleted, pickers would lose track of the stock
+        if !self.get_bin_stocks(client_id, bin_id).await?.is_empty() {
+            return Err(AppError::new(
+                "bin still holds stock, move it first",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        if let Err(err) = self
+            .bin_stock_repo
+            .delete_by_bin_id(client_id, bin_id)
+            .await
+        {
+            error!(
+                "Error deleting stocks of bin: {:?}. Error: {:?}",
+                bin_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete bin",
+                ErrorKind::InternalServerError,
+            ));
+        }
+        if let Err(err) = self.bin_repo.delete_by_id(client_id, bin_id).await {
+            error!("Error deleting bin with id: {:?}. Error: {:?}", bin_id, err);
+            return Err(AppError::new(
+                "cannot delete bin",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // move_stock moves a quantity of a product between two bins of a warehouse. Without a bin
+    // to move from, the quantity is put away from the stock of the warehouse that is not in a bin.
+    // Without a bin to move to, the quantity is taken out of the bins and stays at the warehouse.
+    // Returns what the bins hold of the product after the move
+    pub async fn move_stock(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        from_bin_id: Option<ObjectId>,
+        to_bin_id: Option<ObjectId>,
+        quantity: i32,
+    ) -> Result<(Option<BinStock>, Option<BinStock>), AppError> {
+        let from_bin = match from_bin_id {
+            Some(bin_id) => Some(self.get_bin(client_id, bin_id).await?),
+            None => None,
+        };
+        let to_bin = match to_bin_id {
+            Some(bin_id) => Some(self.get_bin(client_id, bin_id).await?),
+            None => None,
+        };
+        let warehouse_id = match (&from_bin, &to_bin) {
+            (Some(from_bin), Some(to_bin)) if from_bin.warehouse_id != to_bin.warehouse_id => {
+                return Err(AppError::new(
+                    "bins must be in the same warehouse",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            (Some(bin), _) | (None, Some(bin)) => bin.warehouse_id,
+            (None, None) => {
+                return Err(AppError::new(
+                    "from_bin_id or to_bin_id must be set",
+                    ErrorKind::FailedAction,
+                ))
+            }
+        };
+
+        // only the stock of the warehouse that is not in a bin yet can be put away. It is counted
+        // as binned by the same update that checks it, so concurrent moves cannot put it away twice
+        if from_bin.is_none() {
+            self.bin_unbinned(client_id, product_id, warehouse_id, quantity)
+                .await?;
+        }
+
+        let from_stock = match &from_bin {
+            Some(bin) => match self
+                .bin_stock_repo
+                .decrement_if_enough(client_id, bin._id, product_id, quantity)
+                .await
+            {
+                Ok(Some(bin_stock)) => Some(bin_stock),
+                Ok(None) => {
+                    return Err(AppError::new(
+                        &format!(
+                            "bin {} does not hold {} of product_id: {}",
+                            bin.code(),
+                            quantity,
+                            product_id.to_hex()
+                        ),
+                        ErrorKind::FailedAction,
+                    ))
+                }
+                Err(err) => {
+                    error!(
+                        "Error taking stock out of bin: {:?}. Error: {:?}",
+                        bin._id, err
+                    );
+                    return Err(AppError::new(
+                        "cannot move stock",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        let to_stock = match &to_bin {
+            Some(bin) => match self
+                .bin_stock_repo
+                .increment_or_insert(client_id, warehouse_id, bin._id, product_id, quantity)
+                .await
+            {
+                Ok(bin_stock) => bin_stock,
+                Err(err) => {
+                    error!(
+                        "Error putting stock in bin: {:?}. Error: {:?}",
+                        bin._id, err
+                    );
+                    // give the quantity back to the bin it was taken from, or to the stock that
+                    // is not in a bin
+                    if from_bin.is_none() {
+                        self.unbin(client_id, product_id, warehouse_id, quantity)
+                            .await;
+                    }
+                    if let Some(from_bin) = &from_bin {
+                        if let Err(err) = self
+                            .bin_stock_repo
+                            .increment_or_insert(
+                                client_id,
+                                warehouse_id,
+                                from_bin._id,
+                                product_id,
+                                quantity,
+                            )
+                            .await
+                        {
+                            error!(
+                                "Error giving stock back to bin: {:?}. Error: {:?}",
+                                from_bin._id, err
+                            );
+                        }
+                    }
+                    return Err(AppError::new(
+                        "cannot move stock",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            },
+            // the quantity taken out of the bins stays at the warehouse, outside of bins
+            None => {
+                self.unbin(client_id, product_id, warehouse_id, quantity)
+                    .await;
+                None
+            }
+        };
+
+        if from_stock
+            .as_ref()
+            .is_some_and(|bin_stock| bin_stock.quantity == 0)
+        {
+            self.delete_empty_bin_stocks(client_id, product_id).await;
+        }
+
+        Ok((from_stock, to_stock))
+    }
+
+    // fit_to_stock takes quantity out of the bins of a warehouse when they hold more of a product
+    // than the stock at the warehouse, after stock left the warehouse without a bin being given.
+    // The bins holding the least are emptied first. Failures are logged, the stock has already changed
+    pub async fn fit_to_stock(&self, stock: &Stock) {
+        let warehouse_id = match stock.warehouse_id {
+            Some(warehouse_id) => warehouse_id,
+            None => return,
+        };
+
+        let mut bin_stocks = match self
+            .bin_stock_repo
+            .get_by_product_id(stock.client_id, stock.product_id, Some(warehouse_id))
+            .await
+        {
+            Ok(bin_stocks) => bin_stocks,
+            Err(err) => {
+                error!(
+                    "Error fetching bin stocks of product: {:?}. Error: {:?}",
+                    stock.product_id, err
+                );
+                return;
+            }
+        };
+
+        let binned: i32 = bin_stocks.iter().map(|bin_stock| bin_stock.quantity).sum();
+        let mut excess = binned - stock.get_quantity();
+        if excess <= 0 {
+            return;
+        }
+        let taken = excess;
+
+        bin_stocks.sort_by_key(|bin_stock| bin_stock.quantity);
+        for bin_stock in bin_stocks {
+            if excess <= 0 {
+                break;
+            }
+            let number = cmp::min(bin_stock.quantity, excess);
+            if number <= 0 {
+                continue;
+            }
+            match self
+                .bin_stock_repo
+                .decrement_if_enough(stock.client_id, bin_stock.bin_id, stock.product_id, number)
+                .await
+            {
+                Ok(Some(_)) => excess -= number,
+                // the bin was changed by another request, the next bin makes up for it
+                Ok(None) => {}
+                Err(err) => error!(
+                    "Error taking stock out of bin: {:?}. Error: {:?}",
+                    bin_stock.bin_id, err
+                ),
+            }
+        }
+
+        self.unbin(
+            stock.client_id,
+            stock.product_id,
+            warehouse_id,
+            taken - excess,
+        )
+        .await;
+        self.delete_empty_bin_stocks(stock.client_id, stock.product_id)
+            .await;
+    }
+
+    // bin_unbinned counts a quantity of the stock of a product at a warehouse as put away in bins,
+    // failing if the warehouse does not hold that quantity outside of its bins
+    async fn bin_unbinned(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: ObjectId,
+        quantity: i32,
+    ) -> Result<(), AppError> {
+        match self
+            .stock_repo
+            .bin_if_unbinned(client_id, product_id, Some(warehouse_id), quantity)
+            .await
+        {
+            Ok(Some(_)) => return Ok(()),
+            Ok(None) => {}
+            Err(err) => {
+                error!(
+                    "Error putting away stock of product: {:?}. Error: {:?}",
+                    product_id, err
+                );
+                return Err(AppError::new(
+                    "cannot move stock",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        }
+
+        let unbinned: i32 = match self
+            .stock_repo
+            .get_by_client_id_and_product_id(client_id, product_id)
+            .await
+        {
+            Ok(stocks) => stocks
+                .iter()
+                .filter(|stock| stock.warehouse_id == Some(warehouse_id))
+                .map(|stock| stock.get_unbinned())
+                .sum(),
+            Err(err) => {
+                error!("Error fetching stocks of product: {:?}", err);
+                return Err(AppError::new(
+                    "cannot move stock",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+        Err(AppError::new(
+            &format!(
+                "cannot put away {} of product_id: {}, only {} at the warehouse is not in a bin",
+                quantity,
+                product_id.to_hex(),
+                cmp::max(unbinned, 0)
+            ),
+            ErrorKind::FailedAction,
+        ))
+    }
+
+    // unbin counts a quantity of the stock of a product at a warehouse as no longer in bins.
+    // Failures are logged, the bins have already changed
+    async fn unbin(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: ObjectId,
+        quantity: i32,
+    ) {
+        if quantity <= 0 {
+            return;
+        }
+        if let Err(err) = self
+            .stock_repo
+            .unbin(client_id, product_id, Some(warehouse_id), quantity)
+            .await
+        {
+            error!(
+                "Error taking stock of product: {:?} out of bins. Error: {:?}",
+                product_id, err
+            );
+        }
+    }
+
+    // get_bin_stocks gets the products a bin holds any quantity of
+    async fn get_bin_stocks(
+        &self,
+        client_id: ObjectId,
+        bin_id: ObjectId,
+    ) -> Result<Vec<BinStock>, AppError> {
+        match self.bin_stock_repo.get_by_bin_id(client_id, bin_id).await {
+            Ok(bin_stocks) => Ok(bin_stocks
+                .into_iter()
+                .filter(|bin_stock| bin_stock.quantity > 0)
+                .collect()),
+            Err(err) => {
+                error!("Error fetching stocks of bin: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch bin",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // delete_empty_bin_stocks removes the bins a product no longer has any quantity in
+    async fn delete_empty_bin_stocks(&self, client_id: ObjectId, product_id: ObjectId) {
+        if let Err(err) = self
+            .bin_stock_repo
+            .delete_empty_by_product_id(client_id, product_id)
+            .await
+        {
+            error!(
+                "Error deleting empty bin stocks of product: {:?}. Error: {:?}",
+                product_id, err
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use bson::oid::ObjectId;
+use log::error;
+use std::sync::Arc;
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::idempotency_key::IdempotencyKey,
+    repository::idempotency_repo::IdempotencyStore,
+};
+
+// IdempotencyOutcome tells a handler what to do with a request sent with an idempotency key
+pub enum IdempotencyOutcome {
+    // the key is new, or was given up by the request that claimed it, the request should be
+    // processed and its response stored under the claim
+    Proceed {
+        claim_id: ObjectId,
+    },
+    // the request was already processed with the key, its response should be replayed
+    Replay {
+        status_code: i32,
+        response_body: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct IdempotencyService {
+    idempotency_repo: Arc<dyn IdempotencyStore>,
+    // ttl_seconds is how long a key is remembered after it was first used
+    ttl_seconds: i64,
+    // lease_seconds is how long a request holds the key it claimed before a retry can take it
+    // over, should the request never complete
+    lease_seconds: i64,
+}
+
+impl IdempotencyService {
+    // new creates a new idempotency service instance
+    pub fn new(
+        idempotency_repo: Arc<dyn IdempotencyStore>,
+        ttl_seconds: i64,
+        lease_seconds: i64,
+    ) -> IdempotencyService {
+        IdempotencyService {
+            idempotency_repo,
+            ttl_seconds,
+            lease_seconds,
+        }
+    }
+
+    // begin claims an idempotency key of a client for a request. The first request with a key
+    // proceeds, retries of the same request replay its response, and a different request with
+    // the same key is rejected. A retry takes over a claim whose request did not complete within
+    // its lease
+    pub async fn begin(
+        &self,
+        client_id: ObjectId,
+        key: &str,
+        request: &str,
+    ) -> Result<IdempotencyOutcome, AppError> {
+        loop {
+            let idempotency_key = IdempotencyKey::new(
+                client_id,
+                key.to_string(),
+                request.to_string(),
+                self.ttl_seconds,
+                self.lease_seconds,
+            );
+
+            // try to claim the key, only one request can claim it
+            match self.idempotency_repo.insert(&idempotency_key).await {
+                Ok(true) => {
+                    return Ok(IdempotencyOutcome::Proceed {
+                        claim_id: idempotency_key._id,
+                    })
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    error!("Error inserting idempotency key: {:?}", err);
+                    return Err(AppError::new(
+                        "cannot process idempotency key",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            }
+
+            // the key was already used, look up what it was used for
+            let existing = match self.idempotency_repo.get_by_key(client_id, key).await {
+                Ok(Some(existing)) => existing,
+                // the key was removed in the meantime, try to claim it again
+                Ok(None) => continue,
+                Err(err) => {
+                    error!("Error fetching idempotency key: {:?}", err);
+                    return Err(AppError::new(
+                        "cannot process idempotency key",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            };
+
+            // the key expired but the database has not removed it yet, forget it and claim it again
+            if existing.is_expired() {
+                self.forget(&existing).await?;
+                continue;
+            }
+
+            if existing.request != request {
+                return Err(AppError::new(
+                    "idempotency key was already used with a different request",
+                    ErrorKind::FailedAction,
+                ));
+            }
+
+            // the request that claimed the key died before completing, take the claim over. Only
+            // a claim still pending is given up, so a request completing meanwhile is replayed
+            if existing.is_abandoned() {
+                self.give_up(existing._id).await?;
+                continue;
+            }
+
+            return match (existing.status_code, existing.response_body) {
+                (Some(status_code), Some(response_body)) => Ok(IdempotencyOutcome::Replay {
+                    status_code,
+                    response_body,
+                }),
+                _ => Err(AppError::new(
+                    "a request with this idempotency key is still being processed",
+                    ErrorKind::Conflict,
+                )),
+            };
+        }
+    }
+
+    // complete stores the response of the request an idempotency key was claimed for,
+    // so that retries with the key replay it
+    pub async fn complete(
+        &self,
+        claim_id: ObjectId,
+        status_code: i32,
+        response_body: &str,
+    ) -> Result<(), AppError> {
+        if let Err(err) = self
+            .idempotency_repo
+            .set_response(claim_id, status_code, response_body)
+            .await
+        {
+            error!("Error storing idempotency key response: {:?}", err);
+            return Err(AppError::new(
+                "cannot process idempotency key",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // abandon gives up the claim of an idempotency key made for a request whose response should
+    // not be replayed, so that a retry with the key is processed again. A claim another request
+    // took over is left alone
+    pub async fn abandon(&self, claim_id: ObjectId) -> Result<(), AppError> {
+        self.give_up(claim_id).await
+    }
+
+    // give_up deletes the claim of an idempotency key if its request has not completed
+    async fn give_up(&self, claim_id: ObjectId) -> Result<(), AppError> {
+        if let Err(err) = self.idempotency_repo.delete_pending(claim_id).await {
+            error!("Error deleting idempotency key: {:?}", err);
+            return Err(AppError::new(
+                "cannot process idempotency key",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // forget deletes an idempotency key
+    async fn forget(&self, idempotency_key: &IdempotencyKey) -> Result<(), AppError> {
+        if let Err(err) = self.idempotency_repo.delete_by_id(idempotency_key._id).await {
+            error!("Error deleting idempotency key: {:?}", err);
+            return Err(AppError::new(
+                "cannot process idempotency key",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+    use std::sync::Arc;
+
+    use super::{IdempotencyOutcome, IdempotencyService};
+    use crate::repository::memory::idempotency_store::MemoryIdempotencyStore;
+
+    // claim_id returns the claim of an outcome that lets the request proceed
+    fn claim_id(outcome: IdempotencyOutcome) -> ObjectId {
+        match outcome {
+            IdempotencyOutcome::Proceed { claim_id } => claim_id,
+            IdempotencyOutcome::Replay { .. } => panic!("request was replayed"),
+        }
+    }
+
+    #[test]
+    // test_retry_takes_over_abandoned_claim tests that a retry is rejected while the claim of
+    // its key is leased, takes the claim over once the lease is up, and that giving up the old
+    // claim late leaves the new one in place
+    async fn test_retry_takes_over_abandoned_claim() {
+        let client_id = ObjectId::new();
+        let store = Arc::new(MemoryIdempotencyStore::default());
+        let leased = IdempotencyService::new(store.clone(), 60, 60);
+        claim_id(leased.begin(client_id, "key", "request").await.unwrap());
+        let err = leased
+            .begin(client_id, "key", "request")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.message,
+            "a request with this idempotency key is still being processed"
+        );
+
+        // with no lease, the claim is given up as soon as it is made
+        let unleased = IdempotencyService::new(store, 60, 0);
+        let client_id = ObjectId::new();
+        let first = claim_id(unleased.begin(client_id, "key", "request").await.unwrap());
+        let second = claim_id(unleased.begin(client_id, "key", "request").await.unwrap());
+        assert_ne!(first, second);
+
+        // the first request completing late neither gives up nor completes the new claim
+        unleased.abandon(first).await.unwrap();
+        unleased.complete(first, 200, "first").await.unwrap();
+        unleased.complete(second, 200, "second").await.unwrap();
+        match unleased.begin(client_id, "key", "request").await.unwrap() {
+            IdempotencyOutcome::Replay {
+                status_code,
+                response_body,
+            } => assert_eq!((status_code, response_body.as_str()), (200, "second")),
+            IdempotencyOutcome::Proceed { .. } => panic!("request was processed again"),
+        }
+    }
+}
@@ -1 +1,14 @@
+pub mod alert_service;
+pub mod attribute_service;
+pub mod bin_service;
+pub mod idempotency_service;
+pub mod order_service;
 pub mod product_service;
+pub mod purchase_order_service;
+pub mod reservation_service;
+pub mod return_service;
+pub mod sku_service;
+pub mod stream_service;
+pub mod supplier_service;
+pub mod warehouse_service;
+pub mod webhook_service;
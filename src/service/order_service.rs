@@ -0,0 +1,499 @@
+use bson::oid::ObjectId;
+use futures::future;
+use log::error;
+use std::sync::Arc;
+
+use crate::{
+    dto::{
+        order::order_dto::{CancelOrderRequest, OrderResponse},
+        product::product_dto::{ProductQuantity, ProductQuantityRequest},
+    },
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        order::{Order, OrderQuery},
+        stock::AllocatedQuantity,
+        stock_movement::{MovementReason, MovementSource},
+        webhook::WebhookEvent,
+    },
+    repository::order_repo::OrderStore,
+    service::{product_service::ProductService, webhook_service::WebhookService},
+};
+
+#[derive(Clone)]
+pub struct OrderService {
+    order_repo: Arc<dyn OrderStore>,
+    product_service: ProductService,
+    webhook_service: WebhookService,
+}
+
+impl OrderService {
+    // new creates a new order service instance
+    pub fn new(
+        order_repo: Arc<dyn OrderStore>,
+        product_service: ProductService,
+        webhook_service: WebhookService,
+    ) -> OrderService {
+        OrderService {
+            order_repo,
+            product_service,
+            webhook_service,
+        }
+    }
+
+    // place_order takes the order lines out of stock at a location, or across locations if none
+    // is given, and records the order
+    pub async fn place_order(
+        &self,
+        client_id: ObjectId,
+        order_requests: Vec<ProductQuantityRequest>,
+        warehouse_id: Option<ObjectId>,
+        actor: &str,
+    ) -> Result<Order, AppError> {
+        // the stock movements of the order reference it
+        let order_id = ObjectId::new();
+        let source = MovementSource::new(actor, Some(order_id.to_hex()));
+
+        // take the quantities out of stock, nothing is changed if any line fails
+        let lines = self
+            .product_service
+            .process_orders(client_id, order_requests, warehouse_id, &source)
+            .await?;
+
+        // record the order, and give the stock back if it cannot be recorded
+        match self.record_order(order_id, client_id, &lines).await {
+            Ok(order) => Ok(order),
+            Err(err) => {
+                let applied: Vec<&AllocatedQuantity> = lines.iter().collect();
+                self.product_service
+                    .restock_many(client_id, &applied, MovementReason::OrderRollback, &source)
+                    .await?;
+                Err(err)
+            }
+        }
+    }
+
+    // record_order records an order for quantities that were already taken out of stock, and
+    // tells the webhooks of the client it was processed
+    pub async fn record_order(
+        &self,
+        order_id: ObjectId,
+        client_id: ObjectId,
+        lines: &[AllocatedQuantity],
+    ) -> Result<Order, AppError> {
+        let order = Order::new(order_id, client_id, lines);
+        if let Err(err) = self.order_repo.insert(&order).await {
+            error!("Error inserting order: {:?}", err);
+            return Err(AppError::new(
+                "cannot process order",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        self.webhook_service
+            .publish(
+                client_id,
+                WebhookEvent::OrderProcessed,
+                &OrderResponse::new(&order),
+            )
+            .await;
+        Ok(order)
+    }
+
+    // get_order gets an order of a client from the application storage
+    pub async fn get_order(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+    ) -> Result<Order, AppError> {
+        match self.order_repo.get_by_id(client_id, order_id).await {
+            Ok(Some(order)) => Ok(order),
+            Ok(None) => Err(AppError::new("order not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching an order: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch order",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_orders_by_client gets a page of the orders of a client matching a query from the
+    // application storage, and the number of orders matching it
+    pub async fn get_orders_by_client(
+        &self,
+        client_id: ObjectId,
+        query: &OrderQuery,
+        page: u64,
+        limit: i64,
+    ) -> Result<(Vec<Order>, u64), AppError> {
+        let skip = (page - 1).saturating_mul(limit as u64);
+        let orders = self
+            .order_repo
+            .get_by_client_id(client_id, query, skip, limit);
+        let total = self.order_repo.count_by_client_id(client_id, query);
+        match future::try_join(orders, total).await {
+            Ok(orders_total) => Ok(orders_total),
+            Err(err) => {
+                error!("Error fetching all orders: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch orders",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // cancel_order gives the quantities of an order back to the stock. Only the requested lines
+    // and quantities are cancelled, or everything that remains of the order if none are requested
+    pub async fn cancel_order(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+        request: CancelOrderRequest,
+        actor: &str,
+    ) -> Result<Order, AppError> {
+        let mut order = self.get_order(client_id, order_id).await?;
+        let read_version = order.version;
+
+        // work out the quantities to cancel
+        let cancelled = match request.lines {
+            Some(lines) => {
+                let cancelled = self
+                    .product_service
+                    .check_products_and_convert_to_product_quantity_vector(lines)
+                    .await?;
+                for line in &cancelled {
+                    let remaining = match order.get_line(line.product_id) {
+                        Some(order_line) => order_line.remaining_quantity(),
+                        None => {
+                            return Err(AppError::new(
+                                &format!(
+                                    "order has no line with product_id: {}",
+                                    line.product_id.to_hex()
+                                ),
+                                ErrorKind::FailedAction,
+                            ))
+                        }
+                    };
+                    if line.quantity > remaining {
+                        return Err(AppError::new(
+                            &format!(
+                                "cannot cancel {} of product_id: {}, only {} remain on the order",
+                                line.quantity,
+                                line.product_id.to_hex(),
+                                remaining
+                            ),
+                            ErrorKind::FailedAction,
+                        ));
+                    }
+                }
+                cancelled
+            }
+            None => order
+                .lines
+                .iter()
+                .filter(|line| line.remaining_quantity() > 0)
+                .map(|line| ProductQuantity {
+                    product_id: line.product_id,
+                    quantity: line.remaining_quantity(),
+                })
+                .collect(),
+        };
+        if cancelled.is_empty() {
+            return Err(AppError::new(
+                "order is already cancelled",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        // the products must still exist for their quantities to be given back
+        let mut get_product_futs = Vec::with_capacity(cancelled.len());
+        for line in &cancelled {
+            get_product_futs.push(self.product_service.get_product(line.product_id, client_id));
+        }
+        future::try_join_all(get_product_futs).await?;
+
+        // work out the locations the cancelled quantities go back to before they are recorded
+        let restocked: Vec<AllocatedQuantity> = cancelled
+            .iter()
+            .filter_map(|line| {
+                order
+                    .get_line(line.product_id)
+                    .map(|order_line| AllocatedQuantity {
+                        product_id: line.product_id,
+                        quantity: line.quantity,
+                        allocations: order_line.release_allocations(line.quantity),
+                    })
+            })
+            .collect();
+
+        // give the cancelled quantities back to the stock first, so that the order is never
+        // recorded as cancelled without its stock back. The movements reference the order
+        let source = MovementSource::new(actor, Some(order_id.to_hex()));
+        let restocked: Vec<&AllocatedQuantity> = restocked.iter().collect();
+        self.product_service
+            .restock_all(
+                client_id,
+                &restocked,
+                MovementReason::OrderCancellation,
+                &source,
+            )
+            .await?;
+
+        // record the cancellation, and take the quantities back if it cannot be recorded, such
+        // as when another request cancelled the same quantities first
+        order.cancel(&cancelled, &request.reason, actor);
+        if let Err(err) = self.save_if_unchanged(&order, read_version).await {
+            self.product_service
+                .take_back_restocked(client_id, &restocked, &source)
+                .await?;
+            return Err(err);
+        }
+
+        Ok(order)
+    }
+
+    // add_returned checks that the quantities to return do not exceed what remains of an order
+    // and records them on the order, so that they cannot be returned or cancelled again
+    pub async fn add_returned(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+        returned: &[ProductQuantity],
+    ) -> Result<Order, AppError> {
+        let mut order = self.get_order(client_id, order_id).await?;
+        let read_version = order.version;
+
+        for line in returned {
+            let remaining = match order.get_line(line.product_id) {
+                Some(order_line) => order_line.remaining_quantity(),
+                None => {
+                    return Err(AppError::new(
+                        &format!(
+                            "order has no line with product_id: {}",
+                            line.product_id.to_hex()
+                        ),
+                        ErrorKind::FailedAction,
+                    ))
+                }
+            };
+            if line.quantity > remaining {
+                return Err(AppError::new(
+                    &format!(
+                        "cannot return {} of product_id: {}, only {} can still be returned",
+                        line.quantity,
+                        line.product_id.to_hex(),
+                        remaining
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+
+        order.add_returned(returned);
+        self.save_if_unchanged(&order, read_version).await?;
+
+        Ok(order)
+    }
+
+    // remove_returned undoes add_returned for quantities whose return could not be authorized
+    pub async fn remove_returned(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+        returned: &[ProductQuantity],
+    ) -> Result<Order, AppError> {
+        let mut order = self.get_order(client_id, order_id).await?;
+        let read_version = order.version;
+
+        order.remove_returned(returned);
+        self.save_if_unchanged(&order, read_version).await?;
+
+        Ok(order)
+    }
+
+    // save_if_unchanged saves a changed order, failing if another request changed the order
+    // since it was read at the given version
+    async fn save_if_unchanged(&self, order: &Order, read_version: i32) -> Result<(), AppError> {
+        match self
+            .order_repo
+            .replace_if_unchanged(order, read_version)
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::new(
+                "order was changed by another request, try again",
+                ErrorKind::Conflict,
+            )),
+            Err(err) => {
+                error!("Error updating order: {:?}. Error: {:?}", order._id, err);
+                Err(AppError::new(
+                    "cannot update order",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use async_trait::async_trait;
+    use bson::oid::ObjectId;
+    use mongodb::error::Error;
+    use std::sync::Arc;
+
+    use crate::{
+        dto::{order::order_dto::CancelOrderRequest, product::product_dto::ProductQuantityRequest},
+        model::{
+            order::{Order, OrderQuery},
+            product::Product,
+            stock_movement::{MovementReason, MovementSource},
+        },
+        repository::{order_repo::OrderStore, stores::Stores},
+        server::ServiceManager,
+    };
+
+    // ChangedOrderStore stores orders like the store it wraps, but every order it is asked to
+    // replace was changed by another request since it was read
+    struct ChangedOrderStore {
+        orders: Arc<dyn OrderStore>,
+    }
+
+    #[async_trait]
+    impl OrderStore for ChangedOrderStore {
+        async fn insert(&self, order: &Order) -> Result<(), Error> {
+            self.orders.insert(order).await
+        }
+
+        async fn get_by_id(
+            &self,
+            client_id: ObjectId,
+            order_id: ObjectId,
+        ) -> Result<Option<Order>, Error> {
+            self.orders.get_by_id(client_id, order_id).await
+        }
+
+        async fn get_by_client_id(
+            &self,
+            client_id: ObjectId,
+            query: &OrderQuery,
+            skip: u64,
+            limit: i64,
+        ) -> Result<Vec<Order>, Error> {
+            self.orders
+                .get_by_client_id(client_id, query, skip, limit)
+                .await
+        }
+
+        async fn count_by_client_id(
+            &self,
+            client_id: ObjectId,
+            query: &OrderQuery,
+        ) -> Result<u64, Error> {
+            self.orders.count_by_client_id(client_id, query).await
+        }
+
+        async fn replace_if_unchanged(&self, _: &Order, _: i32) -> Result<bool, Error> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    // test_cancel_order_takes_back_restock tests that the quantities given back by a
+    // cancellation are taken out again when the cancellation cannot be recorded, so the order
+    // can still be cancelled later
+    async fn test_cancel_order_takes_back_restock() {
+        let mut stores = Stores::memory();
+        stores.order_store = Arc::new(ChangedOrderStore {
+            orders: stores.order_store.clone(),
+        });
+        let service_manager = ServiceManager::new(&stores);
+        let client_id = ObjectId::new();
+        let mut product = Product::new(
+            "Widget".to_string(),
+            "A widget".to_string(),
+            None,
+            client_id,
+        );
+        service_manager
+            .product_service
+            .create(
+                &mut product,
+                client_id,
+                None,
+                5,
+                None,
+                &MovementSource::new("test", None),
+            )
+            .await
+            .unwrap();
+        let order = service_manager
+            .order_service
+            .place_order(
+                client_id,
+                vec![ProductQuantityRequest {
+                    product_id: product._id.to_hex(),
+                    quantity: 3,
+                }],
+                None,
+                "test",
+            )
+            .await
+            .unwrap();
+
+        let err = service_manager
+            .order_service
+            .cancel_order(
+                client_id,
+                order._id,
+                CancelOrderRequest {
+                    reason: "changed my mind".to_string(),
+                    lines: None,
+                },
+                "test",
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "order was changed by another request, try again"
+        );
+
+        let (_, stocks) = service_manager
+            .product_service
+            .get_product(product._id, client_id)
+            .await
+            .unwrap();
+        assert_eq!(stocks[0].get_quantity(), 2);
+        let (movements, _) = service_manager
+            .product_service
+            .get_movements(client_id, product._id, 1, 10)
+            .await
+            .unwrap();
+        let movements: Vec<(MovementReason, i32)> = movements
+            .iter()
+            .map(|movement| (movement.reason, movement.delta))
+            .collect();
+        assert_eq!(
+            movements,
+            vec![
+                (MovementReason::RestockRollback, -3),
+                (MovementReason::OrderCancellation, 3),
+                (MovementReason::Order, -3),
+                (MovementReason::Create, 5),
+            ]
+        );
+        let order = service_manager
+            .order_service
+            .get_order(client_id, order._id)
+            .await
+            .unwrap();
+        assert!(order
+            .lines
+            .iter()
+            .all(|line| line.remaining_quantity() == 3));
+    }
+}
@@ -1,66 +1,243 @@
 use bson::oid::ObjectId;
 use futures::future;
 use log::error;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use crate::{
-    dto::product::product_dto::{
-        ProductQuantity, ProductQuantityRequest, SetProductQuantityRequest, UpdateProductRequest,
+    dto::{
+        product::product_dto::{
+            ProductQuantity, ProductQuantityRequest, SetProductQuantityRequest,
+            StockMovementResponse, UpdateProductRequest,
+        },
+        webhook::webhook_dto::ProductEventData,
     },
     errors::app_error::{AppError, ErrorKind},
-    model::{product::Product, stock::Stock},
-    repository::{product_repo::ProductRepo, stock_repo::StockRepo},
+    model::{
+        barcode::Barcode,
+        product::{
+            Product, ProductCursor, ProductListQuery, ProductSearchHit, ProductSupplier, StockLevel,
+        },
+        sku,
+        stock::{AllocatedQuantity, Stock, StockAllocation},
+        stock_movement::{MovementReason, MovementSource, StockMovement},
+        webhook::WebhookEvent,
+    },
+    repository::{
+        product_repo::ProductStore, stock_movement_repo::StockMovementStore, stock_repo::StockStore,
+    },
+    service::{
+        alert_service::AlertService, attribute_service::AttributeService, bin_service::BinService,
+        sku_service::SkuService, warehouse_service::WarehouseService,
+        webhook_service::WebhookService,
+    },
 };
 
+// SKU_COLLISION_RETRIES is how many new skus are tried when the generated sku of a new product
+// is already used by the client
+const SKU_COLLISION_RETRIES: usize = 5;
+
+// StockHold is how a quantity allocated from the stock of a location is held
+#[derive(Debug, Clone, Copy)]
+enum StockHold {
+    // the quantity is taken out of the stock
+    Take,
+    // the quantity is held for a reservation
+    Reserve,
+}
+
 #[derive(Clone)]
 pub struct ProductService {
-    product_repo: ProductRepo,
-    stock_repo: StockRepo,
+    product_repo: Arc<dyn ProductStore>,
+    stock_repo: Arc<dyn StockStore>,
+    stock_movement_repo: Arc<dyn StockMovementStore>,
+    warehouse_service: WarehouseService,
+    bin_service: BinService,
+    sku_service: SkuService,
+    attribute_service: AttributeService,
+    alert_service: AlertService,
+    webhook_service: WebhookService,
 }
 
 impl ProductService {
     // new creates a new product service instance
-    pub fn new(product_repo: ProductRepo, stock_repo: StockRepo) -> ProductService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        product_repo: Arc<dyn ProductStore>,
+        stock_repo: Arc<dyn StockStore>,
+        stock_movement_repo: Arc<dyn StockMovementStore>,
+        warehouse_service: WarehouseService,
+        bin_service: BinService,
+        sku_service: SkuService,
+        attribute_service: AttributeService,
+        alert_service: AlertService,
+        webhook_service: WebhookService,
+    ) -> ProductService {
         ProductService {
             product_repo,
             stock_repo,
+            stock_movement_repo,
+            warehouse_service,
+            bin_service,
+            sku_service,
+            attribute_service,
+            alert_service,
+            webhook_service,
         }
     }
 
-    // create implements the business logic for creating a product
+    // create implements the business logic for creating a product. The product gets the given
+    // sku, or one made by the sku generator of the client if none is given. The quantity is kept
+    // at the given warehouse, or not assigned to a warehouse if none is given. A parent product
+    // holds no stock, its variants are created with the quantity instead and are returned. A
+    // bundle holds no stock either, its components do. The attributes of the product are checked
+    // against the attribute definitions of the client. Only products holding stock can have a
+    // reorder point
     pub async fn create(
         &self,
-        product: &Product,
+        product: &mut Product,
         client_id: ObjectId,
+        sku: Option<String>,
         quantity: i32,
+        warehouse_id: Option<ObjectId>,
+        source: &MovementSource,
+    ) -> Result<Vec<Product>, AppError> {
+        // the warehouse must exist before anything is created
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
+            .await?;
+        product.attributes = self
+            .attribute_service
+            .check_attributes(client_id, &product.attributes)
+            .await?;
+        if product.is_bundle() {
+            self.check_components(client_id, product).await?;
+        }
+        check_reorder_settings(product)?;
+        // the product starts at the level of the quantity it is created with, as its variants do
+        product.stock_level = StockLevel::of(product, quantity);
+
+        self.insert_with_sku(product, client_id, sku).await?;
+        self.publish_product(WebhookEvent::ProductCreated, product)
+            .await;
+        if product.is_bundle() {
+            return Ok(vec![]);
+        }
+        if !product.is_parent() {
+            self.create_stock(client_id, product._id, warehouse_id, quantity, source)
+                .await?;
+            return Ok(vec![]);
+        }
+
+        let variants = self.create_variants(product).await?;
+        for variant in &variants {
+            self.publish_product(WebhookEvent::ProductCreated, variant)
+                .await;
+            self.create_stock(client_id, variant._id, warehouse_id, quantity, source)
+                .await?;
+        }
+        Ok(variants)
+    }
+
+    // insert_with_sku inserts a new product with the given sku, or with one made by the sku
+    // generator of the client if none is given
+    async fn insert_with_sku(
+        &self,
+        product: &mut Product,
+        client_id: ObjectId,
+        sku: Option<String>,
     ) -> Result<(), AppError> {
-        // insert the product in the database, get the insertion id and return an error if any
-        let optional_product_id = match self.product_repo.insert(product).await {
-            Ok(product_result) => product_result.inserted_id.as_object_id(),
-            Err(err) => {
-                error!("Error inserting product: {:?}", err);
-                return Err(AppError::new(
-                    "cannot create product",
-                    ErrorKind::InternalServerError,
-                ));
+        match sku {
+            // a given sku is used as it is, so it cannot be replaced if the client already uses it
+            Some(sku) => {
+                product.set_sku(sku);
+                if !self.insert_product(product).await? {
+                    return Err(AppError::new(
+                        "sku is already used by another product",
+                        ErrorKind::FailedAction,
+                    ));
+                }
             }
-        };
-
-        // ensure product_id is valid
-        let product_id = match optional_product_id {
-            Some(product_id) => product_id,
+            // a generated sku the client already uses is replaced by a new one
             None => {
-                error!("Error inserting product: cannot retrieve inserted id");
-                return Err(AppError::new(
-                    "cannot create product",
-                    ErrorKind::InternalServerError,
-                ));
+                let generator = self.sku_service.generator(client_id).await?;
+                let mut attempts = 0;
+                loop {
+                    product.set_sku(generator.generate(client_id, product).await?);
+                    if self.insert_product(product).await? {
+                        break;
+                    }
+                    if attempts == SKU_COLLISION_RETRIES {
+                        error!(
+                            "Error inserting product: sku still taken after {} retries",
+                            attempts
+                        );
+                        return Err(AppError::new(
+                            "cannot create product",
+                            ErrorKind::InternalServerError,
+                        ));
+                    }
+                    attempts += 1;
+                }
             }
-        };
+        }
+
+        Ok(())
+    }
+
+    // create_variants inserts a variant of a parent product for every combination of the values
+    // of its axes. If a variant cannot be inserted, the parent and the variants inserted before
+    // it are removed
+    async fn create_variants(&self, parent: &Product) -> Result<Vec<Product>, AppError> {
+        let client_id = parent.get_created_by();
+        let variants = parent.make_variants();
 
-        // insert the client_id, product_id and quantity in stock
-        let stock = Stock::new(client_id, product_id, quantity);
+        let mut inserted = vec![parent._id];
+        for variant in &variants {
+            let result = match sku::is_valid_sku(&variant.get_sku()) {
+                true => match self.insert_product(variant).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(AppError::new(
+                        &format!(
+                            "sku {} of a variant is already used by another product",
+                            variant.get_sku()
+                        ),
+                        ErrorKind::FailedAction,
+                    )),
+                    Err(err) => Err(err),
+                },
+                false => Err(AppError::new(
+                    &format!("sku of variant {} is too long", variant.name),
+                    ErrorKind::FailedAction,
+                )),
+            };
+
+            if let Err(err) = result {
+                for product_id in inserted {
+                    if let Err(err) = self.product_repo.delete_by_id(client_id, product_id).await {
+                        error!("Error removing product of failed variants: {:?}", err);
+                    }
+                }
+                return Err(err);
+            }
+            inserted.push(variant._id);
+        }
+
+        Ok(variants)
+    }
+
+    // create_stock creates the stock of a new product at a location
+    async fn create_stock(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+        quantity: i32,
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let stock = Stock::new(client_id, product_id, warehouse_id, quantity);
         if let Err(err) = self.stock_repo.insert(&stock).await {
             error!("Error creating stock: {:?}", err);
             return Err(AppError::new(
@@ -68,49 +245,360 @@ impl ProductService {
                 ErrorKind::InternalServerError,
             ));
         }
+        self.record_movement(&stock, MovementReason::Create, source, quantity)
+            .await
+    }
 
-        Ok(())
+    // insert_product inserts a new product. Returns false if the client already uses its sku,
+    // and fails if the client already uses one of its barcodes
+    async fn insert_product(&self, product: &Product) -> Result<bool, AppError> {
+        match self.product_repo.insert(product).await {
+            Ok(true) => Ok(true),
+            // the product may have been rejected for a barcode rather than its sku
+            Ok(false) => {
+                self.check_barcodes(product.get_created_by(), product)
+                    .await?;
+                Ok(false)
+            }
+            Err(err) => {
+                error!("Error inserting product: {:?}", err);
+                Err(AppError::new(
+                    "cannot create product",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
     }
 
-    // get_product gets a product and its quantity from the application storage
+    // get_product gets a product and its stock at every location from the application storage
     pub async fn get_product(
         &self,
         product_id: ObjectId,
         client_id: ObjectId,
-    ) -> Result<(Product, i32), AppError> {
-        let product = match self.product_repo.get_by_id(client_id, product_id).await {
+    ) -> Result<(Product, Vec<Stock>), AppError> {
+        let product = self.find_product(product_id, client_id).await?;
+        self.with_stocks(client_id, product).await
+    }
+
+    // find_product gets a product without its stock from the application storage
+    async fn find_product(
+        &self,
+        product_id: ObjectId,
+        client_id: ObjectId,
+    ) -> Result<Product, AppError> {
+        match self.product_repo.get_by_id(client_id, product_id).await {
+            Ok(Some(product)) => Ok(product),
+            Ok(None) => Err(AppError::new("product not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a product: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch product",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // check_stocked_product checks that a product of the client exists and holds stock, so it
+    // is neither a parent product nor a bundle
+    pub async fn check_stocked_product(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<(), AppError> {
+        let product = self.find_product(product_id, client_id).await?;
+        check_not_parent(&product)?;
+        check_not_bundle(&product)
+    }
+
+    // check_components checks that the components of a bundle are products of the client that
+    // hold stock themselves, so neither parent products nor other bundles
+    async fn check_components(
+        &self,
+        client_id: ObjectId,
+        bundle: &Product,
+    ) -> Result<(), AppError> {
+        for component in &bundle.components {
+            let product = match self.find_product(component.product_id, client_id).await {
+                Ok(product) => product,
+                Err(err) if matches!(err.kind, ErrorKind::NotFound) => {
+                    return Err(AppError::new(
+                        &format!("component {} not found", component.product_id.to_hex()),
+                        ErrorKind::NotFound,
+                    ))
+                }
+                Err(err) => return Err(err),
+            };
+            if product.is_parent() {
+                return Err(AppError::new(
+                    &format!(
+                        "component {} has variants, use the id of one of its variants",
+                        component.product_id.to_hex()
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+            if product.is_bundle() {
+                return Err(AppError::new(
+                    &format!(
+                        "component {} is a bundle, a bundle cannot be made of bundles",
+                        component.product_id.to_hex()
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // bundle_available computes how many of a bundle its components can make at a location, or
+    // across every location if none is given. It is the least, over the components, of the
+    // available quantity of the component divided by how many of it one bundle holds
+    pub async fn bundle_available(
+        &self,
+        client_id: ObjectId,
+        bundle: &Product,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<i32, AppError> {
+        let mut get_stocks_futs = Vec::with_capacity(bundle.components.len());
+        for component in &bundle.components {
+            get_stocks_futs.push(self.get_stocks(client_id, component.product_id));
+        }
+        let stocks = future::try_join_all(get_stocks_futs).await?;
+
+        Ok(bundle
+            .components
+            .iter()
+            .zip(stocks)
+            .map(|(component, stocks)| {
+                let available: i32 = stocks
+                    .iter()
+                    .filter(|stock| warehouse_id.is_none() || stock.warehouse_id == warehouse_id)
+                    .map(|stock| stock.get_available())
+                    .sum();
+                available.max(0) / component.quantity
+            })
+            .min()
+            .unwrap_or(0))
+    }
+
+    // expand_bundles replaces the lines of bundles with lines of their components, for as many
+    // of every component as the bundles hold, and merges the lines of a product that comes up
+    // more than once. Every line comes with the index of the first given line it comes from
+    pub async fn expand_bundles(
+        &self,
+        client_id: ObjectId,
+        lines: &[ProductQuantity],
+    ) -> Result<Vec<(ProductQuantity, usize)>, AppError> {
+        let mut get_product_futs = Vec::with_capacity(lines.len());
+        for line in lines {
+            get_product_futs.push(self.find_product(line.product_id, client_id));
+        }
+        let products = future::try_join_all(get_product_futs).await?;
+
+        let mut expanded: Vec<(ProductQuantity, usize)> = Vec::with_capacity(lines.len());
+        for (index, (line, product)) in lines.iter().zip(products).enumerate() {
+            let parts = match product.is_bundle() {
+                true => product
+                    .components
+                    .iter()
+                    .map(|component| {
+                        (
+                            component.product_id,
+                            component.quantity.saturating_mul(line.quantity),
+                        )
+                    })
+                    .collect(),
+                false => vec![(line.product_id, line.quantity)],
+            };
+            for (product_id, quantity) in parts {
+                match expanded
+                    .iter_mut()
+                    .find(|(part, _)| part.product_id == product_id)
+                {
+                    Some((part, _)) => part.quantity = part.quantity.saturating_add(quantity),
+                    None => expanded.push((
+                        ProductQuantity {
+                            product_id,
+                            quantity,
+                        },
+                        index,
+                    )),
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    // get_product_by_sku gets a product by sku and its stock at every location
+    pub async fn get_product_by_sku(
+        &self,
+        client_id: ObjectId,
+        sku: &str,
+    ) -> Result<(Product, Vec<Stock>), AppError> {
+        let product = match self.product_repo.get_by_sku(client_id, sku).await {
             Ok(Some(product)) => product,
             Ok(None) => return Err(AppError::new("product not found", ErrorKind::NotFound)),
             Err(err) => {
-                error!("Error fetching a product: {:?}", err);
+                error!("Error fetching a product by sku: {:?}", err);
                 return Err(AppError::new(
                     "cannot fetch product",
                     ErrorKind::InternalServerError,
-                ))
+                ));
             }
         };
 
-        let stock = match self.stock_repo.get_by_client_id_and_product_id(client_id, product_id).await {
-            Ok(Some(stock)) => stock,
-            Ok(None) => return Err(AppError::new("stock not found", ErrorKind::NotFound)),
+        self.with_stocks(client_id, product).await
+    }
+
+    // get_product_by_barcode gets a product by one of its barcodes and its stock at every
+    // location. The barcode can be given in any of its forms, such as a UPC-A padded to an EAN-13
+    pub async fn get_product_by_barcode(
+        &self,
+        client_id: ObjectId,
+        code: &str,
+    ) -> Result<(Product, Vec<Stock>), AppError> {
+        let barcode = match Barcode::parse(code) {
+            Some(barcode) => barcode,
+            None => return Err(AppError::new("invalid barcode", ErrorKind::FailedAction)),
+        };
+
+        let product = match self
+            .product_repo
+            .get_by_barcode(client_id, &barcode.gtin)
+            .await
+        {
+            Ok(Some(product)) => product,
+            Ok(None) => return Err(AppError::new("product not found", ErrorKind::NotFound)),
             Err(err) => {
-                error!("Error fetching a stock: {:?}", err);
+                error!("Error fetching a product by barcode: {:?}", err);
                 return Err(AppError::new(
-                    "cannot fetch stock",
+                    "cannot fetch product",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        self.with_stocks(client_id, product).await
+    }
+
+    // check_barcodes fails if another product of the client uses one of the barcodes of a product
+    async fn check_barcodes(&self, client_id: ObjectId, product: &Product) -> Result<(), AppError> {
+        for barcode in &product.barcodes {
+            match self
+                .product_repo
+                .get_by_barcode(client_id, &barcode.gtin)
+                .await
+            {
+                Ok(Some(other)) if other._id != product._id => {
+                    return Err(AppError::new(
+                        &format!(
+                            "barcode {} is already used by another product",
+                            barcode.code
+                        ),
+                        ErrorKind::FailedAction,
+                    ))
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    error!("Error fetching a product by barcode: {:?}", err);
+                    return Err(AppError::new(
+                        "cannot check barcodes",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // get_variants gets the variants of a parent product, none for a product without variants
+    pub async fn get_variants(
+        &self,
+        client_id: ObjectId,
+        parent_id: ObjectId,
+    ) -> Result<Vec<Product>, AppError> {
+        match self.product_repo.get_variants(client_id, parent_id).await {
+            Ok(variants) => Ok(variants),
+            Err(err) => {
+                error!("Error fetching variants: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch variants",
                     ErrorKind::InternalServerError,
                 ))
             }
+        }
+    }
+
+    // with_stocks gets the stock of a product at every location. The stock of a parent product
+    // is the stock of its variants, and a bundle has none. A product kept nowhere, its last
+    // location deleted, has no stock and a quantity of 0 until it is restocked
+    async fn with_stocks(
+        &self,
+        client_id: ObjectId,
+        product: Product,
+    ) -> Result<(Product, Vec<Stock>), AppError> {
+        let stocks = match product.is_parent() {
+            true => {
+                let variants = self.get_variants(client_id, product._id).await?;
+                let mut get_stocks_futs = Vec::with_capacity(variants.len());
+                for variant in &variants {
+                    get_stocks_futs.push(self.get_stocks(client_id, variant._id));
+                }
+                future::try_join_all(get_stocks_futs)
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            false => self.get_stocks(client_id, product._id).await?,
         };
+        Ok((product, stocks))
+    }
 
-        Ok((product, stock.get_quantity()))
+    // get_stocks gets the stocks of a product at every location
+    async fn get_stocks(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<Vec<Stock>, AppError> {
+        match self
+            .stock_repo
+            .get_by_client_id_and_product_id(client_id, product_id)
+            .await
+        {
+            Ok(stocks) => Ok(stocks),
+            Err(err) => {
+                error!("Error fetching a stock: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch stock",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
     }
 
-    // get_products gets products from the application storage
-    pub async fn get_products_by_client(&self, client_id: ObjectId) -> Result<Vec<(Product, i32)>, AppError> {
-        let mut pq_hashmap: HashMap<ObjectId, (Product, i32)> = HashMap::new();
+    // get_products_by_client gets a page of the products of a client with their total quantity
+    // across locations. The cursor of the next page is returned if there is one, with the number
+    // of products matching the filters of the query on every page. The products are also
+    // filtered by the values of their attributes, given as pairs of names and values
+    pub async fn get_products_by_client(
+        &self,
+        client_id: ObjectId,
+        mut query: ProductListQuery,
+        attributes: &[(String, String)],
+    ) -> Result<(Vec<(Product, i32)>, Option<ProductCursor>, u64), AppError> {
+        query.attributes = self
+            .attribute_service
+            .filters(client_id, attributes)
+            .await?;
 
-        let products = match self.product_repo.get_by_client_id(client_id).await {
-            Ok(products) => products,
+        // fetch one more product than the page holds to know if there is a next page
+        let limit = query.limit;
+        query.limit += 1;
+
+        let (mut products, total) = match self.product_repo.list(client_id, &query).await {
+            Ok(products_total) => products_total,
             Err(err) => {
                 error!("Error fetching all products: {:?}", err);
                 return Err(AppError::new(
@@ -120,35 +608,51 @@ impl ProductService {
             }
         };
 
-        // insert the products in the hashmap
-        for product in products {
-            pq_hashmap.insert(product._id, (product, 0));
+        let mut next_cursor = None;
+        if products.len() as i64 > limit {
+            products.truncate(limit as usize);
+            next_cursor = products
+                .last()
+                .map(|(product, quantity)| ProductCursor::new(product, *quantity));
+        }
+
+        // bundles hold no stock, so the quantity filters and the sort see none. They are listed
+        // with how many their components can make
+        for (product, quantity) in &mut products {
+            if product.is_bundle() {
+                *quantity = self.bundle_available(client_id, product, None).await?;
+            }
         }
 
-        let stocks = match self.stock_repo.get_by_client_id(client_id).await {
-            Ok(stocks) => stocks,
+        Ok((products, next_cursor, total))
+    }
+
+    // search_products gets the products of a client matching a text, most relevant first, with
+    // their total quantity across locations
+    pub async fn search_products(
+        &self,
+        client_id: ObjectId,
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<ProductSearchHit>, AppError> {
+        let mut hits = match self.product_repo.search(client_id, text, limit).await {
+            Ok(hits) => hits,
             Err(err) => {
-                error!("Error fetching all stocks: {:?}", err);
+                error!("Error searching products: {:?}", err);
                 return Err(AppError::new(
-                    "cannot fetch stocks",
+                    "cannot search products",
                     ErrorKind::InternalServerError,
                 ));
             }
         };
 
-        for stock in stocks {
-            pq_hashmap
-                .get_mut(&stock.product_id)
-                .map(|pq| pq.1 = stock.get_quantity());
-        }
-
-        let mut pq_vec: Vec<(Product, i32)> = Vec::with_capacity(pq_hashmap.len());
-
-        for (_, pq) in pq_hashmap {
-            pq_vec.push(pq);
+        // bundles are found with how many their components can make
+        for hit in &mut hits {
+            if hit.product.is_bundle() {
+                hit.quantity = self.bundle_available(client_id, &hit.product, None).await?;
+            }
         }
-
-        Ok(pq_vec)
+        Ok(hits)
     }
 
     // update_product updates a product in the application storage
@@ -159,109 +663,240 @@ impl ProductService {
         update: &UpdateProductRequest,
     ) -> Result<Product, AppError> {
         // retrieve the product from the service
-        let mut product = self.get_product(product_id, client_id).await?.0;
+        let (mut product, stocks) = self.get_product(product_id, client_id).await?;
 
-        // update the fields in the product
+        // update the fields in the product, the barcodes, attributes and reorder settings are
+        // kept if none are given
         product.name = update.name.clone();
         product.description = update.description.clone();
+        if let Some(reorder_point) = update.reorder_point {
+            product.reorder_point = Some(reorder_point);
+        }
+        if let Some(reorder_quantity) = update.reorder_quantity {
+            product.reorder_quantity = Some(reorder_quantity);
+        }
+        check_reorder_settings(&product)?;
+        // a new reorder point changes the level of the quantity, without a movement to raise an
+        // alert for
+        let quantity = stocks.iter().map(Stock::get_quantity).sum();
+        product.stock_level = StockLevel::of(&product, quantity);
+        if let Some(barcodes) = update.get_barcodes() {
+            product.barcodes = barcodes;
+            self.check_barcodes(client_id, &product).await?;
+        }
+        if let Some(attributes) = &update.attributes {
+            product.attributes = self
+                .attribute_service
+                .check_attributes(client_id, attributes)
+                .await?;
+        }
+
+        // if failed or nothing was modified, stop process flow and log error.
+        match self.product_repo.update(client_id, &product).await {
+            Ok(true) => {}
+            Ok(false) => {
+                error!(
+                    "Error updating product with id: {:?}. No documents were modified",
+                    product_id
+                );
+                return Err(AppError::new(
+                    "cannot update product",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+            Err(err) => {
+                error!(
+                    "Error updating product with id: {:?}. Error: {:?}",
+                    product_id, err
+                );
+                return Err(AppError::new(
+                    "cannot update product",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        }
 
-        // get the result of updating the document
-        let result = self.product_repo.update(client_id, &product).await;
+        self.publish_product(WebhookEvent::ProductUpdated, &product)
+            .await;
+        Ok(product)
+    }
 
-        // if failed, stop process flow and log error.
-        if result.is_err() {
-            error!(
-                "Error updating product with id: {:?}. Error: {:?}",
-                product_id,
-                result.unwrap_err()
-            );
+    // set_product_supplier links a product to a supplier, or replaces the terms of the link if
+    // the product is already linked to it. Only products holding stock are bought from suppliers,
+    // the supplier must have been checked to be one of the client
+    pub async fn set_product_supplier(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        link: ProductSupplier,
+    ) -> Result<Product, AppError> {
+        let mut product = self.find_product(product_id, client_id).await?;
+        check_not_parent(&product)?;
+        check_not_bundle(&product)?;
+
+        product.set_supplier(link);
+        self.save_product_suppliers(client_id, &product).await?;
+        Ok(product)
+    }
+
+    // remove_product_supplier unlinks a product from a supplier
+    pub async fn remove_product_supplier(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Product, AppError> {
+        let mut product = self.find_product(product_id, client_id).await?;
+        if !product.remove_supplier(supplier_id) {
             return Err(AppError::new(
-                "cannot update product",
-                ErrorKind::InternalServerError,
+                "product is not linked to the supplier",
+                ErrorKind::FailedAction,
             ));
         }
 
-        // if nothing was modified, return an error
-        if result.unwrap().modified_count == 0 {
+        self.save_product_suppliers(client_id, &product).await?;
+        Ok(product)
+    }
+
+    // remove_supplier_links unlinks every product of a client from a supplier
+    pub async fn remove_supplier_links(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<(), AppError> {
+        if let Err(err) = self
+            .product_repo
+            .remove_supplier(client_id, supplier_id)
+            .await
+        {
             error!(
-                "Error updating product with id: {:?}. No documents were modified",
-                product_id
+                "Error unlinking products from supplier: {:?}. Error: {:?}",
+                supplier_id, err
             );
             return Err(AppError::new(
-                "cannot update product",
+                "cannot unlink products from supplier",
                 ErrorKind::InternalServerError,
             ));
         }
+        Ok(())
+    }
 
-        Ok(product)
+    // save_product_suppliers saves the supplier links of a product and publishes the change
+    async fn save_product_suppliers(
+        &self,
+        client_id: ObjectId,
+        product: &Product,
+    ) -> Result<(), AppError> {
+        match self
+            .product_repo
+            .set_suppliers(client_id, product._id, &product.suppliers)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return Err(AppError::new("product not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!(
+                    "Error updating suppliers of product with id: {:?}. Error: {:?}",
+                    product._id, err
+                );
+                return Err(AppError::new(
+                    "cannot update product",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        }
+
+        self.publish_product(WebhookEvent::ProductUpdated, product)
+            .await;
+        Ok(())
     }
 
-    // set_product_quantity sets the quantity of a product in stock to
-    // to a value higher than it previously was. If the value is less than
-    // the current quantity, an error is returned.
+    // set_product_quantity adds the given quantity to the quantity of a product at a location,
+    // the stock not assigned to a warehouse if none is given. A negative quantity is taken out
+    // of the stock, but never more than is available
     pub async fn set_product_quantity(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
         update: &SetProductQuantityRequest,
+        warehouse_id: Option<ObjectId>,
+        source: &MovementSource,
     ) -> Result<Stock, AppError> {
-        // check that the product exists and get the stock if it does
-        let mut stock = self
-            .check_product_and_get_stock(client_id, product_id)
+        // check that the product and the warehouse exist
+        let product = self.get_product(product_id, client_id).await?.0;
+        check_not_parent(&product)?;
+        check_not_bundle(&product)?;
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
             .await?;
 
-        // compare the current quantity with the quantity to set, must be lower
-        // if update.quantity < stock.get_quantity() {
-        //     return Err(AppError::new(
-        //         "quantity to set must be more than current quantity",
-        //         ErrorKind::FailedAction,
-        //     ));
-        // }
-
-
-
-        // if the quantities are equal, no need to update
-        // if update.quantity == stock.get_quantity() {
-        //     return Ok(stock);
-        // }
-        let newQuantity = update.quantity + stock.get_quantity();
-
-        // set the quantity in the stock object
-        stock.set_quantity(newQuantity);
-        // update the quantity in the stock
-        let modified_count = match self.stock_repo.update(&stock).await {
-            Ok(result) => result.modified_count,
-            Err(_) => {
+        // add to the quantity in the stock atomically, so no concurrent change is lost. Adding
+        // to a location the product is not kept at yet starts keeping it there
+        let result = if update.quantity < 0 {
+            self.stock_repo
+                .decrement_if_available(client_id, product_id, warehouse_id, -update.quantity)
+                .await
+        } else {
+            self.stock_repo
+                .increment_or_insert(client_id, product_id, warehouse_id, update.quantity)
+                .await
+        };
+        let stock = match result {
+            Ok(Some(stock)) => stock,
+            Ok(None) if update.quantity < 0 => {
+                return Err(AppError::new(
+                    "product is low in stock",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            Ok(None) => return Err(AppError::new("stock not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!(
+                    "Error setting quantity of client_id: {:?}, product_id: {:?}. Error: {:?}",
+                    client_id, product_id, err
+                );
                 return Err(AppError::new(
                     "cannot set quantity",
                     ErrorKind::InternalServerError,
-                ))
+                ));
             }
         };
-        if modified_count == 0 {
-            return Err(AppError::new(
-                "cannot set quantity",
-                ErrorKind::InternalServerError,
-            ));
-        }
+        self.record_movement(&stock, MovementReason::SetQuantity, source, update.quantity)
+            .await?;
 
         Ok(stock)
     }
 
-    // check_availability checks if a product has the required number in stock
+    // check_availability checks if a product has the required number in stock at a location,
+    // or across every location if none is given. Quantities held by reservations are not
+    // available. A bundle is available as many times as its components can make it
     pub async fn check_availability(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
         has_available: i32,
+        warehouse_id: Option<ObjectId>,
     ) -> Result<(), AppError> {
-        // check that the product exists and get the stock if it does
-        let stock = self
-            .check_product_and_get_stock(client_id, product_id)
+        // check that the product and the warehouse exist and get the stocks of the product
+        let (product, stocks) = self.get_product(product_id, client_id).await?;
+        check_not_parent(&product)?;
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
             .await?;
 
-        // compare the current quantity with the requested quantity
-        if has_available > stock.get_quantity() {
+        // compare the unreserved quantity with the requested quantity
+        let available: i32 = match product.is_bundle() {
+            true => {
+                self.bundle_available(client_id, &product, warehouse_id)
+                    .await?
+            }
+            false => stocks
+                .iter()
+                .filter(|stock| warehouse_id.is_none() || stock.warehouse_id == warehouse_id)
+                .map(|stock| stock.get_available())
+                .sum(),
+        };
+        if has_available > available {
             return Err(AppError::new(
                 "product quantity is less than requested number",
                 ErrorKind::FailedAction,
@@ -276,6 +911,7 @@ impl ProductService {
         &self,
         client_id: ObjectId,
         check_requests: Vec<ProductQuantityRequest>,
+        warehouse_id: Option<ObjectId>,
     ) -> Result<(), AppError> {
         // create a vector of checks to process
         let pq_vec = match self.check_products_and_convert_to_product_quantity_vector(
@@ -284,21 +920,99 @@ impl ProductService {
             Ok(orders) => orders,
             Err(err) => return Err(err),
         };
-
-        // if any product is not available in quantity, return an error
-        if let Err(err) = self.check_availability_many(client_id, &pq_vec).await {
-            return Err(err);
+
+        // if any product is not available in quantity, return an error
+        self.check_availability_many(client_id, &pq_vec, warehouse_id)
+            .await?;
+
+        // bundles take from the stock of their components, which must be available for every
+        // bundle and line they are part of at once
+        let components: Vec<ProductQuantity> = self
+            .expand_bundles(client_id, &pq_vec)
+            .await?
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect();
+        self.check_availability_many(client_id, &components, warehouse_id)
+            .await?;
+
+        Ok(())
+    }
+
+    // delete_product deletes a product and its stock from the database. The variants of a parent
+    // product are deleted with it
+    pub async fn delete_product(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let variants = self.get_variants(client_id, product_id).await?;
+        self.check_not_component(client_id, product_id).await?;
+        for variant in &variants {
+            self.check_not_component(client_id, variant._id).await?;
+        }
+
+        for variant in variants {
+            self.delete_product_and_stock(client_id, variant._id, source)
+                .await?;
+        }
+        self.delete_product_and_stock(client_id, product_id, source)
+            .await
+    }
+
+    // check_not_component fails for a product that bundles are made of, as the bundles could no
+    // longer be ordered without it
+    async fn check_not_component(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+    ) -> Result<(), AppError> {
+        let bundles = match self.product_repo.get_bundles(client_id, product_id).await {
+            Ok(bundles) => bundles,
+            Err(err) => {
+                error!("Error fetching the bundles of a product: {:?}", err);
+                return Err(AppError::new(
+                    "cannot fetch bundles",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+        if let Some(bundle) = bundles.first() {
+            return Err(AppError::new(
+                &format!(
+                    "product {} is a component of bundle {}, delete the bundle first",
+                    product_id.to_hex(),
+                    bundle._id.to_hex()
+                ),
+                ErrorKind::FailedAction,
+            ));
         }
-
         Ok(())
     }
 
-    // delete_product deletes a product and its stock from the database
-    pub async fn delete_product(
+    // delete_product_and_stock deletes a product and its stock at every location
+    async fn delete_product_and_stock(
         &self,
         client_id: ObjectId,
         product_id: ObjectId,
+        source: &MovementSource,
     ) -> Result<(), AppError> {
+        // the product is fetched first, so its deletion can be published
+        let product = match self.product_repo.get_by_id(client_id, product_id).await {
+            Ok(product) => product,
+            Err(err) => {
+                error!(
+                    "Error fetching product with id: {:?} and client_id: {:?}. Error: {:?}",
+                    product_id, client_id, err
+                );
+                return Err(AppError::new(
+                    "cannot delete product",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
         // delete the product from the database
         if let Err(err) = self.product_repo.delete_by_id(client_id, product_id).await {
             error!(
@@ -312,95 +1026,127 @@ impl ProductService {
         }
 
         // delete the stock from the database
-        if let Err(err) = self
+        let recorded = match self
             .stock_repo
             .delete_by_client_id_and_product_id(client_id, product_id)
             .await
         {
-            error!(
-                "Error deleting stock with client_id: {:?} and product_id: {:?}. Error: {:?}",
-                client_id, product_id, err
-            );
-            return Err(AppError::new(
-                "cannot delete stock",
-                ErrorKind::InternalServerError,
-            ));
+            // the whole quantity of every location goes with it, each location is recorded
+            // even when another one could not be
+            Ok(stocks) => {
+                let mut recorded = Ok(());
+                for mut stock in stocks {
+                    let delta = -stock.get_quantity();
+                    stock.set_quantity(0);
+                    if let Err(err) = self
+                        .record_movement(&stock, MovementReason::Delete, source, delta)
+                        .await
+                    {
+                        recorded = Err(err);
+                    }
+                }
+                recorded
+            }
+            Err(err) => {
+                error!(
+                    "Error deleting stock with client_id: {:?} and product_id: {:?}. Error: {:?}",
+                    client_id, product_id, err
+                );
+                return Err(AppError::new(
+                    "cannot delete stock",
+                    ErrorKind::InternalServerError,
+                ));
+            }
         };
 
-        Ok(())
+        if let Some(product) = product {
+            self.publish_product(WebhookEvent::ProductDeleted, &product)
+                .await;
+        }
+        recorded
     }
 
-    // process_orders checks that all orders are eligible to be processes then processes them
+    // process_orders checks that all orders are eligible to be processed then processes them.
+    // Each line is taken from the given location, or from as many locations as needed if none
+    // is given. Orders are all-or-nothing: if any line cannot be applied, the lines already
+    // applied are restocked so that no stock is changed. A bundle is taken out of the stock of
+    // its components. Returns the quantities taken out of stock and the locations they were
+    // taken from
     pub async fn process_orders(
         &self,
         client_id: ObjectId,
         order_requests: Vec<ProductQuantityRequest>,
-    ) -> Result<(), AppError> {
+        warehouse_id: Option<ObjectId>,
+        source: &MovementSource,
+    ) -> Result<Vec<AllocatedQuantity>, AppError> {
         // create a vector of orders to process
-        let orders = match self.check_products_and_convert_to_product_quantity_vector(
-            order_requests
-        ).await {
-            Ok(orders) => orders,
-            Err(err) => return Err(err),
-        };
-
-        // if any product is not available in quantity, return an error
-        if let Err(err) = self.check_availability_many(client_id, &orders).await {
-            return Err(err);
-        }
+        let orders = self
+            .check_products_and_convert_to_product_quantity_vector(order_requests)
+            .await?;
 
-        // create an empty list of unresolved futures for decrementing the quantity of products
-        let mut decrement_quantity_futs = Vec::with_capacity(orders.len());
-        // go ahead to decrement their counts since they're all available
+        // if any product is not available in quantity, return an error early naming the line
+        let mut check_availability_futs = Vec::with_capacity(orders.len());
         for order in &orders {
-            // push the asynchronous call to the list of unresolved futures
-            decrement_quantity_futs.push(self.decrement_quantity_by(
+            check_availability_futs.push(self.check_availability(
                 client_id,
                 order.product_id,
                 order.quantity,
+                warehouse_id,
             ));
         }
-        // ensure the decrements ran without error
-        if let Err(err) = future::try_join_all(decrement_quantity_futs).await {
-            return Err(err);
+        for (index, result) in future::join_all(check_availability_futs)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            if let Err(err) = result {
+                return Err(order_line_error(index, &orders[index], err));
+            }
         }
 
-        Ok(())
-    }
+        // bundles are taken out of the stock of their components
+        let lines = self.expand_bundles(client_id, &orders).await?;
 
-    // check_product_and_get_stock checks that a product exists and retrieves the stock
-    async fn check_product_and_get_stock(
-        &self,
-        client_id: ObjectId,
-        product_id: ObjectId,
-    ) -> Result<Stock, AppError> {
-        // check that the product exists
-        if let Err(err) = self.get_product(product_id, client_id).await {
-            return Err(err);
+        // create an empty list of unresolved futures for taking the quantity of products
+        let mut take_quantity_futs = Vec::with_capacity(lines.len());
+        // go ahead to take their counts. Each decrement re-checks the quantity atomically
+        // since another order may have taken the stock after the availability check
+        for (line, _) in &lines {
+            // push the asynchronous call to the list of unresolved futures
+            take_quantity_futs.push(self.take_quantity(
+                client_id,
+                line.product_id,
+                line.quantity,
+                warehouse_id,
+                source,
+            ));
         }
+        let results = future::join_all(take_quantity_futs).await;
 
-        // retrieve the stock for the product
-        match self
-            .stock_repo
-            .get_by_client_id_and_product_id(client_id, product_id)
-            .await
-        {
-            Ok(Some(stock)) => Ok(stock),
-            Ok(None) => return Err(AppError::new("stock not found", ErrorKind::NotFound)),
-            Err(_) => {
-                error!(
-                    "Error getting stock from client_id: {:?}, and product_id: {:?}",
-                    client_id, product_id
-                );
-                return Err(AppError::new(
-                    "cannot get product quantity",
-                    ErrorKind::InternalServerError,
-                ));
-            }
-        }
+        // find the first line that failed, if every decrement ran without error we are done
+        let failed_index = match results.iter().position(|result| result.is_err()) {
+            Some(index) => index,
+            None => return Ok(results.into_iter().flatten().collect()),
+        };
+
+        // undo the decrements that were applied before reporting the failed line
+        let applied: Vec<&AllocatedQuantity> =
+            results.iter().filter_map(|result| result.as_ref().ok()).collect();
+        self.restock_many(client_id, &applied, MovementReason::OrderRollback, source)
+            .await?;
+
+        let err = results
+            .into_iter()
+            .nth(failed_index)
+            .and_then(|result| result.err())
+            .unwrap_or_else(|| AppError::new("cannot process order", ErrorKind::InternalServerError));
+        let order_index = lines[failed_index].1;
+        Err(order_line_error(order_index, &orders[order_index], err))
     }
 
-    async fn check_products_and_convert_to_product_quantity_vector(
+    // check_products_and_convert_to_product_quantity_vector validates product quantity requests
+    // and converts them to product quantities, rejecting invalid ids, quantities and duplicates
+    pub async fn check_products_and_convert_to_product_quantity_vector(
         &self,
         pq_requests: Vec<ProductQuantityRequest>,
     ) -> Result<Vec<ProductQuantity>, AppError> {
@@ -430,10 +1176,21 @@ impl ProductService {
                 ));
             }
 
+            // the quantity of an order must be positive, otherwise it would add to the stock
+            if pq_request.quantity < 1 {
+                return Err(AppError::new(
+                    &format!(
+                        "quantity cannot be less than 1 for product_id: {:?}",
+                        pq.product_id
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+
             // set the quantity of the order
             pq.quantity = pq_request.quantity;
             // add the order's product_id to the set
-            seen_checks.insert(pq.product_id.clone());
+            seen_checks.insert(pq.product_id);
             // add the created order to the list of orders
             pq_vec.push(pq);
         }
@@ -441,7 +1198,12 @@ impl ProductService {
         Ok(pq_vec)
     }
 
-    async fn check_availability_many(&self, client_id: ObjectId, checks: &Vec<ProductQuantity>) -> Result<(), AppError> {
+    async fn check_availability_many(
+        &self,
+        client_id: ObjectId,
+        checks: &Vec<ProductQuantity>,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<(), AppError> {
         // create an empty list of unresolved futures for checking the availability of all orders
         let mut check_availability_futs = Vec::with_capacity(checks.len());
         // verify all products in the list have quantities requested, return error if a product is unavailable
@@ -451,68 +1213,666 @@ impl ProductService {
                 client_id,
                 check.product_id,
                 check.quantity,
+                warehouse_id,
             ));
         }
         // ensure the products in the orders are all available
-        if let Err(err) = future::try_join_all(check_availability_futs).await {
-            return Err(err);
-        }
+        future::try_join_all(check_availability_futs).await?;
 
         Ok(())
     }
 
-    // decrement_quantity_by decrements the quantity of a product in the stock by the given number
-    // should only be called when it is ensured that the product exists
-    async fn decrement_quantity_by(
+    // restock_many gives back quantities that were taken out of the stock to the locations they
+    // were taken from, such as the lines of an order that were applied when another line failed,
+    // or the lines of a cancelled order
+    pub async fn restock_many(
         &self,
         client_id: ObjectId,
-        product_id: ObjectId,
-        number: i32,
+        orders: &[&AllocatedQuantity],
+        reason: MovementReason,
+        source: &MovementSource,
     ) -> Result<(), AppError> {
-        let mut stock = match self
-            .stock_repo
-            .get_by_client_id_and_product_id(client_id, product_id)
+        let restocked = flatten_allocations(orders);
+        let (_, restock_failed, recorded) = self
+            .restock_allocations(client_id, &restocked, reason, source)
+            .await;
+
+        if restock_failed {
+            return Err(AppError::new(
+                "cannot restock products",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        recorded
+    }
+
+    // restock_all gives back quantities like restock_many, but either every quantity is given
+    // back or none is: if one cannot be, the quantities already given back are taken out again
+    pub async fn restock_all(
+        &self,
+        client_id: ObjectId,
+        orders: &[&AllocatedQuantity],
+        reason: MovementReason,
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let restocked = flatten_allocations(orders);
+        let (applied, restock_failed, recorded) = self
+            .restock_allocations(client_id, &restocked, reason, source)
+            .await;
+        if !restock_failed {
+            return recorded;
+        }
+
+        self.take_back_allocations(client_id, &applied, source)
+            .await?;
+        Err(AppError::new(
+            "cannot restock products",
+            ErrorKind::InternalServerError,
+        ))
+    }
+
+    // take_back_restocked takes quantities given back by restock_all out of the stock again,
+    // when the change they were given back for cannot be recorded
+    pub async fn take_back_restocked(
+        &self,
+        client_id: ObjectId,
+        orders: &[&AllocatedQuantity],
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let restocked = flatten_allocations(orders);
+        self.take_back_allocations(client_id, &restocked, source)
             .await
+    }
+
+    // restock_allocations gives back quantities to the locations they were taken from. Returns
+    // the quantities that were given back, whether one could not be given back, and whether
+    // every one given back was recorded
+    async fn restock_allocations<'a>(
+        &self,
+        client_id: ObjectId,
+        restocked: &[(ObjectId, &'a StockAllocation)],
+        reason: MovementReason,
+        source: &MovementSource,
+    ) -> (Vec<(ObjectId, &'a StockAllocation)>, bool, Result<(), AppError>) {
+        let mut increment_futs = Vec::with_capacity(restocked.len());
+        for (product_id, allocation) in restocked {
+            increment_futs.push(self.stock_repo.increment_or_insert(
+                client_id,
+                *product_id,
+                allocation.warehouse_id,
+                allocation.quantity,
+            ));
+        }
+
+        let mut applied = Vec::with_capacity(restocked.len());
+        let mut restock_failed = false;
+        let mut recorded = Ok(());
+        for (result, (product_id, allocation)) in
+            future::join_all(increment_futs).await.into_iter().zip(restocked)
         {
-            Ok(Some(stock)) => stock,
-            Ok(None) => return Err(AppError::new("stock not found", ErrorKind::NotFound)),
-            Err(_) => {
+            match result {
+                Ok(Some(stock)) => {
+                    if let Err(err) = self
+                        .record_movement(&stock, reason, source, allocation.quantity)
+                        .await
+                    {
+                        recorded = Err(err);
+                    }
+                    applied.push((*product_id, *allocation));
+                }
+                result => {
+                    restock_failed = true;
+                    error!(
+                        "Error restocking client_id: {:?}, product_id: {:?}, warehouse_id: {:?}, quantity: {}. Result: {:?}",
+                        client_id, product_id, allocation.warehouse_id, allocation.quantity, result
+                    );
+                }
+            }
+        }
+        (applied, restock_failed, recorded)
+    }
+
+    // take_back_allocations takes quantities that were given back out of the locations they were
+    // given back to. A quantity already taken by another request cannot be taken back, which is
+    // logged and reported
+    async fn take_back_allocations(
+        &self,
+        client_id: ObjectId,
+        restocked: &[(ObjectId, &StockAllocation)],
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let mut take_back_failed = false;
+        let mut recorded = Ok(());
+        for (product_id, allocation) in restocked {
+            match self
+                .stock_repo
+                .decrement_if_available(
+                    client_id,
+                    *product_id,
+                    allocation.warehouse_id,
+                    allocation.quantity,
+                )
+                .await
+            {
+                Ok(Some(stock)) => {
+                    if let Err(err) = self
+                        .record_movement(
+                            &stock,
+                            MovementReason::RestockRollback,
+                            source,
+                            -allocation.quantity,
+                        )
+                        .await
+                    {
+                        recorded = Err(err);
+                    }
+                }
+                result => {
+                    take_back_failed = true;
+                    error!(
+                        "Error taking back restocked client_id: {:?}, product_id: {:?}, warehouse_id: {:?}, quantity: {}. Result: {:?}",
+                        client_id, product_id, allocation.warehouse_id, allocation.quantity, result
+                    );
+                }
+            }
+        }
+
+        if take_back_failed {
+            return Err(AppError::new(
+                "cannot take back restocked products",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        recorded
+    }
+
+    // take_quantity takes the given number of a product out of the stock at a location,
+    // or out of as many locations as needed if none is given.
+    // should only be called when it is ensured that the product exists
+    async fn take_quantity(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+        warehouse_id: Option<ObjectId>,
+        source: &MovementSource,
+    ) -> Result<AllocatedQuantity, AppError> {
+        let allocated = self
+            .allocate(client_id, product_id, number, warehouse_id, StockHold::Take)
+            .await?;
+        let mut recorded = Ok(());
+        let mut taken = AllocatedQuantity {
+            product_id,
+            quantity: number,
+            allocations: Vec::with_capacity(allocated.len()),
+        };
+        let mut unrecorded = Vec::new();
+        for (stock, allocation) in allocated {
+            match self
+                .record_movement(&stock, MovementReason::Order, source, -allocation.quantity)
+                .await
+            {
+                Ok(()) => taken.allocations.push(allocation),
+                Err(err) => {
+                    recorded = Err(err);
+                    unrecorded.push(allocation);
+                }
+            }
+        }
+        let err = match recorded {
+            Ok(()) => return Ok(taken),
+            Err(err) => err,
+        };
+
+        // a failed line is not given back by the caller. What was taken without a record is
+        // given back without one, so the movements still add up to the quantity
+        for allocation in unrecorded {
+            if let Err(err) = self
+                .stock_repo
+                .increment_or_insert(
+                    client_id,
+                    product_id,
+                    allocation.warehouse_id,
+                    allocation.quantity,
+                )
+                .await
+            {
                 error!(
-                    "Error getting stock from client_id: {:?}, and product_id: {:?}",
-                    client_id, product_id
+                    "Error giving back unrecorded client_id: {:?}, product_id: {:?}, allocation: {:?}. Error: {:?}",
+                    client_id, product_id, allocation, err
                 );
-                return Err(AppError::new(
-                    "cannot get product quantity",
-                    ErrorKind::InternalServerError,
-                ));
+            }
+        }
+        self.restock_many(client_id, &[&taken], MovementReason::OrderRollback, source)
+            .await?;
+        Err(err)
+    }
+
+    // reserve_quantity holds the given number of a product for a reservation at a location,
+    // or at as many locations as needed if none is given
+    pub async fn reserve_quantity(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<AllocatedQuantity, AppError> {
+        let allocated = self
+            .allocate(client_id, product_id, number, warehouse_id, StockHold::Reserve)
+            .await?;
+
+        Ok(AllocatedQuantity {
+            product_id,
+            quantity: number,
+            allocations: allocated.into_iter().map(|(_, allocation)| allocation).collect(),
+        })
+    }
+
+    // allocate holds the given number of a product at a location, or spreads it over as many
+    // locations as needed if none is given, starting with the location that has the most
+    // available. Every hold is an atomic conditional write, so concurrent callers cannot take
+    // more than is available. Either the whole number is held or nothing is
+    async fn allocate(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        number: i32,
+        warehouse_id: Option<ObjectId>,
+        hold: StockHold,
+    ) -> Result<Vec<(Stock, StockAllocation)>, AppError> {
+        // the locations to try and the most each can give
+        let candidates: Vec<(Option<ObjectId>, i32)> = match warehouse_id {
+            Some(_) => vec![(warehouse_id, number)],
+            None => {
+                let mut stocks = self.get_stocks(client_id, product_id).await?;
+                stocks.sort_by_key(|stock| Reverse(stock.get_available()));
+                stocks
+                    .iter()
+                    .filter(|stock| stock.get_available() > 0)
+                    .map(|stock| (stock.warehouse_id, stock.get_available()))
+                    .collect()
             }
         };
 
-        stock.set_quantity(stock.get_quantity() - number);
-        if stock.get_quantity() < 0 {
+        let mut allocated: Vec<(Stock, StockAllocation)> = vec![];
+        let mut remaining = number;
+        for (location, available) in candidates {
+            if remaining == 0 {
+                break;
+            }
+            let quantity = remaining.min(available);
+            let result = match hold {
+                StockHold::Take => {
+                    self.stock_repo
+                        .decrement_if_available(client_id, product_id, location, quantity)
+                        .await
+                }
+                StockHold::Reserve => {
+                    self.stock_repo
+                        .reserve_if_available(client_id, product_id, location, quantity)
+                        .await
+                }
+            };
+            match result {
+                Ok(Some(stock)) => {
+                    remaining -= quantity;
+                    allocated.push((
+                        stock,
+                        StockAllocation {
+                            warehouse_id: location,
+                            quantity,
+                        },
+                    ));
+                }
+                // another request took the stock since it was read, try the next location
+                Ok(None) => {}
+                Err(err) => {
+                    error!(
+                        "Error allocating stock with client_id: {:?}, product_id: {:?}, warehouse_id: {:?}. Error: {:?}",
+                        client_id, product_id, location, err
+                    );
+                    self.deallocate(client_id, product_id, &allocated, hold)
+                        .await;
+                    return Err(AppError::new(
+                        "cannot decrement quantity",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            }
+        }
+
+        // the stock is missing or holds less than the number requested
+        if remaining > 0 {
+            self.deallocate(client_id, product_id, &allocated, hold)
+                .await;
             return Err(AppError::new(
                 "product is low in stock",
                 ErrorKind::FailedAction,
             ));
         }
 
-        let modified_count = match self.stock_repo.update(&stock).await {
-            Ok(result) => result.modified_count,
-            Err(_) => {
-                return Err(AppError::new(
-                    "cannot set quantity",
+        Ok(allocated)
+    }
+
+    // deallocate gives back the holds of an allocation that could not be completed. Nothing was
+    // recorded for the holds yet, so a failure to give one back is logged
+    async fn deallocate(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        allocated: &[(Stock, StockAllocation)],
+        hold: StockHold,
+    ) {
+        for (_, allocation) in allocated {
+            let result = match hold {
+                StockHold::Take => {
+                    self.stock_repo
+                        .increment_or_insert(
+                            client_id,
+                            product_id,
+                            allocation.warehouse_id,
+                            allocation.quantity,
+                        )
+                        .await
+                }
+                StockHold::Reserve => {
+                    self.stock_repo
+                        .release_reserved(
+                            client_id,
+                            product_id,
+                            allocation.warehouse_id,
+                            allocation.quantity,
+                        )
+                        .await
+                }
+            };
+            if !matches!(result, Ok(Some(_))) {
+                error!(
+                    "Error giving back {:?} of client_id: {:?}, product_id: {:?}, allocation: {:?}. Result: {:?}",
+                    hold, client_id, product_id, allocation, result
+                );
+            }
+        }
+    }
+
+    // get_movements gets a page of the stock movements of a product, the most recent first,
+    // and the total number of movements of the product
+    pub async fn get_movements(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        page: u64,
+        limit: i64,
+    ) -> Result<(Vec<StockMovement>, u64), AppError> {
+        // check that the product exists
+        self.get_product(product_id, client_id).await?;
+
+        let skip = (page - 1).saturating_mul(limit as u64);
+        let movements = self
+            .stock_movement_repo
+            .get_by_product_id(client_id, product_id, skip, limit);
+        let total = self
+            .stock_movement_repo
+            .count_by_product_id(client_id, product_id);
+        match future::try_join(movements, total).await {
+            Ok(movements_total) => Ok(movements_total),
+            Err(err) => {
+                error!("Error fetching stock movements: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch stock movements",
                     ErrorKind::InternalServerError,
                 ))
             }
-        };
-        if modified_count == 0 {
-            // if no document was modified, return an error
+        }
+    }
+
+    // record_movement appends a change to the quantity of a stock to the stock movements, and
+    // takes quantity out of the bins of the warehouse when they hold more than is left.
+    // A change that cannot be recorded is not published, and the error is returned so the
+    // caller can undo the change or report it
+    pub async fn record_movement(
+        &self,
+        stock: &Stock,
+        reason: MovementReason,
+        source: &MovementSource,
+        delta: i32,
+    ) -> Result<(), AppError> {
+        let mut movement = StockMovement::new(
+            stock.client_id,
+            stock.product_id,
+            stock.warehouse_id,
+            reason,
+            source,
+            delta,
+            stock.get_quantity(),
+        );
+        if delta < 0 {
+            self.bin_service.fit_to_stock(stock).await;
+        }
+        if let Err(err) = self.stock_movement_repo.insert(&mut movement).await {
+            error!("Error recording stock movement: {:?}. Error: {:?}", movement, err);
             return Err(AppError::new(
-                "cannot decrement quantity",
+                "cannot record stock movement",
                 ErrorKind::InternalServerError,
             ));
         }
-
+        self.webhook_service
+            .publish(
+                stock.client_id,
+                WebhookEvent::QuantityChanged,
+                &StockMovementResponse::new(&movement),
+            )
+            .await;
+        if delta != 0 {
+            self.alert_service.check_stock(stock, &movement).await;
+        }
         Ok(())
     }
+
+    // publish_product tells the webhooks of the client of a product that it was created,
+    // updated or deleted
+    async fn publish_product(&self, event: WebhookEvent, product: &Product) {
+        self.webhook_service
+            .publish(
+                product.get_created_by(),
+                event,
+                &ProductEventData::new(product),
+            )
+            .await;
+    }
+}
+
+// flatten_allocations lists the allocations of quantities of products with their product
+fn flatten_allocations<'a>(
+    orders: &[&'a AllocatedQuantity],
+) -> Vec<(ObjectId, &'a StockAllocation)> {
+    orders
+        .iter()
+        .flat_map(|order| {
+            order
+                .allocations
+                .iter()
+                .map(move |allocation| (order.product_id, allocation))
+        })
+        .collect()
+}
+
+// order_line_error describes which line of an order failed. Lines are numbered from 1
+// and the error confirms that the order did not change any stock
+fn order_line_error(index: usize, order: &ProductQuantity, err: AppError) -> AppError {
+    AppError::new(
+        &format!(
+            "order line {} with product_id: {} failed: {}. no stock was changed",
+            index + 1,
+            order.product_id.to_hex(),
+            err.message
+        ),
+        err.kind,
+    )
+}
+
+// check_not_bundle fails for a bundle, whose stock is held by its components. The stock of the
+// components is changed instead
+fn check_not_bundle(product: &Product) -> Result<(), AppError> {
+    if product.is_bundle() {
+        return Err(AppError::new(
+            "product is a bundle, change the stock of its components",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+// check_reorder_settings fails for a parent product or a bundle with a reorder point, as
+// neither holds stock. Their variants and components can have one
+fn check_reorder_settings(product: &Product) -> Result<(), AppError> {
+    if product.reorder_point.is_none() && product.reorder_quantity.is_none() {
+        return Ok(());
+    }
+    if product.is_parent() {
+        return Err(AppError::new(
+            "a product with variants cannot have a reorder point, its variants can",
+            ErrorKind::FailedAction,
+        ));
+    }
+    if product.is_bundle() {
+        return Err(AppError::new(
+            "a bundle cannot have a reorder point, its components can",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+// check_not_parent fails for a parent product, whose stock is held by its variants. Stock is
+// changed, checked and ordered through the variants
+fn check_not_parent(product: &Product) -> Result<(), AppError> {
+    if product.is_parent() {
+        return Err(AppError::new(
+            "product has variants, use the id of one of its variants",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use bson::oid::ObjectId;
+
+    use crate::{
+        model::{product::Product, sku::SkuStrategy, stock_movement::MovementSource},
+        repository::stores::Stores,
+        server::ServiceManager,
+    };
+
+    #[test]
+    // test_create_retries_sku_collisions tests that a product whose generated sku the client
+    // already uses is created with the next sku, and that a supplied sku is never replaced
+    async fn test_create_retries_sku_collisions() {
+        let service_manager = ServiceManager::new(&Stores::memory());
+        let product_service = service_manager.product_service;
+        let source = MovementSource::new("test", None);
+        let client_id = ObjectId::new();
+        service_manager
+            .sku_service
+            .set_settings(
+                client_id,
+                SkuStrategy::Sequential {
+                    prefix: "ACME".to_string(),
+                    width: 6,
+                },
+            )
+            .await
+            .unwrap();
+
+        let widget = || {
+            Product::new(
+                "Widget".to_string(),
+                "A widget".to_string(),
+                None,
+                client_id,
+            )
+        };
+        let mut supplied = widget();
+        product_service
+            .create(
+                &mut supplied,
+                client_id,
+                Some("ACME-000001".to_string()),
+                1,
+                None,
+                &source,
+            )
+            .await
+            .unwrap();
+
+        let mut generated = widget();
+        product_service
+            .create(&mut generated, client_id, None, 1, None, &source)
+            .await
+            .unwrap();
+        assert_eq!(generated.get_sku(), "ACME-000002");
+
+        let err = product_service
+            .create(
+                &mut widget(),
+                client_id,
+                Some("ACME-000002".to_string()),
+                1,
+                None,
+                &source,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "sku is already used by another product");
+    }
+
+    #[test]
+    // test_create_with_category_skus tests that category skus use the code of the category, or
+    // the first letters of a category without a code
+    async fn test_create_with_category_skus() {
+        let service_manager = ServiceManager::new(&Stores::memory());
+        let product_service = service_manager.product_service;
+        let source = MovementSource::new("test", None);
+        let client_id = ObjectId::new();
+        let codes = [("Electronics".to_string(), "EL".to_string())];
+        service_manager
+            .sku_service
+            .set_settings(
+                client_id,
+                SkuStrategy::Category {
+                    codes: codes.into_iter().collect(),
+                    width: 4,
+                },
+            )
+            .await
+            .unwrap();
+
+        // (category, expected sku)
+        let test_cases = vec![
+            (Some("Electronics"), Ok("EL-0001")),
+            (Some("Electronics"), Ok("EL-0002")),
+            (Some("garden tools"), Ok("GAR-0001")),
+            (Some("--"), Err("category cannot make a sku code")),
+            (None, Err("category is required to generate a sku")),
+        ];
+        for (category, expected) in test_cases {
+            let mut product = Product::new(
+                "Widget".to_string(),
+                "A widget".to_string(),
+                category.map(str::to_string),
+                client_id,
+            );
+            let result = product_service
+                .create(&mut product, client_id, None, 1, None, &source)
+                .await
+                .map(|_| product.get_sku())
+                .map_err(|err| err.message);
+            assert_eq!(result, expected.map(str::to_string).map_err(str::to_string));
+        }
+    }
 }
@@ -0,0 +1,275 @@
+use bson::oid::ObjectId;
+use futures::future;
+use log::{error, warn};
+use std::sync::Arc;
+
+use crate::{
+    dto::product::product_dto::ProductQuantityRequest,
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        purchase_order::{PurchaseOrder, PurchaseOrderStatus},
+        stock::{AllocatedQuantity, StockAllocation},
+        stock_movement::{MovementReason, MovementSource},
+    },
+    repository::purchase_order_repo::PurchaseOrderStore,
+    service::{product_service::ProductService, warehouse_service::WarehouseService},
+};
+
+#[derive(Clone)]
+pub struct PurchaseOrderService {
+    purchase_order_repo: Arc<dyn PurchaseOrderStore>,
+    product_service: ProductService,
+    warehouse_service: WarehouseService,
+}
+
+impl PurchaseOrderService {
+    // new creates a new purchase order service instance
+    pub fn new(
+        purchase_order_repo: Arc<dyn PurchaseOrderStore>,
+        product_service: ProductService,
+        warehouse_service: WarehouseService,
+    ) -> PurchaseOrderService {
+        PurchaseOrderService {
+            purchase_order_repo,
+            product_service,
+            warehouse_service,
+        }
+    }
+
+    // create records the goods a client expects from a supplier, to be received at the given
+    // location or not assigned to a warehouse if none is given. Only products holding stock can
+    // be ordered
+    pub async fn create(
+        &self,
+        client_id: ObjectId,
+        reference: Option<String>,
+        warehouse_id: Option<ObjectId>,
+        line_requests: Vec<ProductQuantityRequest>,
+    ) -> Result<PurchaseOrder, AppError> {
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
+            .await?;
+        let lines = self
+            .product_service
+            .check_products_and_convert_to_product_quantity_vector(line_requests)
+            .await?;
+        let mut check_product_futs = Vec::with_capacity(lines.len());
+        for line in &lines {
+            check_product_futs.push(
+                self.product_service
+                    .check_stocked_product(client_id, line.product_id),
+            );
+        }
+        future::try_join_all(check_product_futs).await?;
+
+        let purchase_order = PurchaseOrder::new(client_id, reference, warehouse_id, &lines);
+        if let Err(err) = self.purchase_order_repo.insert(&purchase_order).await {
+            error!("Error inserting purchase order: {:?}", err);
+            return Err(AppError::new(
+                "cannot create purchase order",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(purchase_order)
+    }
+
+    // get_purchase_order gets a purchase order of a client from the application storage
+    pub async fn get_purchase_order(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+    ) -> Result<PurchaseOrder, AppError> {
+        match self
+            .purchase_order_repo
+            .get_by_id(client_id, purchase_order_id)
+            .await
+        {
+            Ok(Some(purchase_order)) => Ok(purchase_order),
+            Ok(None) => Err(AppError::new(
+                "purchase order not found",
+                ErrorKind::NotFound,
+            )),
+            Err(err) => {
+                error!("Error fetching a purchase order: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch purchase order",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_purchase_orders_by_client gets the purchase orders of a client, optionally only those
+    // in one status
+    pub async fn get_purchase_orders_by_client(
+        &self,
+        client_id: ObjectId,
+        status: Option<PurchaseOrderStatus>,
+    ) -> Result<Vec<PurchaseOrder>, AppError> {
+        match self
+            .purchase_order_repo
+            .get_by_client_id(client_id, status)
+            .await
+        {
+            Ok(purchase_orders) => Ok(purchase_orders),
+            Err(err) => {
+                error!("Error fetching purchase orders: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch purchase orders",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // receive records the quantities that actually arrived for a purchase order, which can be
+    // more or fewer than expected, and adds them to the stock at the given location or at the
+    // location of the purchase order if none is given. The purchase order is closed once every
+    // line was received, or when the delivery is the last one
+    pub async fn receive(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+        received: Vec<ProductQuantityRequest>,
+        warehouse_id: Option<ObjectId>,
+        last: bool,
+        actor: &str,
+    ) -> Result<PurchaseOrder, AppError> {
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
+            .await?;
+
+        let mut purchase_order = self
+            .get_purchase_order(client_id, purchase_order_id)
+            .await?;
+        if purchase_order.status == PurchaseOrderStatus::Closed {
+            return Err(AppError::new(
+                "purchase order is closed",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        // only the products of the purchase order can be received, and they must still exist
+        let received = self
+            .product_service
+            .check_products_and_convert_to_product_quantity_vector(received)
+            .await?;
+        if let Some(line) = received
+            .iter()
+            .find(|line| purchase_order.get_line(line.product_id).is_none())
+        {
+            return Err(AppError::new(
+                &format!(
+                    "purchase order has no line with product_id: {}",
+                    line.product_id.to_hex()
+                ),
+                ErrorKind::FailedAction,
+            ));
+        }
+        let mut get_product_futs = Vec::with_capacity(received.len());
+        for line in &received {
+            get_product_futs.push(self.product_service.get_product(line.product_id, client_id));
+        }
+        future::try_join_all(get_product_futs).await?;
+
+        // record the delivery first, so that it cannot be received twice
+        let warehouse_id = warehouse_id.or(purchase_order.warehouse_id);
+        let read_version = purchase_order.version;
+        purchase_order.receive(&received, warehouse_id, actor, last);
+        self.save_if_unchanged(&purchase_order, read_version)
+            .await?;
+        if purchase_order.status == PurchaseOrderStatus::Closed {
+            for line in purchase_order
+                .lines
+                .iter()
+                .filter(|line| line.discrepancy() != 0)
+            {
+                warn!(
+                    "Purchase order {} closed with a discrepancy of {} for product {}",
+                    purchase_order._id,
+                    line.discrepancy(),
+                    line.product_id
+                );
+            }
+        }
+
+        // add the received quantities to the stock, the movements reference the purchase order
+        let source = MovementSource::new(actor, Some(purchase_order_id.to_hex()));
+        let restocked: Vec<AllocatedQuantity> = received
+            .iter()
+            .map(|line| AllocatedQuantity {
+                product_id: line.product_id,
+                quantity: line.quantity,
+                allocations: vec![StockAllocation {
+                    warehouse_id,
+                    quantity: line.quantity,
+                }],
+            })
+            .collect();
+        let restocked: Vec<&AllocatedQuantity> = restocked.iter().collect();
+        self.product_service
+            .restock_many(
+                client_id,
+                &restocked,
+                MovementReason::PurchaseReceipt,
+                &source,
+            )
+            .await?;
+
+        Ok(purchase_order)
+    }
+
+    // close marks a purchase order as done without receiving what is still expected
+    pub async fn close(
+        &self,
+        client_id: ObjectId,
+        purchase_order_id: ObjectId,
+    ) -> Result<PurchaseOrder, AppError> {
+        let mut purchase_order = self
+            .get_purchase_order(client_id, purchase_order_id)
+            .await?;
+        if purchase_order.status == PurchaseOrderStatus::Closed {
+            return Err(AppError::new(
+                "purchase order is closed",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        let read_version = purchase_order.version;
+        purchase_order.close();
+        self.save_if_unchanged(&purchase_order, read_version)
+            .await?;
+        Ok(purchase_order)
+    }
+
+    // save_if_unchanged saves a purchase order, failing with a conflict if it was changed by
+    // another request since it was read at the given version
+    async fn save_if_unchanged(
+        &self,
+        purchase_order: &PurchaseOrder,
+        read_version: i32,
+    ) -> Result<(), AppError> {
+        match self
+            .purchase_order_repo
+            .replace_if_unchanged(purchase_order, read_version)
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::new(
+                "purchase order was changed by another request, try again",
+                ErrorKind::Conflict,
+            )),
+            Err(err) => {
+                error!(
+                    "Error updating purchase order: {:?}. Error: {:?}",
+                    purchase_order._id, err
+                );
+                Err(AppError::new(
+                    "cannot update purchase order",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+}
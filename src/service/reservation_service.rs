@@ -0,0 +1,539 @@
+use bson::{oid::ObjectId, DateTime};
+use futures::future;
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::{
+    dto::product::product_dto::{ProductQuantity, ProductQuantityRequest},
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        order::Order,
+        reservation::{Reservation, ReservationStatus},
+        stock::{AllocatedQuantity, StockAllocation},
+        stock_movement::{MovementReason, MovementSource},
+    },
+    repository::{reservation_repo::ReservationStore, stock_repo::StockStore},
+    service::{order_service::OrderService, product_service::ProductService},
+};
+
+#[derive(Clone)]
+pub struct ReservationService {
+    reservation_repo: Arc<dyn ReservationStore>,
+    stock_repo: Arc<dyn StockStore>,
+    product_service: ProductService,
+    order_service: OrderService,
+    // default_ttl_seconds is how long a reservation holds stock when no ttl is requested
+    default_ttl_seconds: i64,
+}
+
+impl ReservationService {
+    // new creates a new reservation service instance
+    pub fn new(
+        reservation_repo: Arc<dyn ReservationStore>,
+        stock_repo: Arc<dyn StockStore>,
+        product_service: ProductService,
+        order_service: OrderService,
+        default_ttl_seconds: i64,
+    ) -> ReservationService {
+        ReservationService {
+            reservation_repo,
+            stock_repo,
+            product_service,
+            order_service,
+            default_ttl_seconds,
+        }
+    }
+
+    // reserve holds the quantities of the reservation lines against the stock at a location,
+    // or across locations if none is given, until the reservation expires.
+    // Either every line is held or none is
+    pub async fn reserve(
+        &self,
+        client_id: ObjectId,
+        reservation_requests: Vec<ProductQuantityRequest>,
+        ttl_seconds: Option<i64>,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<Reservation, AppError> {
+        // create a vector of lines to hold
+        let lines = self
+            .product_service
+            .check_products_and_convert_to_product_quantity_vector(reservation_requests)
+            .await?;
+
+        // if any product is not available in quantity, return an error early naming the line
+        let mut check_availability_futs = Vec::with_capacity(lines.len());
+        for line in &lines {
+            check_availability_futs.push(self.product_service.check_availability(
+                client_id,
+                line.product_id,
+                line.quantity,
+                warehouse_id,
+            ));
+        }
+        for (index, result) in future::join_all(check_availability_futs)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            if let Err(err) = result {
+                return Err(reservation_line_error(index, &lines[index], err));
+            }
+        }
+
+        // bundles are held in the stock of their components
+        let held_lines = self
+            .product_service
+            .expand_bundles(client_id, &lines)
+            .await?;
+
+        // hold the quantities. Each hold re-checks the quantity atomically
+        let mut reserve_futs = Vec::with_capacity(held_lines.len());
+        for (line, _) in &held_lines {
+            reserve_futs.push(self.product_service.reserve_quantity(
+                client_id,
+                line.product_id,
+                line.quantity,
+                warehouse_id,
+            ));
+        }
+        let results = future::join_all(reserve_futs).await;
+
+        // if any line could not be held, give back the lines that were held
+        if let Some(failed_index) = results.iter().position(|result| result.is_err()) {
+            let held: Vec<&AllocatedQuantity> = results
+                .iter()
+                .filter_map(|result| result.as_ref().ok())
+                .collect();
+            self.release_many(client_id, &held).await?;
+
+            let err = results
+                .into_iter()
+                .nth(failed_index)
+                .and_then(|result| result.err())
+                .unwrap_or_else(|| {
+                    AppError::new("cannot reserve stock", ErrorKind::InternalServerError)
+                });
+            let line_index = held_lines[failed_index].1;
+            return Err(reservation_line_error(line_index, &lines[line_index], err));
+        }
+        let held: Vec<AllocatedQuantity> = results.into_iter().flatten().collect();
+
+        // record the reservation, and give the stock back if it cannot be recorded
+        let ttl_seconds = ttl_seconds.unwrap_or(self.default_ttl_seconds);
+        let reservation = Reservation::new(client_id, &held, ttl_seconds);
+        if let Err(err) = self.reservation_repo.insert(&reservation).await {
+            error!("Error inserting reservation: {:?}", err);
+            let held: Vec<&AllocatedQuantity> = held.iter().collect();
+            self.release_many(client_id, &held).await?;
+            return Err(AppError::new(
+                "cannot reserve stock",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(reservation)
+    }
+
+    // get_reservation gets a reservation of a client from the application storage
+    pub async fn get_reservation(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+    ) -> Result<Reservation, AppError> {
+        match self
+            .reservation_repo
+            .get_by_id(client_id, reservation_id)
+            .await
+        {
+            Ok(Some(reservation)) => Ok(reservation),
+            Ok(None) => Err(AppError::new("reservation not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a reservation: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch reservation",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // commit takes the quantities held by an active reservation out of the stock
+    // and records them as an order
+    pub async fn commit(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        actor: &str,
+    ) -> Result<(Reservation, Order), AppError> {
+        // only one caller can move the reservation out of active, and not once it has expired, as
+        // the expiry sweep gives its stock back. No stock is taken before the reservation is
+        // committed, so a commit that loses to the sweep has nothing to give back
+        let reservation = match self
+            .reservation_repo
+            .commit_if_unexpired(client_id, reservation_id, DateTime::now())
+            .await
+        {
+            Ok(Some(reservation)) => reservation,
+            Ok(None) => {
+                let reservation = self.get_reservation(client_id, reservation_id).await?;
+                // an expired reservation no longer holds stock, release it if the sweep has not yet
+                if reservation.status == ReservationStatus::Active && reservation.is_expired() {
+                    self.expire(&reservation).await?;
+                    return Err(AppError::new(
+                        "reservation has expired",
+                        ErrorKind::FailedAction,
+                    ));
+                }
+                return Err(AppError::new(
+                    "reservation is not active",
+                    ErrorKind::FailedAction,
+                ));
+            }
+            Err(err) => {
+                error!(
+                    "Error committing reservation: {:?}. Error: {:?}",
+                    reservation_id, err
+                );
+                return Err(AppError::new(
+                    "cannot update reservation",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        // take the held quantities out of the stock, the movements reference the reservation
+        let source = MovementSource::new(actor, Some(reservation_id.to_hex()));
+        let lines = reservation.to_allocated_quantities();
+        let committed: Vec<(ObjectId, &StockAllocation)> = lines
+            .iter()
+            .flat_map(|line| {
+                line.allocations
+                    .iter()
+                    .map(move |allocation| (line.product_id, allocation))
+            })
+            .collect();
+        let mut commit_futs = Vec::with_capacity(committed.len());
+        for (product_id, allocation) in &committed {
+            commit_futs.push(self.stock_repo.commit_reserved(
+                client_id,
+                *product_id,
+                allocation.warehouse_id,
+                allocation.quantity,
+            ));
+        }
+        let mut applied: Vec<(ObjectId, &StockAllocation)> = Vec::with_capacity(committed.len());
+        let mut commit_failed = false;
+        let mut recorded = Ok(());
+        for (result, (product_id, allocation)) in future::join_all(commit_futs)
+            .await
+            .into_iter()
+            .zip(&committed)
+        {
+            match result {
+                Ok(Some(stock)) => {
+                    if let Err(err) = self
+                        .product_service
+                        .record_movement(
+                            &stock,
+                            MovementReason::ReservationCommit,
+                            &source,
+                            -allocation.quantity,
+                        )
+                        .await
+                    {
+                        recorded = Err(err);
+                    }
+                    applied.push((*product_id, allocation));
+                }
+                result => {
+                    commit_failed = true;
+                    error!(
+                        "Error committing reservation: {:?}, client_id: {:?}, product_id: {:?}, allocation: {:?}. Result: {:?}",
+                        reservation_id, client_id, product_id, allocation, result
+                    );
+                }
+            }
+        }
+        if commit_failed {
+            self.uncommit(client_id, reservation_id, &applied, &source)
+                .await?;
+            return Err(AppError::new(
+                "cannot commit reservation",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        // record the committed quantities as an order and link it to the reservation
+        let order = match self
+            .order_service
+            .record_order(ObjectId::new(), client_id, &lines)
+            .await
+        {
+            Ok(order) => order,
+            Err(err) => {
+                self.uncommit(client_id, reservation_id, &applied, &source)
+                    .await?;
+                return Err(err);
+            }
+        };
+        let reservation = match self
+            .reservation_repo
+            .set_order_id(client_id, reservation_id, order._id)
+            .await
+        {
+            Ok(Some(reservation)) => reservation,
+            result => {
+                error!(
+                    "Error linking order: {:?} to reservation: {:?}. Result: {:?}",
+                    order._id, reservation_id, result
+                );
+                reservation
+            }
+        };
+
+        // the commit stands when a movement of it could not be recorded, but it is reported
+        recorded?;
+        Ok((reservation, order))
+    }
+
+    // release gives the quantities held by an active reservation back to the stock
+    pub async fn release(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+    ) -> Result<Reservation, AppError> {
+        let reservation = self
+            .transition(
+                client_id,
+                reservation_id,
+                ReservationStatus::Active,
+                ReservationStatus::Released,
+            )
+            .await?;
+
+        let lines = reservation.to_allocated_quantities();
+        let held: Vec<&AllocatedQuantity> = lines.iter().collect();
+        self.release_many(client_id, &held).await?;
+
+        Ok(reservation)
+    }
+
+    // release_expired gives back the stock held by every active reservation past its expiry
+    pub async fn release_expired(&self) -> Result<(), AppError> {
+        let reservations = match self.reservation_repo.get_expired(DateTime::now()).await {
+            Ok(reservations) => reservations,
+            Err(err) => {
+                error!("Error fetching expired reservations: {:?}", err);
+                return Err(AppError::new(
+                    "cannot release expired reservations",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        for reservation in &reservations {
+            self.expire(reservation).await?;
+        }
+
+        if !reservations.is_empty() {
+            info!("Released {} expired reservations", reservations.len());
+        }
+
+        Ok(())
+    }
+
+    // expire moves an active reservation to expired and gives its stock back.
+    // Does nothing if another caller already moved the reservation out of active
+    async fn expire(&self, reservation: &Reservation) -> Result<(), AppError> {
+        let expired = match self
+            .reservation_repo
+            .transition(
+                reservation.client_id,
+                reservation._id,
+                ReservationStatus::Active,
+                ReservationStatus::Expired,
+            )
+            .await
+        {
+            Ok(Some(expired)) => expired,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                error!(
+                    "Error expiring reservation: {:?}. Error: {:?}",
+                    reservation._id, err
+                );
+                return Err(AppError::new(
+                    "cannot release expired reservations",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        let lines = expired.to_allocated_quantities();
+        let held: Vec<&AllocatedQuantity> = lines.iter().collect();
+        self.release_many(expired.client_id, &held).await
+    }
+
+    // uncommit undoes a commit that failed part way: the quantities already taken out of the
+    // stock are given back and held again, and the reservation is active again so that it can
+    // still be committed, released or expire
+    async fn uncommit(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        committed: &[(ObjectId, &StockAllocation)],
+        source: &MovementSource,
+    ) -> Result<(), AppError> {
+        let mut uncommit_failed = false;
+        let mut recorded = Ok(());
+        for (product_id, allocation) in committed {
+            match self
+                .stock_repo
+                .uncommit_reserved(
+                    client_id,
+                    *product_id,
+                    allocation.warehouse_id,
+                    allocation.quantity,
+                )
+                .await
+            {
+                Ok(Some(stock)) => {
+                    if let Err(err) = self
+                        .product_service
+                        .record_movement(
+                            &stock,
+                            MovementReason::ReservationCommitRollback,
+                            source,
+                            allocation.quantity,
+                        )
+                        .await
+                    {
+                        recorded = Err(err);
+                    }
+                }
+                result => {
+                    uncommit_failed = true;
+                    error!(
+                        "Error undoing commit of reservation: {:?}, client_id: {:?}, product_id: {:?}, allocation: {:?}. Result: {:?}",
+                        reservation_id, client_id, product_id, allocation, result
+                    );
+                }
+            }
+        }
+        if uncommit_failed {
+            return Err(AppError::new(
+                "cannot commit reservation",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        self.transition(
+            client_id,
+            reservation_id,
+            ReservationStatus::Committed,
+            ReservationStatus::Active,
+        )
+        .await?;
+        recorded
+    }
+
+    // transition moves a reservation between statuses, failing if it is not in the expected status
+    async fn transition(
+        &self,
+        client_id: ObjectId,
+        reservation_id: ObjectId,
+        from: ReservationStatus,
+        to: ReservationStatus,
+    ) -> Result<Reservation, AppError> {
+        match self
+            .reservation_repo
+            .transition(client_id, reservation_id, from, to)
+            .await
+        {
+            Ok(Some(reservation)) => Ok(reservation),
+            Ok(None) => {
+                // tell apart a missing reservation from one that is no longer active
+                self.get_reservation(client_id, reservation_id).await?;
+                Err(AppError::new(
+                    "reservation is not active",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            Err(err) => {
+                error!(
+                    "Error updating reservation: {:?}. Error: {:?}",
+                    reservation_id, err
+                );
+                Err(AppError::new(
+                    "cannot update reservation",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // release_many gives back the quantities held for reservations to the stock
+    // at the locations they are held at
+    async fn release_many(
+        &self,
+        client_id: ObjectId,
+        lines: &[&AllocatedQuantity],
+    ) -> Result<(), AppError> {
+        let released: Vec<(ObjectId, &StockAllocation)> = lines
+            .iter()
+            .flat_map(|line| {
+                line.allocations
+                    .iter()
+                    .map(move |allocation| (line.product_id, allocation))
+            })
+            .collect();
+
+        let mut release_futs = Vec::with_capacity(released.len());
+        for (product_id, allocation) in &released {
+            release_futs.push(self.stock_repo.release_reserved(
+                client_id,
+                *product_id,
+                allocation.warehouse_id,
+                allocation.quantity,
+            ));
+        }
+
+        let mut release_failed = false;
+        for (result, (product_id, allocation)) in future::join_all(release_futs)
+            .await
+            .into_iter()
+            .zip(&released)
+        {
+            if !matches!(result, Ok(Some(_))) {
+                release_failed = true;
+                error!(
+                    "Error releasing reserved stock client_id: {:?}, product_id: {:?}, allocation: {:?}. Result: {:?}",
+                    client_id, product_id, allocation, result
+                );
+            }
+        }
+
+        if release_failed {
+            return Err(AppError::new(
+                "cannot release reserved stock",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// reservation_line_error describes which line of a reservation failed. Lines are numbered
+// from 1 and the error confirms that the reservation did not hold any stock
+fn reservation_line_error(index: usize, line: &ProductQuantity, err: AppError) -> AppError {
+    AppError::new(
+        &format!(
+            "reservation line {} with product_id: {} failed: {}. no stock was reserved",
+            index + 1,
+            line.product_id.to_hex(),
+            err.message
+        ),
+        err.kind,
+    )
+}
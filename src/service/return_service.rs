@@ -0,0 +1,281 @@
+use bson::oid::ObjectId;
+use futures::future;
+use log::error;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    dto::{
+        product::product_dto::ProductQuantityRequest,
+        return_authorization::return_dto::ReceiveReturnLineRequest,
+    },
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        return_authorization::{ReturnAuthorization, ReturnStatus},
+        stock::{AllocatedQuantity, StockAllocation},
+        stock_movement::{MovementReason, MovementSource},
+    },
+    repository::return_repo::ReturnStore,
+    service::{
+        order_service::OrderService, product_service::ProductService,
+        warehouse_service::WarehouseService,
+    },
+};
+
+#[derive(Clone)]
+pub struct ReturnService {
+    return_repo: Arc<dyn ReturnStore>,
+    product_service: ProductService,
+    order_service: OrderService,
+    warehouse_service: WarehouseService,
+}
+
+impl ReturnService {
+    // new creates a new return service instance
+    pub fn new(
+        return_repo: Arc<dyn ReturnStore>,
+        product_service: ProductService,
+        order_service: OrderService,
+        warehouse_service: WarehouseService,
+    ) -> ReturnService {
+        ReturnService {
+            return_repo,
+            product_service,
+            order_service,
+            warehouse_service,
+        }
+    }
+
+    // authorize allows a customer to send back quantities of a past order. The quantities
+    // cannot exceed what the order shipped and was neither cancelled nor already returned
+    pub async fn authorize(
+        &self,
+        client_id: ObjectId,
+        order_id: ObjectId,
+        reason: Option<String>,
+        return_requests: Vec<ProductQuantityRequest>,
+    ) -> Result<ReturnAuthorization, AppError> {
+        let lines = self
+            .product_service
+            .check_products_and_convert_to_product_quantity_vector(return_requests)
+            .await?;
+
+        // record the returned quantities on the order first, so they cannot be returned twice
+        let order = self
+            .order_service
+            .add_returned(client_id, order_id, &lines)
+            .await?;
+
+        // record the return, and give the quantities back to the order if it cannot be recorded
+        let return_authorization = ReturnAuthorization::new(&order, reason, &lines);
+        if let Err(err) = self.return_repo.insert(&return_authorization).await {
+            error!("Error inserting return authorization: {:?}", err);
+            if let Err(err) = self
+                .order_service
+                .remove_returned(client_id, order_id, &lines)
+                .await
+            {
+                error!(
+                    "Error undoing returned quantities on order: {:?}. Error: {:?}",
+                    order_id, err
+                );
+            }
+            return Err(AppError::new(
+                "cannot authorize return",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(return_authorization)
+    }
+
+    // get_return gets a return authorization of a client from the application storage
+    pub async fn get_return(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<ReturnAuthorization, AppError> {
+        match self.return_repo.get_by_id(client_id, return_id).await {
+            Ok(Some(return_authorization)) => Ok(return_authorization),
+            Ok(None) => Err(AppError::new("return not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a return authorization: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch return",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_returns_by_client gets the return authorizations of a client, optionally only
+    // those of one order
+    pub async fn get_returns_by_client(
+        &self,
+        client_id: ObjectId,
+        order_id: Option<ObjectId>,
+    ) -> Result<Vec<ReturnAuthorization>, AppError> {
+        match self.return_repo.get_by_client_id(client_id, order_id).await {
+            Ok(return_authorizations) => Ok(return_authorizations),
+            Err(err) => {
+                error!("Error fetching return authorizations: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch returns",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // receive records the condition of the units that arrived for an authorized return and
+    // puts the resellable units back into the stock at the given location, or at the locations
+    // the order lines were taken from if none is given
+    pub async fn receive(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+        received: Vec<ReceiveReturnLineRequest>,
+        warehouse_id: Option<ObjectId>,
+        actor: &str,
+    ) -> Result<ReturnAuthorization, AppError> {
+        self.warehouse_service
+            .check_warehouse(client_id, warehouse_id)
+            .await?;
+
+        let return_authorization = self.get_return(client_id, return_id).await?;
+        if return_authorization.status != ReturnStatus::Authorized {
+            return Err(AppError::new(
+                "return is not authorized",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        // match the received lines to the authorized lines
+        let mut received_by_product = HashMap::with_capacity(received.len());
+        for line in &received {
+            let product_id = match ObjectId::parse_str(&line.product_id) {
+                Ok(product_id) => product_id,
+                Err(_) => {
+                    return Err(AppError::new(
+                        &format!("invalid product id: {:?}", line.product_id),
+                        ErrorKind::FailedAction,
+                    ))
+                }
+            };
+            if received_by_product.insert(product_id, line).is_some() {
+                return Err(AppError::new(
+                    &format!("duplicate line with product_id: {}", product_id.to_hex()),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+
+        let mut lines = return_authorization.lines.clone();
+        for line in &mut lines {
+            if let Some(received_line) = received_by_product.remove(&line.product_id) {
+                line.resellable = received_line.resellable;
+                line.damaged = received_line.damaged;
+                line.scrap = received_line.scrap;
+            }
+            if warehouse_id.is_some() {
+                line.warehouse_id = warehouse_id;
+            }
+            if line.received_quantity() > line.quantity {
+                return Err(AppError::new(
+                    &format!(
+                        "cannot receive {} of product_id: {}, only {} were authorized",
+                        line.received_quantity(),
+                        line.product_id.to_hex(),
+                        line.quantity
+                    ),
+                    ErrorKind::FailedAction,
+                ));
+            }
+        }
+        if let Some(product_id) = received_by_product.keys().next() {
+            return Err(AppError::new(
+                &format!(
+                    "return has no line with product_id: {}",
+                    product_id.to_hex()
+                ),
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        // the products must still exist for resellable units to go back into the stock
+        let restocked: Vec<AllocatedQuantity> = lines
+            .iter()
+            .filter(|line| line.resellable > 0)
+            .map(|line| AllocatedQuantity {
+                product_id: line.product_id,
+                quantity: line.resellable,
+                allocations: vec![StockAllocation {
+                    warehouse_id: line.warehouse_id,
+                    quantity: line.resellable,
+                }],
+            })
+            .collect();
+        let mut get_product_futs = Vec::with_capacity(restocked.len());
+        for line in &restocked {
+            get_product_futs.push(self.product_service.get_product(line.product_id, client_id));
+        }
+        future::try_join_all(get_product_futs).await?;
+
+        // only one caller can move the return out of authorized
+        let return_authorization = match self
+            .return_repo
+            .mark_received(client_id, return_id, &lines)
+            .await
+        {
+            Ok(Some(return_authorization)) => return_authorization,
+            Ok(None) => {
+                return Err(AppError::new(
+                    "return is not authorized",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            Err(err) => {
+                error!("Error receiving return: {:?}. Error: {:?}", return_id, err);
+                return Err(AppError::new(
+                    "cannot receive return",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        // put the resellable units back into the stock, the movements reference the return
+        let source = MovementSource::new(actor, Some(return_id.to_hex()));
+        let restocked: Vec<&AllocatedQuantity> = restocked.iter().collect();
+        self.product_service
+            .restock_many(client_id, &restocked, MovementReason::Return, &source)
+            .await?;
+
+        Ok(return_authorization)
+    }
+
+    // close marks a received return as done
+    pub async fn close(
+        &self,
+        client_id: ObjectId,
+        return_id: ObjectId,
+    ) -> Result<ReturnAuthorization, AppError> {
+        match self.return_repo.mark_closed(client_id, return_id).await {
+            Ok(Some(return_authorization)) => Ok(return_authorization),
+            Ok(None) => {
+                // tell apart a missing return from one that has not been received
+                self.get_return(client_id, return_id).await?;
+                Err(AppError::new(
+                    "return has not been received",
+                    ErrorKind::FailedAction,
+                ))
+            }
+            Err(err) => {
+                error!("Error closing return: {:?}. Error: {:?}", return_id, err);
+                Err(AppError::new(
+                    "cannot close return",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use log::error;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        product::Product,
+        sku::{self, SkuSettings, SkuStrategy},
+    },
+    repository::{sku_counter_repo::SkuCounterStore, sku_settings_repo::SkuSettingsStore},
+    utils::tools,
+};
+
+// MAX_SKU_WIDTH is the most digits the counter of sequential and category skus is padded to
+const MAX_SKU_WIDTH: usize = 12;
+
+// SkuGenerator makes the sku of a new product of a client
+#[async_trait]
+pub trait SkuGenerator: Send + Sync {
+    // generate makes a sku for a product. Every call makes a different sku, so that a sku the
+    // client already uses can be replaced by calling it again
+    async fn generate(&self, client_id: ObjectId, product: &Product) -> Result<String, AppError>;
+}
+
+// RandomSkuGenerator makes skus of 16 random alphanumeric characters in groups of 4
+pub struct RandomSkuGenerator;
+
+#[async_trait]
+impl SkuGenerator for RandomSkuGenerator {
+    async fn generate(&self, _client_id: ObjectId, _product: &Product) -> Result<String, AppError> {
+        match tools::generate_random_alphanum(16) {
+            Ok(sku) => Ok(tools::split_into_parts(sku, 4)),
+            Err(err) => {
+                error!("Error generating random sku: {:?}", err);
+                Err(AppError::new(
+                    "cannot generate sku",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+}
+
+// SequentialSkuGenerator makes skus of a prefix and the next value of the counter of the prefix
+pub struct SequentialSkuGenerator {
+    counter_repo: Arc<dyn SkuCounterStore>,
+    prefix: String,
+    width: usize,
+}
+
+#[async_trait]
+impl SkuGenerator for SequentialSkuGenerator {
+    async fn generate(&self, client_id: ObjectId, _product: &Product) -> Result<String, AppError> {
+        numbered_sku(&self.counter_repo, client_id, &self.prefix, self.width).await
+    }
+}
+
+// CategorySkuGenerator makes skus of the code of the category of a product and the next value
+// of the counter of the code
+pub struct CategorySkuGenerator {
+    counter_repo: Arc<dyn SkuCounterStore>,
+    codes: BTreeMap<String, String>,
+    width: usize,
+}
+
+#[async_trait]
+impl SkuGenerator for CategorySkuGenerator {
+    async fn generate(&self, client_id: ObjectId, product: &Product) -> Result<String, AppError> {
+        let category = match &product.category {
+            Some(category) => category,
+            None => {
+                return Err(AppError::new(
+                    "category is required to generate a sku",
+                    ErrorKind::FailedAction,
+                ))
+            }
+        };
+
+        // categories without a code use their first three letters or digits
+        let code = match self.codes.get(category) {
+            Some(code) => code.clone(),
+            None => sku::sku_code(category).chars().take(3).collect(),
+        };
+        if code.is_empty() {
+            return Err(AppError::new(
+                "category cannot make a sku code",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        numbered_sku(&self.counter_repo, client_id, &code, self.width).await
+    }
+}
+
+// ClientSuppliedSkuGenerator is used by clients who give the sku of every product they add,
+// it never makes one
+pub struct ClientSuppliedSkuGenerator;
+
+#[async_trait]
+impl SkuGenerator for ClientSuppliedSkuGenerator {
+    async fn generate(&self, _client_id: ObjectId, _product: &Product) -> Result<String, AppError> {
+        Err(AppError::new(
+            "sku is required, the client supplies the skus of its products",
+            ErrorKind::FailedAction,
+        ))
+    }
+}
+
+// numbered_sku makes a sku of a prefix and the next value of its counter padded to a width
+async fn numbered_sku(
+    counter_repo: &Arc<dyn SkuCounterStore>,
+    client_id: ObjectId,
+    prefix: &str,
+    width: usize,
+) -> Result<String, AppError> {
+    let value = match counter_repo.next(client_id, prefix).await {
+        Ok(value) => value,
+        Err(err) => {
+            error!("Error incrementing sku counter: {:?}", err);
+            return Err(AppError::new(
+                "cannot generate sku",
+                ErrorKind::InternalServerError,
+            ));
+        }
+    };
+
+    let sku = format!("{}-{:0width$}", prefix, value, width = width);
+    if !sku::is_valid_sku(&sku) {
+        return Err(AppError::new(
+            "cannot generate sku, the generated sku is too long",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(sku)
+}
+
+#[derive(Clone)]
+pub struct SkuService {
+    sku_settings_repo: Arc<dyn SkuSettingsStore>,
+    sku_counter_repo: Arc<dyn SkuCounterStore>,
+}
+
+impl SkuService {
+    // new creates a new sku service instance
+    pub fn new(
+        sku_settings_repo: Arc<dyn SkuSettingsStore>,
+        sku_counter_repo: Arc<dyn SkuCounterStore>,
+    ) -> SkuService {
+        SkuService {
+            sku_settings_repo,
+            sku_counter_repo,
+        }
+    }
+
+    // get_settings gets the sku settings of a client, clients without settings get random skus
+    pub async fn get_settings(&self, client_id: ObjectId) -> Result<SkuSettings, AppError> {
+        match self.sku_settings_repo.get_by_client_id(client_id).await {
+            Ok(Some(settings)) => Ok(settings),
+            Ok(None) => Ok(SkuSettings::new(client_id, SkuStrategy::default())),
+            Err(err) => {
+                error!("Error fetching sku settings: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch sku settings",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // set_settings checks and sets how the skus of the new products of a client are made
+    pub async fn set_settings(
+        &self,
+        client_id: ObjectId,
+        strategy: SkuStrategy,
+    ) -> Result<SkuSettings, AppError> {
+        match &strategy {
+            SkuStrategy::Sequential { prefix, width } => {
+                check_sku_code("prefix", prefix)?;
+                check_sku_width(*width)?;
+            }
+            SkuStrategy::Category { codes, width } => {
+                for code in codes.values() {
+                    check_sku_code("category code", code)?;
+                }
+                check_sku_width(*width)?;
+            }
+            SkuStrategy::Random | SkuStrategy::ClientSupplied => {}
+        }
+
+        let settings = SkuSettings::new(client_id, strategy);
+        if let Err(err) = self.sku_settings_repo.upsert(&settings).await {
+            error!("Error updating sku settings: {:?}", err);
+            return Err(AppError::new(
+                "cannot update sku settings",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        self.get_settings(client_id).await
+    }
+
+    // generator gets the sku generator of the strategy of a client
+    pub async fn generator(&self, client_id: ObjectId) -> Result<Box<dyn SkuGenerator>, AppError> {
+        let settings = self.get_settings(client_id).await?;
+        let generator: Box<dyn SkuGenerator> = match settings.strategy {
+            SkuStrategy::Random => Box::new(RandomSkuGenerator),
+            SkuStrategy::Sequential { prefix, width } => Box::new(SequentialSkuGenerator {
+                counter_repo: self.sku_counter_repo.clone(),
+                prefix,
+                width,
+            }),
+            SkuStrategy::Category { codes, width } => Box::new(CategorySkuGenerator {
+                counter_repo: self.sku_counter_repo.clone(),
+                codes,
+                width,
+            }),
+            SkuStrategy::ClientSupplied => Box::new(ClientSuppliedSkuGenerator),
+        };
+        Ok(generator)
+    }
+}
+
+// check_sku_code checks that a prefix or code of the skus of a client can start a sku
+fn check_sku_code(name: &str, code: &str) -> Result<(), AppError> {
+    if !sku::is_valid_sku(code) {
+        return Err(AppError::new(
+            &format!(
+                "{} must be letters, digits, dashes, underscores or dots",
+                name
+            ),
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
+
+// check_sku_width checks the width the counter of the skus of a client is padded to
+fn check_sku_width(width: usize) -> Result<(), AppError> {
+    if !(1..=MAX_SKU_WIDTH).contains(&width) {
+        return Err(AppError::new(
+            &format!("width must be between 1 and {}", MAX_SKU_WIDTH),
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(())
+}
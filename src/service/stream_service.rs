@@ -0,0 +1,157 @@
+use bson::oid::ObjectId;
+use futures::{stream, Stream};
+use log::{error, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{
+    errors::app_error::{AppError, ErrorKind},
+    model::stock_movement::StockMovement,
+    repository::stock_movement_repo::StockMovementStore,
+};
+
+// STREAM_POLL_INTERVAL is how often the stock movements are read for new changes to stream
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// STREAM_GAP_TIMEOUT is how long the stream waits for a missing sequence number. The sequence
+// number of a movement is taken just before it is inserted, so a movement can be read before
+// one with a smaller number. The stream waits for the missing movement rather than skip past
+// it, and only moves on once its insert must have failed
+const STREAM_GAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+// STREAM_BATCH_LIMIT is the most movements read at once. A full batch is followed by the next
+// one without waiting for the poll interval
+const STREAM_BATCH_LIMIT: i64 = 100;
+
+// KEEP_ALIVE_POLLS is how many polls without changes are made before an empty batch is
+// streamed, so that idle connections are not closed by proxies
+const KEEP_ALIVE_POLLS: u32 = 15;
+
+// StreamService streams the changes to the stock of the products of a client as they happen
+#[derive(Clone)]
+pub struct StreamService {
+    stock_movement_repo: Arc<dyn StockMovementStore>,
+}
+
+// StreamState is where a stream of stock changes is at
+struct StreamState {
+    service: StreamService,
+    client_id: ObjectId,
+    product_ids: Vec<ObjectId>,
+    // after is the sequence number of the last movement streamed
+    after: i64,
+    // gap_since is when the stream started waiting for a missing sequence number
+    gap_since: Option<Instant>,
+    idle_polls: u32,
+    wait: bool,
+}
+
+impl StreamService {
+    // new creates a new stream service instance
+    pub fn new(stock_movement_repo: Arc<dyn StockMovementStore>) -> StreamService {
+        StreamService {
+            stock_movement_repo,
+        }
+    }
+
+    // stock_changes streams the stock movements of a client in batches, in the order they were
+    // inserted. Only the movements of the given products are streamed, or of every product if
+    // none are given. The stream resumes after the movement with the given sequence number, or
+    // starts with the movements inserted from now on. Batches are only empty to keep the
+    // connection alive. The stream ends if the movements cannot be read, so the client
+    // reconnects from the last movement it got
+    pub async fn stock_changes(
+        &self,
+        client_id: ObjectId,
+        product_ids: Vec<ObjectId>,
+        after: Option<i64>,
+    ) -> Result<impl Stream<Item = Vec<StockMovement>>, AppError> {
+        // a stream without a movement to resume after starts after the last one
+        let after = match after {
+            Some(after) => after,
+            None => match self.stock_movement_repo.last_sequence(client_id).await {
+                Ok(after) => after,
+                Err(err) => {
+                    error!("Error fetching the last stock movement: {:?}", err);
+                    return Err(AppError::new(
+                        "cannot stream stock changes",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            },
+        };
+        let state = StreamState {
+            service: self.clone(),
+            client_id,
+            product_ids,
+            after,
+            gap_since: None,
+            idle_polls: 0,
+            wait: false,
+        };
+        Ok(stream::unfold(state, |mut state| async move {
+            loop {
+                if state.wait {
+                    actix_rt::time::sleep(STREAM_POLL_INTERVAL).await;
+                }
+                let after = state.after;
+                let mut movements = match state
+                    .service
+                    .stock_movement_repo
+                    .get_after(state.client_id, after, STREAM_BATCH_LIMIT)
+                    .await
+                {
+                    Ok(movements) => movements,
+                    Err(err) => {
+                        error!("Error fetching stock movements to stream: {:?}", err);
+                        return None;
+                    }
+                };
+                state.wait = movements.len() < STREAM_BATCH_LIMIT as usize;
+
+                // only the movements that follow on without a gap are streamed
+                let in_sequence = movements
+                    .iter()
+                    .zip(after + 1..)
+                    .take_while(|(movement, sequence)| movement.sequence == *sequence)
+                    .count();
+                if in_sequence == 0 && !movements.is_empty() {
+                    let gap_since = *state.gap_since.get_or_insert_with(Instant::now);
+                    if gap_since.elapsed() < STREAM_GAP_TIMEOUT {
+                        state.wait = true;
+                        continue;
+                    }
+                    warn!(
+                        "Skipping stock movements of client {} after {} that were never inserted",
+                        state.client_id, after
+                    );
+                    state.after = movements[0].sequence - 1;
+                    state.gap_since = None;
+                    state.wait = false;
+                    continue;
+                }
+                state.gap_since = None;
+                movements.truncate(in_sequence);
+                if let Some(last) = movements.last() {
+                    state.after = last.sequence;
+                }
+
+                movements.retain(|movement| {
+                    state.product_ids.is_empty() || state.product_ids.contains(&movement.product_id)
+                });
+                if !movements.is_empty() {
+                    state.idle_polls = 0;
+                    return Some((movements, state));
+                }
+                if !state.wait {
+                    continue;
+                }
+                state.idle_polls += 1;
+                if state.idle_polls == KEEP_ALIVE_POLLS {
+                    state.idle_polls = 0;
+                    return Some((movements, state));
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,186 @@
+use bson::oid::ObjectId;
+use log::error;
+use std::sync::Arc;
+
+use crate::{
+    dto::supplier::supplier_dto::{ProductSupplierRequest, SupplierRequest},
+    errors::app_error::{AppError, ErrorKind},
+    model::{
+        product::{Product, ProductSupplier},
+        supplier::Supplier,
+    },
+    repository::supplier_repo::SupplierStore,
+    service::product_service::ProductService,
+};
+
+#[derive(Clone)]
+pub struct SupplierService {
+    supplier_repo: Arc<dyn SupplierStore>,
+    product_service: ProductService,
+}
+
+impl SupplierService {
+    // new creates a new supplier service instance
+    pub fn new(
+        supplier_repo: Arc<dyn SupplierStore>,
+        product_service: ProductService,
+    ) -> SupplierService {
+        SupplierService {
+            supplier_repo,
+            product_service,
+        }
+    }
+
+    // create creates a supplier for a client
+    pub async fn create(
+        &self,
+        client_id: ObjectId,
+        request: SupplierRequest,
+    ) -> Result<Supplier, AppError> {
+        let supplier = Supplier::new(
+            client_id,
+            request.name,
+            request.contact,
+            request.lead_time_days,
+            request.minimum_order_quantity,
+        );
+        if let Err(err) = self.supplier_repo.insert(&supplier).await {
+            error!("Error inserting supplier: {:?}", err);
+            return Err(AppError::new(
+                "cannot create supplier",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(supplier)
+    }
+
+    // get_supplier gets a supplier of a client from the application storage
+    pub async fn get_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Supplier, AppError> {
+        match self.supplier_repo.get_by_id(client_id, supplier_id).await {
+            Ok(Some(supplier)) => Ok(supplier),
+            Ok(None) => Err(AppError::new("supplier not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a supplier: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch supplier",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_suppliers_by_client gets all suppliers of a client from the application storage
+    pub async fn get_suppliers_by_client(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<Supplier>, AppError> {
+        match self.supplier_repo.get_by_client_id(client_id).await {
+            Ok(suppliers) => Ok(suppliers),
+            Err(err) => {
+                error!("Error fetching all suppliers: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch suppliers",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // update_supplier updates the name, contact, lead time and minimum order quantity of a
+    // supplier
+    pub async fn update_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+        request: SupplierRequest,
+    ) -> Result<Supplier, AppError> {
+        let mut supplier = self.get_supplier(client_id, supplier_id).await?;
+        supplier.name = request.name;
+        supplier.contact = request.contact;
+        supplier.lead_time_days = request.lead_time_days;
+        supplier.minimum_order_quantity = request.minimum_order_quantity;
+
+        match self.supplier_repo.update(&supplier).await {
+            Ok(true) => {}
+            Ok(false) => return Err(AppError::new("supplier not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!(
+                    "Error updating supplier with id: {:?}. Error: {:?}",
+                    supplier_id, err
+                );
+                return Err(AppError::new(
+                    "cannot update supplier",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        }
+
+        self.get_supplier(client_id, supplier_id).await
+    }
+
+    // delete_supplier deletes a supplier, the products linked to it are unlinked first
+    pub async fn delete_supplier(
+        &self,
+        client_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<(), AppError> {
+        self.get_supplier(client_id, supplier_id).await?;
+        self.product_service
+            .remove_supplier_links(client_id, supplier_id)
+            .await?;
+
+        if let Err(err) = self
+            .supplier_repo
+            .delete_by_id(client_id, supplier_id)
+            .await
+        {
+            error!(
+                "Error deleting supplier with id: {:?}. Error: {:?}",
+                supplier_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete supplier",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // link_product links a product to a supplier of the client with the sku the supplier knows
+    // it by and its unit cost, replacing those of an existing link
+    pub async fn link_product(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        supplier_id: ObjectId,
+        request: ProductSupplierRequest,
+    ) -> Result<Product, AppError> {
+        self.get_supplier(client_id, supplier_id).await?;
+        let link = ProductSupplier {
+            supplier_id,
+            supplier_sku: request.supplier_sku,
+            unit_cost: request.unit_cost,
+        };
+        self.product_service
+            .set_product_supplier(client_id, product_id, link)
+            .await
+    }
+
+    // unlink_product unlinks a product from a supplier
+    pub async fn unlink_product(
+        &self,
+        client_id: ObjectId,
+        product_id: ObjectId,
+        supplier_id: ObjectId,
+    ) -> Result<Product, AppError> {
+        self.product_service
+            .remove_product_supplier(client_id, product_id, supplier_id)
+            .await
+    }
+}
@@ -0,0 +1,229 @@
+use bson::oid::ObjectId;
+use log::error;
+use std::sync::Arc;
+
+use crate::{
+    dto::warehouse::warehouse_dto::WarehouseRequest,
+    errors::app_error::{AppError, ErrorKind},
+    model::warehouse::Warehouse,
+    repository::{
+        bin_repo::BinStore, bin_stock_repo::BinStockStore, stock_repo::StockStore,
+        warehouse_repo::WarehouseStore,
+    },
+};
+
+#[derive(Clone)]
+pub struct WarehouseService {
+    warehouse_repo: Arc<dyn WarehouseStore>,
+    stock_repo: Arc<dyn StockStore>,
+    bin_repo: Arc<dyn BinStore>,
+    bin_stock_repo: Arc<dyn BinStockStore>,
+}
+
+impl WarehouseService {
+    // new creates a new warehouse service instance
+    pub fn new(
+        warehouse_repo: Arc<dyn WarehouseStore>,
+        stock_repo: Arc<dyn StockStore>,
+        bin_repo: Arc<dyn BinStore>,
+        bin_stock_repo: Arc<dyn BinStockStore>,
+    ) -> WarehouseService {
+        WarehouseService {
+            warehouse_repo,
+            stock_repo,
+            bin_repo,
+            bin_stock_repo,
+        }
+    }
+
+    // create creates a warehouse for a client
+    pub async fn create(
+        &self,
+        client_id: ObjectId,
+        request: WarehouseRequest,
+    ) -> Result<Warehouse, AppError> {
+        let warehouse = Warehouse::new(client_id, request.name, request.address);
+        if let Err(err) = self.warehouse_repo.insert(&warehouse).await {
+            error!("Error inserting warehouse: {:?}", err);
+            return Err(AppError::new(
+                "cannot create warehouse",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(warehouse)
+    }
+
+    // get_warehouse gets a warehouse of a client from the application storage
+    pub async fn get_warehouse(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<Warehouse, AppError> {
+        match self.warehouse_repo.get_by_id(client_id, warehouse_id).await {
+            Ok(Some(warehouse)) => Ok(warehouse),
+            Ok(None) => Err(AppError::new("warehouse not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a warehouse: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch warehouse",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // check_warehouse checks that a location, if any is given, is a warehouse of the client
+    pub async fn check_warehouse(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: Option<ObjectId>,
+    ) -> Result<(), AppError> {
+        if let Some(warehouse_id) = warehouse_id {
+            self.get_warehouse(client_id, warehouse_id).await?;
+        }
+        Ok(())
+    }
+
+    // get_warehouses_by_client gets all warehouses of a client from the application storage
+    pub async fn get_warehouses_by_client(
+        &self,
+        client_id: ObjectId,
+    ) -> Result<Vec<Warehouse>, AppError> {
+        match self.warehouse_repo.get_by_client_id(client_id).await {
+            Ok(warehouses) => Ok(warehouses),
+            Err(err) => {
+                error!("Error fetching all warehouses: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch warehouses",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // update_warehouse updates the name and address of a warehouse
+    pub async fn update_warehouse(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+        request: WarehouseRequest,
+    ) -> Result<Warehouse, AppError> {
+        let mut warehouse = self.get_warehouse(client_id, warehouse_id).await?;
+        warehouse.name = request.name;
+        warehouse.address = request.address;
+
+        match self.warehouse_repo.update(&warehouse).await {
+            Ok(true) => {}
+            Ok(false) => return Err(AppError::new("warehouse not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!(
+                    "Error updating warehouse with id: {:?}. Error: {:?}",
+                    warehouse_id, err
+                );
+                return Err(AppError::new(
+                    "cannot update warehouse",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        }
+
+        self.get_warehouse(client_id, warehouse_id).await
+    }
+
+    // delete_warehouse deletes a warehouse that no longer holds any stock, along with its bins
+    pub async fn delete_warehouse(
+        &self,
+        client_id: ObjectId,
+        warehouse_id: ObjectId,
+    ) -> Result<(), AppError> {
+        self.get_warehouse(client_id, warehouse_id).await?;
+
+        // a warehouse holding stock cannot be deleted, the stock would be lost
+        let stocks = match self
+            .stock_repo
+            .get_by_warehouse_id(client_id, warehouse_id)
+            .await
+        {
+            Ok(stocks) => stocks,
+            Err(err) => {
+                error!(
+                    "Error fetching stocks of warehouse: {:?}. Error: {:?}",
+                    warehouse_id, err
+                );
+                return Err(AppError::new(
+                    "cannot delete warehouse",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+        if stocks
+            .iter()
+            .any(|stock| stock.get_quantity() != 0 || stock.get_reserved() != 0)
+        {
+            return Err(AppError::new(
+                "warehouse still holds stock, move or remove it first",
+                ErrorKind::FailedAction,
+            ));
+        }
+
+        if let Err(err) = self
+            .stock_repo
+            .delete_empty_by_warehouse_id(client_id, warehouse_id)
+            .await
+        {
+            error!(
+                "Error deleting stocks of warehouse: {:?}. Error: {:?}",
+                warehouse_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete warehouse",
+                ErrorKind::InternalServerError,
+            ));
+        }
+        if let Err(err) = self
+            .bin_stock_repo
+            .delete_by_warehouse_id(client_id, warehouse_id)
+            .await
+        {
+            error!(
+                "Error deleting bin stocks of warehouse: {:?}. Error: {:?}",
+                warehouse_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete warehouse",
+                ErrorKind::InternalServerError,
+            ));
+        }
+        if let Err(err) = self
+            .bin_repo
+            .delete_by_warehouse_id(client_id, warehouse_id)
+            .await
+        {
+            error!(
+                "Error deleting bins of warehouse: {:?}. Error: {:?}",
+                warehouse_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete warehouse",
+                ErrorKind::InternalServerError,
+            ));
+        }
+        if let Err(err) = self
+            .warehouse_repo
+            .delete_by_id(client_id, warehouse_id)
+            .await
+        {
+            error!(
+                "Error deleting warehouse with id: {:?}. Error: {:?}",
+                warehouse_id, err
+            );
+            return Err(AppError::new(
+                "cannot delete warehouse",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(())
+    }
+}
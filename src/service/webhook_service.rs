@@ -0,0 +1,466 @@
+use bson::{oid::ObjectId, DateTime};
+use hyper::client::connect::dns::Name;
+use log::{error, info};
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{header, redirect, Client};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    dto::webhook::webhook_dto::{EventPayload, WebhookRequest},
+    errors::app_error::{AppError, ErrorKind},
+    model::webhook::{
+        DeliveryAttempt, DeliveryQuery, DeliveryStatus, Webhook, WebhookDelivery, WebhookEvent,
+    },
+    repository::{webhook_delivery_repo::WebhookDeliveryStore, webhook_repo::WebhookStore},
+    utils::tools,
+};
+
+// SIGNATURE_HEADER carries the HMAC-SHA256 of the timestamp and the body of a delivery, signed
+// with the secret of the webhook and written as "sha256=<hex>"
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+// TIMESTAMP_HEADER carries the unix time in seconds the delivery was signed at. The signed
+// message is the timestamp, a dot and the body, so that an old delivery cannot be sent again
+// with a new timestamp
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+// DELIVERY_HEADER carries the id of the delivery in the delivery log
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+// DELIVERY_TIMEOUT is how long an endpoint has to respond to a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// DELIVERY_LEASE_SECONDS is how long a delivery being sent is left alone by the retries. It is
+// longer than the delivery timeout, so a delivery is only taken again if its sender stopped
+const DELIVERY_LEASE_SECONDS: i64 = 60;
+
+// RETRY_BACKOFF_SECONDS is how long a failed delivery waits before its first retry, the wait
+// doubles after every failed retry
+const RETRY_BACKOFF_SECONDS: i64 = 30;
+
+// MAX_DELIVERY_ATTEMPTS is how many times a delivery is sent before it is given up as failed
+const MAX_DELIVERY_ATTEMPTS: usize = 8;
+
+// WEBHOOK_SECRET_PREFIX starts the secrets of the webhooks, to tell them apart from other keys
+const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+// WebhookService notifies the webhooks of the clients of inventory events. Every event a
+// webhook subscribed to is recorded as a delivery, sent in the background, and sent again with
+// backoff until the endpoint accepts it
+#[derive(Clone)]
+pub struct WebhookService {
+    webhook_repo: Arc<dyn WebhookStore>,
+    delivery_repo: Arc<dyn WebhookDeliveryStore>,
+    http_client: Client,
+    allowed_hosts: Vec<String>,
+}
+
+// PublicResolver looks up the hosts the deliveries are sent to, and refuses the ones pointing to
+// private addresses. A host checked when its webhook was registered can point elsewhere later
+struct PublicResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed_hosts = self.allowed_hosts.clone();
+        Box::pin(async move {
+            let addrs = tools::resolve_public_host(name.as_str(), 0, &allowed_hosts).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl WebhookService {
+    // new creates a new webhook service instance. The webhooks can only point to private
+    // addresses through the allowed hosts
+    pub fn new(
+        webhook_repo: Arc<dyn WebhookStore>,
+        delivery_repo: Arc<dyn WebhookDeliveryStore>,
+        allowed_hosts: Vec<String>,
+    ) -> WebhookService {
+        // redirects are not followed, a delivery is only sent to the registered url
+        let http_client = Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver {
+                allowed_hosts: allowed_hosts.clone(),
+            }))
+            .build()
+            .unwrap_or_default();
+        WebhookService {
+            webhook_repo,
+            delivery_repo,
+            http_client,
+            allowed_hosts,
+        }
+    }
+
+    // allowed_hosts returns the hosts the webhooks can point to even if they are private
+    pub fn allowed_hosts(&self) -> &[String] {
+        &self.allowed_hosts
+    }
+
+    // create registers a webhook of a client with a new secret
+    pub async fn create(
+        &self,
+        client_id: ObjectId,
+        request: &WebhookRequest,
+    ) -> Result<Webhook, AppError> {
+        let secret = match tools::generate_random_alphanum(30) {
+            Ok(secret) => format!("{}{}", WEBHOOK_SECRET_PREFIX, secret),
+            Err(err) => {
+                error!("Error generating webhook secret: {:?}", err);
+                return Err(AppError::new(
+                    "cannot generate webhook secret",
+                    ErrorKind::InternalServerError,
+                ));
+            }
+        };
+
+        let mut webhook =
+            Webhook::new(client_id, request.url.clone(), request.get_events(), secret);
+        webhook.active = request.active.unwrap_or(true);
+        if let Err(err) = self.webhook_repo.insert(&webhook).await {
+            error!("Error inserting webhook: {:?}", err);
+            return Err(AppError::new(
+                "cannot create webhook",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        Ok(webhook)
+    }
+
+    // get_webhooks gets all webhooks of a client
+    pub async fn get_webhooks(&self, client_id: ObjectId) -> Result<Vec<Webhook>, AppError> {
+        match self.webhook_repo.get_by_client_id(client_id).await {
+            Ok(webhooks) => Ok(webhooks),
+            Err(err) => {
+                error!("Error fetching webhooks: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch webhooks",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_webhook gets a webhook of a client
+    pub async fn get_webhook(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+    ) -> Result<Webhook, AppError> {
+        match self.webhook_repo.get_by_id(client_id, webhook_id).await {
+            Ok(Some(webhook)) => Ok(webhook),
+            Ok(None) => Err(AppError::new("webhook not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a webhook: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch webhook",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // update_webhook changes the url and the events of a webhook, and enables or disables it.
+    // The webhook stays as it is if active is not given
+    pub async fn update_webhook(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+        request: &WebhookRequest,
+    ) -> Result<Webhook, AppError> {
+        let mut webhook = self.get_webhook(client_id, webhook_id).await?;
+        webhook.url = request.url.clone();
+        webhook.events = request.get_events();
+        if let Some(active) = request.active {
+            webhook.active = active;
+        }
+
+        match self.webhook_repo.update(&webhook).await {
+            Ok(true) => self.get_webhook(client_id, webhook_id).await,
+            Ok(false) => Err(AppError::new("webhook not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error updating webhook: {:?}", err);
+                Err(AppError::new(
+                    "cannot update webhook",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // delete_webhook deletes a webhook of a client. Its deliveries are kept in the log
+    pub async fn delete_webhook(
+        &self,
+        client_id: ObjectId,
+        webhook_id: ObjectId,
+    ) -> Result<(), AppError> {
+        match self.webhook_repo.delete_by_id(client_id, webhook_id).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::new("webhook not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error deleting webhook: {:?}", err);
+                Err(AppError::new(
+                    "cannot delete webhook",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // publish records a delivery of an event for every active webhook of the client subscribed
+    // to it, and sends the deliveries in the background. The change the event is about has
+    // already been applied, so a failure is logged rather than returned
+    pub async fn publish<T: Serialize>(&self, client_id: ObjectId, event: WebhookEvent, data: &T) {
+        let webhooks = match self.webhook_repo.get_subscribed(client_id, event).await {
+            Ok(webhooks) => webhooks,
+            Err(err) => {
+                error!("Error fetching webhooks of event {:?}: {:?}", event, err);
+                return;
+            }
+        };
+        if webhooks.is_empty() {
+            return;
+        }
+
+        let event_id = ObjectId::new();
+        let payload = EventPayload {
+            id: event_id.to_hex(),
+            event,
+            client_id: client_id.to_hex(),
+            created_at: DateTime::now().try_to_rfc3339_string().unwrap_or_default(),
+            data,
+        };
+        let payload = match serde_json::to_string(&payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                error!("Error serializing payload of event {:?}: {:?}", event, err);
+                return;
+            }
+        };
+
+        for webhook in &webhooks {
+            let delivery = WebhookDelivery::new(
+                webhook,
+                event_id,
+                event,
+                payload.clone(),
+                seconds_from_now(DELIVERY_LEASE_SECONDS),
+            );
+            // a delivery that cannot be recorded is logged and not sent
+            let _ = self.enqueue(delivery).await;
+        }
+    }
+
+    // get_deliveries gets the deliveries of a client matching a query, the most recent first
+    pub async fn get_deliveries(
+        &self,
+        client_id: ObjectId,
+        query: &DeliveryQuery,
+    ) -> Result<Vec<WebhookDelivery>, AppError> {
+        match self.delivery_repo.get_by_client_id(client_id, query).await {
+            Ok(deliveries) => Ok(deliveries),
+            Err(err) => {
+                error!("Error fetching webhook deliveries: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch webhook deliveries",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // get_delivery gets a delivery of a client
+    pub async fn get_delivery(
+        &self,
+        client_id: ObjectId,
+        delivery_id: ObjectId,
+    ) -> Result<WebhookDelivery, AppError> {
+        match self.delivery_repo.get_by_id(client_id, delivery_id).await {
+            Ok(Some(delivery)) => Ok(delivery),
+            Ok(None) => Err(AppError::new("delivery not found", ErrorKind::NotFound)),
+            Err(err) => {
+                error!("Error fetching a webhook delivery: {:?}", err);
+                Err(AppError::new(
+                    "cannot fetch webhook delivery",
+                    ErrorKind::InternalServerError,
+                ))
+            }
+        }
+    }
+
+    // replay sends the payload of a delivery to its webhook again, whatever became of the
+    // delivery. The replay is a new delivery with the same event id
+    pub async fn replay(
+        &self,
+        client_id: ObjectId,
+        delivery_id: ObjectId,
+    ) -> Result<WebhookDelivery, AppError> {
+        let original = self.get_delivery(client_id, delivery_id).await?;
+        let webhook = self.get_webhook(client_id, original.webhook_id).await?;
+
+        let mut delivery = WebhookDelivery::new(
+            &webhook,
+            original.event_id,
+            original.event,
+            original.payload,
+            seconds_from_now(DELIVERY_LEASE_SECONDS),
+        );
+        delivery.replay_of = Some(original._id);
+        self.enqueue(delivery).await
+    }
+
+    // retry_due sends again every pending delivery whose next attempt is due
+    pub async fn retry_due(&self) -> Result<(), AppError> {
+        let mut retried = 0;
+        loop {
+            let claimed = self
+                .delivery_repo
+                .claim_due(DateTime::now(), seconds_from_now(DELIVERY_LEASE_SECONDS))
+                .await;
+            match claimed {
+                Ok(Some(delivery)) => {
+                    self.send_in_background(delivery);
+                    retried += 1;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    error!("Error fetching due webhook deliveries: {:?}", err);
+                    return Err(AppError::new(
+                        "cannot retry webhook deliveries",
+                        ErrorKind::InternalServerError,
+                    ));
+                }
+            }
+        }
+
+        if retried > 0 {
+            info!("Retrying {} webhook deliveries", retried);
+        }
+
+        Ok(())
+    }
+
+    // enqueue records a new delivery in the log and sends it in the background
+    async fn enqueue(&self, delivery: WebhookDelivery) -> Result<WebhookDelivery, AppError> {
+        if let Err(err) = self.delivery_repo.insert(&delivery).await {
+            error!("Error inserting webhook delivery: {:?}", err);
+            return Err(AppError::new(
+                "cannot record webhook delivery",
+                ErrorKind::InternalServerError,
+            ));
+        }
+
+        self.send_in_background(delivery.clone());
+        Ok(delivery)
+    }
+
+    // send_in_background sends a delivery without waiting for the endpoint
+    fn send_in_background(&self, delivery: WebhookDelivery) {
+        let service = self.clone();
+        actix_rt::spawn(async move { service.attempt(delivery).await });
+    }
+
+    // attempt sends a delivery to its webhook and records the attempt. A delivery the endpoint
+    // did not accept is retried after a backoff, until it has been sent too many times
+    async fn attempt(&self, mut delivery: WebhookDelivery) {
+        let attempt = match self
+            .webhook_repo
+            .get_by_id(delivery.client_id, delivery.webhook_id)
+            .await
+        {
+            Ok(Some(webhook)) => self.send(&webhook, &delivery).await,
+            // a delivery of a deleted webhook has nowhere to go
+            Ok(None) => {
+                delivery.status = DeliveryStatus::Failed;
+                delivery.next_attempt_at = None;
+                delivery.attempts.push(DeliveryAttempt {
+                    attempted_at: DateTime::now(),
+                    status_code: None,
+                    error: Some("webhook was deleted".to_string()),
+                });
+                self.record(&delivery).await;
+                return;
+            }
+            // the delivery is retried once its lease is over
+            Err(err) => {
+                error!("Error fetching webhook of delivery: {:?}", err);
+                return;
+            }
+        };
+
+        let accepted = attempt.error.is_none();
+        delivery.attempts.push(attempt);
+        if accepted {
+            delivery.status = DeliveryStatus::Delivered;
+            delivery.next_attempt_at = None;
+        } else if delivery.attempts.len() >= MAX_DELIVERY_ATTEMPTS {
+            delivery.status = DeliveryStatus::Failed;
+            delivery.next_attempt_at = None;
+        } else {
+            let backoff = RETRY_BACKOFF_SECONDS << (delivery.attempts.len() - 1);
+            delivery.next_attempt_at = Some(seconds_from_now(backoff));
+        }
+        self.record(&delivery).await;
+    }
+
+    // send posts the payload of a delivery to the url of its webhook, signed with the secret of
+    // the webhook. The attempt has an error unless the endpoint answers with a success status
+    async fn send(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> DeliveryAttempt {
+        let timestamp = DateTime::now().timestamp_millis() / 1000;
+        let signature = tools::hmac_sha256(
+            &webhook.secret,
+            &format!("{}.{}", timestamp, delivery.payload),
+        );
+        let response = self
+            .http_client
+            .post(&webhook.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(DELIVERY_HEADER, delivery._id.to_hex())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        let (status_code, error) = match response {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!(
+                    "endpoint responded with status {}",
+                    response.status().as_u16()
+                )),
+            ),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code,
+            error,
+        }
+    }
+
+    // record saves the outcome of the attempts of a delivery in the log
+    async fn record(&self, delivery: &WebhookDelivery) {
+        if let Err(err) = self.delivery_repo.update(delivery).await {
+            error!(
+                "Error recording webhook delivery: {:?}. Error: {:?}",
+                delivery._id, err
+            );
+        }
+    }
+}
+
+// seconds_from_now returns the time a number of seconds from now
+fn seconds_from_now(seconds: i64) -> DateTime {
+    DateTime::from_millis(DateTime::now().timestamp_millis() + seconds * 1000)
+}
@@ -1,6 +1,11 @@
 use crate::errors::app_error::{AppError, ErrorKind};
+use hmac::{Hmac, Mac};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use sha2::Sha256;
+use std::env;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
 
 // generate_random_alphanum generates random alphanumeric characters of a given size
 pub fn generate_random_alphanum(size: usize) -> Result<String, AppError> {
@@ -31,17 +36,132 @@ pub fn split_into_parts(str: String, size: usize) -> String {
         res.push(c)
     }
 
-    return res;
+    res
+}
+
+// env_or_default reads and parses a variable from the env, or returns the default
+// if the variable is not set or cannot be parsed
+pub fn env_or_default<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// env_list reads a comma-separated list from the env, which is empty if the variable is not set
+pub fn env_list(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// encode_hex encodes bytes as a string of lowercase hexadecimal digits
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// decode_hex decodes a string made by encode_hex, returning None if it is not valid hexadecimal
+pub fn decode_hex(str: &str) -> Option<Vec<u8>> {
+    if str.len() % 2 != 0 || !str.is_ascii() {
+        return None;
+    }
+    (0..str.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&str[i..i + 2], 16).ok())
+        .collect()
+}
+
+// hmac_sha256 signs a message with a key, and returns the signature as lowercase hexadecimal
+pub fn hmac_sha256(key: &str, message: &str) -> String {
+    // a key of any length is accepted, so creating the mac cannot fail
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac takes any key");
+    mac.update(message.as_bytes());
+    encode_hex(&mac.finalize().into_bytes())
+}
+
+// is_public_ip tells if an address is reachable from the internet. Loopback, private, shared,
+// link-local (which holds the metadata endpoint of cloud hosts), multicast, documentation and
+// unspecified addresses are not
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let shared = first == 100 && (64..128).contains(&second);
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || first == 0
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                // fc00::/7 is unique local and fe80::/10 is link-local
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+// resolve_public_host looks up the addresses of a host, and fails unless they are all public or
+// the host is one of the allowed hosts
+pub async fn resolve_public_host(
+    host: &str,
+    port: u16,
+    allowed_hosts: &[String],
+) -> Result<Vec<SocketAddr>, AppError> {
+    // the host of an ipv6 url is written in brackets
+    let name = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let addrs = actix_rt::task::spawn_blocking(move || {
+        (name, port)
+            .to_socket_addrs()
+            .map(|addrs| addrs.collect::<Vec<SocketAddr>>())
+    })
+    .await;
+    let addrs = match addrs {
+        Ok(Ok(addrs)) if !addrs.is_empty() => addrs,
+        _ => {
+            return Err(AppError::new(
+                "url host cannot be resolved",
+                ErrorKind::FailedAction,
+            ))
+        }
+    };
+
+    let allowed = allowed_hosts
+        .iter()
+        .any(|allowed_host| allowed_host.eq_ignore_ascii_case(host));
+    if !allowed && !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+        return Err(AppError::new(
+            "url must not point to a private address",
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(addrs)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
-
     use actix_web::test;
 
     use super::generate_random_alphanum;
     use super::split_into_parts;
+    use super::{decode_hex, encode_hex, hmac_sha256, is_public_ip};
 
     #[test]
     async fn test_split_into_parts() {
@@ -76,9 +196,62 @@ mod tests {
             let got = generate_random_alphanum(test_case);
             assert!(got.is_err());
             assert_eq!(
-                got.unwrap_err().description(),
+                got.unwrap_err().to_string(),
                 "size must not be greater than 30"
             );
         }
     }
+
+    #[test]
+    // test_hex_round_trip tests that decode_hex reads what encode_hex writes and rejects
+    // strings that are not hexadecimal
+    async fn test_hex_round_trip() {
+        let bytes = "{\"name\": \"Widget & co\"}".as_bytes();
+        assert_eq!(decode_hex(&encode_hex(bytes)).unwrap(), bytes);
+
+        let test_cases = vec!["abc", "zz", "é"];
+        for test_case in test_cases {
+            assert!(decode_hex(test_case).is_none());
+        }
+    }
+
+    #[test]
+    // test_hmac_sha256 tests the signature of a message against a known HMAC-SHA256 value
+    async fn test_hmac_sha256() {
+        assert_eq!(
+            hmac_sha256("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    // test_is_public_ip tests that the addresses a webhook must not reach are not public
+    async fn test_is_public_ip() {
+        // (address, public)
+        let test_cases = vec![
+            ("93.184.216.34", true),
+            ("2606:2800:220:1::", true),
+            ("127.0.0.1", false),
+            ("10.1.2.3", false),
+            ("172.16.0.1", false),
+            ("192.168.1.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("0.0.0.0", false),
+            ("::1", false),
+            ("fd00::1", false),
+            ("fc00::1", false),
+            ("fe80::1", false),
+            ("febf::1", false),
+            ("::ffff:127.0.0.1", false),
+        ];
+        for (address, public) in test_cases {
+            assert_eq!(
+                is_public_ip(address.parse().unwrap()),
+                public,
+                "{}",
+                address
+            );
+        }
+    }
 }